@@ -0,0 +1,201 @@
+use crate::circuits::blake2::{Blake2Chip, Blake2GateConfig};
+use crate::host::blake2::{blake2_blocks, Blake2, BLAKE2B, BLAKE2S};
+use crate::host::ForeignInst::{
+    Blake2bFinalize, Blake2bNew, Blake2bPush, Blake2sFinalize, Blake2sNew, Blake2sPush,
+};
+use crate::host::{ExternalHostCallEntry, ExternalHostCallEntryTable, ForeignInst};
+use ark_std::{end_timer, start_timer};
+use halo2_proofs::circuit::{Layouter, Region};
+use halo2_proofs::pairing::bn256::Fr;
+use halo2_proofs::plonk::ConstraintSystem;
+use halo2_proofs::plonk::{Error, Column, Advice};
+use crate::adaptor::get_selected_entries;
+use crate::circuits::host::{HostOpConfig, HostOpSelector};
+
+use crate::utils::Limb;
+
+// reset, t and last
+const NEW_SIZE: usize = 3;
+
+const TOTAL_CONSTRUCTIONS: usize = 16;
+
+fn blake2_entry(op: ForeignInst, value: u64) -> ExternalHostCallEntry {
+    ExternalHostCallEntry {
+        op: op as usize,
+        value,
+        is_ret: false,
+    }
+}
+
+/// pack little endian words into u64 host arguments
+fn pack_words(words: &[u64], bits: usize) -> Vec<u64> {
+    words
+        .chunks_exact(64 / bits)
+        .map(|ws| ws.iter().rev().fold(0u128, |acc, w| (acc << bits) + *w as u128) as u64)
+        .collect()
+}
+
+/// Emit one new/push/finalize group per compressed block, the first block of every
+/// message restarts from the initial state.
+pub fn blake2_to_host_call_table(bits: usize, inputs: Vec<Vec<u8>>) -> ExternalHostCallEntryTable {
+    let (params, new, push, finalize) = if bits == 32 {
+        (&BLAKE2S, Blake2sNew, Blake2sPush, Blake2sFinalize)
+    } else {
+        (&BLAKE2B, Blake2bNew, Blake2bPush, Blake2bFinalize)
+    };
+    let mut r = vec![];
+    for message in inputs.iter() {
+        let mut hasher = Blake2::new(params);
+        for (i, (block, t, last)) in blake2_blocks(params, message).iter().enumerate() {
+            r.push(blake2_entry(new, (i == 0) as u64));
+            r.push(blake2_entry(new, *t));
+            r.push(blake2_entry(new, *last as u64));
+            for v in pack_words(block, bits) {
+                r.push(blake2_entry(push, v));
+            }
+            for v in pack_words(&hasher.update_exact(block, *t, *last), bits) {
+                r.push(blake2_entry(finalize, v));
+            }
+        }
+    }
+    ExternalHostCallEntryTable(r)
+}
+
+impl<const BITS: usize> Blake2Chip<Fr, BITS> {
+    const PUSH_SIZE: usize = 16 / Self::WORDS_PER_LIMB;
+    const CHUNK_SIZE: usize = NEW_SIZE + Self::PUSH_SIZE + 8 / Self::WORDS_PER_LIMB;
+
+    fn host_opcodes() -> [ForeignInst; 3] {
+        if BITS == 32 {
+            [Blake2sNew, Blake2sPush, Blake2sFinalize]
+        } else {
+            [Blake2bNew, Blake2bPush, Blake2bFinalize]
+        }
+    }
+}
+
+impl<const BITS: usize> HostOpSelector for Blake2Chip<Fr, BITS> {
+    type Config = Blake2GateConfig;
+    fn configure(meta: &mut ConstraintSystem<Fr>, _shared_advices: &Vec<Column<Advice>>) -> Self::Config {
+        Blake2Chip::<Fr, BITS>::configure(meta)
+    }
+
+    fn construct(c: Self::Config) -> Self {
+        Blake2Chip::new(c)
+    }
+
+    fn opcodes() -> Vec<Fr> {
+        Self::host_opcodes()
+            .into_iter()
+            .map(|x| Fr::from(x as u64))
+            .collect()
+    }
+
+    fn assign(
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+        config: &HostOpConfig,
+    ) -> Result<Vec<Limb<Fr>>, Error> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        assert!(selected_entries.len() % Self::CHUNK_SIZE == 0);
+        let total_used_instructions = selected_entries.len() / Self::CHUNK_SIZE;
+
+        let mut r = vec![];
+
+        // the single block of the empty message
+        let default_table = blake2_to_host_call_table(BITS, vec![vec![]]);
+        let default_entries: Vec<((Fr, Fr), Fr)> = default_table
+            .0
+            .into_iter()
+            .map(|x| ((Fr::from(x.value), Fr::from(x.op as u64)), Fr::zero()))
+            .collect::<Vec<((Fr, Fr), Fr)>>();
+
+        let groups = selected_entries
+            .chunks_exact(Self::CHUNK_SIZE)
+            .map(|group| (group, true))
+            .chain(
+                (0..TOTAL_CONSTRUCTIONS - total_used_instructions)
+                    .map(|_| (&default_entries[..], false)),
+            );
+
+        for (group, enable) in groups {
+            assert!(group[0].0 .1 == opcodes[0]);
+            for ((operand, opcode), index) in group.iter() {
+                let (limb, _op) = config.assign_one_line(
+                    region,
+                    offset,
+                    *operand,
+                    *opcode,
+                    *index,
+                    *operand,
+                    Fr::zero(),
+                    enable,
+                )?;
+                r.push(limb);
+            }
+        }
+
+        Ok(r)
+    }
+
+    fn synthesize(
+        &mut self,
+        offset: &mut usize,
+        arg_cells: &Vec<Limb<Fr>>,
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        println!("blake2 adaptor total args is {}", arg_cells.len());
+        *offset = layouter.assign_region(
+            || "blake2 hash region",
+            |mut region| {
+                let mut local_offset = *offset;
+                let timer = start_timer!(|| "assign");
+                self.initialize(&mut region, &mut local_offset)?;
+                // arg_cells format reset + t + last + block + result
+                for args in arg_cells.chunks_exact(Self::CHUNK_SIZE).into_iter() {
+                    self.assign_compress(
+                        &mut region,
+                        &mut local_offset,
+                        &args[0],
+                        &args[1],
+                        &args[2],
+                        &args[NEW_SIZE..NEW_SIZE + Self::PUSH_SIZE],
+                        &args[NEW_SIZE + Self::PUSH_SIZE..Self::CHUNK_SIZE],
+                    )?;
+                }
+                end_timer!(timer);
+                Ok(local_offset)
+            },
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::blake2_to_host_call_table;
+    use std::fs::File;
+
+    #[test]
+    fn generate_blake2s_input() {
+        let table = blake2_to_host_call_table(
+            32,
+            vec![b"abc".to_vec(), (0..200).map(|x| x as u8).collect()],
+        );
+        let file = File::create("blake2stest.json").expect("can not create file");
+        serde_json::to_writer_pretty(file, &table).expect("can not write to file");
+    }
+
+    #[test]
+    fn generate_blake2b_input() {
+        let table = blake2_to_host_call_table(
+            64,
+            vec![b"abc".to_vec(), (0..200).map(|x| x as u8).collect()],
+        );
+        let file = File::create("blake2btest.json").expect("can not create file");
+        serde_json::to_writer_pretty(file, &table).expect("can not write to file");
+    }
+}
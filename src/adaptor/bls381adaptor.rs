@@ -1,30 +1,61 @@
 use halo2_proofs::arithmetic::FieldExt;
 use halo2_proofs::pairing::bn256::Fr;
 use halo2_proofs::pairing::bls12_381::pairing;
-use halo2_proofs::pairing::bls12_381::{G1Affine, G2Affine};
+use ff::Field;
+use halo2_proofs::pairing::bls12_381::{Fq, Fq12, Fq2, Fq6, G1Affine, G2Affine};
 use halo2_proofs::{
     circuit::{Layouter, Region},
     plonk::{ConstraintSystem, Error, Advice, Column},
 };
 
 pub const BLS381FR_SIZE: usize = 5;
-//pub const BLS381FQ_SIZE: usize = 8;
+pub const BLS381FQ_SIZE: usize = 8;
 pub const BLS381G1_SIZE: usize = 17;
 pub const BLS381G2_SIZE: usize = 33;
 pub const BLS381GT_SIZE: usize = 96;
+pub const BLS381GT_COMPRESSED_SIZE: usize = 6 * 8 + 1;
 const BLSPAIR_SIZE: usize = BLS381G1_SIZE + BLS381G2_SIZE + BLS381GT_SIZE;
 const BLSSUM_SIZE: usize = 1 + BLS381FR_SIZE + 2 * BLS381G1_SIZE;
+const BLSSUMG2_SIZE: usize = 1 + BLS381FR_SIZE + 2 * BLS381G2_SIZE;
+const BLSMSM_SIZE: usize = 1 + BLS381_MSM_POINTS * (BLS381FR_SIZE + BLS381G1_SIZE) + BLS381G1_SIZE;
+const BLSPAIRCHECK_PAIR_SIZE: usize = BLS381G1_SIZE + BLS381G2_SIZE;
+const BLSPAIRCOMPRESSED_SIZE: usize = BLS381G1_SIZE + BLS381G2_SIZE + BLS381GT_COMPRESSED_SIZE;
+const BLSGTOP_SIZE: usize = 1 + 3 * BLS381GT_SIZE;
+const BLSKZG_SIZE: usize =
+    KZG_VERSIONED_HASH_WORDS + 2 * BLS381FR_SIZE + 2 * BLS381G1_SIZE + 1 + KZG_COMPRESSED_WORDS;
+const BLSVERIFY_SIZE: usize =
+    BLS381_VERIFY_PUBKEYS * BLS381G1_SIZE + BLS_UNIFORM_WORDS + BLS381G2_SIZE + 1;
 
-use crate::circuits::bls::{Bls381ChipConfig, Bls381PairChip, Bls381SumChip};
+use crate::circuits::bls::{
+    Bls381ChipConfig, Bls381GtChip, Bls381KzgChip, Bls381MsmChip, Bls381PairChip, Bls381PairCheckChip,
+    Bls381PairCompressedChip, Bls381SumChip, Bls381SumG2Chip, Bls381VerifyChip,
+    BLS381_MSM_POINTS, BLS381_PAIR_CHECK_PAIRS, BLS381_VERIFY_PUBKEYS,
+};
 
+use crate::adaptor::validate::{
+    check_affine, check_call_sizes, fq_from_entries, CallError, HostCallError, PointError,
+};
+use crate::circuits::gt::{GT_OP_EQUAL, GT_OP_INVERT, GT_OP_MUL};
 use crate::circuits::host::{HostOpConfig, HostOpSelector};
-use crate::utils::Limb;
+use crate::utils::{field_to_u64, Limb};
 
+use crate::host::bls::{
+    bls381_expand_message, bls381_g1_compressed_words, bls381_gt_compress, kzg_to_versioned_hash,
+    kzg_versioned_hash_words, BLS_SIG_DST, BLS_UNIFORM_WORDS, KZG_COMPRESSED_WORDS,
+    KZG_VERSIONED_HASH_WORDS, VERSIONED_HASH_VERSION_KZG,
+};
 use crate::host::{ExternalHostCallEntry, ForeignInst};
 use super::get_selected_entries;
 
 const TOTAL_CONSTRUCTIONS_PAIR: usize = 1;
 const TOTAL_CONSTRUCTIONS_SUM: usize = 16;
+const TOTAL_CONSTRUCTIONS_SUM_G2: usize = 4;
+const TOTAL_CONSTRUCTIONS_MSM: usize = 4;
+const TOTAL_CONSTRUCTIONS_PAIR_CHECK: usize = 1;
+const TOTAL_CONSTRUCTIONS_KZG: usize = 4;
+const TOTAL_CONSTRUCTIONS_VERIFY: usize = 1;
+const TOTAL_CONSTRUCTIONS_PAIR_COMPRESSED: usize = 1;
+const TOTAL_CONSTRUCTIONS_GT: usize = 4;
 
 fn bls381_fr_default(op: ForeignInst) -> Vec<ExternalHostCallEntry> {
     let mut r = vec![];
@@ -65,6 +96,23 @@ fn bls381_g1_generator(op: ForeignInst) -> Vec<ExternalHostCallEntry> {
     r
 }
 
+fn bls381_g2_default(op: ForeignInst) -> Vec<ExternalHostCallEntry> {
+    let mut r = vec![];
+    for _ in 0..BLS381G2_SIZE - 1 {
+        r.push(ExternalHostCallEntry {
+            op: op as usize,
+            value: 0u64,
+            is_ret: false,
+        });
+    }
+    r.push(ExternalHostCallEntry {
+        op: op as usize,
+        value: 1u64,
+        is_ret: false,
+    });
+    r
+}
+
 fn bls381_g2_generator(op: ForeignInst) -> Vec<ExternalHostCallEntry> {
     let mut r = vec![];
     let b = G2Affine::generator();
@@ -100,6 +148,72 @@ fn bls381_gt_pairing_generator(op: ForeignInst) -> Vec<ExternalHostCallEntry> {
     r
 }
 
+fn bls381_gt_compressed_pairing_generator(op: ForeignInst) -> Vec<ExternalHostCallEntry> {
+    let mut r = vec![];
+    let ab = pairing(&G1Affine::generator(), &G2Affine::generator());
+    let c = bls381_gt_compress(&ab).unwrap();
+    for fq2 in [c.c0, c.c1, c.c2] {
+        r.append(&mut crate::adaptor::fr_to_args(fq2.c0, 8, 54, op));
+        r.append(&mut crate::adaptor::fr_to_args(fq2.c1, 8, 54, op));
+    }
+    r.push(ExternalHostCallEntry {
+        op: op as usize,
+        value: 0u64,
+        is_ret: false,
+    });
+    r
+}
+
+fn bls381_fq2_from_entries(entries: &[((Fr, Fr), Fr)]) -> Option<Fq2> {
+    let c0 = fq_from_entries::<Fq>(&entries[0..8], 54);
+    let c1 = fq_from_entries::<Fq>(&entries[8..16], 54);
+    c0.zip(c1).map(|(c0, c1)| Fq2 { c0, c1 })
+}
+
+fn bls381_fq12_from_entries(entries: &[((Fr, Fr), Fr)]) -> Option<Fq12> {
+    let c = entries
+        .chunks_exact(16)
+        .map(bls381_fq2_from_entries)
+        .collect::<Option<Vec<_>>>()?;
+    Some(Fq12 {
+        c0: Fq6 { c0: c[0], c1: c[1], c2: c[2] },
+        c1: Fq6 { c0: c[3], c1: c[4], c2: c[5] },
+    })
+}
+
+/// Native check of the operation and the operands of a gt op call
+fn check_bls381_gt_op(entries: &[((Fr, Fr), Fr)], call: usize) -> Result<(), HostCallError> {
+    let mut operands = vec![];
+    for (i, arg) in ["a", "b", "result"].into_iter().enumerate() {
+        let start = 1 + i * BLS381GT_SIZE;
+        let f = bls381_fq12_from_entries(&entries[start..start + BLS381GT_SIZE])
+            .ok_or(PointError::NonCanonical.at("bls381gt", call, arg))?;
+        operands.push(f);
+    }
+    let op = entries[0].0 .0;
+    if ![GT_OP_MUL, GT_OP_INVERT, GT_OP_EQUAL].into_iter().any(|x| op == Fr::from(x)) {
+        return Err(CallError::UnknownOp.at("bls381gt", call, "op").into());
+    }
+    if op == Fr::from(GT_OP_INVERT) && operands[0] == Fq12::zero() {
+        return Err(CallError::NotInvertible.at("bls381gt", call, "a").into());
+    }
+    Ok(())
+}
+
+/// Native check of a pushed g1 point, both groups of bls12-381 have a cofactor
+fn check_bls381_g1(entries: &[((Fr, Fr), Fr)]) -> Result<(), PointError> {
+    let x = fq_from_entries::<Fq>(&entries[0..8], 54);
+    let y = fq_from_entries::<Fq>(&entries[8..16], 54);
+    check_affine::<G1Affine>(x, y, &entries[16].0 .0, true)
+}
+
+/// Native check of a pushed g2 point
+fn check_bls381_g2(entries: &[((Fr, Fr), Fr)]) -> Result<(), PointError> {
+    let x = bls381_fq2_from_entries(&entries[0..16]);
+    let y = bls381_fq2_from_entries(&entries[16..32]);
+    check_affine::<G2Affine>(x, y, &entries[32].0 .0, true)
+}
+
 impl HostOpSelector for Bls381PairChip<Fr> {
     type Config = Bls381ChipConfig;
     fn configure(meta: &mut ConstraintSystem<Fr>, _shared_advices: &Vec<Column<Advice>>) -> Self::Config {
@@ -118,6 +232,20 @@ impl HostOpSelector for Bls381PairChip<Fr> {
         ]
     }
 
+    fn validate(
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+    ) -> Result<(), HostCallError> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        for (call, group) in selected_entries.chunks_exact(BLSPAIR_SIZE).enumerate() {
+            check_bls381_g1(&group[0..BLS381G1_SIZE]).map_err(|e| e.at("bls381pair", call, "g1"))?;
+            check_bls381_g2(&group[BLS381G1_SIZE..BLS381G1_SIZE + BLS381G2_SIZE])
+                .map_err(|e| e.at("bls381pair", call, "g2"))?;
+        }
+        Ok(())
+    }
+
     fn assign(
         region: &mut Region<Fr>,
         _offset: &mut usize,
@@ -307,6 +435,20 @@ impl HostOpSelector for Bls381SumChip<Fr> {
         ]
     }
 
+    fn validate(
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+    ) -> Result<(), HostCallError> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        for (call, group) in selected_entries.chunks_exact(BLSSUM_SIZE).enumerate() {
+            let start = 1 + BLS381FR_SIZE;
+            check_bls381_g1(&group[start..start + BLS381G1_SIZE])
+                .map_err(|e| e.at("bls381sum", call, "g1"))?;
+        }
+        Ok(())
+    }
+
     fn assign(
         region: &mut Region<Fr>,
         _offset: &mut usize,
@@ -487,3 +629,1019 @@ impl HostOpSelector for Bls381SumChip<Fr> {
         Ok(())
     }
 }
+
+// the coordinates of a g1 or g2 point in pairs of 54 bits and whether it is zero or not
+// fq of 8 * u54 in 4 limbs each
+fn assign_bls381_fqs(
+    region: &mut Region<Fr>,
+    offset: &mut usize,
+    entries: &[((Fr, Fr), Fr)],
+    config: &HostOpConfig,
+    enable: bool,
+    r: &mut Vec<Limb<Fr>>,
+) -> Result<(), Error> {
+    for pair in entries.chunks_exact(2) {
+        let (limb, _op) = config.assign_merged_operands(
+            region,
+            offset,
+            vec![&pair[0], &pair[1]],
+            Fr::from_u128(1u128 << 54),
+            enable,
+        )?;
+        r.push(limb);
+    }
+    Ok(())
+}
+
+fn assign_bls381_point(
+    region: &mut Region<Fr>,
+    offset: &mut usize,
+    entries: &[((Fr, Fr), Fr)],
+    config: &HostOpConfig,
+    enable: bool,
+    r: &mut Vec<Limb<Fr>>,
+) -> Result<(), Error> {
+    let nb_limbs = entries.len() / 2;
+    assign_bls381_fqs(region, offset, &entries[0..2 * nb_limbs], config, enable, r)?;
+    let ((operand, opcode), index) = entries[2 * nb_limbs].clone();
+    let (limb, _) = config.assign_one_line(
+        region,
+        offset,
+        operand,
+        opcode,
+        index,
+        operand,
+        Fr::zero(),
+        enable,
+    )?;
+    r.push(limb);
+    Ok(())
+}
+
+/// Same as assign_bls381_point but also pushes the enable cell of every operand to enables
+fn assign_bls381_point_with_enables(
+    region: &mut Region<Fr>,
+    offset: &mut usize,
+    entries: &[((Fr, Fr), Fr)],
+    config: &HostOpConfig,
+    enable: bool,
+    r: &mut Vec<Limb<Fr>>,
+    enables: &mut Vec<Limb<Fr>>,
+) -> Result<(), Error> {
+    let nb_limbs = entries.len() / 2;
+    for pair in entries[0..2 * nb_limbs].chunks_exact(2) {
+        let (limb, _op, en) = config.assign_merged_operands_with_enable(
+            region,
+            offset,
+            vec![&pair[0], &pair[1]],
+            Fr::from_u128(1u128 << 54),
+            enable,
+        )?;
+        r.push(limb);
+        enables.push(en);
+    }
+    let ((operand, opcode), index) = entries[2 * nb_limbs].clone();
+    let (limb, _op, en) = config.assign_one_line_with_enable(
+        region,
+        offset,
+        operand,
+        opcode,
+        index,
+        operand,
+        Fr::zero(),
+        enable,
+    )?;
+    r.push(limb);
+    enables.push(en);
+    Ok(())
+}
+
+/// A call pushes 1 to BLS381_PAIR_CHECK_PAIRS pairs, the missing pairs of a call are
+/// holes of identity pairs, see load_bls381_pair_check_circuit
+impl HostOpSelector for Bls381PairCheckChip<Fr> {
+    type Config = Bls381ChipConfig;
+    fn configure(meta: &mut ConstraintSystem<Fr>, _shared_advices: &Vec<Column<Advice>>) -> Self::Config {
+        Bls381PairCheckChip::<Fr>::configure(meta)
+    }
+
+    fn construct(c: Self::Config) -> Self {
+        Bls381PairCheckChip::construct(c)
+    }
+
+    fn opcodes() -> Vec<Fr> {
+        vec![
+            Fr::from(ForeignInst::BlsPairCheckG1 as u64),
+            Fr::from(ForeignInst::BlsPairCheckG2 as u64),
+            Fr::from(ForeignInst::BlsPairCheckResult as u64),
+        ]
+    }
+
+    fn holes() -> bool {
+        true
+    }
+
+    fn validate(
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+    ) -> Result<(), HostCallError> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        check_call_sizes(
+            &selected_entries,
+            opcodes[2],
+            BLSPAIRCHECK_PAIR_SIZE,
+            BLS381_PAIR_CHECK_PAIRS,
+            "bls381paircheck",
+        )?;
+        let calls = selected_entries
+            .split_inclusive(|((_, opcode), _)| *opcode == opcodes[2])
+            .collect::<Vec<_>>();
+        if calls.len() > TOTAL_CONSTRUCTIONS_PAIR_CHECK {
+            return Err(CallError::TooManyCalls
+                .at("bls381paircheck", TOTAL_CONSTRUCTIONS_PAIR_CHECK, "call")
+                .into());
+        }
+        for (call, group) in calls.iter().enumerate() {
+            for pair in group[0..group.len() - 1].chunks_exact(BLSPAIRCHECK_PAIR_SIZE) {
+                check_bls381_g1(&pair[0..BLS381G1_SIZE])
+                    .map_err(|e| e.at("bls381paircheck", call, "g1"))?;
+                check_bls381_g2(&pair[BLS381G1_SIZE..])
+                    .map_err(|e| e.at("bls381paircheck", call, "g2"))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn assign(
+        region: &mut Region<Fr>,
+        _offset: &mut usize,
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+        config: &HostOpConfig,
+    ) -> Result<Vec<Limb<Fr>>, Error> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        let calls = selected_entries
+            .split_inclusive(|((_, opcode), _)| *opcode == opcodes[2])
+            .collect::<Vec<_>>();
+        let total_used_instructions = calls.len();
+        assert!(total_used_instructions <= TOTAL_CONSTRUCTIONS_PAIR_CHECK);
+
+        let mut offset = 0;
+        let mut r = vec![];
+
+        // an identity pair contributes one to the product
+        let mut default_pair = vec![];
+        default_pair.append(&mut bls381_g1_default(ForeignInst::BlsPairCheckG1));
+        default_pair.append(&mut bls381_g2_default(ForeignInst::BlsPairCheckG2));
+        // a check of identity pairs holds
+        let default_result = vec![ExternalHostCallEntry {
+            op: ForeignInst::BlsPairCheckResult as usize,
+            value: 1u64,
+            is_ret: false,
+        }];
+        let into_entries = |table: Vec<ExternalHostCallEntry>| {
+            table
+                .into_iter()
+                .map(|x| ((Fr::from(x.value), Fr::from(x.op as u64)), Fr::zero()))
+                .collect::<Vec<((Fr, Fr), Fr)>>()
+        };
+        let default_pair = into_entries(default_pair);
+        let default_result = into_entries(default_result);
+
+        let groups = calls
+            .into_iter()
+            .map(|group| (group, true))
+            .chain(
+                (0..TOTAL_CONSTRUCTIONS_PAIR_CHECK - total_used_instructions)
+                    .map(|_| (&default_result[..], false)),
+            );
+
+        for (group, enable) in groups {
+            let (pairs, result) = group.split_at(group.len() - 1);
+            let pairs = pairs.chunks_exact(BLSPAIRCHECK_PAIR_SIZE).collect::<Vec<_>>();
+            // the holes keep the index of the last pushed entry
+            let index = pairs.last().map_or(Fr::zero(), |pair| pair[pair.len() - 1].1);
+            let hole = default_pair
+                .iter()
+                .map(|(operand, _)| (*operand, index))
+                .collect::<Vec<_>>();
+            for i in 0..BLS381_PAIR_CHECK_PAIRS {
+                let (pair, pair_enable) = if i < pairs.len() {
+                    (pairs[i], enable)
+                } else {
+                    (&hole[..], false)
+                };
+                let mut enables = vec![];
+                assign_bls381_point_with_enables(
+                    region,
+                    &mut offset,
+                    &pair[0..BLS381G1_SIZE],
+                    config,
+                    pair_enable,
+                    &mut r,
+                    &mut enables,
+                )?;
+                assign_bls381_point_with_enables(
+                    region,
+                    &mut offset,
+                    &pair[BLS381G1_SIZE..],
+                    config,
+                    pair_enable,
+                    &mut r,
+                    &mut enables,
+                )?;
+                r.append(&mut enables);
+            }
+
+            // whether the product is one or not
+            let ((operand, opcode), index) = result[0].clone();
+            let (limb, op, en) = config.assign_one_line_with_enable(
+                region,
+                &mut offset,
+                operand,
+                opcode,
+                index,
+                operand,
+                Fr::zero(),
+                enable,
+            )?;
+            r.append(&mut vec![limb, op, en]);
+        }
+        Ok(r)
+    }
+
+    fn synthesize(
+        &mut self,
+        _offset: &mut usize,
+        arg_cells: &Vec<Limb<Fr>>,
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        self.range_chip.init_table(layouter)?;
+        self.load_bls381_pair_check_circuit(&arg_cells, layouter)?;
+        Ok(())
+    }
+}
+
+// a scalar of 5 * u54 in 3 limbs
+fn assign_bls381_fr(
+    region: &mut Region<Fr>,
+    offset: &mut usize,
+    entries: &[((Fr, Fr), Fr)],
+    config: &HostOpConfig,
+    enable: bool,
+    r: &mut Vec<Limb<Fr>>,
+) -> Result<(), Error> {
+    for i in 0..2 {
+        let (limb, _op) = config.assign_merged_operands(
+            region,
+            offset,
+            vec![&entries[2 * i], &entries[2 * i + 1]],
+            Fr::from_u128(1u128 << 54),
+            enable,
+        )?;
+        r.push(limb);
+    }
+    let ((operand, opcode), index) = entries[4].clone();
+    let (limb, _) = config.assign_one_line(
+        region,
+        offset,
+        operand,
+        opcode,
+        index,
+        operand,
+        Fr::zero(),
+        enable,
+    )?;
+    r.push(limb);
+    Ok(())
+}
+
+impl HostOpSelector for Bls381KzgChip<Fr> {
+    type Config = Bls381ChipConfig;
+    fn configure(meta: &mut ConstraintSystem<Fr>, _shared_advices: &Vec<Column<Advice>>) -> Self::Config {
+        Bls381KzgChip::<Fr>::configure(meta)
+    }
+
+    fn construct(c: Self::Config) -> Self {
+        Bls381KzgChip::construct(c)
+    }
+
+    fn opcodes() -> Vec<Fr> {
+        vec![
+            Fr::from(ForeignInst::BlsKzgPointEvalVersionedHash as u64),
+            Fr::from(ForeignInst::BlsKzgPointEvalZ as u64),
+            Fr::from(ForeignInst::BlsKzgPointEvalY as u64),
+            Fr::from(ForeignInst::BlsKzgPointEvalCommitment as u64),
+            Fr::from(ForeignInst::BlsKzgPointEvalProof as u64),
+            Fr::from(ForeignInst::BlsKzgPointEvalResult as u64),
+            Fr::from(ForeignInst::BlsKzgPointEvalCompressed as u64),
+        ]
+    }
+
+    fn validate(
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+    ) -> Result<(), HostCallError> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        if selected_entries.len() / BLSKZG_SIZE > TOTAL_CONSTRUCTIONS_KZG {
+            return Err(CallError::TooManyCalls
+                .at("bls381kzg", TOTAL_CONSTRUCTIONS_KZG, "call")
+                .into());
+        }
+        for (call, group) in selected_entries.chunks_exact(BLSKZG_SIZE).enumerate() {
+            let ((version, _), _) = group[0];
+            if field_to_u64(&version) >> 56 != VERSIONED_HASH_VERSION_KZG as u64 {
+                return Err(CallError::HashVersion.at("bls381kzg", call, "versioned hash").into());
+            }
+            let start = KZG_VERSIONED_HASH_WORDS + 2 * BLS381FR_SIZE;
+            check_bls381_g1(&group[start..start + BLS381G1_SIZE])
+                .map_err(|e| e.at("bls381kzg", call, "commitment"))?;
+            check_bls381_g1(&group[start + BLS381G1_SIZE..start + 2 * BLS381G1_SIZE])
+                .map_err(|e| e.at("bls381kzg", call, "proof"))?;
+        }
+        Ok(())
+    }
+
+    fn assign(
+        region: &mut Region<Fr>,
+        _offset: &mut usize,
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+        config: &HostOpConfig,
+    ) -> Result<Vec<Limb<Fr>>, Error> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        assert!(selected_entries.len() % BLSKZG_SIZE == 0);
+        let total_used_instructions = selected_entries.len() / BLSKZG_SIZE;
+
+        let mut offset = 0;
+        let mut r = vec![];
+
+        // the zero polynomial opens to zero with the identity as its commitment and proof
+        let mut default_table = vec![];
+        for word in kzg_versioned_hash_words(&kzg_to_versioned_hash(&G1Affine::default())) {
+            default_table.push(ExternalHostCallEntry {
+                op: ForeignInst::BlsKzgPointEvalVersionedHash as usize,
+                value: word,
+                is_ret: false,
+            });
+        }
+        default_table.append(&mut bls381_fr_default(ForeignInst::BlsKzgPointEvalZ));
+        default_table.append(&mut bls381_fr_default(ForeignInst::BlsKzgPointEvalY));
+        default_table.append(&mut bls381_g1_default(ForeignInst::BlsKzgPointEvalCommitment));
+        default_table.append(&mut bls381_g1_default(ForeignInst::BlsKzgPointEvalProof));
+        default_table.push(ExternalHostCallEntry {
+            op: ForeignInst::BlsKzgPointEvalResult as usize,
+            value: 1u64,
+            is_ret: false,
+        });
+        for word in bls381_g1_compressed_words(&G1Affine::default()) {
+            default_table.push(ExternalHostCallEntry {
+                op: ForeignInst::BlsKzgPointEvalCompressed as usize,
+                value: word,
+                is_ret: true,
+            });
+        }
+
+        let default_entries: Vec<((Fr, Fr), Fr)> = default_table
+            .into_iter()
+            .map(|x| ((Fr::from(x.value), Fr::from(x.op as u64)), Fr::zero()))
+            .collect::<Vec<((Fr, Fr), Fr)>>();
+
+        let groups = selected_entries
+            .chunks_exact(BLSKZG_SIZE)
+            .map(|group| (group, true))
+            .chain(
+                (0..TOTAL_CONSTRUCTIONS_KZG - total_used_instructions)
+                    .map(|_| (&default_entries[..], false)),
+            );
+
+        for (group, enable) in groups {
+            let (hash, group) = group.split_at(KZG_VERSIONED_HASH_WORDS);
+            for entry in hash.iter() {
+                let ((operand, opcode), index) = entry.clone();
+                let (limb, _) = config.assign_one_line(
+                    region,
+                    &mut offset,
+                    operand,
+                    opcode,
+                    index,
+                    operand,
+                    Fr::zero(),
+                    enable,
+                )?;
+                r.push(limb);
+            }
+            let (frs, points) = group.split_at(2 * BLS381FR_SIZE);
+            assign_bls381_fr(region, &mut offset, &frs[0..BLS381FR_SIZE], config, enable, &mut r)?;
+            assign_bls381_fr(region, &mut offset, &frs[BLS381FR_SIZE..], config, enable, &mut r)?;
+            assign_bls381_point(region, &mut offset, &points[0..BLS381G1_SIZE], config, enable, &mut r)?;
+            assign_bls381_point(
+                region,
+                &mut offset,
+                &points[BLS381G1_SIZE..2 * BLS381G1_SIZE],
+                config,
+                enable,
+                &mut r,
+            )?;
+
+            // whether the opening holds or not, then the compressed commitment
+            for entry in group[group.len() - 1 - KZG_COMPRESSED_WORDS..].iter() {
+                let ((operand, opcode), index) = entry.clone();
+                let (limb, _) = config.assign_one_line(
+                    region,
+                    &mut offset,
+                    operand,
+                    opcode,
+                    index,
+                    operand,
+                    Fr::zero(),
+                    enable,
+                )?;
+                r.push(limb);
+            }
+        }
+        Ok(r)
+    }
+
+    fn synthesize(
+        &mut self,
+        _offset: &mut usize,
+        arg_cells: &Vec<Limb<Fr>>,
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        self.range_chip.init_table(layouter)?;
+        self.load_bls381_kzg_circuit(&arg_cells, layouter)?;
+        Ok(())
+    }
+}
+
+impl HostOpSelector for Bls381VerifyChip<Fr> {
+    type Config = Bls381ChipConfig;
+    fn configure(meta: &mut ConstraintSystem<Fr>, _shared_advices: &Vec<Column<Advice>>) -> Self::Config {
+        Bls381VerifyChip::<Fr>::configure(meta)
+    }
+
+    fn construct(c: Self::Config) -> Self {
+        Bls381VerifyChip::construct(c)
+    }
+
+    fn opcodes() -> Vec<Fr> {
+        vec![
+            Fr::from(ForeignInst::BlsVerifyPubkey as u64),
+            Fr::from(ForeignInst::BlsVerifyUniformBytes as u64),
+            Fr::from(ForeignInst::BlsVerifySignature as u64),
+            Fr::from(ForeignInst::BlsVerifyResult as u64),
+        ]
+    }
+
+    fn validate(
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+    ) -> Result<(), HostCallError> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        for (call, group) in selected_entries.chunks_exact(BLSVERIFY_SIZE).enumerate() {
+            let sig_start = BLS381_VERIFY_PUBKEYS * BLS381G1_SIZE + BLS_UNIFORM_WORDS;
+            for pk in group[0..BLS381_VERIFY_PUBKEYS * BLS381G1_SIZE].chunks_exact(BLS381G1_SIZE) {
+                check_bls381_g1(pk).map_err(|e| e.at("bls381verify", call, "public key"))?;
+            }
+            check_bls381_g2(&group[sig_start..sig_start + BLS381G2_SIZE])
+                .map_err(|e| e.at("bls381verify", call, "signature"))?;
+        }
+        Ok(())
+    }
+
+    fn assign(
+        region: &mut Region<Fr>,
+        _offset: &mut usize,
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+        config: &HostOpConfig,
+    ) -> Result<Vec<Limb<Fr>>, Error> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        assert!(selected_entries.len() % BLSVERIFY_SIZE == 0);
+        let total_used_instructions = selected_entries.len() / BLSVERIFY_SIZE;
+
+        let mut offset = 0;
+        let mut r = vec![];
+
+        // identity keys never verify, the message is the empty one since the two
+        // points its hash adds up have to be different
+        let mut default_table = vec![];
+        for _ in 0..BLS381_VERIFY_PUBKEYS {
+            default_table.append(&mut bls381_g1_default(ForeignInst::BlsVerifyPubkey));
+        }
+        for word in bls381_expand_message(&[], BLS_SIG_DST) {
+            default_table.push(ExternalHostCallEntry {
+                op: ForeignInst::BlsVerifyUniformBytes as usize,
+                value: word,
+                is_ret: false,
+            });
+        }
+        default_table.append(&mut bls381_g2_default(ForeignInst::BlsVerifySignature));
+        default_table.push(ExternalHostCallEntry {
+            op: ForeignInst::BlsVerifyResult as usize,
+            value: 0u64,
+            is_ret: false,
+        });
+
+        let default_entries: Vec<((Fr, Fr), Fr)> = default_table
+            .into_iter()
+            .map(|x| ((Fr::from(x.value), Fr::from(x.op as u64)), Fr::zero()))
+            .collect::<Vec<((Fr, Fr), Fr)>>();
+
+        let groups = selected_entries
+            .chunks_exact(BLSVERIFY_SIZE)
+            .map(|group| (group, true))
+            .chain(
+                (0..TOTAL_CONSTRUCTIONS_VERIFY - total_used_instructions)
+                    .map(|_| (&default_entries[..], false)),
+            );
+
+        let hash_start = BLS381_VERIFY_PUBKEYS * BLS381G1_SIZE;
+        let sig_start = hash_start + BLS_UNIFORM_WORDS;
+        for (group, enable) in groups {
+            for pk in group[0..hash_start].chunks_exact(BLS381G1_SIZE) {
+                assign_bls381_point(region, &mut offset, pk, config, enable, &mut r)?;
+            }
+            // the uniform bytes of the message, one word per line
+            for ((operand, opcode), index) in group[hash_start..sig_start].iter().cloned() {
+                let (limb, _) = config.assign_one_line(
+                    region,
+                    &mut offset,
+                    operand,
+                    opcode,
+                    index,
+                    operand,
+                    Fr::zero(),
+                    enable,
+                )?;
+                r.push(limb);
+            }
+            assign_bls381_point(
+                region,
+                &mut offset,
+                &group[sig_start..sig_start + BLS381G2_SIZE],
+                config,
+                enable,
+                &mut r,
+            )?;
+
+            // whether the signature is valid or not
+            let ((operand, opcode), index) = group[BLSVERIFY_SIZE - 1].clone();
+            let (limb, _) = config.assign_one_line(
+                region,
+                &mut offset,
+                operand,
+                opcode,
+                index,
+                operand,
+                Fr::zero(),
+                enable,
+            )?;
+            r.push(limb);
+        }
+        Ok(r)
+    }
+
+    fn synthesize(
+        &mut self,
+        _offset: &mut usize,
+        arg_cells: &Vec<Limb<Fr>>,
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        self.range_chip.init_table(layouter)?;
+        self.load_bls381_verify_circuit(&arg_cells, layouter)?;
+        Ok(())
+    }
+}
+
+impl HostOpSelector for Bls381SumG2Chip<Fr> {
+    type Config = Bls381ChipConfig;
+    fn configure(meta: &mut ConstraintSystem<Fr>, _shared_advices: &Vec<Column<Advice>>) -> Self::Config {
+        Bls381SumG2Chip::<Fr>::configure(meta)
+    }
+
+    fn construct(c: Self::Config) -> Self {
+        Bls381SumG2Chip::construct(c)
+    }
+
+    fn opcodes() -> Vec<Fr> {
+        vec![
+            Fr::from(ForeignInst::BlsSumG2New as u64),
+            Fr::from(ForeignInst::BlsSumG2Scalar as u64),
+            Fr::from(ForeignInst::BlsSumG2 as u64),
+            Fr::from(ForeignInst::BlsSumG2Result as u64),
+        ]
+    }
+
+    fn validate(
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+    ) -> Result<(), HostCallError> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        for (call, group) in selected_entries.chunks_exact(BLSSUMG2_SIZE).enumerate() {
+            let start = 1 + BLS381FR_SIZE;
+            check_bls381_g2(&group[start..start + BLS381G2_SIZE])
+                .map_err(|e| e.at("bls381sumg2", call, "g2"))?;
+        }
+        Ok(())
+    }
+
+    fn assign(
+        region: &mut Region<Fr>,
+        _offset: &mut usize,
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+        config: &HostOpConfig,
+    ) -> Result<Vec<Limb<Fr>>, Error> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        assert!(selected_entries.len() % BLSSUMG2_SIZE == 0);
+        let total_used_instructions = selected_entries.len() / BLSSUMG2_SIZE;
+
+        let mut offset = 0;
+        let mut r = vec![];
+
+        let mut default_table = vec![];
+        default_table.push(ExternalHostCallEntry {
+            op: ForeignInst::BlsSumG2New as usize,
+            value: 1u64,
+            is_ret: false,
+        });
+        default_table.append(&mut bls381_fr_default(ForeignInst::BlsSumG2Scalar));
+        default_table.append(&mut bls381_g2_default(ForeignInst::BlsSumG2));
+        default_table.append(&mut bls381_g2_default(ForeignInst::BlsSumG2Result));
+
+        let default_entries: Vec<((Fr, Fr), Fr)> = default_table
+            .into_iter()
+            .map(|x| ((Fr::from(x.value), Fr::from(x.op as u64)), Fr::zero()))
+            .collect::<Vec<((Fr, Fr), Fr)>>();
+
+        let groups = selected_entries
+            .chunks_exact(BLSSUMG2_SIZE)
+            .map(|group| (group, true))
+            .chain(
+                (0..TOTAL_CONSTRUCTIONS_SUM_G2 - total_used_instructions)
+                    .map(|_| (&default_entries[..], false)),
+            );
+
+        for (group, enable) in groups {
+            // whether new is zero or not
+            let ((operand, opcode), index) = group[0].clone();
+            let (limb, _op) = config.assign_one_line(
+                region,
+                &mut offset,
+                operand,
+                opcode,
+                index,
+                operand,
+                Fr::zero(),
+                enable,
+            )?;
+            r.push(limb);
+
+            assign_bls381_fr(region, &mut offset, &group[1..1 + BLS381FR_SIZE], config, enable, &mut r)?;
+
+            // G2 and sum
+            for point in group[1 + BLS381FR_SIZE..].chunks_exact(BLS381G2_SIZE) {
+                assign_bls381_point(region, &mut offset, point, config, enable, &mut r)?;
+            }
+        }
+        Ok(r)
+    }
+
+    fn synthesize(
+        &mut self,
+        _offset: &mut usize,
+        arg_cells: &Vec<Limb<Fr>>,
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        self.range_chip.init_table(layouter)?;
+        self.load_bls381_sum_g2_circuit(&arg_cells, layouter)?;
+        Ok(())
+    }
+}
+
+impl HostOpSelector for Bls381MsmChip<Fr> {
+    type Config = Bls381ChipConfig;
+    fn configure(meta: &mut ConstraintSystem<Fr>, _shared_advices: &Vec<Column<Advice>>) -> Self::Config {
+        Bls381MsmChip::<Fr>::configure(meta)
+    }
+
+    fn construct(c: Self::Config) -> Self {
+        Bls381MsmChip::construct(c)
+    }
+
+    fn opcodes() -> Vec<Fr> {
+        vec![
+            Fr::from(ForeignInst::BlsMsmNew as u64),
+            Fr::from(ForeignInst::BlsMsmScalar as u64),
+            Fr::from(ForeignInst::BlsMsmG1 as u64),
+            Fr::from(ForeignInst::BlsMsmResult as u64),
+        ]
+    }
+
+    fn validate(
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+    ) -> Result<(), HostCallError> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        let pairs_end = BLSMSM_SIZE - BLS381G1_SIZE;
+        for (call, group) in selected_entries.chunks_exact(BLSMSM_SIZE).enumerate() {
+            for pair in group[1..pairs_end].chunks_exact(BLS381FR_SIZE + BLS381G1_SIZE) {
+                check_bls381_g1(&pair[BLS381FR_SIZE..])
+                    .map_err(|e| e.at("bls381msm", call, "g1"))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn assign(
+        region: &mut Region<Fr>,
+        _offset: &mut usize,
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+        config: &HostOpConfig,
+    ) -> Result<Vec<Limb<Fr>>, Error> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        assert!(selected_entries.len() % BLSMSM_SIZE == 0);
+        let total_used_instructions = selected_entries.len() / BLSMSM_SIZE;
+
+        let pairs_end = BLSMSM_SIZE - BLS381G1_SIZE;
+
+        let mut offset = 0;
+        let mut r = vec![];
+
+        // zero scalars of identity points sum to the identity
+        let mut default_table = vec![];
+        default_table.push(ExternalHostCallEntry {
+            op: ForeignInst::BlsMsmNew as usize,
+            value: 1u64,
+            is_ret: false,
+        });
+        for _ in 0..BLS381_MSM_POINTS {
+            default_table.append(&mut bls381_fr_default(ForeignInst::BlsMsmScalar));
+            default_table.append(&mut bls381_g1_default(ForeignInst::BlsMsmG1));
+        }
+        default_table.append(&mut bls381_g1_default(ForeignInst::BlsMsmResult));
+
+        let default_entries: Vec<((Fr, Fr), Fr)> = default_table
+            .into_iter()
+            .map(|x| ((Fr::from(x.value), Fr::from(x.op as u64)), Fr::zero()))
+            .collect::<Vec<((Fr, Fr), Fr)>>();
+
+        let groups = selected_entries
+            .chunks_exact(BLSMSM_SIZE)
+            .map(|group| (group, true))
+            .chain(
+                (0..TOTAL_CONSTRUCTIONS_MSM - total_used_instructions)
+                    .map(|_| (&default_entries[..], false)),
+            );
+
+        for (group, enable) in groups {
+            // whether new is zero or not
+            let ((operand, opcode), index) = group[0].clone();
+            let (limb, _op) = config.assign_one_line(
+                region,
+                &mut offset,
+                operand,
+                opcode,
+                index,
+                operand,
+                Fr::zero(),
+                enable,
+            )?;
+            r.push(limb);
+
+            // Fr and G1 of the pairs
+            for pair in group[1..pairs_end].chunks_exact(BLS381FR_SIZE + BLS381G1_SIZE) {
+                assign_bls381_fr(region, &mut offset, &pair[0..BLS381FR_SIZE], config, enable, &mut r)?;
+                assign_bls381_point(region, &mut offset, &pair[BLS381FR_SIZE..], config, enable, &mut r)?;
+            }
+
+            // sum
+            assign_bls381_point(region, &mut offset, &group[pairs_end..], config, enable, &mut r)?;
+        }
+        Ok(r)
+    }
+
+    fn synthesize(
+        &mut self,
+        _offset: &mut usize,
+        arg_cells: &Vec<Limb<Fr>>,
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        self.range_chip.init_table(layouter)?;
+        self.load_bls381_msm_circuit(&arg_cells, layouter)?;
+        Ok(())
+    }
+}
+
+impl HostOpSelector for Bls381PairCompressedChip<Fr> {
+    type Config = Bls381ChipConfig;
+    fn configure(meta: &mut ConstraintSystem<Fr>, _shared_advices: &Vec<Column<Advice>>) -> Self::Config {
+        Bls381PairCompressedChip::<Fr>::configure(meta)
+    }
+
+    fn construct(c: Self::Config) -> Self {
+        Bls381PairCompressedChip::construct(c)
+    }
+
+    fn opcodes() -> Vec<Fr> {
+        vec![
+            Fr::from(ForeignInst::BlsPairCompressedG1 as u64),
+            Fr::from(ForeignInst::BlsPairCompressedG2 as u64),
+            Fr::from(ForeignInst::BlsPairCompressedGt as u64),
+        ]
+    }
+
+    fn validate(
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+    ) -> Result<(), HostCallError> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        for (call, group) in selected_entries.chunks_exact(BLSPAIRCOMPRESSED_SIZE).enumerate() {
+            check_bls381_g1(&group[0..BLS381G1_SIZE])
+                .map_err(|e| e.at("bls381paircompressed", call, "g1"))?;
+            check_bls381_g2(&group[BLS381G1_SIZE..BLS381G1_SIZE + BLS381G2_SIZE])
+                .map_err(|e| e.at("bls381paircompressed", call, "g2"))?;
+        }
+        Ok(())
+    }
+
+    fn assign(
+        region: &mut Region<Fr>,
+        _offset: &mut usize,
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+        config: &HostOpConfig,
+    ) -> Result<Vec<Limb<Fr>>, Error> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        assert!(selected_entries.len() % BLSPAIRCOMPRESSED_SIZE == 0);
+        let total_used_instructions = selected_entries.len() / BLSPAIRCOMPRESSED_SIZE;
+
+        let mut offset = 0;
+        let mut r = vec![];
+
+        let mut default_table = vec![];
+        default_table.append(&mut bls381_g1_generator(ForeignInst::BlsPairCompressedG1));
+        default_table.append(&mut bls381_g2_generator(ForeignInst::BlsPairCompressedG2));
+        default_table.append(&mut bls381_gt_compressed_pairing_generator(
+            ForeignInst::BlsPairCompressedGt,
+        ));
+
+        let default_entries: Vec<((Fr, Fr), Fr)> = default_table
+            .into_iter()
+            .map(|x| ((Fr::from(x.value), Fr::from(x.op as u64)), Fr::zero()))
+            .collect::<Vec<((Fr, Fr), Fr)>>();
+
+        let groups = selected_entries
+            .chunks_exact(BLSPAIRCOMPRESSED_SIZE)
+            .map(|group| (group, true))
+            .chain(
+                (0..TOTAL_CONSTRUCTIONS_PAIR_COMPRESSED - total_used_instructions)
+                    .map(|_| (&default_entries[..], false)),
+            );
+
+        for (group, enable) in groups {
+            let g2_start = BLS381G1_SIZE;
+            let gt_start = BLS381G1_SIZE + BLS381G2_SIZE;
+            assign_bls381_point(region, &mut offset, &group[0..g2_start], config, enable, &mut r)?;
+            assign_bls381_point(region, &mut offset, &group[g2_start..gt_start], config, enable, &mut r)?;
+            // the compressed gt is six fq followed by the flag of one
+            assign_bls381_point(region, &mut offset, &group[gt_start..], config, enable, &mut r)?;
+        }
+        Ok(r)
+    }
+
+    fn synthesize(
+        &mut self,
+        _offset: &mut usize,
+        arg_cells: &Vec<Limb<Fr>>,
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        self.range_chip.init_table(layouter)?;
+        let a = arg_cells[0..9].to_vec();
+        let b = arg_cells[9..26].to_vec();
+        let ab = arg_cells[26..51].to_vec();
+        self.load_bls381_pair_compressed_circuit(&a, &b, &ab, layouter)?;
+        Ok(())
+    }
+}
+
+impl HostOpSelector for Bls381GtChip<Fr> {
+    type Config = Bls381ChipConfig;
+    fn configure(meta: &mut ConstraintSystem<Fr>, _shared_advices: &Vec<Column<Advice>>) -> Self::Config {
+        Bls381GtChip::<Fr>::configure(meta)
+    }
+
+    fn construct(c: Self::Config) -> Self {
+        Bls381GtChip::construct(c)
+    }
+
+    fn opcodes() -> Vec<Fr> {
+        vec![
+            Fr::from(ForeignInst::BlsGtOp as u64),
+            Fr::from(ForeignInst::BlsGtA as u64),
+            Fr::from(ForeignInst::BlsGtB as u64),
+            Fr::from(ForeignInst::BlsGtResult as u64),
+        ]
+    }
+
+    fn validate(
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+    ) -> Result<(), HostCallError> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        for (call, group) in selected_entries.chunks_exact(BLSGTOP_SIZE).enumerate() {
+            check_bls381_gt_op(group, call)?;
+        }
+        Ok(())
+    }
+
+    fn assign(
+        region: &mut Region<Fr>,
+        _offset: &mut usize,
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+        config: &HostOpConfig,
+    ) -> Result<Vec<Limb<Fr>>, Error> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        assert!(selected_entries.len() % BLSGTOP_SIZE == 0);
+        let total_used_instructions = selected_entries.len() / BLSGTOP_SIZE;
+
+        let mut offset = 0;
+        let mut r = vec![];
+
+        // 0 * 0 == 0
+        let mut default_table = vec![ExternalHostCallEntry {
+            op: ForeignInst::BlsGtOp as usize,
+            value: GT_OP_MUL,
+            is_ret: false,
+        }];
+        for op in [ForeignInst::BlsGtA, ForeignInst::BlsGtB, ForeignInst::BlsGtResult] {
+            for _ in 0..BLS381GT_SIZE {
+                default_table.push(ExternalHostCallEntry {
+                    op: op as usize,
+                    value: 0u64,
+                    is_ret: false,
+                });
+            }
+        }
+
+        let default_entries: Vec<((Fr, Fr), Fr)> = default_table
+            .into_iter()
+            .map(|x| ((Fr::from(x.value), Fr::from(x.op as u64)), Fr::zero()))
+            .collect::<Vec<((Fr, Fr), Fr)>>();
+
+        let groups = selected_entries
+            .chunks_exact(BLSGTOP_SIZE)
+            .map(|group| (group, true))
+            .chain(
+                (0..TOTAL_CONSTRUCTIONS_GT - total_used_instructions)
+                    .map(|_| (&default_entries[..], false)),
+            );
+
+        for (group, enable) in groups {
+            let ((operand, opcode), index) = group[0].clone();
+            let (limb, _op) = config.assign_one_line(
+                region,
+                &mut offset,
+                operand,
+                opcode,
+                index,
+                operand,
+                Fr::zero(),
+                enable,
+            )?;
+            r.push(limb);
+
+            // a, b and the result
+            assign_bls381_fqs(region, &mut offset, &group[1..], config, enable, &mut r)?;
+        }
+        Ok(r)
+    }
+
+    fn synthesize(
+        &mut self,
+        _offset: &mut usize,
+        arg_cells: &Vec<Limb<Fr>>,
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        self.range_chip.init_table(layouter)?;
+        self.load_bls381_gt_circuit(&arg_cells, layouter)?;
+        Ok(())
+    }
+}
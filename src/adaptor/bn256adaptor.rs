@@ -1,6 +1,7 @@
 use halo2_proofs::pairing::bn256::Fr;
 use halo2_proofs::pairing::bn256::pairing;
-use halo2_proofs::pairing::bn256::{G1Affine, G2Affine, G1, G2};
+use ff::Field;
+use halo2_proofs::pairing::bn256::{Fq, Fq12, Fq2, Fq6, G1Affine, G2Affine, G1, G2};
 use halo2_proofs::pairing::group::Group;
 use halo2_proofs::{
     arithmetic::FieldExt,
@@ -14,19 +15,38 @@ pub const BN256FR_SIZE: usize = 4;
 pub const BN256G1_SIZE: usize = 11;
 pub const BN256G2_SIZE: usize = 21;
 pub const BN256GT_SIZE: usize = 60;
+pub const BN256GT_COMPRESSED_SIZE: usize = 6 * 5 + 1;
 
 const BN256PAIR_SIZE: usize = BN256G1_SIZE + BN256G2_SIZE + BN256GT_SIZE;
 const BN256SUM_SIZE: usize = 1 + BN256FR_SIZE + 2 * BN256G1_SIZE;
+const BN256SUMG2_SIZE: usize = 1 + BN256FR_SIZE + 2 * BN256G2_SIZE;
+const BN256MSM_SIZE: usize = 1 + BN256_MSM_POINTS * (BN256FR_SIZE + BN256G1_SIZE) + BN256G1_SIZE;
+const BN256PAIRCHECK_PAIR_SIZE: usize = BN256G1_SIZE + BN256G2_SIZE;
+const BN256PAIRCOMPRESSED_SIZE: usize = BN256G1_SIZE + BN256G2_SIZE + BN256GT_COMPRESSED_SIZE;
+const BN256GTOP_SIZE: usize = 1 + 3 * BN256GT_SIZE;
 
-use crate::circuits::bn256::{Bn256ChipConfig, Bn256PairChip, Bn256SumChip};
+use crate::circuits::bn256::{
+    Bn256ChipConfig, Bn256GtChip, Bn256PairChip, Bn256PairCheckChip, Bn256PairCompressedChip,
+    Bn256SumChip, Bn256MsmChip, Bn256SumG2Chip, BN256_MSM_POINTS, BN256_PAIR_CHECK_PAIRS,
+};
 
+use crate::adaptor::validate::{
+    check_affine, check_call_sizes, fq_from_entries, CallError, HostCallError, PointError,
+};
+use crate::circuits::gt::{GT_OP_EQUAL, GT_OP_INVERT, GT_OP_MUL};
 use crate::circuits::host::{HostOpConfig, HostOpSelector};
 use crate::utils::Limb;
 
+use crate::host::bn256::bn256_gt_compress;
 use crate::host::{ExternalHostCallEntry, ForeignInst};
 
 const TOTAL_CONSTRUCTIONS_PAIR: usize = 1;
 const TOTAL_CONSTRUCTIONS_SUM: usize = 32;
+const TOTAL_CONSTRUCTIONS_SUM_G2: usize = 4;
+const TOTAL_CONSTRUCTIONS_MSM: usize = 8;
+const TOTAL_CONSTRUCTIONS_PAIR_CHECK: usize = 1;
+const TOTAL_CONSTRUCTIONS_PAIR_COMPRESSED: usize = 1;
+const TOTAL_CONSTRUCTIONS_GT: usize = 8;
 
 fn bn256_fr_default(op: ForeignInst) -> Vec<ExternalHostCallEntry> {
     let mut r = vec![];
@@ -44,7 +64,8 @@ fn bn256_g1_default(op: ForeignInst) -> Vec<ExternalHostCallEntry> {
     let mut r = vec![];
     let a = G1::default();
     r.append(&mut crate::adaptor::fr_to_args(a.x, 5, 54, op));
-    r.append(&mut crate::adaptor::fr_to_args(a.y, 5, 54, op));
+    // the identity is (0, 0) in the circuits while G1's default has a.y = 1, use a.x twice
+    r.append(&mut crate::adaptor::fr_to_args(a.x, 5, 54, op));
     r.push(ExternalHostCallEntry {
         op: op as usize,
         value: a.is_identity().unwrap_u8() as u64,
@@ -66,6 +87,23 @@ fn bn256_g1_generator(op: ForeignInst) -> Vec<ExternalHostCallEntry> {
     r
 }
 
+fn bn256_g2_default(op: ForeignInst) -> Vec<ExternalHostCallEntry> {
+    let mut r = vec![];
+    for _ in 0..BN256G2_SIZE - 1 {
+        r.push(ExternalHostCallEntry {
+            op: op as usize,
+            value: 0u64,
+            is_ret: false,
+        });
+    }
+    r.push(ExternalHostCallEntry {
+        op: op as usize,
+        value: 1u64,
+        is_ret: false,
+    });
+    r
+}
+
 fn bn256_g2_generator(op: ForeignInst) -> Vec<ExternalHostCallEntry> {
     let mut r = vec![];
     let b = G2::generator();
@@ -103,6 +141,72 @@ fn bn256_gt_pairing_generator(op: ForeignInst) -> Vec<ExternalHostCallEntry> {
     r
 }
 
+fn bn256_gt_compressed_pairing_generator(op: ForeignInst) -> Vec<ExternalHostCallEntry> {
+    let mut r = vec![];
+    let ab = pairing(&G1Affine::generator(), &G2Affine::generator());
+    let c = bn256_gt_compress(&ab).unwrap();
+    for fq2 in [c.c0, c.c1, c.c2] {
+        r.append(&mut crate::adaptor::fr_to_args(fq2.c0, 5, 54, op));
+        r.append(&mut crate::adaptor::fr_to_args(fq2.c1, 5, 54, op));
+    }
+    r.push(ExternalHostCallEntry {
+        op: op as usize,
+        value: 0u64,
+        is_ret: false,
+    });
+    r
+}
+
+fn bn256_fq2_from_entries(entries: &[((Fr, Fr), Fr)]) -> Option<Fq2> {
+    let c0 = fq_from_entries::<Fq>(&entries[0..5], 54);
+    let c1 = fq_from_entries::<Fq>(&entries[5..10], 54);
+    c0.zip(c1).map(|(c0, c1)| Fq2 { c0, c1 })
+}
+
+fn bn256_fq12_from_entries(entries: &[((Fr, Fr), Fr)]) -> Option<Fq12> {
+    let c = entries
+        .chunks_exact(10)
+        .map(bn256_fq2_from_entries)
+        .collect::<Option<Vec<_>>>()?;
+    Some(Fq12 {
+        c0: Fq6 { c0: c[0], c1: c[1], c2: c[2] },
+        c1: Fq6 { c0: c[3], c1: c[4], c2: c[5] },
+    })
+}
+
+/// Native check of the operation and the operands of a gt op call
+fn check_bn256_gt_op(entries: &[((Fr, Fr), Fr)], call: usize) -> Result<(), HostCallError> {
+    let mut operands = vec![];
+    for (i, arg) in ["a", "b", "result"].into_iter().enumerate() {
+        let start = 1 + i * BN256GT_SIZE;
+        let f = bn256_fq12_from_entries(&entries[start..start + BN256GT_SIZE])
+            .ok_or(PointError::NonCanonical.at("bn256gt", call, arg))?;
+        operands.push(f);
+    }
+    let op = entries[0].0 .0;
+    if ![GT_OP_MUL, GT_OP_INVERT, GT_OP_EQUAL].into_iter().any(|x| op == Fr::from(x)) {
+        return Err(CallError::UnknownOp.at("bn256gt", call, "op").into());
+    }
+    if op == Fr::from(GT_OP_INVERT) && operands[0] == Fq12::zero() {
+        return Err(CallError::NotInvertible.at("bn256gt", call, "a").into());
+    }
+    Ok(())
+}
+
+/// Native check of a pushed g1 point, which is in the subgroup once it is on the curve
+pub(crate) fn check_bn256_g1(entries: &[((Fr, Fr), Fr)]) -> Result<(), PointError> {
+    let x = fq_from_entries::<Fq>(&entries[0..5], 54);
+    let y = fq_from_entries::<Fq>(&entries[5..10], 54);
+    check_affine::<G1Affine>(x, y, &entries[10].0 .0, false)
+}
+
+/// Native check of a pushed g2 point, the twist has points out of the subgroup
+pub(crate) fn check_bn256_g2(entries: &[((Fr, Fr), Fr)]) -> Result<(), PointError> {
+    let x = bn256_fq2_from_entries(&entries[0..10]);
+    let y = bn256_fq2_from_entries(&entries[10..20]);
+    check_affine::<G2Affine>(x, y, &entries[20].0 .0, true)
+}
+
 impl HostOpSelector for Bn256PairChip<Fr> {
     type Config = Bn256ChipConfig;
     fn configure(meta: &mut ConstraintSystem<Fr>, _shared_advice: &Vec<Column<Advice>>) -> Self::Config {
@@ -121,6 +225,20 @@ impl HostOpSelector for Bn256PairChip<Fr> {
         ]
     }
 
+    fn validate(
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+    ) -> Result<(), HostCallError> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        for (call, group) in selected_entries.chunks_exact(BN256PAIR_SIZE).enumerate() {
+            check_bn256_g1(&group[0..BN256G1_SIZE]).map_err(|e| e.at("bn256pair", call, "g1"))?;
+            check_bn256_g2(&group[BN256G1_SIZE..BN256G1_SIZE + BN256G2_SIZE])
+                .map_err(|e| e.at("bn256pair", call, "g2"))?;
+        }
+        Ok(())
+    }
+
     fn assign(
         region: &mut Region<Fr>,
         offset: &mut usize,
@@ -401,6 +519,20 @@ impl HostOpSelector for Bn256SumChip<Fr> {
         ]
     }
 
+    fn validate(
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+    ) -> Result<(), HostCallError> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        for (call, group) in selected_entries.chunks_exact(BN256SUM_SIZE).enumerate() {
+            let start = 1 + BN256FR_SIZE;
+            check_bn256_g1(&group[start..start + BN256G1_SIZE])
+                .map_err(|e| e.at("bn256sum", call, "g1"))?;
+        }
+        Ok(())
+    }
+
     fn assign(
         region: &mut Region<Fr>,
         offset: &mut usize,
@@ -580,3 +712,727 @@ impl HostOpSelector for Bn256SumChip<Fr> {
         Ok(())
     }
 }
+
+// the coordinates of a g1 or g2 point in ((1,1) (1,1) 1) and whether it is zero or not
+// fq of 5 * u54 in 3 limbs each
+fn assign_bn256_fqs(
+    region: &mut Region<Fr>,
+    offset: &mut usize,
+    entries: &[((Fr, Fr), Fr)],
+    config: &HostOpConfig,
+    enable: bool,
+    r: &mut Vec<Limb<Fr>>,
+) -> Result<(), Error> {
+    for fq in entries.chunks_exact(5) {
+        for i in 0..2 {
+            let (p_01, _op) = config.assign_merged_operands(
+                region,
+                offset,
+                vec![&fq[2 * i], &fq[2 * i + 1]],
+                Fr::from_u128(1u128 << 54),
+                enable,
+            )?;
+            r.push(p_01);
+        }
+        let ((operand, opcode), index) = fq[4].clone();
+        let (p_2, _op) = config.assign_one_line(
+            region,
+            offset,
+            operand,
+            opcode,
+            index,
+            operand,
+            Fr::zero(),
+            enable,
+        )?;
+        r.push(p_2);
+    }
+    Ok(())
+}
+
+pub(crate) fn assign_bn256_point(
+    region: &mut Region<Fr>,
+    offset: &mut usize,
+    entries: &[((Fr, Fr), Fr)],
+    config: &HostOpConfig,
+    enable: bool,
+    r: &mut Vec<Limb<Fr>>,
+) -> Result<(), Error> {
+    let nb_fq = entries.len() / 5;
+    assign_bn256_fqs(region, offset, &entries[0..5 * nb_fq], config, enable, r)?;
+    let ((operand, opcode), index) = entries[5 * nb_fq].clone();
+    let (zero, _op) = config.assign_one_line(
+        region,
+        offset,
+        operand,
+        opcode,
+        index,
+        operand,
+        Fr::zero(),
+        enable,
+    )?;
+    r.push(zero);
+    Ok(())
+}
+
+/// Same as assign_bn256_point but also pushes the enable cell of every operand to enables
+fn assign_bn256_point_with_enables(
+    region: &mut Region<Fr>,
+    offset: &mut usize,
+    entries: &[((Fr, Fr), Fr)],
+    config: &HostOpConfig,
+    enable: bool,
+    r: &mut Vec<Limb<Fr>>,
+    enables: &mut Vec<Limb<Fr>>,
+) -> Result<(), Error> {
+    let nb_fq = entries.len() / 5;
+    for fq in entries[0..5 * nb_fq].chunks_exact(5) {
+        for i in 0..2 {
+            let (p_01, _op, en) = config.assign_merged_operands_with_enable(
+                region,
+                offset,
+                vec![&fq[2 * i], &fq[2 * i + 1]],
+                Fr::from_u128(1u128 << 54),
+                enable,
+            )?;
+            r.push(p_01);
+            enables.push(en);
+        }
+        let ((operand, opcode), index) = fq[4].clone();
+        let (p_2, _op, en) = config.assign_one_line_with_enable(
+            region,
+            offset,
+            operand,
+            opcode,
+            index,
+            operand,
+            Fr::zero(),
+            enable,
+        )?;
+        r.push(p_2);
+        enables.push(en);
+    }
+    let ((operand, opcode), index) = entries[5 * nb_fq].clone();
+    let (zero, _op, en) = config.assign_one_line_with_enable(
+        region,
+        offset,
+        operand,
+        opcode,
+        index,
+        operand,
+        Fr::zero(),
+        enable,
+    )?;
+    r.push(zero);
+    enables.push(en);
+    Ok(())
+}
+
+/// A call pushes 1 to BN256_PAIR_CHECK_PAIRS pairs, the missing pairs of a call are
+/// holes of identity pairs, see load_bn256_pair_check_circuit
+impl HostOpSelector for Bn256PairCheckChip<Fr> {
+    type Config = Bn256ChipConfig;
+    fn configure(meta: &mut ConstraintSystem<Fr>, _shared_advices: &Vec<Column<Advice>>) -> Self::Config {
+        Bn256PairCheckChip::<Fr>::configure(meta)
+    }
+
+    fn construct(c: Self::Config) -> Self {
+        Bn256PairCheckChip::construct(c)
+    }
+
+    fn opcodes() -> Vec<Fr> {
+        vec![
+            Fr::from(ForeignInst::Bn254PairCheckG1 as u64),
+            Fr::from(ForeignInst::Bn254PairCheckG2 as u64),
+            Fr::from(ForeignInst::Bn254PairCheckResult as u64),
+        ]
+    }
+
+    fn holes() -> bool {
+        true
+    }
+
+    fn validate(
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+    ) -> Result<(), HostCallError> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        check_call_sizes(
+            &selected_entries,
+            opcodes[2],
+            BN256PAIRCHECK_PAIR_SIZE,
+            BN256_PAIR_CHECK_PAIRS,
+            "bn256paircheck",
+        )?;
+        let calls = selected_entries
+            .split_inclusive(|((_, opcode), _)| *opcode == opcodes[2])
+            .collect::<Vec<_>>();
+        if calls.len() > TOTAL_CONSTRUCTIONS_PAIR_CHECK {
+            return Err(CallError::TooManyCalls
+                .at("bn256paircheck", TOTAL_CONSTRUCTIONS_PAIR_CHECK, "call")
+                .into());
+        }
+        for (call, group) in calls.iter().enumerate() {
+            for pair in group[0..group.len() - 1].chunks_exact(BN256PAIRCHECK_PAIR_SIZE) {
+                check_bn256_g1(&pair[0..BN256G1_SIZE])
+                    .map_err(|e| e.at("bn256paircheck", call, "g1"))?;
+                check_bn256_g2(&pair[BN256G1_SIZE..])
+                    .map_err(|e| e.at("bn256paircheck", call, "g2"))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn assign(
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+        config: &HostOpConfig,
+    ) -> Result<Vec<Limb<Fr>>, Error> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        let calls = selected_entries
+            .split_inclusive(|((_, opcode), _)| *opcode == opcodes[2])
+            .collect::<Vec<_>>();
+        let total_used_instructions = calls.len();
+        assert!(total_used_instructions <= TOTAL_CONSTRUCTIONS_PAIR_CHECK);
+
+        assert_eq!(*offset, 0);
+        let mut r = vec![];
+
+        // an identity pair contributes one to the product
+        let mut default_pair = vec![];
+        default_pair.append(&mut bn256_g1_default(ForeignInst::Bn254PairCheckG1));
+        default_pair.append(&mut bn256_g2_default(ForeignInst::Bn254PairCheckG2));
+        // a check of identity pairs holds
+        let default_result = vec![ExternalHostCallEntry {
+            op: ForeignInst::Bn254PairCheckResult as usize,
+            value: 1u64,
+            is_ret: false,
+        }];
+        let into_entries = |table: Vec<ExternalHostCallEntry>| {
+            table
+                .into_iter()
+                .map(|x| ((Fr::from(x.value), Fr::from(x.op as u64)), Fr::zero()))
+                .collect::<Vec<((Fr, Fr), Fr)>>()
+        };
+        let default_pair = into_entries(default_pair);
+        let default_result = into_entries(default_result);
+
+        let groups = calls
+            .into_iter()
+            .map(|group| (group, true))
+            .chain(
+                (0..TOTAL_CONSTRUCTIONS_PAIR_CHECK - total_used_instructions)
+                    .map(|_| (&default_result[..], false)),
+            );
+
+        for (group, enable) in groups {
+            let (pairs, result) = group.split_at(group.len() - 1);
+            let pairs = pairs.chunks_exact(BN256PAIRCHECK_PAIR_SIZE).collect::<Vec<_>>();
+            // the holes keep the index of the last pushed entry
+            let index = pairs.last().map_or(Fr::zero(), |pair| pair[pair.len() - 1].1);
+            let hole = default_pair
+                .iter()
+                .map(|(operand, _)| (*operand, index))
+                .collect::<Vec<_>>();
+            for i in 0..BN256_PAIR_CHECK_PAIRS {
+                let (pair, pair_enable) = if i < pairs.len() {
+                    (pairs[i], enable)
+                } else {
+                    (&hole[..], false)
+                };
+                let mut enables = vec![];
+                assign_bn256_point_with_enables(
+                    region,
+                    offset,
+                    &pair[0..BN256G1_SIZE],
+                    config,
+                    pair_enable,
+                    &mut r,
+                    &mut enables,
+                )?;
+                assign_bn256_point_with_enables(
+                    region,
+                    offset,
+                    &pair[BN256G1_SIZE..],
+                    config,
+                    pair_enable,
+                    &mut r,
+                    &mut enables,
+                )?;
+                r.append(&mut enables);
+            }
+
+            // whether the product is one or not
+            let ((operand, opcode), index) = result[0].clone();
+            let (limb, op, en) = config.assign_one_line_with_enable(
+                region,
+                offset,
+                operand,
+                opcode,
+                index,
+                operand,
+                Fr::zero(),
+                enable,
+            )?;
+            r.append(&mut vec![limb, op, en]);
+        }
+        Ok(r)
+    }
+
+    fn synthesize(
+        &mut self,
+        _offset: &mut usize,
+        arg_cells: &Vec<Limb<Fr>>,
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        self.range_chip.init_table(layouter)?;
+        self.load_bn256_pair_check_circuit(&arg_cells, layouter)?;
+        Ok(())
+    }
+}
+
+impl HostOpSelector for Bn256SumG2Chip<Fr> {
+    type Config = Bn256ChipConfig;
+    fn configure(meta: &mut ConstraintSystem<Fr>, _shared_advices: &Vec<Column<Advice>>) -> Self::Config {
+        Bn256SumG2Chip::<Fr>::configure(meta)
+    }
+
+    fn construct(c: Self::Config) -> Self {
+        Bn256SumG2Chip::construct(c)
+    }
+
+    fn opcodes() -> Vec<Fr> {
+        vec![
+            Fr::from(ForeignInst::Bn254SumG2New as u64),
+            Fr::from(ForeignInst::Bn254SumG2Scalar as u64),
+            Fr::from(ForeignInst::Bn254SumG2 as u64),
+            Fr::from(ForeignInst::Bn254SumG2Result as u64),
+        ]
+    }
+
+    fn validate(
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+    ) -> Result<(), HostCallError> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        for (call, group) in selected_entries.chunks_exact(BN256SUMG2_SIZE).enumerate() {
+            let start = 1 + BN256FR_SIZE;
+            check_bn256_g2(&group[start..start + BN256G2_SIZE])
+                .map_err(|e| e.at("bn256sumg2", call, "g2"))?;
+        }
+        Ok(())
+    }
+
+    fn assign(
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+        config: &HostOpConfig,
+    ) -> Result<Vec<Limb<Fr>>, Error> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        assert!(selected_entries.len() % BN256SUMG2_SIZE == 0);
+        let total_used_instructions = selected_entries.len() / BN256SUMG2_SIZE;
+
+        assert_eq!(*offset, 0);
+        let mut r = vec![];
+
+        let mut default_table = vec![];
+        default_table.push(ExternalHostCallEntry {
+            op: ForeignInst::Bn254SumG2New as usize,
+            value: 1u64,
+            is_ret: false,
+        });
+        default_table.append(&mut bn256_fr_default(ForeignInst::Bn254SumG2Scalar));
+        default_table.append(&mut bn256_g2_default(ForeignInst::Bn254SumG2));
+        default_table.append(&mut bn256_g2_default(ForeignInst::Bn254SumG2Result));
+
+        let default_entries: Vec<((Fr, Fr), Fr)> = default_table
+            .into_iter()
+            .map(|x| ((Fr::from(x.value), Fr::from(x.op as u64)), Fr::zero()))
+            .collect::<Vec<((Fr, Fr), Fr)>>();
+
+        let groups = selected_entries
+            .chunks_exact(BN256SUMG2_SIZE)
+            .map(|group| (group, true))
+            .chain(
+                (0..TOTAL_CONSTRUCTIONS_SUM_G2 - total_used_instructions)
+                    .map(|_| (&default_entries[..], false)),
+            );
+
+        for (group, enable) in groups {
+            // whether new is zero or not
+            let ((operand, opcode), index) = group[0].clone();
+            let (limb, _op) = config.assign_one_line(
+                region,
+                offset,
+                operand,
+                opcode,
+                index,
+                operand,
+                Fr::zero(),
+                enable,
+            )?;
+            r.push(limb);
+
+            // Fr
+            let (limb, _op) = config.assign_merged_operands(
+                region,
+                offset,
+                vec![&group[1], &group[2], &group[3], &group[4]],
+                Fr::from_u128(1u128 << 64),
+                enable,
+            )?;
+            r.push(limb);
+
+            // G2 and sum
+            let start = 1 + BN256FR_SIZE;
+            for point in group[start..].chunks_exact(BN256G2_SIZE) {
+                assign_bn256_point(region, offset, point, config, enable, &mut r)?;
+            }
+        }
+        Ok(r)
+    }
+
+    fn synthesize(
+        &mut self,
+        _offset: &mut usize,
+        arg_cells: &Vec<Limb<Fr>>,
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        self.range_chip.init_table(layouter)?;
+        self.load_bn256_sum_g2_circuit(&arg_cells, layouter)?;
+        Ok(())
+    }
+}
+
+impl HostOpSelector for Bn256MsmChip<Fr> {
+    type Config = Bn256ChipConfig;
+    fn configure(meta: &mut ConstraintSystem<Fr>, _shared_advices: &Vec<Column<Advice>>) -> Self::Config {
+        Bn256MsmChip::<Fr>::configure(meta)
+    }
+
+    fn construct(c: Self::Config) -> Self {
+        Bn256MsmChip::construct(c)
+    }
+
+    fn opcodes() -> Vec<Fr> {
+        vec![
+            Fr::from(ForeignInst::Bn254MsmNew as u64),
+            Fr::from(ForeignInst::Bn254MsmScalar as u64),
+            Fr::from(ForeignInst::Bn254MsmG1 as u64),
+            Fr::from(ForeignInst::Bn254MsmResult as u64),
+        ]
+    }
+
+    fn validate(
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+    ) -> Result<(), HostCallError> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        let pairs_end = BN256MSM_SIZE - BN256G1_SIZE;
+        for (call, group) in selected_entries.chunks_exact(BN256MSM_SIZE).enumerate() {
+            for pair in group[1..pairs_end].chunks_exact(BN256FR_SIZE + BN256G1_SIZE) {
+                check_bn256_g1(&pair[BN256FR_SIZE..])
+                    .map_err(|e| e.at("bn256msm", call, "g1"))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn assign(
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+        config: &HostOpConfig,
+    ) -> Result<Vec<Limb<Fr>>, Error> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        assert!(selected_entries.len() % BN256MSM_SIZE == 0);
+        let total_used_instructions = selected_entries.len() / BN256MSM_SIZE;
+
+        let pairs_end = BN256MSM_SIZE - BN256G1_SIZE;
+
+        assert_eq!(*offset, 0);
+        let mut r = vec![];
+
+        // zero scalars of identity points sum to the identity
+        let mut default_table = vec![];
+        default_table.push(ExternalHostCallEntry {
+            op: ForeignInst::Bn254MsmNew as usize,
+            value: 1u64,
+            is_ret: false,
+        });
+        for _ in 0..BN256_MSM_POINTS {
+            default_table.append(&mut bn256_fr_default(ForeignInst::Bn254MsmScalar));
+            default_table.append(&mut bn256_g1_default(ForeignInst::Bn254MsmG1));
+        }
+        default_table.append(&mut bn256_g1_default(ForeignInst::Bn254MsmResult));
+
+        let default_entries: Vec<((Fr, Fr), Fr)> = default_table
+            .into_iter()
+            .map(|x| ((Fr::from(x.value), Fr::from(x.op as u64)), Fr::zero()))
+            .collect::<Vec<((Fr, Fr), Fr)>>();
+
+        let groups = selected_entries
+            .chunks_exact(BN256MSM_SIZE)
+            .map(|group| (group, true))
+            .chain(
+                (0..TOTAL_CONSTRUCTIONS_MSM - total_used_instructions)
+                    .map(|_| (&default_entries[..], false)),
+            );
+
+        for (group, enable) in groups {
+            // whether new is zero or not
+            let ((operand, opcode), index) = group[0].clone();
+            let (limb, _op) = config.assign_one_line(
+                region,
+                offset,
+                operand,
+                opcode,
+                index,
+                operand,
+                Fr::zero(),
+                enable,
+            )?;
+            r.push(limb);
+
+            // Fr and G1 of the pairs
+            for pair in group[1..pairs_end].chunks_exact(BN256FR_SIZE + BN256G1_SIZE) {
+                let (limb, _op) = config.assign_merged_operands(
+                    region,
+                    offset,
+                    pair[0..BN256FR_SIZE].iter().collect(),
+                    Fr::from_u128(1u128 << 64),
+                    enable,
+                )?;
+                r.push(limb);
+                assign_bn256_point(region, offset, &pair[BN256FR_SIZE..], config, enable, &mut r)?;
+            }
+
+            // sum
+            assign_bn256_point(region, offset, &group[pairs_end..], config, enable, &mut r)?;
+        }
+        Ok(r)
+    }
+
+    fn synthesize(
+        &mut self,
+        _offset: &mut usize,
+        arg_cells: &Vec<Limb<Fr>>,
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        self.range_chip.init_table(layouter)?;
+        self.load_bn256_msm_circuit(&arg_cells, layouter)?;
+        Ok(())
+    }
+}
+
+impl HostOpSelector for Bn256PairCompressedChip<Fr> {
+    type Config = Bn256ChipConfig;
+    fn configure(meta: &mut ConstraintSystem<Fr>, _shared_advices: &Vec<Column<Advice>>) -> Self::Config {
+        Bn256PairCompressedChip::<Fr>::configure(meta)
+    }
+
+    fn construct(c: Self::Config) -> Self {
+        Bn256PairCompressedChip::construct(c)
+    }
+
+    fn opcodes() -> Vec<Fr> {
+        vec![
+            Fr::from(ForeignInst::Bn254PairCompressedG1 as u64),
+            Fr::from(ForeignInst::Bn254PairCompressedG2 as u64),
+            Fr::from(ForeignInst::Bn254PairCompressedGt as u64),
+        ]
+    }
+
+    fn validate(
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+    ) -> Result<(), HostCallError> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        for (call, group) in selected_entries.chunks_exact(BN256PAIRCOMPRESSED_SIZE).enumerate() {
+            check_bn256_g1(&group[0..BN256G1_SIZE])
+                .map_err(|e| e.at("bn256paircompressed", call, "g1"))?;
+            check_bn256_g2(&group[BN256G1_SIZE..BN256G1_SIZE + BN256G2_SIZE])
+                .map_err(|e| e.at("bn256paircompressed", call, "g2"))?;
+        }
+        Ok(())
+    }
+
+    fn assign(
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+        config: &HostOpConfig,
+    ) -> Result<Vec<Limb<Fr>>, Error> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        assert!(selected_entries.len() % BN256PAIRCOMPRESSED_SIZE == 0);
+        let total_used_instructions = selected_entries.len() / BN256PAIRCOMPRESSED_SIZE;
+
+        assert_eq!(*offset, 0);
+        let mut r = vec![];
+
+        let mut default_table = vec![];
+        default_table.append(&mut bn256_g1_generator(ForeignInst::Bn254PairCompressedG1));
+        default_table.append(&mut bn256_g2_generator(ForeignInst::Bn254PairCompressedG2));
+        default_table.append(&mut bn256_gt_compressed_pairing_generator(
+            ForeignInst::Bn254PairCompressedGt,
+        ));
+
+        let default_entries: Vec<((Fr, Fr), Fr)> = default_table
+            .into_iter()
+            .map(|x| ((Fr::from(x.value), Fr::from(x.op as u64)), Fr::zero()))
+            .collect::<Vec<((Fr, Fr), Fr)>>();
+
+        let groups = selected_entries
+            .chunks_exact(BN256PAIRCOMPRESSED_SIZE)
+            .map(|group| (group, true))
+            .chain(
+                (0..TOTAL_CONSTRUCTIONS_PAIR_COMPRESSED - total_used_instructions)
+                    .map(|_| (&default_entries[..], false)),
+            );
+
+        for (group, enable) in groups {
+            let g2_start = BN256G1_SIZE;
+            let gt_start = BN256G1_SIZE + BN256G2_SIZE;
+            assign_bn256_point(region, offset, &group[0..g2_start], config, enable, &mut r)?;
+            assign_bn256_point(region, offset, &group[g2_start..gt_start], config, enable, &mut r)?;
+            // the compressed gt is six fq followed by the flag of one
+            assign_bn256_point(region, offset, &group[gt_start..], config, enable, &mut r)?;
+        }
+        Ok(r)
+    }
+
+    fn synthesize(
+        &mut self,
+        _offset: &mut usize,
+        arg_cells: &Vec<Limb<Fr>>,
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        self.range_chip.init_table(layouter)?;
+        let a = arg_cells[0..7].to_vec();
+        let b = arg_cells[7..20].to_vec();
+        let ab = arg_cells[20..39].to_vec();
+        self.load_bn256_pair_compressed_circuit(&a, &b, &ab, layouter)?;
+        Ok(())
+    }
+}
+
+impl HostOpSelector for Bn256GtChip<Fr> {
+    type Config = Bn256ChipConfig;
+    fn configure(meta: &mut ConstraintSystem<Fr>, _shared_advices: &Vec<Column<Advice>>) -> Self::Config {
+        Bn256GtChip::<Fr>::configure(meta)
+    }
+
+    fn construct(c: Self::Config) -> Self {
+        Bn256GtChip::construct(c)
+    }
+
+    fn opcodes() -> Vec<Fr> {
+        vec![
+            Fr::from(ForeignInst::Bn254GtOp as u64),
+            Fr::from(ForeignInst::Bn254GtA as u64),
+            Fr::from(ForeignInst::Bn254GtB as u64),
+            Fr::from(ForeignInst::Bn254GtResult as u64),
+        ]
+    }
+
+    fn validate(
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+    ) -> Result<(), HostCallError> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        for (call, group) in selected_entries.chunks_exact(BN256GTOP_SIZE).enumerate() {
+            check_bn256_gt_op(group, call)?;
+        }
+        Ok(())
+    }
+
+    fn assign(
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+        config: &HostOpConfig,
+    ) -> Result<Vec<Limb<Fr>>, Error> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        assert!(selected_entries.len() % BN256GTOP_SIZE == 0);
+        let total_used_instructions = selected_entries.len() / BN256GTOP_SIZE;
+
+        assert_eq!(*offset, 0);
+        let mut r = vec![];
+
+        // 0 * 0 == 0
+        let mut default_table = vec![ExternalHostCallEntry {
+            op: ForeignInst::Bn254GtOp as usize,
+            value: GT_OP_MUL,
+            is_ret: false,
+        }];
+        for op in [ForeignInst::Bn254GtA, ForeignInst::Bn254GtB, ForeignInst::Bn254GtResult] {
+            for _ in 0..BN256GT_SIZE {
+                default_table.push(ExternalHostCallEntry {
+                    op: op as usize,
+                    value: 0u64,
+                    is_ret: false,
+                });
+            }
+        }
+
+        let default_entries: Vec<((Fr, Fr), Fr)> = default_table
+            .into_iter()
+            .map(|x| ((Fr::from(x.value), Fr::from(x.op as u64)), Fr::zero()))
+            .collect::<Vec<((Fr, Fr), Fr)>>();
+
+        let groups = selected_entries
+            .chunks_exact(BN256GTOP_SIZE)
+            .map(|group| (group, true))
+            .chain(
+                (0..TOTAL_CONSTRUCTIONS_GT - total_used_instructions)
+                    .map(|_| (&default_entries[..], false)),
+            );
+
+        for (group, enable) in groups {
+            let ((operand, opcode), index) = group[0].clone();
+            let (limb, _op) = config.assign_one_line(
+                region,
+                offset,
+                operand,
+                opcode,
+                index,
+                operand,
+                Fr::zero(),
+                enable,
+            )?;
+            r.push(limb);
+
+            // a, b and the result
+            assign_bn256_fqs(region, offset, &group[1..], config, enable, &mut r)?;
+        }
+        Ok(r)
+    }
+
+    fn synthesize(
+        &mut self,
+        _offset: &mut usize,
+        arg_cells: &Vec<Limb<Fr>>,
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        self.range_chip.init_table(layouter)?;
+        self.load_bn256_gt_circuit(&arg_cells, layouter)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,203 @@
+use crate::host::ExternalHostCallEntry;
+use ark_std::{end_timer, start_timer};
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::{Layouter, Region};
+use halo2_proofs::pairing::bn256::Fr;
+use halo2_proofs::plonk::ConstraintSystem;
+use halo2_proofs::plonk::{Error, Column, Advice};
+use crate::circuits::babyjub::Point as CircuitPoint;
+use crate::circuits::eddsa::AltJubEddsaChip;
+use crate::circuits::poseidon::PoseidonGateConfig;
+use crate::circuits::CommonGateConfig;
+use crate::host::ForeignInst::{
+    JubjubEddsaVerifyMsg, JubjubEddsaVerifyPk, JubjubEddsaVerifyResult, JubjubEddsaVerifySig,
+};
+use crate::circuits::host::{HostOpConfig, HostOpSelector};
+use crate::adaptor::get_selected_entries;
+use crate::host::jubjub::{eddsa_sign, eddsa_verify, Point, BASE};
+use crate::host::poseidon::POSEIDON_HASHER_SPEC;
+use crate::utils::{bn_to_field, Limb};
+use num_bigint::BigUint;
+
+const MERGE_SIZE: usize = 4;
+// msg, R.x, R.y, s, A.x, A.y and the result
+const CHUNK_SIZE: usize = 6 * MERGE_SIZE + 1;
+
+const TOTAL_CONSTRUCTIONS: usize = 200;
+
+fn eddsa_to_host_call_table(inputs: &Vec<(Point, Point, BigUint, Fr)>) -> Vec<ExternalHostCallEntry> {
+    let mut r = vec![];
+    for (pk, sig_r, sig_s, msg) in inputs.into_iter() {
+        r.push(crate::adaptor::fr_to_args(*msg, 4, 64, JubjubEddsaVerifyMsg));
+        r.push(crate::adaptor::fr_to_args(sig_r.x, 4, 64, JubjubEddsaVerifySig));
+        r.push(crate::adaptor::fr_to_args(sig_r.y, 4, 64, JubjubEddsaVerifySig));
+        r.push(crate::adaptor::fr_to_args(
+            bn_to_field::<Fr>(sig_s),
+            4,
+            64,
+            JubjubEddsaVerifySig,
+        ));
+        r.push(crate::adaptor::fr_to_args(pk.x, 4, 64, JubjubEddsaVerifyPk));
+        r.push(crate::adaptor::fr_to_args(pk.y, 4, 64, JubjubEddsaVerifyPk));
+        r.push(vec![ExternalHostCallEntry {
+            op: JubjubEddsaVerifyResult as usize,
+            value: eddsa_verify(pk, sig_r, sig_s, msg) as u64,
+            is_ret: true,
+        }]);
+    }
+    r.into_iter().flatten().collect::<Vec<_>>()
+}
+
+/// The signature of the zero message under secret key 1 and nonce 1
+fn default_signature() -> (Point, Point, BigUint, Fr) {
+    let one = BigUint::from(1u64);
+    let (sig_r, sig_s) = eddsa_sign(&one, &Fr::zero(), &one);
+    (BASE.clone(), sig_r, sig_s, Fr::zero())
+}
+
+impl HostOpSelector for AltJubEddsaChip<Fr> {
+    type Config = (CommonGateConfig, PoseidonGateConfig);
+    fn configure(meta: &mut ConstraintSystem<Fr>, shared_advices: &Vec<Column<Advice>>) -> Self::Config {
+        AltJubEddsaChip::<Fr>::configure(meta, shared_advices)
+    }
+
+    fn construct(c: Self::Config) -> Self {
+        AltJubEddsaChip::construct(c.0, c.1, POSEIDON_HASHER_SPEC.clone())
+    }
+
+    fn opcodes() -> Vec<Fr> {
+        vec![
+            Fr::from(JubjubEddsaVerifyMsg as u64),
+            Fr::from(JubjubEddsaVerifySig as u64),
+            Fr::from(JubjubEddsaVerifyPk as u64),
+            Fr::from(JubjubEddsaVerifyResult as u64),
+        ]
+    }
+
+    fn assign(
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+        config: &HostOpConfig,
+    ) -> Result<Vec<Limb<Fr>>, Error> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        assert!(selected_entries.len() % CHUNK_SIZE == 0);
+        let total_used_instructions = selected_entries.len() / CHUNK_SIZE;
+
+        let mut r = vec![];
+
+        let default_table = eddsa_to_host_call_table(&vec![default_signature()]);
+        let default_entries: Vec<((Fr, Fr), Fr)> = default_table
+            .into_iter()
+            .map(|x| ((Fr::from(x.value), Fr::from(x.op as u64)), Fr::zero()))
+            .collect::<Vec<((Fr, Fr), Fr)>>();
+
+        let groups = selected_entries
+            .chunks_exact(CHUNK_SIZE)
+            .map(|group| (group, true))
+            .chain(
+                (0..TOTAL_CONSTRUCTIONS - total_used_instructions)
+                    .map(|_| (&default_entries[..], false)),
+            );
+
+        for (group, enable) in groups {
+            for subgroup in group[0..CHUNK_SIZE - 1].chunks_exact(MERGE_SIZE) {
+                let (limb, _) = config.assign_merged_operands(
+                    region,
+                    offset,
+                    subgroup.iter().collect::<Vec<_>>(),
+                    Fr::from_u128(1u128 << 64),
+                    enable,
+                )?;
+                r.push(limb);
+            }
+
+            let ((operand, opcode), index) = group[CHUNK_SIZE - 1];
+            assert!(opcode == Fr::from(JubjubEddsaVerifyResult as u64));
+            let (limb, _) = config.assign_one_line(
+                region,
+                offset,
+                operand,
+                opcode,
+                index,
+                operand,
+                Fr::zero(),
+                enable,
+            )?;
+            r.push(limb);
+        }
+
+        Ok(r)
+    }
+
+    fn synthesize(
+        &mut self,
+        offset: &mut usize,
+        arg_cells: &Vec<Limb<Fr>>,
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        println!("eddsa adaptor total args is {}", arg_cells.len());
+        *offset = layouter.assign_region(
+            || "jubjub eddsa region",
+            |mut region| {
+                let mut local_offset = *offset;
+                let timer = start_timer!(|| "assign");
+                let config = self.jub.config.clone();
+                self.initialize(&config, &mut region, &mut local_offset)?;
+                // arg_cells format msg + R + s + A + result
+                for arg_group in arg_cells.chunks_exact(7).into_iter() {
+                    let args = arg_group.into_iter().map(|x| x.clone());
+                    let args = args.collect::<Vec<_>>();
+                    self.assign_eddsa_verify(
+                        &mut region,
+                        &mut local_offset,
+                        &args[0],
+                        &CircuitPoint {
+                            x: args[1].clone(),
+                            y: args[2].clone(),
+                        },
+                        &args[3],
+                        &CircuitPoint {
+                            x: args[4].clone(),
+                            y: args[5].clone(),
+                        },
+                        &args[6],
+                    )?;
+                }
+                end_timer!(timer);
+                Ok(local_offset)
+            },
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::eddsa_to_host_call_table;
+    use crate::host::jubjub::{eddsa_sign, BASE};
+    use crate::host::ExternalHostCallEntryTable;
+    use halo2_proofs::pairing::bn256::Fr;
+    use num_bigint::BigUint;
+    use std::fs::File;
+
+    #[test]
+    fn generate_jubjub_eddsa_input() {
+        let mut inputs = vec![];
+        for i in 0..4u64 {
+            let sk = BigUint::from(0x1234567890abcdefu64 + i);
+            let k = BigUint::from(0xfedcba0987654321u64 + i);
+            let msg = Fr::from(i);
+            let (sig_r, sig_s) = eddsa_sign(&sk, &msg, &k);
+            // corrupt every other message so that both results are covered
+            let msg = if i % 2 == 0 { msg } else { msg + Fr::one() };
+            inputs.push((BASE.mul_scalar(&sk), sig_r, sig_s, msg));
+        }
+        let table = eddsa_to_host_call_table(&inputs);
+        let file = File::create("jubjub_eddsa.json").expect("can not create file");
+        serde_json::to_writer_pretty(file, &ExternalHostCallEntryTable(table))
+            .expect("can not write to file");
+    }
+}
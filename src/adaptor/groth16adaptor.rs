@@ -0,0 +1,312 @@
+use crate::adaptor::bn256adaptor::{assign_bn256_point, check_bn256_g1, check_bn256_g2};
+use crate::adaptor::get_selected_entries;
+use crate::adaptor::validate::{CallError, HostCallError};
+use crate::circuits::groth16::{Groth16VerifyChip, Groth16VerifyConfig, GROTH16_LIMBS, VK_LIMBS};
+use crate::circuits::host::{HostOpConfig, HostOpSelector};
+use crate::host::groth16::{
+    groth16_verify, point_operands_to_limbs, vk_limbs_hash, vk_point_words, Groth16Proof,
+    Groth16VerifyingKey, G1_WORDS, G2_WORDS, GROTH16_PUBLIC_INPUTS, PROOF_WORDS, VK_WORDS,
+};
+use crate::host::poseidon::POSEIDON_HASHER_SPEC;
+use crate::host::ExternalHostCallEntry;
+use crate::host::ForeignInst;
+use crate::host::ForeignInst::{
+    Groth16VerifyInput, Groth16VerifyProof, Groth16VerifyResult, Groth16VerifyVk,
+    Groth16VerifyVkHash,
+};
+use crate::utils::Limb;
+use ark_std::{end_timer, start_timer};
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::{Layouter, Region};
+use halo2_proofs::pairing::bn256::{Fr, G1, G2};
+use halo2_proofs::pairing::group::{Curve, Group};
+use halo2_proofs::plonk::ConstraintSystem;
+use halo2_proofs::plonk::{Advice, Column, Error};
+
+const MERGE_SIZE: usize = 4;
+// hash, key, inputs, proof and the result
+const CHUNK_SIZE: usize =
+    MERGE_SIZE + VK_WORDS + GROTH16_PUBLIC_INPUTS * MERGE_SIZE + PROOF_WORDS + 1;
+
+const TOTAL_CONSTRUCTIONS: usize = 1;
+
+fn words_to_entries(words: Vec<u64>, op: ForeignInst) -> Vec<ExternalHostCallEntry> {
+    words
+        .into_iter()
+        .map(|value| ExternalHostCallEntry {
+            op: op as usize,
+            value,
+            is_ret: false,
+        })
+        .collect()
+}
+
+fn groth16_to_host_call_table(
+    inputs: &Vec<(Groth16VerifyingKey, Vec<Fr>, Groth16Proof)>,
+) -> Vec<ExternalHostCallEntry> {
+    let mut r = vec![];
+    for (vk, xs, proof) in inputs.into_iter() {
+        r.push(crate::adaptor::fr_to_args(vk.hash(), 4, 64, Groth16VerifyVkHash));
+        r.push(words_to_entries(vk.to_words(), Groth16VerifyVk));
+        let mut padded = xs.clone();
+        padded.resize(GROTH16_PUBLIC_INPUTS, Fr::zero());
+        for x in padded.into_iter() {
+            r.push(crate::adaptor::fr_to_args(x, 4, 64, Groth16VerifyInput));
+        }
+        r.push(words_to_entries(proof.to_words(), Groth16VerifyProof));
+        r.push(vec![ExternalHostCallEntry {
+            op: Groth16VerifyResult as usize,
+            value: groth16_verify(vk, xs, proof) as u64,
+            is_ret: true,
+        }]);
+    }
+    r.into_iter().flatten().collect::<Vec<_>>()
+}
+
+/// Whether the pushed hash of a call is the hash of its pushed key, which is what the
+/// circuit constrains. Operands are merged as the circuit does without reducing them to
+/// words, so a word out of range is not hidden.
+fn check_vk_hash(group: &[((Fr, Fr), Fr)]) -> bool {
+    let hash = group[0..MERGE_SIZE]
+        .iter()
+        .rev()
+        .fold(Fr::zero(), |acc, ((operand, _), _)| {
+            acc * Fr::from_u128(1u128 << 64) + operand
+        });
+    let mut limbs = vec![];
+    let mut start = MERGE_SIZE;
+    for size in vk_point_words() {
+        let words = group[start..start + size]
+            .iter()
+            .map(|((operand, _), _)| *operand)
+            .collect::<Vec<_>>();
+        limbs.append(&mut point_operands_to_limbs(&words));
+        start += size;
+    }
+    vk_limbs_hash(&limbs) == hash
+}
+
+/// The key and the proof of identity points, which verifies no inputs
+fn default_instance() -> (Groth16VerifyingKey, Vec<Fr>, Groth16Proof) {
+    let g1 = G1::identity().to_affine();
+    let g2 = G2::identity().to_affine();
+    let vk = Groth16VerifyingKey {
+        alpha: g1,
+        beta: g2,
+        gamma: g2,
+        delta: g2,
+        ic: vec![g1],
+    };
+    let proof = Groth16Proof { a: g1, b: g2, c: g1 };
+    (vk, vec![], proof)
+}
+
+impl HostOpSelector for Groth16VerifyChip<Fr> {
+    type Config = Groth16VerifyConfig;
+    fn configure(meta: &mut ConstraintSystem<Fr>, shared_advices: &Vec<Column<Advice>>) -> Self::Config {
+        Groth16VerifyChip::<Fr>::configure(meta, shared_advices)
+    }
+
+    fn construct(c: Self::Config) -> Self {
+        Groth16VerifyChip::construct(c, POSEIDON_HASHER_SPEC.clone())
+    }
+
+    fn opcodes() -> Vec<Fr> {
+        vec![
+            Fr::from(Groth16VerifyVkHash as u64),
+            Fr::from(Groth16VerifyVk as u64),
+            Fr::from(Groth16VerifyInput as u64),
+            Fr::from(Groth16VerifyProof as u64),
+            Fr::from(Groth16VerifyResult as u64),
+        ]
+    }
+
+    fn validate(
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+    ) -> Result<(), HostCallError> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        if selected_entries.len() / CHUNK_SIZE > TOTAL_CONSTRUCTIONS {
+            return Err(CallError::TooManyCalls
+                .at("groth16verify", TOTAL_CONSTRUCTIONS, "call")
+                .into());
+        }
+        let vk_sizes = vk_point_words();
+        for (call, group) in selected_entries.chunks_exact(CHUNK_SIZE).enumerate() {
+            if !check_vk_hash(group) {
+                return Err(CallError::HashMismatch.at("groth16verify", call, "vk hash").into());
+            }
+            let mut start = MERGE_SIZE;
+            for size in vk_sizes.iter() {
+                let point = &group[start..start + size];
+                if *size == G1_WORDS {
+                    check_bn256_g1(point).map_err(|e| e.at("groth16verify", call, "vk g1"))?;
+                } else {
+                    check_bn256_g2(point).map_err(|e| e.at("groth16verify", call, "vk g2"))?;
+                }
+                start += size;
+            }
+            start += GROTH16_PUBLIC_INPUTS * MERGE_SIZE;
+            check_bn256_g1(&group[start..start + G1_WORDS])
+                .map_err(|e| e.at("groth16verify", call, "proof a"))?;
+            start += G1_WORDS;
+            check_bn256_g2(&group[start..start + G2_WORDS])
+                .map_err(|e| e.at("groth16verify", call, "proof b"))?;
+            start += G2_WORDS;
+            check_bn256_g1(&group[start..start + G1_WORDS])
+                .map_err(|e| e.at("groth16verify", call, "proof c"))?;
+        }
+        Ok(())
+    }
+
+    fn assign(
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+        config: &HostOpConfig,
+    ) -> Result<Vec<Limb<Fr>>, Error> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        assert!(selected_entries.len() % CHUNK_SIZE == 0);
+        let total_used_instructions = selected_entries.len() / CHUNK_SIZE;
+        assert!(total_used_instructions <= TOTAL_CONSTRUCTIONS);
+
+        let mut r = vec![];
+
+        let default_table = groth16_to_host_call_table(&vec![default_instance()]);
+        let default_entries: Vec<((Fr, Fr), Fr)> = default_table
+            .into_iter()
+            .map(|x| ((Fr::from(x.value), Fr::from(x.op as u64)), Fr::zero()))
+            .collect::<Vec<((Fr, Fr), Fr)>>();
+
+        let groups = selected_entries
+            .chunks_exact(CHUNK_SIZE)
+            .map(|group| (group, true))
+            .chain(
+                (0..TOTAL_CONSTRUCTIONS - total_used_instructions)
+                    .map(|_| (&default_entries[..], false)),
+            );
+
+        let vk_sizes = vk_point_words();
+
+        for (group, enable) in groups {
+            let (limb, _) = config.assign_merged_operands(
+                region,
+                offset,
+                group[0..MERGE_SIZE].iter().collect::<Vec<_>>(),
+                Fr::from_u128(1u128 << 64),
+                enable,
+            )?;
+            r.push(limb);
+
+            let mut start = MERGE_SIZE;
+            for size in vk_sizes.iter() {
+                assign_bn256_point(region, offset, &group[start..start + size], config, enable, &mut r)?;
+                start += size;
+            }
+
+            for subgroup in group[start..start + GROTH16_PUBLIC_INPUTS * MERGE_SIZE].chunks_exact(MERGE_SIZE) {
+                let (limb, _) = config.assign_merged_operands(
+                    region,
+                    offset,
+                    subgroup.iter().collect::<Vec<_>>(),
+                    Fr::from_u128(1u128 << 64),
+                    enable,
+                )?;
+                r.push(limb);
+            }
+            start += GROTH16_PUBLIC_INPUTS * MERGE_SIZE;
+
+            // A, B and C
+            for size in [G1_WORDS, G2_WORDS, G1_WORDS] {
+                assign_bn256_point(region, offset, &group[start..start + size], config, enable, &mut r)?;
+                start += size;
+            }
+
+            let ((operand, opcode), index) = group[start].clone();
+            let (limb, _) = config.assign_one_line(
+                region,
+                offset,
+                operand,
+                opcode,
+                index,
+                operand,
+                Fr::zero(),
+                enable,
+            )?;
+            r.push(limb);
+        }
+
+        Ok(r)
+    }
+
+    fn synthesize(
+        &mut self,
+        offset: &mut usize,
+        arg_cells: &Vec<Limb<Fr>>,
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        println!("groth16 adaptor total args is {}", arg_cells.len());
+        *offset = layouter.assign_region(
+            || "groth16 vk hash region",
+            |mut region| {
+                let mut local_offset = *offset;
+                let timer = start_timer!(|| "assign");
+                let config = self.poseidon.config.clone();
+                self.initialize(&config, &mut region, &mut local_offset)?;
+                // arg_cells format hash + vk + inputs + proof + result
+                for args in arg_cells.chunks_exact(GROTH16_LIMBS) {
+                    self.assign_vk_hash(&mut region, &mut local_offset, &args[1..1 + VK_LIMBS], &args[0])?;
+                }
+                end_timer!(timer);
+                Ok(local_offset)
+            },
+        )?;
+        self.pair_check.range_chip.init_table(layouter)?;
+        self.load_groth16_verify_circuit(arg_cells, layouter)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::groth16_to_host_call_table;
+    use crate::adaptor::validate::CallError;
+    use crate::circuits::groth16::Groth16VerifyChip;
+    use crate::host::groth16::tests::random_instance;
+    use crate::host::ExternalHostCallEntryTable;
+    use crate::proof::build_host_circuit;
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pairing::bn256::Fr;
+    use std::fs::File;
+
+    #[test]
+    fn generate_groth16_verify_input() {
+        let table = groth16_to_host_call_table(&vec![random_instance(3)]);
+        let file = File::create("groth16verify.json").expect("can not create file");
+        serde_json::to_writer_pretty(file, &ExternalHostCallEntryTable(table))
+            .expect("can not write to file");
+    }
+
+    #[test]
+    fn test_groth16_verify_circuit() {
+        // a key of 3 inputs is padded with identity points in IC
+        let table = groth16_to_host_call_table(&vec![random_instance(3)]);
+        let circuit =
+            build_host_circuit::<Groth16VerifyChip<Fr>>(&ExternalHostCallEntryTable(table)).unwrap();
+        let prover = MockProver::run(22, &circuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn test_groth16_verify_vk_hash_mismatch() {
+        let mut table = groth16_to_host_call_table(&vec![random_instance(3)]);
+        table[0].value += 1;
+        assert_eq!(
+            build_host_circuit::<Groth16VerifyChip<Fr>>(&ExternalHostCallEntryTable(table)).err(),
+            Some(CallError::HashMismatch.at("groth16verify", 0, "vk hash").into())
+        );
+    }
+}
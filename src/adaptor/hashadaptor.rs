@@ -1,11 +1,17 @@
 use crate::circuits::poseidon::PoseidonChip;
 use crate::circuits::poseidon::PoseidonGateConfig;
+use crate::circuits::poseidon::PoseidonSpongeChip;
+use crate::circuits::poseidon::SpongeCall;
 use crate::circuits::CommonGateConfig;
 use crate::circuits::LookupAssistChip;
 use crate::circuits::LookupAssistConfig;
 use crate::host::poseidon::POSEIDON_HASHER;
 use crate::host::poseidon::POSEIDON_HASHER_SPEC;
-use crate::host::ForeignInst::{PoseidonFinalize, PoseidonNew, PoseidonPush};
+use crate::host::poseidon::{circom_poseidon, PoseidonFamily, CIRCOM_HASHER_SPEC};
+use crate::host::ForeignInst::{
+    PoseidonCircomFinalize, PoseidonCircomNew, PoseidonCircomPush, PoseidonFinalize, PoseidonNew,
+    PoseidonPush, PoseidonSpongeFinalize, PoseidonSpongeNew, PoseidonSpongePush,
+};
 use crate::host::{ExternalHostCallEntry, ExternalHostCallEntryTable, ForeignInst};
 use ark_std::{end_timer, start_timer};
 use halo2_proofs::arithmetic::FieldExt;
@@ -41,25 +47,318 @@ impl<F: FieldExt> LookupAssistChip<F> for () {
     }
 }
 
-fn hash_cont(restart: bool) -> Vec<ExternalHostCallEntry> {
+fn hash_cont(restart: bool, op: ForeignInst) -> Vec<ExternalHostCallEntry> {
     vec![ExternalHostCallEntry {
-        op: PoseidonNew as usize,
+        op: op as usize,
         value: if restart { 1u64 } else { 0u64 },
         is_ret: false,
     }]
 }
 
-fn hash_to_host_call_table(inputs: [Fr; 8], result: Fr) -> ExternalHostCallEntryTable {
-    let mut r = vec![];
-    r.push(hash_cont(true));
+const RATE: usize = 8;
+
+// the new call, RATE elements and the result of one absorb round
+const SPONGE_ROUND_CALLS: usize = RATE + 2;
+
+const TOTAL_CONSTRUCTIONS: usize = 2048;
+
+const CIRCOM_TOTAL_CONSTRUCTIONS: usize = 512;
+
+// the restart flag, the absorbed elements and the result of one absorb round
+const fn chunk_size(rate: usize) -> usize {
+    1 + rate * 4 + 4
+}
+
+/// new(restart), a push of 4 limbs for every input and the 4 limbs of the result
+fn calls_to_host_call_table(
+    restart: bool,
+    inputs: &[Fr],
+    result: Fr,
+    opcodes: [ForeignInst; 3],
+) -> Vec<ExternalHostCallEntry> {
+    let mut r = vec![hash_cont(restart, opcodes[0])];
     for f in inputs.iter() {
-        r.push(crate::adaptor::fr_to_args(*f, 4, 64, PoseidonPush));
+        r.push(crate::adaptor::fr_to_args(*f, 4, 64, opcodes[1]));
+    }
+    r.push(crate::adaptor::fr_to_args(result, 4, 64, opcodes[2]));
+    r.into_iter().flatten().collect()
+}
+
+/// Emit one new/push/finalize group per absorb round of exactly RATE elements, the
+/// sponge restarts on the first round and the guest pads the message itself
+pub fn hash_to_host_call_table(inputs: Vec<[Fr; RATE]>) -> ExternalHostCallEntryTable {
+    let mut r = vec![];
+    let mut start = true;
+    let mut hasher = POSEIDON_HASHER.clone();
+    for round in inputs.iter() {
+        let result = hasher.update_exact(round);
+        r.push(calls_to_host_call_table(
+            start,
+            round,
+            result,
+            [PoseidonNew, PoseidonPush, PoseidonFinalize],
+        ));
+        start = false;
     }
-    r.push(crate::adaptor::fr_to_args(result, 4, 64, PoseidonFinalize));
     ExternalHostCallEntryTable(r.into_iter().flatten().collect())
 }
 
-const TOTAL_CONSTRUCTIONS: usize = 2048;
+/// Emit one restarting sponge new call, a sponge push for every element of the message
+/// and the squeezed hash of the message. The padding of the sponge is done by the circuit.
+pub fn sponge_hash_to_host_call_table(inputs: Vec<Vec<Fr>>) -> ExternalHostCallEntryTable {
+    let mut r = vec![];
+    for message in inputs.iter() {
+        let mut hasher = POSEIDON_HASHER.clone();
+        hasher.update(message);
+        let result = hasher.squeeze();
+        r.push(calls_to_host_call_table(
+            true,
+            message,
+            result,
+            [PoseidonSpongeNew, PoseidonSpongePush, PoseidonSpongeFinalize],
+        ));
+    }
+    ExternalHostCallEntryTable(r.into_iter().flatten().collect())
+}
+
+/// Emit one new/push/finalize group per circomlib hash of two elements
+pub fn circom_hash_to_host_call_table(inputs: Vec<[Fr; 2]>) -> ExternalHostCallEntryTable {
+    let mut r = vec![];
+    for round in inputs.iter() {
+        let result = circom_poseidon(&CIRCOM_HASHER_SPEC, round);
+        r.push(calls_to_host_call_table(
+            true,
+            round,
+            result,
+            [PoseidonCircomNew, PoseidonCircomPush, PoseidonCircomFinalize],
+        ));
+    }
+    ExternalHostCallEntryTable(r.into_iter().flatten().collect())
+}
+
+/// Assign the selected rounds of a poseidon family followed by disabled default rounds
+fn assign_rounds(
+    region: &mut Region<Fr>,
+    offset: &mut usize,
+    selected_entries: Vec<((Fr, Fr), Fr)>,
+    default_table: ExternalHostCallEntryTable,
+    rate: usize,
+    total_constructions: usize,
+    config: &HostOpConfig,
+) -> Result<Vec<Limb<Fr>>, Error> {
+    let chunk_size = chunk_size(rate);
+    assert!(selected_entries.len() % chunk_size == 0);
+    let total_used_instructions = selected_entries.len() / chunk_size;
+
+    let mut r = vec![];
+
+    let default_entries: Vec<((Fr, Fr), Fr)> = default_table
+        .0
+        .into_iter()
+        .map(|x| ((Fr::from(x.value), Fr::from(x.op as u64)), Fr::zero()))
+        .collect::<Vec<((Fr, Fr), Fr)>>();
+    let new_op = default_entries[0].0 .1;
+    let finalize_op = default_entries[chunk_size - 1].0 .1;
+
+    let groups = selected_entries
+        .chunks_exact(chunk_size)
+        .map(|group| (group, true))
+        .chain(
+            (0..total_constructions - total_used_instructions)
+                .map(|_| (&default_entries[..], false)),
+        );
+
+    for (group, enable) in groups {
+        let ((operand, opcode), index) = group[0];
+        assert!(opcode == new_op);
+        assert!(group[chunk_size - 1].0 .1 == finalize_op);
+
+        let (limb, _op) = config.assign_one_line(
+            region,
+            offset,
+            operand,
+            opcode,
+            index,
+            operand,
+            Fr::zero(),
+            enable,
+        )?;
+        r.push(limb);
+
+        for subgroup in group[1..].chunks_exact(4) {
+            let (limb, _op) = config.assign_merged_operands(
+                region,
+                offset,
+                subgroup.iter().collect::<Vec<_>>(),
+                Fr::from_u128(1u128 << 64),
+                enable,
+            )?;
+            r.push(limb);
+        }
+    }
+
+    Ok(r)
+}
+
+type Entry = ((Fr, Fr), Fr);
+
+/// entries of a call group missing from the table, disabled with the given operands
+fn hole(operands: &[Fr], opcode: Fr, index: Fr) -> Vec<Entry> {
+    operands
+        .iter()
+        .map(|operand| ((*operand, opcode), index))
+        .collect()
+}
+
+/// Split the selected entries into messages of a new call, any number of pushed
+/// elements and a result, and lay out every message in absorb rounds of RATE
+/// elements. The missing calls of a round (the new call after the first round, the
+/// padding elements and the result before the last round) are disabled holes that
+/// keep the index of the entry before them, see HostOpSelector::holes.
+fn sponge_round_groups(
+    selected_entries: &Vec<Entry>,
+    opcodes: [Fr; 3],
+) -> Vec<Vec<(Vec<Entry>, bool)>> {
+    let [new_op, push_op, finalize_op] = opcodes;
+    let mut rounds = vec![];
+    let mut i = 0;
+    while i < selected_entries.len() {
+        let new = selected_entries[i];
+        assert!(new.0 .1 == new_op);
+        i += 1;
+        let start = i;
+        while i < selected_entries.len() && selected_entries[i].0 .1 == push_op {
+            i += 1;
+        }
+        assert!((i - start) % 4 == 0);
+        let elements = selected_entries[start..i].chunks_exact(4).collect::<Vec<_>>();
+        let result = &selected_entries[i..i + 4];
+        assert!(result.iter().all(|x| x.0 .1 == finalize_op));
+        i += 4;
+
+        let mut index = new.1;
+        let total_rounds = elements.len() / RATE + 1;
+        for k in 0..total_rounds {
+            let mut groups = vec![];
+            if k == 0 {
+                groups.push((vec![new], true));
+            } else {
+                groups.push((hole(&[Fr::zero()], new_op, index), false));
+            }
+            for j in 0..RATE {
+                let e = k * RATE + j;
+                if e < elements.len() {
+                    groups.push((elements[e].to_vec(), true));
+                    index = elements[e][3].1;
+                } else {
+                    let pad = if e == elements.len() {
+                        Fr::one()
+                    } else {
+                        Fr::zero()
+                    };
+                    let operands = [pad, Fr::zero(), Fr::zero(), Fr::zero()];
+                    groups.push((hole(&operands, push_op, index), false));
+                }
+            }
+            if k == total_rounds - 1 {
+                groups.push((result.to_vec(), true));
+            } else {
+                groups.push((hole(&[Fr::zero(); 4], finalize_op, index), false));
+            }
+            rounds.push(groups);
+        }
+    }
+    rounds
+}
+
+/// Assign the sponge rounds of the selected entries followed by rounds of holes,
+/// every call group is passed to the chip as its operand, opcode and enable cells
+fn assign_sponge_rounds(
+    region: &mut Region<Fr>,
+    offset: &mut usize,
+    selected_entries: Vec<Entry>,
+    opcodes: [Fr; 3],
+    total_constructions: usize,
+    config: &HostOpConfig,
+) -> Result<Vec<Limb<Fr>>, Error> {
+    let [new_op, push_op, finalize_op] = opcodes;
+    let rounds = sponge_round_groups(&selected_entries, opcodes);
+    assert!(
+        rounds.len() <= total_constructions,
+        "poseidon: {} absorb rounds exceed the {} rounds of the circuit",
+        rounds.len(),
+        total_constructions
+    );
+
+    let mut default_round = vec![(hole(&[Fr::zero()], new_op, Fr::zero()), false)];
+    for _ in 0..RATE {
+        default_round.push((hole(&[Fr::zero(); 4], push_op, Fr::zero()), false));
+    }
+    default_round.push((hole(&[Fr::zero(); 4], finalize_op, Fr::zero()), false));
+    let default_rounds = vec![default_round; total_constructions - rounds.len()];
+
+    let mut r = vec![];
+    for groups in rounds.iter().chain(default_rounds.iter()) {
+        for (group, enable) in groups.iter() {
+            let (limb, op, en) = if group.len() == 1 {
+                let ((operand, opcode), index) = group[0];
+                config.assign_one_line_with_enable(
+                    region,
+                    offset,
+                    operand,
+                    opcode,
+                    index,
+                    operand,
+                    Fr::zero(),
+                    *enable,
+                )?
+            } else {
+                config.assign_merged_operands_with_enable(
+                    region,
+                    offset,
+                    group.iter().collect::<Vec<_>>(),
+                    Fr::from_u128(1u128 << 64),
+                    *enable,
+                )?
+            };
+            r.append(&mut vec![limb, op, en]);
+        }
+    }
+    Ok(r)
+}
+
+fn synthesize_rounds<const T: usize, const R: usize>(
+    chip: &mut PoseidonChip<Fr, T, R>,
+    offset: &mut usize,
+    arg_cells: &Vec<Limb<Fr>>,
+    layouter: &mut impl Layouter<Fr>,
+) -> Result<(), Error> {
+    println!("total args is {}", arg_cells.len());
+    *offset = layouter.assign_region(
+        || "poseidon hash region",
+        |mut region| {
+            let mut local_offset = *offset;
+            let timer = start_timer!(|| "assign");
+            let config = chip.config.clone();
+            chip.initialize(&config, &mut region, &mut local_offset)?;
+            // arg_cells format restart + R elements + result
+            for arg_group in arg_cells.chunks_exact(R + 2).into_iter() {
+                let args = arg_group.into_iter().map(|x| x.clone());
+                let args = args.collect::<Vec<_>>();
+                chip.assign_permute(
+                    &mut region,
+                    &mut local_offset,
+                    &args[1..R + 1].to_vec().try_into().unwrap(),
+                    &args[0],
+                    &args[R + 1],
+                )?;
+            }
+            end_timer!(timer);
+            Ok(local_offset)
+        },
+    )?;
+    Ok(())
+}
 
 impl HostOpSelector for PoseidonChip<Fr, 9, 8> {
     type Config = (CommonGateConfig, PoseidonGateConfig);
@@ -88,101 +387,70 @@ impl HostOpSelector for PoseidonChip<Fr, 9, 8> {
     ) -> Result<Vec<Limb<Fr>>, Error> {
         let opcodes = Self::opcodes();
         let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
-        let total_used_instructions = selected_entries.len() / (1 + 8 * 4 + 4);
-
-        let mut r = vec![];
-
-        // TODO: Change 8 to RATE ?
-        for group in selected_entries.chunks_exact(1 + 8 * 4 + 4) {
-            let ((operand, opcode), index) = *group.get(0).clone().unwrap();
-            assert!(opcode.clone() == Fr::from(PoseidonNew as u64));
-
-            let (limb, _op) = config.assign_one_line(
-                region,
-                offset,
-                operand,
-                opcode,
-                index,
-                operand,
-                Fr::zero(),
-                true,
-            )?;
-            r.push(limb);
-
-            for subgroup in group
-                .clone()
-                .into_iter()
-                .skip(1)
-                .collect::<Vec<_>>()
-                .chunks_exact(4)
-            {
-                let (limb, _op) = config.assign_merged_operands(
-                    region,
-                    offset,
-                    subgroup.to_vec(),
-                    Fr::from_u128(1u128 << 64),
-                    true,
-                )?;
-                r.push(limb);
-            }
-        }
+        let mut padding = [Fr::zero(); RATE];
+        padding[0] = Fr::one();
+        let default_table = hash_to_host_call_table(vec![padding]);
+        assign_rounds(
+            region,
+            offset,
+            selected_entries,
+            default_table,
+            RATE,
+            TOTAL_CONSTRUCTIONS,
+            config,
+        )
+    }
 
-        let default_table = hash_to_host_call_table(
-            [
-                Fr::one(),
-                Fr::zero(),
-                Fr::zero(),
-                Fr::zero(),
-                Fr::zero(),
-                Fr::zero(),
-                Fr::zero(),
-                Fr::zero(),
-            ],
-            POSEIDON_HASHER.clone().squeeze(),
-        );
+    fn synthesize(
+        &mut self,
+        offset: &mut usize,
+        arg_cells: &Vec<Limb<Fr>>,
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        synthesize_rounds(self, offset, arg_cells, layouter)
+    }
+}
 
-        //let entries = default_table.
-        let default_entries: Vec<((Fr, Fr), Fr)> = default_table
-            .0
-            .into_iter()
-            .map(|x| ((Fr::from(x.value), Fr::from(x.op as u64)), Fr::zero()))
-            .collect::<Vec<((Fr, Fr), Fr)>>();
+/// The variable-length sponge on its own opcode family, see sponge_round_groups
+impl HostOpSelector for PoseidonSpongeChip<Fr, 9, 8> {
+    type Config = (CommonGateConfig, PoseidonGateConfig);
+    fn configure(meta: &mut ConstraintSystem<Fr>, shared_advices: &Vec<Column<Advice>>) -> Self::Config {
+        PoseidonChip::<Fr, 9, 8>::configure(meta, shared_advices)
+    }
 
-        for _ in 0..TOTAL_CONSTRUCTIONS - total_used_instructions {
-            let ((operand, opcode), index) = default_entries[0].clone();
-            assert!(opcode.clone() == Fr::from(PoseidonNew as u64));
+    fn construct(c: Self::Config) -> Self {
+        PoseidonSpongeChip(PoseidonChip::construct(c.0, c.1, POSEIDON_HASHER_SPEC.clone()))
+    }
 
-            let (limb, _op) = config.assign_one_line(
-                region,
-                offset,
-                operand,
-                opcode,
-                index,
-                operand,
-                Fr::zero(),
-                false,
-            )?;
-            r.push(limb);
+    fn opcodes() -> Vec<Fr> {
+        vec![
+            Fr::from(ForeignInst::PoseidonSpongeNew as u64),
+            Fr::from(ForeignInst::PoseidonSpongePush as u64),
+            Fr::from(ForeignInst::PoseidonSpongeFinalize as u64),
+        ]
+    }
 
-            for subgroup in default_entries
-                .clone()
-                .iter()
-                .skip(1)
-                .collect::<Vec<_>>()
-                .chunks_exact(4)
-            {
-                let (limb, _op) = config.assign_merged_operands(
-                    region,
-                    offset,
-                    subgroup.to_vec(),
-                    Fr::from_u128(1u128 << 64),
-                    false,
-                )?;
-                r.push(limb);
-            }
-        }
+    fn holes() -> bool {
+        true
+    }
 
-        Ok(r)
+    fn assign(
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+        config: &HostOpConfig,
+    ) -> Result<Vec<Limb<Fr>>, Error> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        assign_sponge_rounds(
+            region,
+            offset,
+            selected_entries,
+            opcodes.try_into().unwrap(),
+            TOTAL_CONSTRUCTIONS,
+            config,
+        )
     }
 
     fn synthesize(
@@ -192,22 +460,36 @@ impl HostOpSelector for PoseidonChip<Fr, 9, 8> {
         layouter: &mut impl Layouter<Fr>,
     ) -> Result<(), Error> {
         println!("total args is {}", arg_cells.len());
+        let opcodes = Self::opcodes().try_into().unwrap();
         *offset = layouter.assign_region(
-            || "poseidon hash region",
+            || "poseidon sponge region",
             |mut region| {
                 let mut local_offset = *offset;
                 let timer = start_timer!(|| "assign");
-                let config = self.config.clone();
-                self.initialize(&config, &mut region, &mut local_offset)?;
-                for arg_group in arg_cells.chunks_exact(10).into_iter() {
-                    let args = arg_group.into_iter().map(|x| x.clone());
-                    let args = args.collect::<Vec<_>>();
-                    self.assign_permute(
+                let config = self.0.config.clone();
+                self.0.initialize(&config, &mut region, &mut local_offset)?;
+                let zero = config.assign_constant(&mut region, &mut (), &mut local_offset, &Fr::zero())?;
+                let mut live = zero.clone();
+                let mut last = zero;
+                // arg_cells format (operand, opcode, enable) of new + RATE elements + result
+                for arg_group in arg_cells.chunks_exact(3 * SPONGE_ROUND_CALLS) {
+                    let calls = arg_group
+                        .chunks_exact(3)
+                        .map(|x| SpongeCall {
+                            operand: x[0].clone(),
+                            opcode: x[1].clone(),
+                            enable: x[2].clone(),
+                        })
+                        .collect::<Vec<_>>();
+                    (live, last) = self.0.assign_sponge_round(
                         &mut region,
                         &mut local_offset,
-                        &args[1..9].to_vec().try_into().unwrap(),
-                        &args[0],
-                        &args[9],
+                        &calls[0],
+                        &calls[1..RATE + 1].to_vec().try_into().unwrap(),
+                        &calls[RATE + 1],
+                        opcodes,
+                        &live,
+                        &last,
                     )?;
                 }
                 end_timer!(timer);
@@ -218,70 +500,108 @@ impl HostOpSelector for PoseidonChip<Fr, 9, 8> {
     }
 }
 
+/// The circomlib poseidon of width 3 on its own opcode family
+impl HostOpSelector for PoseidonChip<Fr, 3, 2> {
+    type Config = (CommonGateConfig, PoseidonGateConfig);
+    fn configure(meta: &mut ConstraintSystem<Fr>, shared_advices: &Vec<Column<Advice>>) -> Self::Config {
+        PoseidonChip::<Fr, 3, 2>::configure(meta, shared_advices)
+    }
+
+    fn construct(c: Self::Config) -> Self {
+        PoseidonChip::construct_with_family(
+            c.0,
+            c.1,
+            CIRCOM_HASHER_SPEC.clone(),
+            PoseidonFamily::Circom,
+        )
+    }
+
+    fn opcodes() -> Vec<Fr> {
+        vec![
+            Fr::from(ForeignInst::PoseidonCircomNew as u64),
+            Fr::from(ForeignInst::PoseidonCircomPush as u64),
+            Fr::from(ForeignInst::PoseidonCircomFinalize as u64),
+        ]
+    }
+
+    fn assign(
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+        config: &HostOpConfig,
+    ) -> Result<Vec<Limb<Fr>>, Error> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        let default_table = circom_hash_to_host_call_table(vec![[Fr::zero(); 2]]);
+        assign_rounds(
+            region,
+            offset,
+            selected_entries,
+            default_table,
+            2,
+            CIRCOM_TOTAL_CONSTRUCTIONS,
+            config,
+        )
+    }
+
+    fn synthesize(
+        &mut self,
+        offset: &mut usize,
+        arg_cells: &Vec<Limb<Fr>>,
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        synthesize_rounds(self, offset, arg_cells, layouter)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::host::{ExternalHostCallEntry, ExternalHostCallEntryTable};
+    use super::{
+        circom_hash_to_host_call_table, hash_to_host_call_table, sponge_hash_to_host_call_table,
+    };
     use halo2_proofs::pairing::bn256::Fr;
     use std::fs::File;
 
-    use crate::host::ForeignInst::{PoseidonFinalize, PoseidonNew, PoseidonPush};
-
-    fn hash_cont(restart: bool) -> Vec<ExternalHostCallEntry> {
-        vec![ExternalHostCallEntry {
-            op: PoseidonNew as usize,
-            value: if restart { 1u64 } else { 0u64 },
-            is_ret: false,
-        }]
-    }
-
-    fn hash_to_host_call_table(inputs: Vec<[Fr; 8]>) -> ExternalHostCallEntryTable {
-        let mut r = vec![];
-        let mut start = true;
-        let mut hasher = crate::host::poseidon::POSEIDON_HASHER.clone();
-        for round in inputs.into_iter() {
-            r.push(hash_cont(start));
-            start = false;
-            for f in round.iter() {
-                r.push(crate::adaptor::fr_to_args(*f, 4, 64, PoseidonPush));
-            }
-            let result = hasher.update_exact(&round);
-            r.push(crate::adaptor::fr_to_args(result, 4, 64, PoseidonFinalize));
-        }
-        ExternalHostCallEntryTable(r.into_iter().flatten().collect())
+    fn padded(inputs: &[Fr]) -> [Fr; 8] {
+        let mut round = [Fr::zero(); 8];
+        round[..inputs.len()].copy_from_slice(inputs);
+        round[inputs.len()] = Fr::one();
+        round
     }
 
     #[test]
     fn generate_poseidon_input() {
-        let table = hash_to_host_call_table(vec![[
-            Fr::one(),
-            Fr::zero(),
-            Fr::zero(),
-            Fr::zero(),
-            Fr::zero(),
-            Fr::zero(),
-            Fr::zero(),
-            Fr::zero(),
-        ]]);
+        let table = hash_to_host_call_table(vec![padded(&[])]);
         let file = File::create("poseidontest.json").expect("can not create file");
         serde_json::to_writer_pretty(file, &table).expect("can not write to file");
     }
 
     #[test]
     fn generate_poseidon_input_multi() {
-        let table = hash_to_host_call_table(vec![
-            [Fr::one(); 8],
-            [
-                Fr::one(),
-                Fr::zero(),
-                Fr::zero(),
-                Fr::zero(),
-                Fr::zero(),
-                Fr::zero(),
-                Fr::zero(),
-                Fr::zero(),
-            ],
-        ]);
+        let table = hash_to_host_call_table(vec![[Fr::one(); 8], padded(&[])]);
         let file = File::create("poseidontest_multi.json").expect("can not create file");
         serde_json::to_writer_pretty(file, &table).expect("can not write to file");
     }
+
+    #[test]
+    fn generate_poseidon_sponge_input() {
+        let table = sponge_hash_to_host_call_table(vec![
+            vec![Fr::one(); 8],
+            vec![Fr::one()],
+            (0..20).map(|x| Fr::from(x as u64)).collect(),
+        ]);
+        let file = File::create("poseidonspongetest.json").expect("can not create file");
+        serde_json::to_writer_pretty(file, &table).expect("can not write to file");
+    }
+
+    #[test]
+    fn generate_poseidon_circom_input() {
+        let table = circom_hash_to_host_call_table(vec![
+            [Fr::from(1u64), Fr::from(2u64)],
+            [Fr::zero(), Fr::one()],
+        ]);
+        let file = File::create("poseidoncircomtest.json").expect("can not create file");
+        serde_json::to_writer_pretty(file, &table).expect("can not write to file");
+    }
 }
@@ -0,0 +1,167 @@
+use crate::circuits::keccak256::{KeccakChip, KeccakGateConfig};
+use crate::host::keccak256::{keccak256_blocks, Keccak256, OUTPUT_LANES, RATE_LANES};
+use crate::host::ForeignInst::{Keccak256Finalize, Keccak256New, Keccak256Push};
+use crate::host::{ExternalHostCallEntry, ExternalHostCallEntryTable, ForeignInst};
+use ark_std::{end_timer, start_timer};
+use halo2_proofs::circuit::{Layouter, Region};
+use halo2_proofs::pairing::bn256::Fr;
+use halo2_proofs::plonk::ConstraintSystem;
+use halo2_proofs::plonk::{Error, Column, Advice};
+use crate::adaptor::get_selected_entries;
+use crate::circuits::host::{HostOpConfig, HostOpSelector};
+
+use crate::utils::Limb;
+
+const CHUNK_SIZE: usize = 1 + RATE_LANES + OUTPUT_LANES;
+
+const TOTAL_CONSTRUCTIONS: usize = 32;
+
+fn keccak_entry(op: ForeignInst, value: u64) -> ExternalHostCallEntry {
+    ExternalHostCallEntry {
+        op: op as usize,
+        value,
+        is_ret: false,
+    }
+}
+
+/// Pad each message and emit one new/push/finalize group per absorbed block,
+/// the first block of every message restarts the sponge.
+pub fn keccak_to_host_call_table(inputs: Vec<Vec<u8>>) -> ExternalHostCallEntryTable {
+    let mut r = vec![];
+    for message in inputs.iter() {
+        let mut hasher = Keccak256::new();
+        for (i, block) in keccak256_blocks(message).iter().enumerate() {
+            r.push(keccak_entry(Keccak256New, (i == 0) as u64));
+            for lane in block.iter() {
+                r.push(keccak_entry(Keccak256Push, *lane));
+            }
+            for lane in hasher.update_exact(block).iter() {
+                r.push(keccak_entry(Keccak256Finalize, *lane));
+            }
+        }
+    }
+    ExternalHostCallEntryTable(r)
+}
+
+impl HostOpSelector for KeccakChip<Fr> {
+    type Config = KeccakGateConfig;
+    fn configure(meta: &mut ConstraintSystem<Fr>, _shared_advices: &Vec<Column<Advice>>) -> Self::Config {
+        KeccakChip::<Fr>::configure(meta)
+    }
+
+    fn construct(c: Self::Config) -> Self {
+        KeccakChip::new(c)
+    }
+
+    fn opcodes() -> Vec<Fr> {
+        vec![
+            Fr::from(Keccak256New as u64),
+            Fr::from(Keccak256Push as u64),
+            Fr::from(Keccak256Finalize as u64),
+        ]
+    }
+
+    fn assign(
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+        config: &HostOpConfig,
+    ) -> Result<Vec<Limb<Fr>>, Error> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        assert!(selected_entries.len() % CHUNK_SIZE == 0);
+        let total_used_instructions = selected_entries.len() / CHUNK_SIZE;
+
+        let mut r = vec![];
+
+        // the padded block of the empty message
+        let default_table = keccak_to_host_call_table(vec![vec![]]);
+        let default_entries: Vec<((Fr, Fr), Fr)> = default_table
+            .0
+            .into_iter()
+            .map(|x| ((Fr::from(x.value), Fr::from(x.op as u64)), Fr::zero()))
+            .collect::<Vec<((Fr, Fr), Fr)>>();
+
+        let groups = selected_entries
+            .chunks_exact(CHUNK_SIZE)
+            .map(|group| (group, true))
+            .chain(
+                (0..TOTAL_CONSTRUCTIONS - total_used_instructions)
+                    .map(|_| (&default_entries[..], false)),
+            );
+
+        for (group, enable) in groups {
+            assert!(group[0].0 .1 == Fr::from(Keccak256New as u64));
+            for ((operand, opcode), index) in group.iter() {
+                let (limb, _op) = config.assign_one_line(
+                    region,
+                    offset,
+                    *operand,
+                    *opcode,
+                    *index,
+                    *operand,
+                    Fr::zero(),
+                    enable,
+                )?;
+                r.push(limb);
+            }
+        }
+
+        Ok(r)
+    }
+
+    fn synthesize(
+        &mut self,
+        offset: &mut usize,
+        arg_cells: &Vec<Limb<Fr>>,
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        println!("keccak adaptor total args is {}", arg_cells.len());
+        *offset = layouter.assign_region(
+            || "keccak hash region",
+            |mut region| {
+                let mut local_offset = *offset;
+                let timer = start_timer!(|| "assign");
+                self.initialize(&mut region, &mut local_offset)?;
+                for arg_group in arg_cells.chunks_exact(CHUNK_SIZE).into_iter() {
+                    let args = arg_group.into_iter().map(|x| x.clone());
+                    let args = args.collect::<Vec<_>>();
+                    self.assign_permute(
+                        &mut region,
+                        &mut local_offset,
+                        &args[1..1 + RATE_LANES].to_vec().try_into().unwrap(),
+                        &args[0],
+                        &args[1 + RATE_LANES..CHUNK_SIZE].to_vec().try_into().unwrap(),
+                    )?;
+                }
+                end_timer!(timer);
+                Ok(local_offset)
+            },
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::keccak_to_host_call_table;
+    use std::fs::File;
+
+    #[test]
+    fn generate_keccak_input() {
+        let table = keccak_to_host_call_table(vec![b"abc".to_vec()]);
+        let file = File::create("keccaktest.json").expect("can not create file");
+        serde_json::to_writer_pretty(file, &table).expect("can not write to file");
+    }
+
+    #[test]
+    fn generate_keccak_input_multi() {
+        let table = keccak_to_host_call_table(vec![
+            (0..200).map(|x| x as u8).collect(),
+            vec![],
+        ]);
+        let file = File::create("keccaktest_multi.json").expect("can not create file");
+        serde_json::to_writer_pretty(file, &table).expect("can not write to file");
+    }
+}
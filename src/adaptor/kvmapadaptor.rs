@@ -0,0 +1,264 @@
+use crate::adaptor::get_selected_entries;
+use crate::circuits::host::{HostOpConfig, HostOpSelector};
+use crate::circuits::kvmap::KVMapChip;
+use crate::circuits::merkle::MerkleGateConfig;
+use crate::circuits::CommonGateConfig;
+use crate::host::kvmap::MongoKVMap;
+use crate::host::mongomerkle::DEFAULT_HASH_VEC;
+use crate::host::ExternalHostCallEntry;
+use crate::host::ForeignInst;
+use crate::host::ForeignInst::{KVMapGet, KVMapGetRoot, KVMapKey, KVMapSet, KVMapSetRoot};
+use crate::utils::bytes_to_u64;
+use crate::utils::data_to_bytes;
+use crate::utils::field_to_bytes;
+use crate::utils::field_to_u64;
+use crate::utils::Limb;
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::{Chip, Layouter, Region};
+use halo2_proofs::pairing::bn256::Fr;
+use halo2_proofs::plonk::Advice;
+use halo2_proofs::plonk::Column;
+use halo2_proofs::plonk::ConstraintSystem;
+use halo2_proofs::plonk::Error;
+
+/* The calling convention will be
+ * KVMapSetRoot
+ * KVMapKey
+ * KVMapSet / KVMapGet
+ * KVMapGetRoot
+ */
+const MERGE_SIZE: usize = 4;
+const MERGE_DATA_SIZE: usize = 2;
+// 0-3: root 4-7: key hash 8-11: value 12-15: new root
+const CHUNK_SIZE: usize = 4 * MERGE_SIZE; // should equal to 16
+const TOTAL_CONSTRUCTIONS: usize = 64;
+
+fn kvmap_to_host_call_table(
+    inputs: &Vec<([u8; 32], [u8; 32], [u8; 32], [u8; 32], ForeignInst)>,
+) -> Vec<ExternalHostCallEntry> {
+    let mut r = vec![];
+    for (root, key_hash, value, new_root, op) in inputs.into_iter() {
+        r.push(crate::adaptor::fr_to_args(
+            Fr::from_raw(bytes_to_u64(root)),
+            4,
+            64,
+            KVMapSetRoot,
+        ));
+        for w in bytes_to_u64(key_hash) {
+            r.push(vec![ExternalHostCallEntry {
+                op: KVMapKey as usize,
+                value: w,
+                is_ret: false,
+            }]);
+        }
+        r.push(
+            bytes_to_u64(value)
+                .into_iter()
+                .map(|w| ExternalHostCallEntry {
+                    op: *op as usize,
+                    value: w,
+                    is_ret: false,
+                })
+                .collect(),
+        );
+        r.push(crate::adaptor::fr_to_args(
+            Fr::from_raw(bytes_to_u64(new_root)),
+            4,
+            64,
+            KVMapGetRoot,
+        ));
+    }
+    r.into_iter().flatten().collect::<Vec<_>>()
+}
+
+impl<const DEPTH: usize> HostOpSelector for KVMapChip<Fr, DEPTH> {
+    type Config = (CommonGateConfig, MerkleGateConfig);
+    fn configure(
+        meta: &mut ConstraintSystem<Fr>,
+        shared_advices: &Vec<Column<Advice>>,
+    ) -> Self::Config {
+        KVMapChip::<Fr, DEPTH>::configure(meta, shared_advices)
+    }
+
+    fn construct(c: Self::Config) -> Self {
+        KVMapChip::new(c.0, c.1)
+    }
+
+    fn opcodes() -> Vec<Fr> {
+        vec![
+            Fr::from(KVMapSetRoot as u64),
+            Fr::from(KVMapGetRoot as u64),
+            Fr::from(KVMapKey as u64),
+            Fr::from(KVMapSet as u64),
+            Fr::from(KVMapGet as u64),
+        ]
+    }
+
+    fn assign(
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+        config: &HostOpConfig,
+    ) -> Result<Vec<Limb<Fr>>, Error> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+
+        let total_used_instructions = selected_entries.len() / (CHUNK_SIZE);
+        assert!(total_used_instructions <= TOTAL_CONSTRUCTIONS);
+
+        let default_table = kvmap_to_host_call_table(&vec![(
+            DEFAULT_HASH_VEC[DEPTH],
+            [0; 32],
+            [0; 32],
+            DEFAULT_HASH_VEC[DEPTH],
+            KVMapGet,
+        )]);
+        let default_entries: Vec<((Fr, Fr), Fr)> = default_table
+            .into_iter()
+            .map(|x| ((Fr::from(x.value), Fr::from(x.op as u64)), Fr::zero()))
+            .collect::<Vec<((Fr, Fr), Fr)>>();
+
+        let mut r = vec![];
+        let groups = selected_entries
+            .chunks_exact(CHUNK_SIZE)
+            .map(|group| (group.iter().collect::<Vec<_>>(), true))
+            .chain(
+                (0..TOTAL_CONSTRUCTIONS - total_used_instructions)
+                    .map(|_| (default_entries.iter().collect::<Vec<_>>(), false)),
+            );
+
+        for (group, enable) in groups {
+            // root
+            let (limb, _) = config.assign_merged_operands(
+                region,
+                offset,
+                group[0..4].to_vec(),
+                Fr::from_u128(1u128 << 64),
+                enable,
+            )?;
+            r.push(limb);
+
+            // the words of the key hash
+            for entry in group[4..8].iter() {
+                let ((operand, opcode), index) = **entry;
+                assert!(opcode == Fr::from(KVMapKey as u64));
+                let (limb, _) = config.assign_one_line(
+                    region,
+                    offset,
+                    operand,
+                    opcode,
+                    index,
+                    operand,
+                    Fr::zero(),
+                    enable,
+                )?;
+                r.push(limb);
+            }
+
+            // set or get
+            let mut setget = None;
+            for subgroup in group[8..12].chunks_exact(MERGE_DATA_SIZE) {
+                let (limb, op) = config.assign_merged_operands(
+                    region,
+                    offset,
+                    subgroup.to_vec(),
+                    Fr::from_u128(1u128 << 64),
+                    enable,
+                )?;
+                setget = Some(op);
+                r.push(limb);
+            }
+
+            // new root
+            let (limb, _) = config.assign_merged_operands(
+                region,
+                offset,
+                group[12..16].to_vec(),
+                Fr::from_u128(1u128 << 64),
+                enable,
+            )?;
+            r.push(limb);
+
+            r.push(setget.unwrap());
+        }
+
+        Ok(r)
+    }
+
+    fn synthesize(
+        &mut self,
+        offset: &mut usize,
+        arg_cells: &Vec<Limb<Fr>>,
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        *offset = layouter.assign_region(
+            || "kvmap region",
+            |mut region| {
+                let config = self.config().clone();
+                let mut local_offset = *offset;
+                self.initialize(&config, &mut region, &mut local_offset)?;
+                // 0: root
+                // 1-4: key hash
+                // 5-6: value
+                // 7: new_root
+                // 8: op_code
+                for args in arg_cells.chunks_exact(9) {
+                    let args: &[Limb<Fr>; 9] = args.try_into().unwrap();
+                    let [root, w0, w1, w2, w3, value_lo, value_hi, _, opcode] = args;
+                    let key_hash: [u8; 32] = [w0, w1, w2, w3]
+                        .iter()
+                        .flat_map(|w| field_to_u64(&w.value).to_le_bytes())
+                        .collect::<Vec<_>>()
+                        .try_into()
+                        .unwrap();
+                    let mut map = MongoKVMap::<DEPTH>::construct(
+                        [0u8; 32],
+                        field_to_bytes(&root.value),
+                        None,
+                    );
+                    let (probes, _) = map.probe(&key_hash).expect("probe key error");
+                    self.assign_call(&mut region, &mut local_offset, args, &probes)?;
+                    if opcode.value == Fr::from(KVMapSet as u64) {
+                        let value: [u8; 32] = data_to_bytes(vec![value_lo.value, value_hi.value])
+                            .try_into()
+                            .unwrap();
+                        map.set_by_hash(&key_hash, &value).expect("set key error");
+                    }
+                }
+                Ok(local_offset)
+            },
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::kvmap_to_host_call_table;
+    use crate::host::kvmap::{key_hash, MongoKVMap};
+    use crate::host::mongomerkle::DEFAULT_HASH_VEC;
+    use crate::host::ExternalHostCallEntryTable;
+    use crate::host::ForeignInst::{KVMapGet, KVMapSet};
+    use crate::proof::MERKLE_DEPTH;
+    use std::fs::File;
+
+    #[test]
+    fn generate_kvmap_input_get_set() {
+        let root_default = DEFAULT_HASH_VEC[MERKLE_DEPTH];
+        let key = key_hash(b"alice");
+        let value = [1u8; 32];
+        let mut map = MongoKVMap::<MERKLE_DEPTH>::construct([0u8; 32], root_default, None);
+        map.set_by_hash(&key, &value).unwrap();
+        let root_new = map.get_root_hash();
+
+        let default_table = kvmap_to_host_call_table(&vec![
+            (root_default, key, [0; 32], root_default, KVMapGet),
+            (root_default, key, value, root_new, KVMapSet),
+            (root_new, key, value, root_new, KVMapGet),
+        ]);
+        let file = File::create("kvmap_test1.json").expect("can not create file");
+        serde_json::to_writer_pretty(file, &ExternalHostCallEntryTable(default_table))
+            .expect("can not write to file");
+    }
+}
@@ -9,8 +9,7 @@ use halo2_proofs::plonk::ConstraintSystem;
 use halo2_proofs::plonk::Error;
 use halo2_proofs::plonk::Advice;
 use halo2_proofs::plonk::Column;
-use crate::circuits::merkle::MerkleChip;
-use crate::circuits::poseidon::PoseidonGateConfig;
+use crate::circuits::merkle::{MerkleChip, MerkleGateConfig};
 use crate::circuits::CommonGateConfig;
 use crate::circuits::host::{HostOpConfig, HostOpSelector};
 use crate::adaptor::get_selected_entries;
@@ -58,7 +57,7 @@ fn kvpair_to_host_call_table(
 }
 
 impl<const DEPTH: usize> HostOpSelector for MerkleChip<Fr, DEPTH> {
-    type Config = (CommonGateConfig, PoseidonGateConfig);
+    type Config = (CommonGateConfig, MerkleGateConfig);
     fn configure(meta: &mut ConstraintSystem<Fr>, shared_advices: &Vec<Column<Advice>>) -> Self::Config {
         MerkleChip::<Fr, DEPTH>::configure(meta, shared_advices)
     }
@@ -4,11 +4,20 @@ use crate::utils::field_to_bn;
 use halo2_proofs::arithmetic::{BaseExt, FieldExt};
 use num_bigint::BigUint;
 
+pub mod blake2adaptor;
 pub mod bls381adaptor;
 pub mod bn256adaptor;
+pub mod eddsaadaptor;
+pub mod groth16adaptor;
 pub mod hashadaptor;
+pub mod keccakadaptor;
+pub mod kvmapadaptor;
 pub mod merkleadaptor;
 pub mod msmadaptor;
+pub mod pedersenadaptor;
+pub mod secp256k1adaptor;
+pub mod secp256r1adaptor;
+pub mod validate;
 
 pub fn fr_to_args<F: BaseExt>(
     f: F,
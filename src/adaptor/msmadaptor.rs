@@ -5,12 +5,17 @@ use halo2_proofs::circuit::{Layouter, Region};
 use halo2_proofs::pairing::bn256::Fr;
 use halo2_proofs::plonk::ConstraintSystem;
 use halo2_proofs::plonk::{Error, Column, Advice};
-use crate::circuits::babyjub::{AltJubChip, Point as CircuitPoint};
+use crate::circuits::babyjub::{
+    AltJubBatchMsmChip, AltJubMsmChip, MsmRouteConfig, Point as CircuitPoint, BATCH_MSM_POINTS,
+};
 use crate::circuits::CommonGateConfig;
-use crate::host::ForeignInst::{JubjubSumNew, JubjubSumPush, JubjubSumResult};
+use crate::host::ForeignInst::{
+    JubjubMsmNew, JubjubMsmPush, JubjubMsmResult, JubjubSumNew, JubjubSumPush, JubjubSumResult,
+};
 use crate::circuits::host::{HostOpConfig, HostOpSelector};
 use crate::adaptor::field_to_bn;
 use crate::adaptor::get_selected_entries;
+use crate::adaptor::validate::{fq_from_entries, HostCallError, PointError};
 use crate::host::jubjub::Point;
 use crate::utils::Limb;
 
@@ -18,6 +23,13 @@ const MERGE_SIZE: usize = 4;
 const CHUNK_SIZE: usize = 1 + (2 + 1 + 2) * MERGE_SIZE;
 
 const TOTAL_CONSTRUCTIONS: usize = 400;
+// slots of the distinct products of other points and of the generator
+const VAR_CONSTRUCTIONS: usize = 256;
+const BASE_CONSTRUCTIONS: usize = 256;
+
+// new, the pairs (point, scalar) and the result
+const BATCH_CHUNK_SIZE: usize = 1 + (3 * BATCH_MSM_POINTS + 2) * MERGE_SIZE;
+const BATCH_TOTAL_CONSTRUCTIONS: usize = 48;
 
 fn msm_new(restart: bool) -> Vec<ExternalHostCallEntry> {
     vec![ExternalHostCallEntry {
@@ -44,14 +56,60 @@ fn msm_to_host_call_table<F: FieldExt>(inputs: &Vec<(Point, F)>) -> Vec<External
     r.into_iter().flatten().collect::<Vec<_>>()
 }
 
-impl HostOpSelector for AltJubChip<Fr> {
-    type Config = CommonGateConfig;
+/// The calls of batched msm sessions, each call is padded with zero products
+fn batch_msm_to_host_call_table<F: FieldExt>(
+    calls: &Vec<(bool, Vec<(Point, F)>)>,
+) -> Vec<ExternalHostCallEntry> {
+    let mut r = vec![];
+    let mut result = Point::identity();
+    for (restart, pairs) in calls.iter() {
+        assert!(pairs.len() <= BATCH_MSM_POINTS);
+        r.push(vec![ExternalHostCallEntry {
+            op: JubjubMsmNew as usize,
+            value: *restart as u64,
+            is_ret: false,
+        }]);
+        if *restart {
+            result = Point::identity();
+        }
+        let padding = (Point::identity(), F::zero());
+        for (p, c) in pairs.iter().chain(std::iter::repeat(&padding)).take(BATCH_MSM_POINTS) {
+            r.push(crate::adaptor::fr_to_args(p.x, 4, 64, JubjubMsmPush));
+            r.push(crate::adaptor::fr_to_args(p.y, 4, 64, JubjubMsmPush));
+            r.push(crate::adaptor::fr_to_args(*c, 4, 64, JubjubMsmPush));
+            result = result.add(&p.mul_scalar(&field_to_bn(c)));
+        }
+        r.push(crate::adaptor::fr_to_args(result.x, 4, 64, JubjubMsmResult));
+        r.push(crate::adaptor::fr_to_args(result.y, 4, 64, JubjubMsmResult));
+    }
+    r.into_iter().flatten().collect::<Vec<_>>()
+}
+
+/// Native check of a pushed point of two coordinates of four 64 bits words
+fn check_jubjub_point(entries: &[((Fr, Fr), Fr)]) -> Result<(), PointError> {
+    let x = fq_from_entries::<Fr>(&entries[0..MERGE_SIZE], 64);
+    let y = fq_from_entries::<Fr>(&entries[MERGE_SIZE..2 * MERGE_SIZE], 64);
+    let p = x
+        .zip(y)
+        .map(|(x, y)| Point { x, y })
+        .ok_or(PointError::NonCanonical)?;
+    if !p.is_on_curve() {
+        return Err(PointError::NotOnCurve);
+    }
+    if !p.is_in_subgroup() {
+        return Err(PointError::NotInSubgroup);
+    }
+    Ok(())
+}
+
+impl HostOpSelector for AltJubMsmChip<Fr> {
+    type Config = (CommonGateConfig, MsmRouteConfig);
     fn configure(meta: &mut ConstraintSystem<Fr>, shared_advice: &Vec<Column<Advice>>) -> Self::Config {
-        AltJubChip::<Fr>::configure(meta, shared_advice)
+        AltJubMsmChip::<Fr>::configure(meta, shared_advice)
     }
 
     fn construct(c: Self::Config) -> Self {
-        AltJubChip::new(c)
+        AltJubMsmChip::construct(c.0, c.1)
     }
 
     fn opcodes() -> Vec<Fr> {
@@ -62,6 +120,19 @@ impl HostOpSelector for AltJubChip<Fr> {
         ]
     }
 
+    fn validate(
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+    ) -> Result<(), HostCallError> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        for (call, group) in selected_entries.chunks_exact(CHUNK_SIZE).enumerate() {
+            check_jubjub_point(&group[1..1 + 2 * MERGE_SIZE])
+                .map_err(|e| e.at("jubjubsum", call, "point"))?;
+        }
+        Ok(())
+    }
+
     fn assign(
         region: &mut Region<Fr>,
         offset: &mut usize,
@@ -168,26 +239,165 @@ impl HostOpSelector for AltJubChip<Fr> {
                 println!("msm adaptor starting offset is {}", offset);
                 let mut local_offset = *offset;
                 let timer = start_timer!(|| "assign");
-                let config = self.config.clone();
+                let config = self.jub.config.clone();
                 self.initialize(&config, &mut region, &mut local_offset)?;
                 // arg_cells format 1 + 2 + 1 + 2
-                for arg_group in arg_cells.chunks_exact(6).into_iter() {
-                    let args = arg_group.into_iter().map(|x| x.clone());
-                    let args = args.collect::<Vec<_>>();
-                    self.assign_incremental_msm(
-                        &mut region,
-                        &mut local_offset,
-                        &CircuitPoint {
-                            x: args[1].clone(),
-                            y: args[2].clone(),
-                        },
-                        &args[3],
-                        &args[0],
-                        &CircuitPoint {
-                            x: args[4].clone(),
-                            y: args[5].clone(),
-                        },
-                    )?;
+                let pushes = arg_cells
+                    .chunks_exact(6)
+                    .map(|args| {
+                        (
+                            CircuitPoint {
+                                x: args[1].clone(),
+                                y: args[2].clone(),
+                            },
+                            args[3].clone(),
+                            args[0].clone(),
+                            CircuitPoint {
+                                x: args[4].clone(),
+                                y: args[5].clone(),
+                            },
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                self.assign_msm(
+                    &mut region,
+                    &mut local_offset,
+                    &pushes,
+                    VAR_CONSTRUCTIONS,
+                    BASE_CONSTRUCTIONS,
+                )?;
+                end_timer!(timer);
+                Ok(local_offset)
+            },
+        )?;
+        Ok(())
+    }
+}
+
+impl HostOpSelector for AltJubBatchMsmChip<Fr> {
+    type Config = CommonGateConfig;
+    fn configure(meta: &mut ConstraintSystem<Fr>, shared_advices: &Vec<Column<Advice>>) -> Self::Config {
+        AltJubBatchMsmChip::<Fr>::configure(meta, shared_advices)
+    }
+
+    fn construct(c: Self::Config) -> Self {
+        AltJubBatchMsmChip::construct(c)
+    }
+
+    fn opcodes() -> Vec<Fr> {
+        vec![
+            Fr::from(JubjubMsmNew as u64),
+            Fr::from(JubjubMsmPush as u64),
+            Fr::from(JubjubMsmResult as u64),
+        ]
+    }
+
+    fn validate(
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+    ) -> Result<(), HostCallError> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        for (call, group) in selected_entries.chunks_exact(BATCH_CHUNK_SIZE).enumerate() {
+            for pair in group[1..BATCH_CHUNK_SIZE - 2 * MERGE_SIZE].chunks_exact(3 * MERGE_SIZE) {
+                check_jubjub_point(&pair[0..2 * MERGE_SIZE])
+                    .map_err(|e| e.at("jubjubmsm", call, "point"))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn assign(
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+        config: &HostOpConfig,
+    ) -> Result<Vec<Limb<Fr>>, Error> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        assert!(selected_entries.len() % BATCH_CHUNK_SIZE == 0);
+        let total_used_instructions = selected_entries.len() / BATCH_CHUNK_SIZE;
+
+        let mut r = vec![];
+
+        let default_table = batch_msm_to_host_call_table::<Fr>(&vec![(true, vec![])]);
+        let default_entries: Vec<((Fr, Fr), Fr)> = default_table
+            .into_iter()
+            .map(|x| ((Fr::from(x.value), Fr::from(x.op as u64)), Fr::zero()))
+            .collect::<Vec<((Fr, Fr), Fr)>>();
+
+        let groups = selected_entries
+            .chunks_exact(BATCH_CHUNK_SIZE)
+            .map(|group| (group, true))
+            .chain(
+                (0..BATCH_TOTAL_CONSTRUCTIONS - total_used_instructions)
+                    .map(|_| (&default_entries[..], false)),
+            );
+
+        for (group, enable) in groups {
+            let ((operand, opcode), index) = group[0];
+            assert!(opcode == Fr::from(JubjubMsmNew as u64));
+            let (limb, _) = config.assign_one_line(
+                region,
+                offset,
+                operand,
+                opcode,
+                index,
+                operand,
+                Fr::zero(),
+                enable,
+            )?;
+            r.push(limb);
+
+            for subgroup in group[1..].chunks_exact(MERGE_SIZE) {
+                let (limb, _) = config.assign_merged_operands(
+                    region,
+                    offset,
+                    subgroup.iter().collect::<Vec<_>>(),
+                    Fr::from_u128(1u128 << 64),
+                    enable,
+                )?;
+                r.push(limb);
+            }
+        }
+
+        Ok(r)
+    }
+
+    fn synthesize(
+        &mut self,
+        offset: &mut usize,
+        arg_cells: &Vec<Limb<Fr>>,
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        println!("batch msm adaptor total args is {}", arg_cells.len());
+        *offset = layouter.assign_region(
+            || "jubjub batch msm region",
+            |mut region| {
+                let mut local_offset = *offset;
+                let timer = start_timer!(|| "assign");
+                let config = self.jub.config.clone();
+                self.initialize(&config, &mut region, &mut local_offset)?;
+                // arg_cells format 1 + (2 + 1) * BATCH_MSM_POINTS + 2
+                for args in arg_cells.chunks_exact(1 + 3 * BATCH_MSM_POINTS + 2) {
+                    let pairs = args[1..1 + 3 * BATCH_MSM_POINTS]
+                        .chunks_exact(3)
+                        .map(|pair| {
+                            (
+                                CircuitPoint {
+                                    x: pair[0].clone(),
+                                    y: pair[1].clone(),
+                                },
+                                pair[2].clone(),
+                            )
+                        })
+                        .collect::<Vec<_>>();
+                    let result = CircuitPoint {
+                        x: args[1 + 3 * BATCH_MSM_POINTS].clone(),
+                        y: args[2 + 3 * BATCH_MSM_POINTS].clone(),
+                    };
+                    self.assign_batch_msm(&mut region, &mut local_offset, &pairs, &args[0], &result)?;
                 }
                 end_timer!(timer);
                 Ok(local_offset)
@@ -199,8 +409,8 @@ impl HostOpSelector for AltJubChip<Fr> {
 
 #[cfg(test)]
 mod tests {
-    use super::msm_to_host_call_table;
-    use crate::host::jubjub::Point;
+    use super::{batch_msm_to_host_call_table, msm_to_host_call_table};
+    use crate::host::jubjub::{Point, BASE};
     use crate::host::ExternalHostCallEntryTable;
     use halo2_proofs::pairing::bn256::Fr;
     use std::fs::File;
@@ -220,4 +430,52 @@ mod tests {
         serde_json::to_writer_pretty(file, &ExternalHostCallEntryTable(default_table))
             .expect("can not write to file");
     }
+
+    #[test]
+    fn generate_jubjub_msm_input_base() {
+        let p = BASE.mul_scalar(&7u64.into());
+        let inputs = vec![
+            (BASE.clone(), Fr::from(3u64)),
+            (p.clone(), Fr::from(5u64)),
+            (BASE.clone(), Fr::from(0x1234567890abcdefu64)),
+            (p, Fr::from(5u64)),
+        ];
+        let table = msm_to_host_call_table(&inputs);
+        let file = File::create("jubjub_base.json").expect("can not create file");
+        serde_json::to_writer_pretty(file, &ExternalHostCallEntryTable(table))
+            .expect("can not write to file");
+    }
+
+    #[test]
+    fn generate_jubjub_msm_input_identity() {
+        // an identity point, a zero scalar and opposite products that cancel
+        let p = BASE.mul_scalar(&7u64.into());
+        let neg_p = Point { x: -p.x, y: p.y };
+        let inputs = vec![
+            (Point::identity(), Fr::from(5u64)),
+            (p.clone(), Fr::zero()),
+            (p, Fr::from(3u64)),
+            (neg_p, Fr::from(3u64)),
+        ];
+        let table = msm_to_host_call_table(&inputs);
+        let file = File::create("jubjub_identity.json").expect("can not create file");
+        serde_json::to_writer_pretty(file, &ExternalHostCallEntryTable(table))
+            .expect("can not write to file");
+    }
+
+    #[test]
+    fn generate_jubjub_batch_msm_input() {
+        // two calls of one session and a new session which sums to the identity
+        let p = BASE.mul_scalar(&7u64.into());
+        let neg_p = Point { x: -p.x, y: p.y };
+        let first = (1..=16u64)
+            .map(|i| (BASE.mul_scalar(&i.into()), Fr::from(i * 0x1234567u64)))
+            .collect::<Vec<_>>();
+        let second = vec![(Point::identity(), Fr::from(3u64)), (p.clone(), Fr::zero())];
+        let third = vec![(p, Fr::from(5u64)), (neg_p, Fr::from(5u64))];
+        let table = batch_msm_to_host_call_table(&vec![(true, first), (false, second), (true, third)]);
+        let file = File::create("jubjub_batch.json").expect("can not create file");
+        serde_json::to_writer_pretty(file, &ExternalHostCallEntryTable(table))
+            .expect("can not write to file");
+    }
 }
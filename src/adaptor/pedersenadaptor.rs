@@ -0,0 +1,246 @@
+use crate::host::ExternalHostCallEntry;
+use ark_std::{end_timer, start_timer};
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::{Layouter, Region};
+use halo2_proofs::pairing::bn256::Fr;
+use halo2_proofs::plonk::ConstraintSystem;
+use halo2_proofs::plonk::{Error, Column, Advice};
+use crate::circuits::babyjub::Point as CircuitPoint;
+use crate::circuits::pedersen::{AltJubPedersenChip, HASH_WORDS};
+use crate::circuits::CommonGateConfig;
+use crate::host::ForeignInst::{
+    JubjubPedersenCommitResult, JubjubPedersenCommitScalar, JubjubPedersenHashMsg,
+    JubjubPedersenHashResult,
+};
+use crate::circuits::host::{HostOpConfig, HostOpSelector};
+use crate::adaptor::get_selected_entries;
+use crate::host::pedersen::{pedersen_commit, pedersen_hash, words_to_bits};
+use crate::utils::Limb;
+
+const MERGE_SIZE: usize = 4;
+// v, r and the commitment
+const COMMIT_CHUNK_SIZE: usize = 4 * MERGE_SIZE;
+// the message words and the hash
+const HASH_CHUNK_SIZE: usize = HASH_WORDS + 2 * MERGE_SIZE;
+
+const COMMIT_TOTAL_CONSTRUCTIONS: usize = 200;
+const HASH_TOTAL_CONSTRUCTIONS: usize = 400;
+
+fn pedersen_commit_to_host_call_table(inputs: &Vec<(Fr, Fr)>) -> Vec<ExternalHostCallEntry> {
+    let mut r = vec![];
+    for (v, blind) in inputs.into_iter() {
+        let c = pedersen_commit(v, blind);
+        r.push(crate::adaptor::fr_to_args(*v, 4, 64, JubjubPedersenCommitScalar));
+        r.push(crate::adaptor::fr_to_args(*blind, 4, 64, JubjubPedersenCommitScalar));
+        r.push(crate::adaptor::fr_to_args(c.x, 4, 64, JubjubPedersenCommitResult));
+        r.push(crate::adaptor::fr_to_args(c.y, 4, 64, JubjubPedersenCommitResult));
+    }
+    r.into_iter().flatten().collect::<Vec<_>>()
+}
+
+fn pedersen_hash_to_host_call_table(
+    inputs: &Vec<[u64; HASH_WORDS]>,
+) -> Vec<ExternalHostCallEntry> {
+    let mut r = vec![];
+    for words in inputs.into_iter() {
+        let h = pedersen_hash(&words_to_bits(words));
+        r.push(
+            words
+                .iter()
+                .map(|w| ExternalHostCallEntry {
+                    op: JubjubPedersenHashMsg as usize,
+                    value: *w,
+                    is_ret: false,
+                })
+                .collect::<Vec<_>>(),
+        );
+        r.push(crate::adaptor::fr_to_args(h.x, 4, 64, JubjubPedersenHashResult));
+        r.push(crate::adaptor::fr_to_args(h.y, 4, 64, JubjubPedersenHashResult));
+    }
+    r.into_iter().flatten().collect::<Vec<_>>()
+}
+
+impl<const HASH: bool> AltJubPedersenChip<Fr, HASH> {
+    fn chunk_size() -> usize {
+        if HASH {
+            HASH_CHUNK_SIZE
+        } else {
+            COMMIT_CHUNK_SIZE
+        }
+    }
+
+    fn total_constructions() -> usize {
+        if HASH {
+            HASH_TOTAL_CONSTRUCTIONS
+        } else {
+            COMMIT_TOTAL_CONSTRUCTIONS
+        }
+    }
+
+    fn default_table() -> Vec<ExternalHostCallEntry> {
+        if HASH {
+            pedersen_hash_to_host_call_table(&vec![[0; HASH_WORDS]])
+        } else {
+            pedersen_commit_to_host_call_table(&vec![(Fr::zero(), Fr::zero())])
+        }
+    }
+}
+
+impl<const HASH: bool> HostOpSelector for AltJubPedersenChip<Fr, HASH> {
+    type Config = CommonGateConfig;
+    fn configure(meta: &mut ConstraintSystem<Fr>, shared_advices: &Vec<Column<Advice>>) -> Self::Config {
+        AltJubPedersenChip::<Fr, HASH>::configure(meta, shared_advices)
+    }
+
+    fn construct(c: Self::Config) -> Self {
+        AltJubPedersenChip::construct(c)
+    }
+
+    fn opcodes() -> Vec<Fr> {
+        if HASH {
+            vec![
+                Fr::from(JubjubPedersenHashMsg as u64),
+                Fr::from(JubjubPedersenHashResult as u64),
+            ]
+        } else {
+            vec![
+                Fr::from(JubjubPedersenCommitScalar as u64),
+                Fr::from(JubjubPedersenCommitResult as u64),
+            ]
+        }
+    }
+
+    fn assign(
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+        config: &HostOpConfig,
+    ) -> Result<Vec<Limb<Fr>>, Error> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        assert!(selected_entries.len() % Self::chunk_size() == 0);
+        let total_used_instructions = selected_entries.len() / Self::chunk_size();
+
+        let mut r = vec![];
+
+        let default_entries: Vec<((Fr, Fr), Fr)> = Self::default_table()
+            .into_iter()
+            .map(|x| ((Fr::from(x.value), Fr::from(x.op as u64)), Fr::zero()))
+            .collect::<Vec<((Fr, Fr), Fr)>>();
+
+        let groups = selected_entries
+            .chunks_exact(Self::chunk_size())
+            .map(|group| (group, true))
+            .chain(
+                (0..Self::total_constructions() - total_used_instructions)
+                    .map(|_| (&default_entries[..], false)),
+            );
+
+        for (group, enable) in groups {
+            // the message words of the hash are assigned one by one
+            let words = if HASH { HASH_WORDS } else { 0 };
+            for ((operand, opcode), index) in group[0..words].iter() {
+                let (limb, _) = config.assign_one_line(
+                    region,
+                    offset,
+                    *operand,
+                    *opcode,
+                    *index,
+                    *operand,
+                    Fr::zero(),
+                    enable,
+                )?;
+                r.push(limb);
+            }
+            for subgroup in group[words..].chunks_exact(MERGE_SIZE) {
+                let (limb, _) = config.assign_merged_operands(
+                    region,
+                    offset,
+                    subgroup.iter().collect::<Vec<_>>(),
+                    Fr::from_u128(1u128 << 64),
+                    enable,
+                )?;
+                r.push(limb);
+            }
+        }
+
+        Ok(r)
+    }
+
+    fn synthesize(
+        &mut self,
+        offset: &mut usize,
+        arg_cells: &Vec<Limb<Fr>>,
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        println!("pedersen adaptor total args is {}", arg_cells.len());
+        *offset = layouter.assign_region(
+            || "jubjub pedersen region",
+            |mut region| {
+                let mut local_offset = *offset;
+                let timer = start_timer!(|| "assign");
+                let config = self.jub.config.clone();
+                self.initialize(&config, &mut region, &mut local_offset)?;
+                if HASH {
+                    // arg_cells format words + hash
+                    for args in arg_cells.chunks_exact(HASH_WORDS + 2) {
+                        self.assign_hash(
+                            &mut region,
+                            &mut local_offset,
+                            &args[0..HASH_WORDS],
+                            &CircuitPoint {
+                                x: args[HASH_WORDS].clone(),
+                                y: args[HASH_WORDS + 1].clone(),
+                            },
+                        )?;
+                    }
+                } else {
+                    // arg_cells format v + r + commitment
+                    for args in arg_cells.chunks_exact(4) {
+                        self.assign_commit(
+                            &mut region,
+                            &mut local_offset,
+                            &args[0],
+                            &args[1],
+                            &CircuitPoint {
+                                x: args[2].clone(),
+                                y: args[3].clone(),
+                            },
+                        )?;
+                    }
+                }
+                end_timer!(timer);
+                Ok(local_offset)
+            },
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pedersen_commit_to_host_call_table, pedersen_hash_to_host_call_table};
+    use crate::host::ExternalHostCallEntryTable;
+    use halo2_proofs::pairing::bn256::Fr;
+    use std::fs::File;
+
+    #[test]
+    fn generate_jubjub_pedersen_input() {
+        let inputs = (0..4u64)
+            .map(|i| (Fr::from(1000 * i), Fr::from(0x1234567890abcdefu64 + i)))
+            .collect();
+        let table = pedersen_commit_to_host_call_table(&inputs);
+        let file = File::create("jubjub_pedersen_commit.json").expect("can not create file");
+        serde_json::to_writer_pretty(file, &ExternalHostCallEntryTable(table))
+            .expect("can not write to file");
+
+        let inputs = (0..4u64)
+            .map(|i| [i, u64::MAX - i, 0x0123456789abcdef, i << 32])
+            .collect();
+        let table = pedersen_hash_to_host_call_table(&inputs);
+        let file = File::create("jubjub_pedersen_hash.json").expect("can not create file");
+        serde_json::to_writer_pretty(file, &ExternalHostCallEntryTable(table))
+            .expect("can not write to file");
+    }
+}
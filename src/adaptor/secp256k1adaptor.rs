@@ -0,0 +1,338 @@
+use halo2_proofs::arithmetic::{CurveAffine, Field, FieldExt};
+use halo2_proofs::pairing::bn256::Fr;
+use halo2_proofs::pairing::secp256k1::{Fp, Fq, Secp256k1Affine};
+use halo2_proofs::{
+    circuit::{Layouter, Region},
+    plonk::{Advice, Column, ConstraintSystem, Error},
+};
+
+pub const SECP256K1FQ_SIZE: usize = 5;
+pub const SECP256K1POINT_SIZE: usize = 11;
+const SECP256K1VERIFY_SIZE: usize = 3 * SECP256K1FQ_SIZE + SECP256K1POINT_SIZE + 1;
+const SECP256K1RECOVER_SIZE: usize = 3 * SECP256K1FQ_SIZE + 1 + SECP256K1POINT_SIZE + 1;
+
+use crate::circuits::secp256k1::{Secp256k1ChipConfig, Secp256k1RecoverChip, Secp256k1VerifyChip};
+
+use crate::adaptor::validate::{
+    check_affine, fq_from_entries, CallError, HostCallError, PointError,
+};
+use crate::circuits::host::{HostOpConfig, HostOpSelector};
+use crate::host::secp256k1::ecdsa_sign;
+use crate::utils::Limb;
+
+use super::get_selected_entries;
+use crate::host::{ExternalHostCallEntry, ForeignInst};
+
+const TOTAL_CONSTRUCTIONS_VERIFY: usize = 8;
+const TOTAL_CONSTRUCTIONS_RECOVER: usize = 8;
+
+fn secp256k1_point_args(p: Secp256k1Affine, op: ForeignInst) -> Vec<ExternalHostCallEntry> {
+    let mut r = vec![];
+    let coordinates = p.coordinates().unwrap();
+    r.append(&mut crate::adaptor::fr_to_args(*coordinates.x(), 5, 54, op));
+    r.append(&mut crate::adaptor::fr_to_args(*coordinates.y(), 5, 54, op));
+    r.push(ExternalHostCallEntry {
+        op: op as usize,
+        value: p.is_identity().unwrap_u8() as u64,
+        is_ret: false,
+    });
+    r
+}
+
+/// Native check of a pushed key, which is in the group once it is on the curve
+fn check_secp256k1_pk(entries: &[((Fr, Fr), Fr)]) -> Result<(), PointError> {
+    let x = fq_from_entries::<Fp>(&entries[0..5], 54);
+    let y = fq_from_entries::<Fp>(&entries[5..10], 54);
+    check_affine::<Secp256k1Affine>(x, y, &entries[10].0 .0, false)
+}
+
+/// The signature of the zero message under secret key 1 and nonce 1, which fills
+/// the unused slots of both circuits.
+fn secp256k1_default_signature() -> (Fq, Fq, Fq, u64, Secp256k1Affine) {
+    let (r, s, v) = ecdsa_sign(&Fq::one(), &Fq::zero(), &Fq::one()).unwrap();
+    (Fq::zero(), r, s, v, Secp256k1Affine::generator())
+}
+
+// Fq (5 * u54, 3 limbs)
+fn assign_secp256k1_fq(
+    config: &HostOpConfig,
+    region: &mut Region<Fr>,
+    offset: &mut usize,
+    entries: &[((Fr, Fr), Fr)],
+    enable: bool,
+) -> Result<Vec<Limb<Fr>>, Error> {
+    let mut r = vec![];
+    for i in 0..2 {
+        let (limb, _op) = config.assign_merged_operands(
+            region,
+            offset,
+            vec![&entries[2 * i], &entries[2 * i + 1]],
+            Fr::from_u128(1u128 << 54),
+            enable,
+        )?;
+        r.push(limb);
+    }
+    r.push(assign_secp256k1_single(config, region, offset, &entries[4], enable)?);
+    Ok(r)
+}
+
+fn assign_secp256k1_single(
+    config: &HostOpConfig,
+    region: &mut Region<Fr>,
+    offset: &mut usize,
+    entry: &((Fr, Fr), Fr),
+    enable: bool,
+) -> Result<Limb<Fr>, Error> {
+    let ((operand, opcode), index) = *entry;
+    let (limb, _op) = config.assign_one_line(
+        region,
+        offset,
+        operand,
+        opcode,
+        index,
+        operand,
+        Fr::zero(),
+        enable,
+    )?;
+    Ok(limb)
+}
+
+// Point (x, y, is_identity), 7 limbs
+fn assign_secp256k1_point(
+    config: &HostOpConfig,
+    region: &mut Region<Fr>,
+    offset: &mut usize,
+    entries: &[((Fr, Fr), Fr)],
+    enable: bool,
+) -> Result<Vec<Limb<Fr>>, Error> {
+    let mut r = assign_secp256k1_fq(config, region, offset, &entries[0..5], enable)?;
+    r.append(&mut assign_secp256k1_fq(config, region, offset, &entries[5..10], enable)?);
+    r.push(assign_secp256k1_single(config, region, offset, &entries[10], enable)?);
+    Ok(r)
+}
+
+fn into_entries(table: Vec<ExternalHostCallEntry>) -> Vec<((Fr, Fr), Fr)> {
+    table
+        .into_iter()
+        .map(|x| ((Fr::from(x.value), Fr::from(x.op as u64)), Fr::zero()))
+        .collect::<Vec<((Fr, Fr), Fr)>>()
+}
+
+impl HostOpSelector for Secp256k1VerifyChip<Fr> {
+    type Config = Secp256k1ChipConfig;
+    fn configure(
+        meta: &mut ConstraintSystem<Fr>,
+        _shared_advices: &Vec<Column<Advice>>,
+    ) -> Self::Config {
+        Secp256k1VerifyChip::<Fr>::configure(meta)
+    }
+
+    fn construct(c: Self::Config) -> Self {
+        Secp256k1VerifyChip::construct(c)
+    }
+
+    fn opcodes() -> Vec<Fr> {
+        vec![
+            Fr::from(ForeignInst::Secp256k1VerifyMsg as u64),
+            Fr::from(ForeignInst::Secp256k1VerifySig as u64),
+            Fr::from(ForeignInst::Secp256k1VerifyPk as u64),
+            Fr::from(ForeignInst::Secp256k1VerifyResult as u64),
+        ]
+    }
+
+    fn validate(
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+    ) -> Result<(), HostCallError> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        for (call, group) in selected_entries.chunks_exact(SECP256K1VERIFY_SIZE).enumerate() {
+            let start = 3 * SECP256K1FQ_SIZE;
+            check_secp256k1_pk(&group[start..start + SECP256K1POINT_SIZE])
+                .map_err(|e| e.at("secp256k1verify", call, "pk"))?;
+        }
+        Ok(())
+    }
+
+    fn assign(
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+        config: &HostOpConfig,
+    ) -> Result<Vec<Limb<Fr>>, Error> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        assert!(selected_entries.len() % SECP256K1VERIFY_SIZE == 0);
+        let total_used_instructions = selected_entries.len() / SECP256K1VERIFY_SIZE;
+
+        assert_eq!(*offset, 0);
+        let mut r = vec![];
+
+        let (msg, sig_r, sig_s, _, pk) = secp256k1_default_signature();
+        let mut default_table = vec![];
+        default_table.append(&mut crate::adaptor::fr_to_args(msg, 5, 54, ForeignInst::Secp256k1VerifyMsg));
+        default_table.append(&mut crate::adaptor::fr_to_args(sig_r, 5, 54, ForeignInst::Secp256k1VerifySig));
+        default_table.append(&mut crate::adaptor::fr_to_args(sig_s, 5, 54, ForeignInst::Secp256k1VerifySig));
+        default_table.append(&mut secp256k1_point_args(pk, ForeignInst::Secp256k1VerifyPk));
+        default_table.push(ExternalHostCallEntry {
+            op: ForeignInst::Secp256k1VerifyResult as usize,
+            value: 1u64,
+            is_ret: true,
+        });
+        let default_entries = into_entries(default_table);
+
+        let groups = selected_entries
+            .chunks_exact(SECP256K1VERIFY_SIZE)
+            .map(|group| (group, true))
+            .chain(
+                (0..TOTAL_CONSTRUCTIONS_VERIFY - total_used_instructions)
+                    .map(|_| (&default_entries[..], false)),
+            );
+
+        for (group, enable) in groups {
+            // msg, r and s
+            for i in 0..3 {
+                let mut limbs = assign_secp256k1_fq(
+                    config,
+                    region,
+                    offset,
+                    &group[SECP256K1FQ_SIZE * i..SECP256K1FQ_SIZE * (i + 1)],
+                    enable,
+                )?;
+                r.append(&mut limbs);
+            }
+            // public key
+            r.append(&mut assign_secp256k1_point(config, region, offset, &group[15..26], enable)?);
+            // result
+            r.push(assign_secp256k1_single(config, region, offset, &group[26], enable)?);
+        }
+        Ok(r)
+    }
+
+    fn synthesize(
+        &mut self,
+        _offset: &mut usize,
+        arg_cells: &Vec<Limb<Fr>>,
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        self.range_chip.init_table(layouter)?;
+        self.load_secp256k1_verify_circuit(&arg_cells, layouter)?;
+        Ok(())
+    }
+}
+
+impl HostOpSelector for Secp256k1RecoverChip<Fr> {
+    type Config = Secp256k1ChipConfig;
+    fn configure(
+        meta: &mut ConstraintSystem<Fr>,
+        _shared_advices: &Vec<Column<Advice>>,
+    ) -> Self::Config {
+        Secp256k1RecoverChip::<Fr>::configure(meta)
+    }
+
+    fn construct(c: Self::Config) -> Self {
+        Secp256k1RecoverChip::construct(c)
+    }
+
+    fn opcodes() -> Vec<Fr> {
+        vec![
+            Fr::from(ForeignInst::Secp256k1RecoverMsg as u64),
+            Fr::from(ForeignInst::Secp256k1RecoverSig as u64),
+            Fr::from(ForeignInst::Secp256k1RecoverPk as u64),
+            Fr::from(ForeignInst::Secp256k1RecoverResult as u64),
+        ]
+    }
+
+    fn validate(
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+    ) -> Result<(), HostCallError> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        for (call, group) in selected_entries.chunks_exact(SECP256K1RECOVER_SIZE).enumerate() {
+            let v = group[3 * SECP256K1FQ_SIZE].0 .0;
+            if v != Fr::zero() && v != Fr::one() {
+                return Err(CallError::RecoveryId.at("secp256k1recover", call, "v").into());
+            }
+            let start = 3 * SECP256K1FQ_SIZE + 1;
+            check_secp256k1_pk(&group[start..start + SECP256K1POINT_SIZE])
+                .map_err(|e| e.at("secp256k1recover", call, "pk"))?;
+        }
+        Ok(())
+    }
+
+    fn assign(
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+        config: &HostOpConfig,
+    ) -> Result<Vec<Limb<Fr>>, Error> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        assert!(selected_entries.len() % SECP256K1RECOVER_SIZE == 0);
+        let total_used_instructions = selected_entries.len() / SECP256K1RECOVER_SIZE;
+
+        assert_eq!(*offset, 0);
+        let mut r = vec![];
+
+        let (msg, sig_r, sig_s, v, pk) = secp256k1_default_signature();
+        let mut default_table = vec![];
+        default_table.append(&mut crate::adaptor::fr_to_args(msg, 5, 54, ForeignInst::Secp256k1RecoverMsg));
+        default_table.append(&mut crate::adaptor::fr_to_args(sig_r, 5, 54, ForeignInst::Secp256k1RecoverSig));
+        default_table.append(&mut crate::adaptor::fr_to_args(sig_s, 5, 54, ForeignInst::Secp256k1RecoverSig));
+        default_table.push(ExternalHostCallEntry {
+            op: ForeignInst::Secp256k1RecoverSig as usize,
+            value: v,
+            is_ret: false,
+        });
+        default_table.append(&mut secp256k1_point_args(pk, ForeignInst::Secp256k1RecoverPk));
+        default_table.push(ExternalHostCallEntry {
+            op: ForeignInst::Secp256k1RecoverResult as usize,
+            value: 1u64,
+            is_ret: true,
+        });
+        let default_entries = into_entries(default_table);
+
+        let groups = selected_entries
+            .chunks_exact(SECP256K1RECOVER_SIZE)
+            .map(|group| (group, true))
+            .chain(
+                (0..TOTAL_CONSTRUCTIONS_RECOVER - total_used_instructions)
+                    .map(|_| (&default_entries[..], false)),
+            );
+
+        for (group, enable) in groups {
+            // msg, r and s
+            for i in 0..3 {
+                let mut limbs = assign_secp256k1_fq(
+                    config,
+                    region,
+                    offset,
+                    &group[SECP256K1FQ_SIZE * i..SECP256K1FQ_SIZE * (i + 1)],
+                    enable,
+                )?;
+                r.append(&mut limbs);
+            }
+            // recovery id
+            r.push(assign_secp256k1_single(config, region, offset, &group[15], enable)?);
+            // recovered public key, the identity if the recovery failed
+            r.append(&mut assign_secp256k1_point(config, region, offset, &group[16..27], enable)?);
+            // result
+            r.push(assign_secp256k1_single(config, region, offset, &group[27], enable)?);
+        }
+        Ok(r)
+    }
+
+    fn synthesize(
+        &mut self,
+        _offset: &mut usize,
+        arg_cells: &Vec<Limb<Fr>>,
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        self.range_chip.init_table(layouter)?;
+        self.load_secp256k1_recover_circuit(&arg_cells, layouter)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,173 @@
+use halo2_proofs::arithmetic::{Field, FieldExt};
+use halo2_proofs::pairing::bn256::Fr;
+use halo2_proofs::pairing::secp256r1::Fq;
+use halo2_proofs::{
+    circuit::{Layouter, Region},
+    plonk::{Advice, Column, ConstraintSystem, Error},
+};
+
+pub const SECP256R1FQ_SIZE: usize = 5;
+pub const SECP256R1POINT_SIZE: usize = 11;
+const SECP256R1VERIFY_SIZE: usize = 3 * SECP256R1FQ_SIZE + SECP256R1POINT_SIZE + 1;
+
+use crate::circuits::secp256r1::{Secp256r1ChipConfig, Secp256r1VerifyChip};
+
+use crate::circuits::host::{HostOpConfig, HostOpSelector};
+use crate::host::secp256r1::{ecdsa_sign, P256Point};
+use crate::utils::Limb;
+
+use super::get_selected_entries;
+use crate::host::{ExternalHostCallEntry, ForeignInst};
+
+const TOTAL_CONSTRUCTIONS: usize = 4;
+
+fn secp256r1_point_args(p: &P256Point, op: ForeignInst) -> Vec<ExternalHostCallEntry> {
+    let mut r = vec![];
+    let (x, y) = p.to_affine().unwrap();
+    r.append(&mut crate::adaptor::fr_to_args(x, 5, 54, op));
+    r.append(&mut crate::adaptor::fr_to_args(y, 5, 54, op));
+    r.push(ExternalHostCallEntry {
+        op: op as usize,
+        value: p.is_identity() as u64,
+        is_ret: false,
+    });
+    r
+}
+
+// Fq (5 * u54, 3 limbs)
+fn assign_secp256r1_fq(
+    config: &HostOpConfig,
+    region: &mut Region<Fr>,
+    offset: &mut usize,
+    entries: &[((Fr, Fr), Fr)],
+    enable: bool,
+) -> Result<Vec<Limb<Fr>>, Error> {
+    let mut r = vec![];
+    for i in 0..2 {
+        let (limb, _op) = config.assign_merged_operands(
+            region,
+            offset,
+            vec![&entries[2 * i], &entries[2 * i + 1]],
+            Fr::from_u128(1u128 << 54),
+            enable,
+        )?;
+        r.push(limb);
+    }
+    r.push(assign_secp256r1_single(config, region, offset, &entries[4], enable)?);
+    Ok(r)
+}
+
+fn assign_secp256r1_single(
+    config: &HostOpConfig,
+    region: &mut Region<Fr>,
+    offset: &mut usize,
+    entry: &((Fr, Fr), Fr),
+    enable: bool,
+) -> Result<Limb<Fr>, Error> {
+    let ((operand, opcode), index) = *entry;
+    let (limb, _op) = config.assign_one_line(
+        region,
+        offset,
+        operand,
+        opcode,
+        index,
+        operand,
+        Fr::zero(),
+        enable,
+    )?;
+    Ok(limb)
+}
+
+impl HostOpSelector for Secp256r1VerifyChip<Fr> {
+    type Config = Secp256r1ChipConfig;
+    fn configure(
+        meta: &mut ConstraintSystem<Fr>,
+        _shared_advices: &Vec<Column<Advice>>,
+    ) -> Self::Config {
+        Secp256r1VerifyChip::<Fr>::configure(meta)
+    }
+
+    fn construct(c: Self::Config) -> Self {
+        Secp256r1VerifyChip::construct(c)
+    }
+
+    fn opcodes() -> Vec<Fr> {
+        vec![
+            Fr::from(ForeignInst::Secp256r1VerifyMsg as u64),
+            Fr::from(ForeignInst::Secp256r1VerifySig as u64),
+            Fr::from(ForeignInst::Secp256r1VerifyPk as u64),
+            Fr::from(ForeignInst::Secp256r1VerifyResult as u64),
+        ]
+    }
+
+    fn assign(
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        shared_operands: &Vec<Fr>,
+        shared_opcodes: &Vec<Fr>,
+        config: &HostOpConfig,
+    ) -> Result<Vec<Limb<Fr>>, Error> {
+        let opcodes = Self::opcodes();
+        let selected_entries = get_selected_entries(shared_operands, shared_opcodes, &opcodes);
+        assert!(selected_entries.len() % SECP256R1VERIFY_SIZE == 0);
+        let total_used_instructions = selected_entries.len() / SECP256R1VERIFY_SIZE;
+
+        assert_eq!(*offset, 0);
+        let mut r = vec![];
+
+        // the signature of the zero message under secret key 1 and nonce 1
+        let (sig_r, sig_s) = ecdsa_sign(&Fq::one(), &Fq::zero(), &Fq::one()).unwrap();
+        let mut default_table = vec![];
+        default_table.append(&mut crate::adaptor::fr_to_args(Fq::zero(), 5, 54, ForeignInst::Secp256r1VerifyMsg));
+        default_table.append(&mut crate::adaptor::fr_to_args(sig_r, 5, 54, ForeignInst::Secp256r1VerifySig));
+        default_table.append(&mut crate::adaptor::fr_to_args(sig_s, 5, 54, ForeignInst::Secp256r1VerifySig));
+        default_table.append(&mut secp256r1_point_args(&P256Point::generator(), ForeignInst::Secp256r1VerifyPk));
+        default_table.push(ExternalHostCallEntry {
+            op: ForeignInst::Secp256r1VerifyResult as usize,
+            value: 1u64,
+            is_ret: true,
+        });
+        let default_entries: Vec<((Fr, Fr), Fr)> = default_table
+            .into_iter()
+            .map(|x| ((Fr::from(x.value), Fr::from(x.op as u64)), Fr::zero()))
+            .collect::<Vec<((Fr, Fr), Fr)>>();
+
+        let groups = selected_entries
+            .chunks_exact(SECP256R1VERIFY_SIZE)
+            .map(|group| (group, true))
+            .chain(
+                (0..TOTAL_CONSTRUCTIONS - total_used_instructions)
+                    .map(|_| (&default_entries[..], false)),
+            );
+
+        for (group, enable) in groups {
+            // msg, r, s and the public key coordinates
+            for i in 0..5 {
+                let mut limbs = assign_secp256r1_fq(
+                    config,
+                    region,
+                    offset,
+                    &group[SECP256R1FQ_SIZE * i..SECP256R1FQ_SIZE * (i + 1)],
+                    enable,
+                )?;
+                r.append(&mut limbs);
+            }
+            // whether the public key is the identity
+            r.push(assign_secp256r1_single(config, region, offset, &group[25], enable)?);
+            // result
+            r.push(assign_secp256r1_single(config, region, offset, &group[26], enable)?);
+        }
+        Ok(r)
+    }
+
+    fn synthesize(
+        &mut self,
+        _offset: &mut usize,
+        arg_cells: &Vec<Limb<Fr>>,
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        self.range_chip.init_table(layouter)?;
+        self.load_secp256r1_verify_circuit(&arg_cells, layouter)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,332 @@
+use crate::utils::field_to_bn;
+use ff::Field;
+use halo2_proofs::arithmetic::{BaseExt, CurveAffine};
+use halo2_proofs::pairing::bn256::Fr;
+use halo2_proofs::pairing::group::prime::PrimeCurveAffine;
+use halo2_proofs::pairing::group::Group;
+use num_bigint::BigUint;
+use std::fmt;
+
+/* Native checks of the points that the guest pushes to the curve adaptors.
+ *
+ * A bad call of the host trace used to surface as an unsatisfied constraint deep
+ * inside the prover. The adaptors check their calls in HostOpSelector::validate
+ * before synthesis and report the host call and the argument that is wrong: a pushed
+ * point as an InvalidPoint and the other arguments of a call as an InvalidCall.
+ */
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PointError {
+    NonCanonical,
+    InvalidFlag,
+    NotOnCurve,
+    NotInSubgroup,
+}
+
+impl fmt::Display for PointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PointError::NonCanonical => write!(f, "has a coordinate out of the base field"),
+            PointError::InvalidFlag => write!(f, "has an identity flag other than 0 or 1"),
+            PointError::NotOnCurve => write!(f, "is not on the curve"),
+            PointError::NotInSubgroup => write!(f, "is not in the prime order subgroup"),
+        }
+    }
+}
+
+impl PointError {
+    /// Names the argument arg of the call-th host call of the op opname
+    pub fn at(self, opname: &'static str, call: usize, arg: &'static str) -> InvalidPoint {
+        InvalidPoint {
+            opname,
+            call,
+            arg,
+            error: self,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct InvalidPoint {
+    pub opname: &'static str,
+    pub call: usize,
+    pub arg: &'static str,
+    pub error: PointError,
+}
+
+impl fmt::Display for InvalidPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} call {}: {} {}",
+            self.opname, self.call, self.arg, self.error
+        )
+    }
+}
+
+impl std::error::Error for InvalidPoint {}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CallError {
+    UnknownOp,
+    NotInvertible,
+    PairCount,
+    HashMismatch,
+    HashVersion,
+    TooManyCalls,
+    RecoveryId,
+}
+
+impl fmt::Display for CallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CallError::UnknownOp => write!(f, "is not an operation of the op"),
+            CallError::NotInvertible => write!(f, "is zero and has no inverse"),
+            CallError::PairCount => {
+                write!(f, "is not preceded by one to the maximal number of pairs of the op")
+            }
+            CallError::HashMismatch => write!(f, "is not the hash of the pushed arguments"),
+            CallError::HashVersion => write!(f, "does not start with the version byte 0x01"),
+            CallError::TooManyCalls => write!(f, "is beyond the calls the circuit has room for"),
+            CallError::RecoveryId => write!(f, "is not a recovery id of 0 or 1"),
+        }
+    }
+}
+
+impl CallError {
+    /// Names the argument arg of the call-th host call of the op opname
+    pub fn at(self, opname: &'static str, call: usize, arg: &'static str) -> InvalidCall {
+        InvalidCall {
+            opname,
+            call,
+            arg,
+            error: self,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct InvalidCall {
+    pub opname: &'static str,
+    pub call: usize,
+    pub arg: &'static str,
+    pub error: CallError,
+}
+
+impl fmt::Display for InvalidCall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} call {}: {} {}",
+            self.opname, self.call, self.arg, self.error
+        )
+    }
+}
+
+impl std::error::Error for InvalidCall {}
+
+/// The error of HostOpSelector::validate
+#[derive(Clone, Debug, PartialEq)]
+pub enum HostCallError {
+    Point(InvalidPoint),
+    Call(InvalidCall),
+}
+
+impl fmt::Display for HostCallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HostCallError::Point(e) => e.fmt(f),
+            HostCallError::Call(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for HostCallError {}
+
+impl From<InvalidPoint> for HostCallError {
+    fn from(e: InvalidPoint) -> HostCallError {
+        HostCallError::Point(e)
+    }
+}
+
+impl From<InvalidCall> for HostCallError {
+    fn from(e: InvalidCall) -> HostCallError {
+        HostCallError::Call(e)
+    }
+}
+
+/// The field element of the little endian limbs of bits bits, None if it is not canonical
+pub fn fq_from_entries<F: BaseExt>(entries: &[((Fr, Fr), Fr)], bits: usize) -> Option<F> {
+    let mut bn = BigUint::from(0u64);
+    for ((operand, _), _) in entries.iter().rev() {
+        bn = (bn << bits) + field_to_bn(operand);
+    }
+    let modulus = field_to_bn(&-F::one()) + 1u64;
+    if bn < modulus {
+        let mut bytes = bn.to_bytes_le();
+        bytes.resize(64, 0);
+        F::read(&mut &bytes[..]).ok()
+    } else {
+        None
+    }
+}
+
+/// Whether the order of the group kills p, by double and add over its bits
+fn is_torsion_free<C: CurveAffine>(p: &C) -> bool {
+    let order = field_to_bn(&-C::ScalarExt::one()) + 1u64;
+    let p = p.to_curve();
+    let mut acc = C::CurveExt::identity();
+    for i in (0..order.bits()).rev() {
+        acc = acc.double();
+        if order.bit(i) {
+            acc = acc + p;
+        }
+    }
+    bool::from(acc.is_identity())
+}
+
+/// Checks a pushed affine point with its identity flag. The coordinates of the
+/// identity are not checked beyond being canonical since the circuits ignore them.
+pub fn check_affine<C: CurveAffine>(
+    x: Option<C::Base>,
+    y: Option<C::Base>,
+    flag: &Fr,
+    check_subgroup: bool,
+) -> Result<(), PointError> {
+    let (x, y) = x.zip(y).ok_or(PointError::NonCanonical)?;
+    if *flag == Fr::one() {
+        return Ok(());
+    }
+    if *flag != Fr::zero() {
+        return Err(PointError::InvalidFlag);
+    }
+    let p: C = Option::from(C::from_xy(x, y)).ok_or(PointError::NotOnCurve)?;
+    if check_subgroup && !is_torsion_free(&p) {
+        return Err(PointError::NotInSubgroup);
+    }
+    Ok(())
+}
+
+/// Checks that every call of an op ends with its result entry after 1 to max_pairs
+/// pairs of pair_size entries, a call of another number of entries is not cut to fit
+pub fn check_call_sizes(
+    entries: &[((Fr, Fr), Fr)],
+    result_op: Fr,
+    pair_size: usize,
+    max_pairs: usize,
+    opname: &'static str,
+) -> Result<(), InvalidCall> {
+    let mut call = 0;
+    let mut len = 0;
+    for ((_, opcode), _) in entries.iter() {
+        if *opcode == result_op {
+            if len == 0 || len % pair_size != 0 || len / pair_size > max_pairs {
+                return Err(CallError::PairCount.at(opname, call, "result"));
+            }
+            call += 1;
+            len = 0;
+        } else {
+            len += 1;
+        }
+    }
+    if len != 0 {
+        return Err(CallError::PairCount.at(opname, call, "result"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        check_affine, check_call_sizes, fq_from_entries, CallError, HostCallError, PointError,
+    };
+    use crate::host::ForeignInst;
+    use ff::Field;
+    use halo2_proofs::arithmetic::CurveAffine;
+    use halo2_proofs::pairing::bls12_381::{Fq as Bls381Fq, G1Affine as Bls381G1Affine};
+    use halo2_proofs::pairing::bn256::{Fq, Fr, G1Affine, G2Affine};
+
+    fn entries(f: Fq) -> Vec<((Fr, Fr), Fr)> {
+        crate::adaptor::fr_to_args(f, 5, 54, ForeignInst::Bn254SumG1)
+            .into_iter()
+            .map(|x| ((Fr::from(x.value), Fr::from(x.op as u64)), Fr::zero()))
+            .collect()
+    }
+
+    #[test]
+    fn test_fq_from_entries() {
+        let f = Fq::from(123456789u64).invert().unwrap();
+        assert_eq!(fq_from_entries::<Fq>(&entries(f), 54), Some(f));
+        // the modulus itself is not canonical
+        let mut e = entries(-Fq::one());
+        e[0].0 .0 += Fr::one();
+        assert_eq!(fq_from_entries::<Fq>(&e, 54), None);
+    }
+
+    #[test]
+    fn test_check_call_sizes() {
+        // calls of pairs of 2 entries
+        let call = |entries: usize| {
+            let mut e = vec![((Fr::zero(), Fr::one()), Fr::zero()); entries];
+            e.push(((Fr::one(), Fr::from(2u64)), Fr::zero()));
+            e
+        };
+        let check = |e: &[((Fr, Fr), Fr)]| check_call_sizes(e, Fr::from(2u64), 2, 4, "paircheck");
+        assert_eq!(check(&call(8)), Ok(()));
+        assert_eq!(check(&[call(6), call(8), call(2)].concat()), Ok(()));
+        assert_eq!(check(&[]), Ok(()));
+        assert_eq!(check(&call(0)), Err(CallError::PairCount.at("paircheck", 0, "result")));
+        // half a pair and 5 pairs
+        let e = [call(4), call(5)].concat();
+        assert_eq!(check(&e), Err(CallError::PairCount.at("paircheck", 1, "result")));
+        let e = [call(8), call(10)].concat();
+        assert_eq!(check(&e), Err(CallError::PairCount.at("paircheck", 1, "result")));
+        assert_eq!(
+            check(&call(8)[0..8]),
+            Err(CallError::PairCount.at("paircheck", 0, "result"))
+        );
+    }
+
+    #[test]
+    fn test_host_call_error() {
+        let e = HostCallError::from(PointError::NotOnCurve.at("bn254sumg1", 3, "p"));
+        assert_eq!(e.to_string(), "bn254sumg1 call 3: p is not on the curve");
+        let e = HostCallError::from(CallError::UnknownOp.at("bn256gt", 0, "op"));
+        assert_eq!(e.to_string(), "bn256gt call 0: op is not an operation of the op");
+    }
+
+    #[test]
+    fn test_check_affine() {
+        let g = G1Affine::generator();
+        let flag = Fr::zero();
+        assert_eq!(check_affine::<G1Affine>(Some(g.x), Some(g.y), &flag, false), Ok(()));
+        assert_eq!(
+            check_affine::<G1Affine>(Some(g.x), Some(g.x), &flag, false),
+            Err(PointError::NotOnCurve)
+        );
+        assert_eq!(
+            check_affine::<G1Affine>(Some(g.x), Some(g.y), &Fr::from(2u64), false),
+            Err(PointError::InvalidFlag)
+        );
+        assert_eq!(
+            check_affine::<G1Affine>(Some(Fq::zero()), Some(Fq::zero()), &Fr::one(), false),
+            Ok(())
+        );
+        let q = G2Affine::generator();
+        assert_eq!(check_affine::<G2Affine>(Some(q.x), Some(q.y), &flag, true), Ok(()));
+
+        // a point of y^2 = x^3 + 4 outside of the subgroup of bls12-381
+        let mut x = Bls381Fq::zero();
+        let p = loop {
+            x += Bls381Fq::one();
+            let y: Option<Bls381Fq> = Option::from((x.square() * x + Bls381Fq::from(4u64)).sqrt());
+            if let Some(y) = y {
+                break Bls381G1Affine::from_xy(x, y).unwrap();
+            }
+        };
+        assert_eq!(
+            check_affine::<Bls381G1Affine>(Some(p.x), Some(p.y), &flag, true),
+            Err(PointError::NotInSubgroup)
+        );
+    }
+}
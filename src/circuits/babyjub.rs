@@ -1,10 +1,20 @@
 use crate::circuits::{CommonGateConfig, Limb};
+use crate::host::jubjub::{
+    Point as HostPoint, A_BIG, BASE, BASE_TABLES, D_BIG, FIXED_BASE_WINDOWS,
+    FIXED_BASE_WINDOW_BITS, SUBGROUP_ORDER,
+};
+use crate::utils::{bn_to_field, field_to_bn, GateCell};
+use crate::{
+    customized_circuits, customized_circuits_expand, item_count, table_item, value_for_assign,
+};
 
 use halo2_proofs::{
     arithmetic::FieldExt,
     circuit::{Chip, Region},
-    plonk::{ConstraintSystem, Error, Advice, Column},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector, VirtualCells},
+    poly::Rotation,
 };
+use num_bigint::BigUint;
 use std::marker::PhantomData;
 
 pub struct AltJubChip<F: FieldExt> {
@@ -37,6 +47,38 @@ impl<F: FieldExt> Chip<F> for AltJubChip<F> {
     }
 }
 
+const WINDOW_SIZE: usize = 1 << FIXED_BASE_WINDOW_BITS;
+
+/// The number of scalar bits that one window of AltJubChip::msm consumes
+const MSM_WINDOW_BITS: usize = 2;
+
+/// The number of (point, scalar) pairs of a batched msm call
+pub const BATCH_MSM_POINTS: usize = 16;
+
+/// The coefficients (a, d) of the curve a * x^2 + y^2 = 1 + d * x^2 * y^2
+fn curve_coeffs<F: FieldExt>() -> (F, F) {
+    (bn_to_field(&A_BIG), bn_to_field(&D_BIG))
+}
+
+pub(crate) fn host_point_to_field<F: FieldExt>(p: &HostPoint) -> (F, F) {
+    (bn_to_field(&field_to_bn(&p.x)), bn_to_field(&field_to_bn(&p.y)))
+}
+
+/// The coefficients of the multilinear polynomial over the window bits (b0 is the
+/// lowest) which evaluates to values[m] when m = sum b_i * 2^i. The coefficient at
+/// mask S is the one of the monomial prod_{i in S} b_i.
+fn multilinear_coeffs<F: FieldExt>(values: &[F]) -> Vec<F> {
+    let mut coeffs = values.to_vec();
+    for i in 0..FIXED_BASE_WINDOW_BITS {
+        for mask in 0..WINDOW_SIZE {
+            if mask & (1 << i) != 0 {
+                coeffs[mask] = coeffs[mask] - coeffs[mask ^ (1 << i)];
+            }
+        }
+    }
+    coeffs
+}
+
 impl<F: FieldExt> JubState<F> {
     pub fn initialize(
         &mut self,
@@ -91,6 +133,19 @@ impl<F: FieldExt> AltJubChip<F> {
         scalar: &Limb<F>,
         reset: &Limb<F>,
         result: &Point<F>,
+    ) -> Result<(), Error> {
+        let operand = self.mul_scalar(region, offset, scalar, point)?;
+        self.accumulate(region, offset, &operand, reset, result)
+    }
+
+    /// acc = (reset ? identity : acc) + operand and constrain the result to be acc
+    pub fn accumulate(
+        &mut self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        operand: &Point<F>,
+        reset: &Limb<F>,
+        result: &Point<F>,
     ) -> Result<(), Error> {
         self.state.acc.x = self.config.select(
             region,
@@ -112,9 +167,7 @@ impl<F: FieldExt> AltJubChip<F> {
             0,
         )?;
 
-        let operand = self.mul_scalar(region, offset, scalar, point)?;
-
-        let acc = self.add(region, offset, &self.state.acc, &operand)?;
+        let acc = self.add(region, offset, &self.state.acc, operand)?;
 
         self.state.acc = acc;
         region.constrain_equal(
@@ -144,15 +197,7 @@ impl<F: FieldExt> AltJubChip<F> {
          * x3 = (x1y2 + y1x2)/(1 + lambda)
          * y3 = (y1y2 - ax1x2)/(1 - lambda)
          */
-        // constants
-        let a = F::from_str_vartime(
-            "21888242871839275222246405745257275088548364400416034343698204186575808495616",
-        )
-        .unwrap();
-        let d = F::from_str_vartime(
-            "12181644023421730124874158521699555681764249180949974110617291017600649128846",
-        )
-        .unwrap();
+        let (a, d) = curve_coeffs::<F>();
 
         // constraint lambda
         let x1x2 = lhs.x.value * rhs.x.value;
@@ -472,6 +517,197 @@ impl<F: FieldExt> AltJubChip<F> {
         Ok(Point { x: x3, y: y3 })
     }
 
+    fn mul(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        a: &Limb<F>,
+        b: &Limb<F>,
+    ) -> Result<Limb<F>, Error> {
+        let l = self.config.assign_line(
+            region,
+            &mut (),
+            offset,
+            [
+                Some(a.clone()),
+                None,
+                None,
+                Some(b.clone()),
+                Some(Limb::new(None, a.value * b.value)),
+                None,
+            ],
+            [
+                None,
+                None,
+                None,
+                None,
+                Some(-F::one()),
+                None,
+                Some(F::one()),
+                None,
+                None,
+            ],
+            0,
+        )?;
+        Ok(l[2].clone())
+    }
+
+    /// Constrain the point to be on the curve a * x^2 + y^2 = 1 + d * x^2 * y^2
+    pub fn assert_on_curve(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        p: &Point<F>,
+    ) -> Result<(), Error> {
+        let (a, d) = curve_coeffs::<F>();
+        let x2 = self.mul(region, offset, &p.x, &p.x)?;
+        let y2 = self.mul(region, offset, &p.y, &p.y)?;
+        self.config.assign_line(
+            region,
+            &mut (),
+            offset,
+            [Some(x2), None, None, Some(y2), None, None],
+            [
+                Some(a),
+                None,
+                None,
+                Some(F::one()),
+                None,
+                None,
+                Some(-d),
+                None,
+                Some(-F::one()),
+            ],
+            0,
+        )?;
+        Ok(())
+    }
+
+    /// Constrain the point to be in the prime order subgroup. The curve has cofactor 8,
+    /// thus p is in the subgroup iff p = 8 * q for some q on the curve. q is witnessed
+    /// as (1/8 mod the subgroup order) * p.
+    pub fn assert_in_subgroup(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        p: &Point<F>,
+    ) -> Result<(), Error> {
+        let host_p = HostPoint {
+            x: bn_to_field(&field_to_bn(&p.x.value)),
+            y: bn_to_field(&field_to_bn(&p.y.value)),
+        };
+        let inv8 = BigUint::from(8u64).modpow(&(SUBGROUP_ORDER.clone() - 2u64), &SUBGROUP_ORDER);
+        let (qx, qy) = host_point_to_field::<F>(&host_p.mul_scalar(&inv8));
+        let l = self.config.assign_witness(
+            region,
+            &mut (),
+            offset,
+            [
+                Some(Limb::new(None, qx)),
+                Some(Limb::new(None, qy)),
+                None,
+                None,
+                None,
+            ],
+            0,
+        )?;
+        let q = Point {
+            x: l[0].clone(),
+            y: l[1].clone(),
+        };
+        self.assert_on_curve(region, offset, &q)?;
+        let q2 = self.add(region, offset, &q, &q)?;
+        let q4 = self.add(region, offset, &q2, &q2)?;
+        let q8 = self.add(region, offset, &q4, &q4)?;
+        region.constrain_equal(
+            p.x.cell.as_ref().unwrap().cell(),
+            q8.x.cell.as_ref().unwrap().cell(),
+        )?;
+        region.constrain_equal(
+            p.y.cell.as_ref().unwrap().cell(),
+            q8.y.cell.as_ref().unwrap().cell(),
+        )?;
+        Ok(())
+    }
+
+    /// table[m] for the 4 window bits (lowest first) of m where the table is known
+    /// in advance. The coordinates are evaluated as the multilinear polynomials of
+    /// the bits which interpolate the table, thus the table lives in the fixed
+    /// coefficients of the gates.
+    pub fn assign_window(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        table: &[HostPoint],
+        bits: &[Limb<F>],
+    ) -> Result<Point<F>, Error> {
+        assert!(table.len() == WINDOW_SIZE && bits.len() == FIXED_BASE_WINDOW_BITS);
+        // monomials[mask] = prod of the bits in mask
+        let mut monomials: Vec<Option<Limb<F>>> = vec![None; WINDOW_SIZE];
+        for mask in 1..WINDOW_SIZE {
+            let low = mask.trailing_zeros() as usize;
+            let rest = mask ^ (1 << low);
+            monomials[mask] = Some(if rest == 0 {
+                bits[low].clone()
+            } else {
+                self.mul(region, offset, monomials[rest].as_ref().unwrap(), &bits[low])?
+            });
+        }
+
+        let table = table
+            .iter()
+            .map(|p| host_point_to_field::<F>(p))
+            .collect::<Vec<_>>();
+        let mut coordinate = |values: Vec<F>| {
+            let coeffs = multilinear_coeffs(&values);
+            let inputs = monomials
+                .iter()
+                .zip(coeffs.iter())
+                .skip(1)
+                .map(|(m, c)| (m.as_ref().unwrap(), *c))
+                .collect::<Vec<_>>();
+            self.config
+                .sum_with_constant(region, &mut (), offset, inputs, Some(coeffs[0]))
+        };
+        let x = coordinate(table.iter().map(|p| p.0).collect())?;
+        let y = coordinate(table.iter().map(|p| p.1).collect())?;
+        Ok(Point { x, y })
+    }
+
+    /// lhs * B for a base B known in advance, given its window tables (see
+    /// host::jubjub::fixed_base_tables). Every 4 bits of the scalar cost one table
+    /// lookup and one addition instead of 4 doublings and 4 conditional additions.
+    pub fn mul_scalar_fixed(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        lhs: &Limb<F>,
+        tables: &[Vec<HostPoint>],
+    ) -> Result<Point<F>, Error> {
+        assert!(tables.len() == FIXED_BASE_WINDOWS);
+        let mut scalar_bin: Vec<Limb<F>> = vec![];
+        self.config.decompose_limb(
+            region,
+            &mut (),
+            offset,
+            lhs,
+            &mut scalar_bin,
+            FIXED_BASE_WINDOWS * FIXED_BASE_WINDOW_BITS,
+        )?;
+        // decompose_limb returns the bits from the highest
+        scalar_bin.reverse();
+
+        let mut ret: Option<Point<F>> = None;
+        for (window, table) in scalar_bin.chunks_exact(FIXED_BASE_WINDOW_BITS).zip(tables.iter()) {
+            let p = self.assign_window(region, offset, table, window)?;
+            ret = Some(match ret {
+                None => p,
+                Some(acc) => self.add(region, offset, &acc, &p)?,
+            });
+        }
+        Ok(ret.unwrap())
+    }
+
     pub fn mul_scalar(
         &self,
         region: &mut Region<F>,
@@ -485,8 +721,8 @@ impl<F: FieldExt> AltJubChip<F> {
 
         // get the additive identity point
         let iden_ele = Point {
-            x: Limb::new(None, F::zero()),
-            y: Limb::new(None, F::one()),
+            x: self.config.assign_constant(region, &mut (), offset, &F::zero())?,
+            y: self.config.assign_constant(region, &mut (), offset, &F::one())?,
         };
 
         let mut ret = iden_ele.clone();
@@ -500,8 +736,8 @@ impl<F: FieldExt> AltJubChip<F> {
                 &mut (),
                 offset,
                 &limb,
-                &Limb::new(None, iden_ele.clone().x.value),
-                &Limb::new(None, temp.clone().x.value),
+                &iden_ele.x,
+                &temp.x,
                 0,
             )?;
             let add_y = self.config.select(
@@ -509,8 +745,8 @@ impl<F: FieldExt> AltJubChip<F> {
                 &mut (),
                 offset,
                 &limb,
-                &Limb::new(None, iden_ele.clone().y.value),
-                &Limb::new(None, temp.clone().y.value),
+                &iden_ele.y,
+                &temp.y,
                 0,
             )?;
             let point_to_add = Point { x: add_x, y: add_y }; // this would be identity element if the cond is not satisfied
@@ -519,6 +755,403 @@ impl<F: FieldExt> AltJubChip<F> {
         }
         Ok(ret)
     }
+
+    /// table[m] for the window bits of m given from the highest, by a tree of selects
+    fn select_window(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        table: &[Point<F>],
+        bits: &[Limb<F>],
+    ) -> Result<Point<F>, Error> {
+        let mut candidates = table[0..1 << bits.len()].to_vec();
+        for bit in bits.iter().rev() {
+            let mut selected = vec![];
+            for pair in candidates.chunks_exact(2) {
+                let x = self
+                    .config
+                    .select(region, &mut (), offset, bit, &pair[0].x, &pair[1].x, 0)?;
+                let y = self
+                    .config
+                    .select(region, &mut (), offset, bit, &pair[0].y, &pair[1].y, 0)?;
+                selected.push(Point { x, y });
+            }
+            candidates = selected;
+        }
+        Ok(candidates.pop().unwrap())
+    }
+
+    /* sum s_i * p_i over windows of MSM_WINDOW_BITS bits with the doublings shared by
+     * all points, see circuits::g1::g1_msm. Every point costs the additions of its
+     * table of multiples and one select tree and one addition per window instead of
+     * a doubling, two selects and an addition per bit in mul_scalar. The addition is
+     * complete, so the identity and zero scalars need no special case.
+     */
+    pub fn msm(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        pairs: &[(Point<F>, Limb<F>)],
+    ) -> Result<Point<F>, Error> {
+        let identity = Point {
+            x: self.config.assign_constant(region, &mut (), offset, &F::zero())?,
+            y: self.config.assign_constant(region, &mut (), offset, &F::one())?,
+        };
+        let mut tables = vec![];
+        let mut windows = vec![];
+        for (point, scalar) in pairs.iter() {
+            let mut table = vec![identity.clone(), point.clone()];
+            for i in 2..1 << MSM_WINDOW_BITS {
+                let multiple = self.add(region, offset, &table[i - 1], point)?;
+                table.push(multiple);
+            }
+            tables.push(table);
+
+            let mut scalar_bin: Vec<Limb<F>> = vec![];
+            self.config
+                .decompose_limb(region, &mut (), offset, scalar, &mut scalar_bin, 256)?;
+            // decompose_limb returns the bits from the highest, so are the windows
+            windows.push(
+                scalar_bin
+                    .chunks(MSM_WINDOW_BITS)
+                    .map(|w| w.to_vec())
+                    .collect::<Vec<_>>(),
+            );
+        }
+
+        let mut acc = identity;
+        for w in 0..256 / MSM_WINDOW_BITS {
+            if w != 0 {
+                for _ in 0..MSM_WINDOW_BITS {
+                    acc = self.add(region, offset, &acc, &acc)?;
+                }
+            }
+            for (table, window) in tables.iter().zip(windows.iter()) {
+                let addend = self.select_window(region, offset, table, &window[w])?;
+                acc = self.add(region, offset, &acc, &addend)?;
+            }
+        }
+        Ok(acc)
+    }
+}
+
+#[rustfmt::skip]
+customized_circuits!(MsmRouteConfig, 2, 5, 14, 0,
+    | px   | py    | s    | rx   | ry  |  c0  | c1  | c2  | c3  | cd  | cdn | c   | c03  | c12  | lookup_hint | lookup_ind  | common_sel | product_sel | query_sel
+    | nil  | nil   | nil  | nil  | nil |  nil | nil | nil | nil | nil | nil | nil | nil  | nil  | nil         | nil         |    nil     |     nil     |    nil
+);
+
+impl MsmRouteConfig {
+    // configured as an extend of CommonGateConfig with one more fixed column
+    pub fn configure<F: FieldExt>(
+        cs: &mut ConstraintSystem<F>,
+        config: &CommonGateConfig,
+    ) -> MsmRouteConfig {
+        let mut fixed = [config.fixed[0]; 14];
+        fixed[0..13].copy_from_slice(&config.fixed);
+        fixed[13] = cs.fixed_column();
+        let route = MsmRouteConfig {
+            fixed,
+            selector: config.selector,
+            witness: config.witness,
+        };
+
+        // product_sel is used as the tag of the table so that the lookup stays at degree 5
+        cs.lookup_any("jubjub msm product route", |meta| {
+            let query = route.get_expr(meta, Self::query_sel());
+            let mut lookup = vec![(query.clone(), route.get_expr(meta, Self::product_sel()))];
+            for cell in [Self::px(), Self::py(), Self::s(), Self::rx(), Self::ry()] {
+                let v = route.get_expr(meta, cell);
+                lookup.push((query.clone() * v.clone(), v));
+            }
+            lookup
+        });
+        route
+    }
+}
+
+/* Incremental multi scalar multiplication which multiplies the generator with
+ * mul_scalar_fixed.
+ *
+ * The layout of a host circuit must not depend on the witness, thus whether a push
+ * multiplies the generator can not decide which rows compute its product. Instead the
+ * products are computed in two pools of fixed sizes: the slots of the variable base
+ * pool use mul_scalar and the slots of the base pool use mul_scalar_fixed with the
+ * point copied from the generator. Every slot ends with a product row
+ * (P.x, P.y, s, R.x, R.y) marked by product_sel, and every push queries its
+ * (P, s, s * P) from the product rows (query_sel) before the accumulation. Pushes of
+ * the same point and scalar share one slot.
+ */
+pub struct AltJubMsmChip<F: FieldExt> {
+    pub jub: AltJubChip<F>,
+    pub route: MsmRouteConfig,
+    base: Point<F>,
+}
+
+impl<F: FieldExt> Chip<F> for AltJubMsmChip<F> {
+    type Config = CommonGateConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.jub.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt> AltJubMsmChip<F> {
+    pub fn construct(config: CommonGateConfig, route: MsmRouteConfig) -> Self {
+        let base = Point {
+            x: Limb::new(None, F::zero()),
+            y: Limb::new(None, F::one()),
+        };
+        AltJubMsmChip {
+            jub: AltJubChip::new(config),
+            route,
+            base,
+        }
+    }
+
+    pub fn configure(
+        cs: &mut ConstraintSystem<F>,
+        shared_advices: &Vec<Column<Advice>>,
+    ) -> (CommonGateConfig, MsmRouteConfig) {
+        let config = AltJubChip::<F>::configure(cs, shared_advices);
+        let route = MsmRouteConfig::configure(cs, &config);
+        (config, route)
+    }
+
+    pub fn initialize(
+        &mut self,
+        config: &CommonGateConfig,
+        region: &mut Region<F>,
+        offset: &mut usize,
+    ) -> Result<(), Error> {
+        self.jub.initialize(config, region, offset)?;
+        // the lookup of the rows without query_sel is all zero
+        config.assign_line(region, &mut (), offset, [None, None, None, None, None, None], [None; 9], 0)?;
+        let (x, y) = host_point_to_field::<F>(&BASE);
+        self.base = Point {
+            x: config.assign_constant(region, &mut (), offset, &x)?,
+            y: config.assign_constant(region, &mut (), offset, &y)?,
+        };
+        Ok(())
+    }
+
+    fn assign_route_line(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        point: &Point<F>,
+        scalar: &Limb<F>,
+        product: &Point<F>,
+        sel: &GateCell,
+    ) -> Result<Point<F>, Error> {
+        let row = *offset;
+        let l = self.jub.config.assign_line(
+            region,
+            &mut (),
+            offset,
+            [
+                Some(point.x.clone()),
+                Some(point.y.clone()),
+                Some(scalar.clone()),
+                Some(product.x.clone()),
+                Some(product.y.clone()),
+                None,
+            ],
+            [None; 9],
+            0,
+        )?;
+        self.route.assign_cell(region, row, sel, F::one())?;
+        Ok(Point {
+            x: l[3].clone(),
+            y: l[4].clone(),
+        })
+    }
+
+    /// fill one slot of the pools and return the product
+    fn assign_product(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        point: (F, F),
+        scalar: F,
+        fixed_base: bool,
+    ) -> Result<Point<F>, Error> {
+        let l = self.jub.config.assign_line(
+            region,
+            &mut (),
+            offset,
+            [
+                Some(Limb::new(None, point.0)),
+                Some(Limb::new(None, point.1)),
+                Some(Limb::new(None, scalar)),
+                None,
+                None,
+                None,
+            ],
+            [None; 9],
+            0,
+        )?;
+        let (point, product) = if fixed_base {
+            let product = self.jub.mul_scalar_fixed(region, offset, &l[2], &BASE_TABLES)?;
+            (self.base.clone(), product)
+        } else {
+            let point = Point {
+                x: l[0].clone(),
+                y: l[1].clone(),
+            };
+            // the pushes are routed to the slots, so their points are checked here
+            self.jub.assert_on_curve(region, offset, &point)?;
+            self.jub.assert_in_subgroup(region, offset, &point)?;
+            let product = self.jub.mul_scalar(region, offset, &l[2], &point)?;
+            (point, product)
+        };
+        self.assign_route_line(
+            region,
+            offset,
+            &point,
+            &l[2],
+            &product,
+            &MsmRouteConfig::product_sel(),
+        )
+    }
+
+    /// Assign the pushes (point, scalar, reset, result) of the msm. The distinct
+    /// products of the generator go to the base pool of base_slots slots as long as
+    /// it has room and the others go to the variable base pool of var_slots slots.
+    pub fn assign_msm(
+        &mut self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        pushes: &[(Point<F>, Limb<F>, Limb<F>, Point<F>)],
+        var_slots: usize,
+        base_slots: usize,
+    ) -> Result<(), Error> {
+        let base = (self.base.x.value, self.base.y.value);
+        let mut var_pool = vec![];
+        let mut base_pool = vec![];
+        for (point, scalar, _, _) in pushes.iter() {
+            let key = (point.x.value, point.y.value, scalar.value);
+            if var_pool.contains(&key) || base_pool.contains(&key) {
+                continue;
+            }
+            if (key.0, key.1) == base && base_pool.len() < base_slots {
+                base_pool.push(key);
+            } else {
+                var_pool.push(key);
+            }
+        }
+        assert!(
+            var_pool.len() <= var_slots,
+            "too many distinct products of points other than the generator"
+        );
+
+        // unused slots multiply with zero
+        let mut products = vec![];
+        for i in 0..var_slots {
+            let key = var_pool
+                .get(i)
+                .cloned()
+                .unwrap_or((F::zero(), F::one(), F::zero()));
+            let product = self.assign_product(region, offset, (key.0, key.1), key.2, false)?;
+            products.push((key, product));
+        }
+        for i in 0..base_slots {
+            let key = base_pool
+                .get(i)
+                .cloned()
+                .unwrap_or((base.0, base.1, F::zero()));
+            let product = self.assign_product(region, offset, (key.0, key.1), key.2, true)?;
+            products.push((key, product));
+        }
+
+        for (point, scalar, reset, result) in pushes.iter() {
+            let key = (point.x.value, point.y.value, scalar.value);
+            let (_, product) = products.iter().find(|(k, _)| *k == key).unwrap();
+            let product = Point {
+                x: Limb::new(None, product.x.value),
+                y: Limb::new(None, product.y.value),
+            };
+            let operand = self.assign_route_line(
+                region,
+                offset,
+                point,
+                scalar,
+                &product,
+                &MsmRouteConfig::query_sel(),
+            )?;
+            self.jub.accumulate(region, offset, &operand, reset, result)?;
+        }
+        Ok(())
+    }
+}
+
+/* Batched multi scalar multiplication. A call pushes whether it starts a new sum and
+ * BATCH_MSM_POINTS pairs of a point and a scalar, and gets back the running sum of
+ * the session after the msm of its pairs. The partial sums of the pairs of a call are
+ * not returned, which lets AltJubChip::msm share the doublings between them.
+ */
+pub struct AltJubBatchMsmChip<F: FieldExt> {
+    pub jub: AltJubChip<F>,
+}
+
+impl<F: FieldExt> Chip<F> for AltJubBatchMsmChip<F> {
+    type Config = CommonGateConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.jub.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt> AltJubBatchMsmChip<F> {
+    pub fn construct(config: CommonGateConfig) -> Self {
+        AltJubBatchMsmChip {
+            jub: AltJubChip::new(config),
+        }
+    }
+
+    pub fn configure(
+        cs: &mut ConstraintSystem<F>,
+        shared_advices: &Vec<Column<Advice>>,
+    ) -> CommonGateConfig {
+        AltJubChip::<F>::configure(cs, shared_advices)
+    }
+
+    pub fn initialize(
+        &mut self,
+        config: &CommonGateConfig,
+        region: &mut Region<F>,
+        offset: &mut usize,
+    ) -> Result<(), Error> {
+        self.jub.initialize(config, region, offset)
+    }
+
+    /// acc = (reset ? identity : acc) + sum s_i * p_i and constrain the result to be acc
+    pub fn assign_batch_msm(
+        &mut self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        pairs: &[(Point<F>, Limb<F>)],
+        reset: &Limb<F>,
+        result: &Point<F>,
+    ) -> Result<(), Error> {
+        assert!(pairs.len() == BATCH_MSM_POINTS);
+        for (point, _) in pairs.iter() {
+            self.jub.assert_on_curve(region, offset, point)?;
+            self.jub.assert_in_subgroup(region, offset, point)?;
+        }
+        let operand = self.jub.msm(region, offset, pairs)?;
+        self.jub.accumulate(region, offset, &operand, reset, result)
+    }
 }
 
 #[cfg(test)]
@@ -534,7 +1167,12 @@ mod tests {
         plonk::{Advice, Circuit, Column, ConstraintSystem, Error},
     };
 
-    use super::{AltJubChip, Limb, Point};
+    use super::{
+        multilinear_coeffs, AltJubBatchMsmChip, AltJubChip, AltJubMsmChip, Limb, MsmRouteConfig,
+        Point, BATCH_MSM_POINTS, WINDOW_SIZE,
+    };
+    use crate::host::jubjub::{Point as HostPoint, BASE, BASE_TABLES};
+    use crate::utils::field_to_bn;
 
     #[derive(Clone, Debug)]
     pub struct HelperChipConfig {
@@ -793,6 +1431,7 @@ mod tests {
         mul_result_x: Fr,
         mul_result_y: Fr,
         scalar: Fr,
+        fixed_base: bool,
     }
 
     #[derive(Clone, Debug)]
@@ -838,12 +1477,21 @@ mod tests {
                     // point 1
                     let p1 =
                         helperchip.assign_p1(&mut region, &mut offset, self.p1_x, self.p1_y)?;
-                    let p3 = altjubchip.mul_scalar(
-                        &mut region,
-                        &mut offset,
-                        &Limb::new(None, self.scalar.clone()),
-                        &p1,
-                    )?;
+                    let p3 = if self.fixed_base {
+                        altjubchip.mul_scalar_fixed(
+                            &mut region,
+                            &mut offset,
+                            &Limb::new(None, self.scalar.clone()),
+                            &BASE_TABLES,
+                        )?
+                    } else {
+                        altjubchip.mul_scalar(
+                            &mut region,
+                            &mut offset,
+                            &Limb::new(None, self.scalar.clone()),
+                            &p1,
+                        )?
+                    };
                     let (x, y) =
                         helperchip.assign_addition_result(&mut region, &mut offset, &p3)?;
                     let (fixed_x, fixed_y) = helperchip.assign_known_val(
@@ -866,7 +1514,87 @@ mod tests {
         }
     }
 
+    #[derive(Clone, Debug, Default)]
+    struct OnCurveTestCircuit {
+        x: Fr,
+        y: Fr,
+        subgroup: bool,
+    }
+
+    impl Circuit<Fr> for OnCurveTestCircuit {
+        type Config = MulTestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(cs: &mut ConstraintSystem<Fr>) -> Self::Config {
+            MulTestCircuit::configure(cs)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let altjubchip = AltJubChip::<Fr>::new(config.clone().altjubconfig);
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            layouter.assign_region(
+                || "test on curve",
+                |mut region| {
+                    let mut offset = 0;
+                    let p = helperchip.assign_p1(&mut region, &mut offset, self.x, self.y)?;
+                    altjubchip.assert_on_curve(&mut region, &mut offset, &p)?;
+                    if self.subgroup {
+                        altjubchip.assert_in_subgroup(&mut region, &mut offset, &p)?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
     use ff::PrimeField;
+    #[test]
+    fn test_circuit_on_curve() {
+        let circuit = OnCurveTestCircuit {
+            x: BASE.x,
+            y: BASE.y,
+            subgroup: false,
+        };
+        let prover = MockProver::run(18, &circuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let circuit = OnCurveTestCircuit {
+            x: BASE.x,
+            y: BASE.x,
+            subgroup: false,
+        };
+        let prover = MockProver::run(18, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_circuit_in_subgroup() {
+        let circuit = OnCurveTestCircuit {
+            x: BASE.x,
+            y: BASE.y,
+            subgroup: true,
+        };
+        let prover = MockProver::run(18, &circuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // (0, -1) is on the curve with order 2
+        let circuit = OnCurveTestCircuit {
+            x: Fr::zero(),
+            y: -Fr::one(),
+            subgroup: true,
+        };
+        let prover = MockProver::run(18, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
     #[test]
     fn test_circuit_add() {
         let p1_x = Fr::from_str_vartime(
@@ -932,9 +1660,222 @@ mod tests {
             mul_result_x,
             mul_result_y,
             scalar,
+            fixed_base: false,
         };
 
         let prover_2 = MockProver::run(18, &test_circuit_2, vec![]).unwrap();
         assert_eq!(prover_2.verify(), Ok(()));
     }
+
+    #[test]
+    fn test_circuit_mul_fixed_base() {
+        let scalar = Fr::from_str_vartime(
+            "2736030358979909402780800718157159386076813972158567259200215660948447373040",
+        )
+        .unwrap();
+        let result = BASE.mul_scalar(&field_to_bn(&scalar));
+        let test_circuit = MulTestCircuit {
+            p1_x: BASE.x,
+            p1_y: BASE.y,
+            mul_result_x: result.x,
+            mul_result_y: result.y,
+            scalar,
+            fixed_base: true,
+        };
+        let prover = MockProver::run(18, &test_circuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn test_multilinear_coeffs() {
+        let values = (0..WINDOW_SIZE)
+            .map(|m| Fr::from((m * m + 3) as u64))
+            .collect::<Vec<_>>();
+        let coeffs = multilinear_coeffs(&values);
+        for m in 0..WINDOW_SIZE {
+            let eval = (0..WINDOW_SIZE)
+                .filter(|mask| mask & m == *mask)
+                .fold(Fr::zero(), |acc, mask| acc + coeffs[mask]);
+            assert_eq!(eval, values[m]);
+        }
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct MsmTestCircuit {
+        pushes: Vec<(HostPoint, Fr)>,
+        var_slots: usize,
+        base_slots: usize,
+    }
+
+    impl Circuit<Fr> for MsmTestCircuit {
+        type Config = (CommonGateConfig, MsmRouteConfig);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(cs: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let witness = (0..5).map(|_| cs.advice_column()).collect::<Vec<_>>();
+            AltJubMsmChip::<Fr>::configure(cs, &witness)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let (common, route) = config;
+            let mut chip = AltJubMsmChip::<Fr>::construct(common.clone(), route);
+            layouter.assign_region(
+                || "test msm",
+                |mut region| {
+                    let mut offset = 0;
+                    chip.initialize(&common, &mut region, &mut offset)?;
+                    let mut acc = HostPoint::identity();
+                    let mut pushes = vec![];
+                    for (i, (p, s)) in self.pushes.iter().enumerate() {
+                        acc = acc.add(&p.mul_scalar(&field_to_bn(s)));
+                        let mut values = vec![];
+                        for v in [p.x, p.y, *s, Fr::from((i == 0) as u64), acc.x, acc.y] {
+                            values.push(common.assign_constant(&mut region, &mut (), &mut offset, &v)?);
+                        }
+                        pushes.push((
+                            Point {
+                                x: values[0].clone(),
+                                y: values[1].clone(),
+                            },
+                            values[2].clone(),
+                            values[3].clone(),
+                            Point {
+                                x: values[4].clone(),
+                                y: values[5].clone(),
+                            },
+                        ));
+                    }
+                    chip.assign_msm(&mut region, &mut offset, &pushes, self.var_slots, self.base_slots)
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn test_circuit_msm_fixed_base() {
+        let p = BASE.mul_scalar(&7u64.into());
+        let test_circuit = MsmTestCircuit {
+            pushes: vec![
+                (BASE.clone(), Fr::from(3u64)),
+                (p.clone(), Fr::from(5u64)),
+                (BASE.clone(), Fr::from(0x1234567890abcdefu64)),
+                (p, Fr::from(5u64)),
+            ],
+            // the second push of the generator does not fit in the base pool
+            var_slots: 2,
+            base_slots: 1,
+        };
+        let prover = MockProver::run(18, &test_circuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn test_circuit_msm_identity() {
+        // the identity point, a zero scalar and products that cancel to the identity
+        let p = BASE.mul_scalar(&7u64.into());
+        let neg_p = HostPoint { x: -p.x, y: p.y };
+        let test_circuit = MsmTestCircuit {
+            pushes: vec![
+                (HostPoint::identity(), Fr::from(5u64)),
+                (p.clone(), Fr::zero()),
+                (p, Fr::from(3u64)),
+                (neg_p, Fr::from(3u64)),
+                (BASE.clone(), Fr::zero()),
+            ],
+            var_slots: 4,
+            base_slots: 1,
+        };
+        let prover = MockProver::run(18, &test_circuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct BatchMsmTestCircuit {
+        calls: Vec<Vec<(HostPoint, Fr)>>,
+    }
+
+    impl Circuit<Fr> for BatchMsmTestCircuit {
+        type Config = CommonGateConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(cs: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let witness = (0..5).map(|_| cs.advice_column()).collect::<Vec<_>>();
+            AltJubBatchMsmChip::<Fr>::configure(cs, &witness)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let mut chip = AltJubBatchMsmChip::<Fr>::construct(config.clone());
+            layouter.assign_region(
+                || "test batch msm",
+                |mut region| {
+                    let mut offset = 0;
+                    chip.initialize(&config, &mut region, &mut offset)?;
+                    let mut acc = HostPoint::identity();
+                    for (i, call) in self.calls.iter().enumerate() {
+                        let mut pairs = vec![];
+                        for (p, s) in call.iter() {
+                            acc = acc.add(&p.mul_scalar(&field_to_bn(s)));
+                            let mut values = vec![];
+                            for v in [p.x, p.y, *s] {
+                                values.push(config.assign_constant(&mut region, &mut (), &mut offset, &v)?);
+                            }
+                            pairs.push((
+                                Point {
+                                    x: values[0].clone(),
+                                    y: values[1].clone(),
+                                },
+                                values[2].clone(),
+                            ));
+                        }
+                        let reset = Fr::from((i == 0) as u64);
+                        let reset = config.assign_constant(&mut region, &mut (), &mut offset, &reset)?;
+                        let result = Point {
+                            x: config.assign_constant(&mut region, &mut (), &mut offset, &acc.x)?,
+                            y: config.assign_constant(&mut region, &mut (), &mut offset, &acc.y)?,
+                        };
+                        chip.assign_batch_msm(&mut region, &mut offset, &pairs, &reset, &result)?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn test_circuit_batch_msm() {
+        let p = BASE.mul_scalar(&7u64.into());
+        let neg_p = HostPoint { x: -p.x, y: p.y };
+        let mut first = vec![
+            (BASE.clone(), Fr::from(0x1234567890abcdefu64)),
+            (p.clone(), Fr::from(5u64)),
+            (HostPoint::identity(), Fr::from(3u64)),
+            (p.clone(), Fr::zero()),
+        ];
+        // the second call cancels the products of p and continues the sum
+        let mut second = vec![(neg_p, Fr::from(5u64)), (p, Fr::from(9u64))];
+        for call in [&mut first, &mut second] {
+            call.resize(BATCH_MSM_POINTS, (HostPoint::identity(), Fr::zero()));
+        }
+        let test_circuit = BatchMsmTestCircuit {
+            calls: vec![first, second],
+        };
+        let prover = MockProver::run(18, &test_circuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
 }
@@ -0,0 +1,430 @@
+// The constraint system matrix for blake2s and blake2b.
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Chip, Region},
+    plonk::{ConstraintSystem, Error},
+};
+
+use crate::circuits::nibble::{
+    u64_to_nibbles, NibbleChip, NibbleGateConfig, Word, OP_NIBBLE, OP_XOR2, OP_XOR3,
+};
+use crate::host::blake2::{
+    Blake2Params, BLAKE2B, BLAKE2S, BLOCK_WORDS, G_INDICES, SIGMA, STATE_WORDS,
+};
+use crate::utils::Limb;
+
+/*
+ * Every word of the blake2 state is kept as BITS / 4 nibbles and the xors and rotations
+ * are looked up nibble by nibble, see circuits::nibble. A word also carries its
+ * composed value once it is known, which is needed for the modular additions of G.
+ *
+ * The host arguments are packed in u64 limbs, so a limb holds two words of blake2s
+ * and one word of blake2b.
+ */
+pub type Blake2GateConfig = NibbleGateConfig;
+
+#[derive(Clone, Debug)]
+struct Blake2Word<F: FieldExt> {
+    value: Option<Limb<F>>,
+    nibbles: Word<F>,
+}
+
+impl<F: FieldExt> Blake2Word<F> {
+    fn from_nibbles(nibbles: Word<F>) -> Self {
+        Blake2Word {
+            value: None,
+            nibbles,
+        }
+    }
+}
+
+pub struct Blake2Chip<F: FieldExt, const BITS: usize> {
+    pub nibble: NibbleChip<F>,
+    state: Vec<Word<F>>,
+}
+
+pub type Blake2sChip<F> = Blake2Chip<F, 32>;
+pub type Blake2bChip<F> = Blake2Chip<F, 64>;
+
+impl<F: FieldExt, const BITS: usize> Chip<F> for Blake2Chip<F, BITS> {
+    type Config = Blake2GateConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.nibble.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt, const BITS: usize> Blake2Chip<F, BITS> {
+    /// nibbles of a word
+    const NIBBLES: usize = BITS / 4;
+    /// words packed in one u64 host argument
+    pub const WORDS_PER_LIMB: usize = 64 / BITS;
+
+    pub fn params() -> &'static Blake2Params {
+        if BITS == 32 {
+            &BLAKE2S
+        } else {
+            &BLAKE2B
+        }
+    }
+
+    pub fn new(config: Blake2GateConfig) -> Self {
+        let nibble = NibbleChip::new(config);
+        Blake2Chip {
+            state: vec![nibble.zero_word(Self::NIBBLES); STATE_WORDS],
+            nibble,
+        }
+    }
+
+    pub fn configure(cs: &mut ConstraintSystem<F>) -> Blake2GateConfig {
+        NibbleChip::configure(cs)
+    }
+
+    /// assign the lookup table and the zero constant, the state is reset to zero
+    pub fn initialize(&mut self, region: &mut Region<F>, offset: &mut usize) -> Result<(), Error> {
+        self.nibble.initialize(region, offset)?;
+        self.state = vec![self.nibble.zero_word(Self::NIBBLES); STATE_WORDS];
+        Ok(())
+    }
+
+    fn value(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        word: &mut Blake2Word<F>,
+    ) -> Result<Limb<F>, Error> {
+        if word.value.is_none() {
+            word.value = Some(self.nibble.compose(region, offset, &word.nibbles, None)?);
+        }
+        Ok(word.value.clone().unwrap())
+    }
+
+    fn add(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        a: &mut Blake2Word<F>,
+        b: &mut Blake2Word<F>,
+        c: Option<&mut Blake2Word<F>>,
+    ) -> Result<Blake2Word<F>, Error> {
+        let a = self.value(region, offset, a)?;
+        let b = self.value(region, offset, b)?;
+        let c = match c {
+            Some(c) => Some(self.value(region, offset, c)?),
+            None => None,
+        };
+        let (value, nibbles) = self.nibble.add(region, offset, &a, &b, c.as_ref(), BITS)?;
+        Ok(Blake2Word {
+            value: Some(value),
+            nibbles,
+        })
+    }
+
+    /// rotate_right(x ^ y, r)
+    fn xor_rotate(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        x: &Blake2Word<F>,
+        y: &Blake2Word<F>,
+        r: u32,
+    ) -> Result<Blake2Word<F>, Error> {
+        let zero = self.nibble.zero_word(Self::NIBBLES);
+        let w = self
+            .nibble
+            .word_op(region, offset, OP_XOR2, &x.nibbles, &y.nibbles, &zero)?;
+        let w = self
+            .nibble
+            .rotate_left(region, offset, &w, BITS as u32 - r)?;
+        Ok(Blake2Word::from_nibbles(w))
+    }
+
+    /// xor the nibbles of word with the constant c
+    fn xor_constant(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        word: &[Limb<F>],
+        c: u64,
+    ) -> Result<Blake2Word<F>, Error> {
+        let mut r = vec![];
+        for (nibble, c) in word.iter().zip(u64_to_nibbles(c, Self::NIBBLES)) {
+            let [_, _, _, out] = self.nibble.assign_op(
+                region,
+                *offset,
+                OP_XOR2,
+                nibble,
+                &Limb::new(None, F::from(c)),
+                &self.nibble.zero,
+            )?;
+            self.nibble.assign_constant_y(region, *offset, c)?;
+            *offset += 1;
+            r.push(out);
+        }
+        Ok(Blake2Word::from_nibbles(r))
+    }
+
+    fn mix(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        v: &mut Vec<Blake2Word<F>>,
+        i: [usize; 4],
+        x: &mut Blake2Word<F>,
+        y: &mut Blake2Word<F>,
+    ) -> Result<(), Error> {
+        let [a, b, c, d] = i;
+        let rotations = Self::params().rotations;
+        for (k, m) in [x, y].into_iter().enumerate() {
+            let (mut va, mut vb) = (v[a].clone(), v[b].clone());
+            v[a] = self.add(region, offset, &mut va, &mut vb, Some(m))?;
+            v[b] = vb;
+            v[d] = self.xor_rotate(region, offset, &v[d], &v[a], rotations[2 * k])?;
+            let (mut vc, mut vd) = (v[c].clone(), v[d].clone());
+            v[c] = self.add(region, offset, &mut vc, &mut vd, None)?;
+            v[d] = vd;
+            v[b] = self.xor_rotate(region, offset, &v[b], &v[c], rotations[2 * k + 1])?;
+        }
+        Ok(())
+    }
+
+    /// split the u64 host arguments into range checked words
+    fn limbs_to_words(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        limbs: &[Limb<F>],
+    ) -> Result<Vec<Blake2Word<F>>, Error> {
+        let mut words = vec![];
+        for limb in limbs.iter() {
+            let nibbles = self.nibble.decompose(region, offset, limb, 16)?;
+            for w in nibbles.chunks_exact(Self::NIBBLES) {
+                let value = if Self::WORDS_PER_LIMB == 1 {
+                    limb.clone()
+                } else {
+                    self.nibble.compose(region, offset, w, None)?
+                };
+                words.push(Blake2Word {
+                    value: Some(value),
+                    nibbles: w.to_vec(),
+                });
+            }
+        }
+        Ok(words)
+    }
+
+    /// compress one block of words packed in u64 limbs with the byte counter t and
+    /// the last block flag (restart from the initial state if reset is 1) and
+    /// constrain the chained state packed in u64 limbs to be equal to result
+    pub fn assign_compress(
+        &mut self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        reset: &Limb<F>,
+        t: &Limb<F>,
+        last: &Limb<F>,
+        block: &[Limb<F>],
+        result: &[Limb<F>],
+    ) -> Result<(), Error> {
+        let params = Self::params();
+        let n = Self::NIBBLES;
+        assert!(block.len() * Self::WORDS_PER_LIMB == BLOCK_WORDS);
+        assert!(result.len() * Self::WORDS_PER_LIMB == STATE_WORDS);
+        let zero = Limb::new(None, F::zero());
+
+        // h = reset ? init : h
+        let init = params.init_state();
+        let mut h = vec![];
+        for (prev, init) in self.state.iter().zip(init.iter()) {
+            let mut word = vec![];
+            for (nibble, init) in prev.iter().zip(u64_to_nibbles(*init, n)) {
+                let x = self
+                    .nibble
+                    .assign_reset(region, *offset, nibble, reset, init)?;
+                let [_, _, _, out] = self.nibble.assign_op(
+                    region,
+                    *offset,
+                    OP_NIBBLE,
+                    &Limb::new(None, x),
+                    &zero,
+                    &zero,
+                )?;
+                *offset += 1;
+                word.push(out);
+            }
+            h.push(word);
+        }
+
+        let mut m = self.limbs_to_words(region, offset, block)?;
+
+        // the counter is split into t0 and t1 and f is 0xf in every nibble if last is 1
+        let t = self.nibble.decompose(region, offset, t, 16)?;
+        let t1 = if Self::WORDS_PER_LIMB == 2 {
+            t[n..2 * n].to_vec()
+        } else {
+            self.nibble.zero_word(n)
+        };
+        let x = self
+            .nibble
+            .assign_reset(region, *offset, &self.nibble.zero, last, 0xf)?;
+        let [_, _, _, f] = self.nibble.assign_op(
+            region,
+            *offset,
+            OP_NIBBLE,
+            &Limb::new(None, x),
+            &zero,
+            &zero,
+        )?;
+        *offset += 1;
+
+        let mut v = h
+            .iter()
+            .map(|x| Blake2Word::from_nibbles(x.clone()))
+            .collect::<Vec<_>>();
+        for i in 0..STATE_WORDS {
+            let w = match i {
+                4 => self.xor_constant(region, offset, &t[0..n], params.iv[i])?,
+                5 => self.xor_constant(region, offset, &t1, params.iv[i])?,
+                6 => self.xor_constant(region, offset, &vec![f.clone(); n], params.iv[i])?,
+                _ => Blake2Word::from_nibbles(self.nibble.assign_constant_word(
+                    region,
+                    offset,
+                    params.iv[i],
+                    n,
+                )?),
+            };
+            v.push(w);
+        }
+
+        for r in 0..params.rounds {
+            let s = SIGMA[r % 10];
+            for (k, i) in G_INDICES.iter().enumerate() {
+                let (mut x, mut y) = (m[s[2 * k]].clone(), m[s[2 * k + 1]].clone());
+                self.mix(region, offset, &mut v, *i, &mut x, &mut y)?;
+                m[s[2 * k]] = x;
+                m[s[2 * k + 1]] = y;
+            }
+        }
+
+        for i in 0..STATE_WORDS {
+            self.state[i] = self.nibble.word_op(
+                region,
+                offset,
+                OP_XOR3,
+                &h[i],
+                &v[i].nibbles,
+                &v[i + 8].nibbles,
+            )?;
+        }
+
+        let nibbles = self.state.concat();
+        for (limb, nibbles) in result.iter().zip(nibbles.chunks_exact(16)) {
+            self.nibble.compose(region, offset, nibbles, Some(limb))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pairing::bn256::Fr;
+
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+
+    use super::{Blake2Chip, Blake2GateConfig};
+    use crate::host::blake2::{blake2_blocks, Blake2};
+    use crate::utils::Limb;
+
+    #[derive(Clone, Debug, Default)]
+    struct TestCircuit<const BITS: usize> {
+        message: Vec<u8>,
+    }
+
+    /// pack the words into u64 limbs
+    fn to_limbs<const BITS: usize>(words: &[u64]) -> Vec<Limb<Fr>> {
+        words
+            .chunks_exact(64 / BITS)
+            .map(|ws| {
+                let v = ws
+                    .iter()
+                    .rev()
+                    .fold(0u128, |acc, w| (acc << BITS) + *w as u128);
+                Limb::new(None, Fr::from(v as u64))
+            })
+            .collect()
+    }
+
+    impl<const BITS: usize> Circuit<Fr> for TestCircuit<BITS> {
+        type Config = Blake2GateConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            Blake2Chip::<Fr, BITS>::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let mut chip = Blake2Chip::<Fr, BITS>::new(config);
+            let params = Blake2Chip::<Fr, BITS>::params();
+            layouter.assign_region(
+                || "blake2 test",
+                |mut region| {
+                    let mut offset = 0;
+                    chip.initialize(&mut region, &mut offset)?;
+                    let mut hasher = Blake2::new(params);
+                    for (i, (block, t, last)) in
+                        blake2_blocks(params, &self.message).iter().enumerate()
+                    {
+                        let result = hasher.update_exact(block, *t, *last);
+                        chip.assign_compress(
+                            &mut region,
+                            &mut offset,
+                            &Limb::new(None, Fr::from((i == 0) as u64)),
+                            &Limb::new(None, Fr::from(*t)),
+                            &Limb::new(None, Fr::from(*last as u64)),
+                            &to_limbs::<BITS>(block),
+                            &to_limbs::<BITS>(&result),
+                        )?;
+                    }
+                    Ok(())
+                },
+            )?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_blake2s_circuit() {
+        let test_circuit = TestCircuit::<32> {
+            message: (0..100).map(|x| x as u8).collect(),
+        };
+        let prover = MockProver::run(18, &test_circuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    #[test]
+    fn test_blake2b_circuit() {
+        let test_circuit = TestCircuit::<64> {
+            message: b"abc".to_vec(),
+        };
+        let prover = MockProver::run(18, &test_circuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+}
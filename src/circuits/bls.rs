@@ -4,20 +4,21 @@ use halo2_proofs::pairing::bls12_381::Fr as Scalar;
 use halo2_proofs::{
     arithmetic::{BaseExt, FieldExt},
     circuit::{AssignedCell, Chip, Layouter, Region},
-    pairing::bls12_381::G1Affine,
+    pairing::bls12_381::{G1Affine, G2Affine, G1, G2},
+    pairing::group::Group,
     plonk::{ConstraintSystem, Error},
 };
 use halo2ecc_s::circuit::integer_chip::IntegerChipOps;
 use halo2ecc_s::circuit::{base_chip::BaseChipOps, ecc_chip::EccChipScalarOps};
-use halo2ecc_s::circuit::fq12::Fq12ChipOps;
+use halo2ecc_s::circuit::fq12::{Fq12ChipOps, Fq2ChipOps};
 use std::cell::RefCell;
 use std::marker::PhantomData;
 use std::rc::Rc;
 use std::sync::Arc;
 
 use halo2_proofs::pairing::bls12_381::Fq as Bls381Fq;
-use halo2ecc_s::assign::{AssignedCondition, AssignedFq, Cell as ContextCell, AssignedInteger};
-use halo2ecc_s::assign::{AssignedFq12, AssignedG2Affine, AssignedPoint};
+use halo2ecc_s::assign::{AssignedCondition, AssignedFq, Cell as ContextCell, AssignedInteger, AssignedValue};
+use halo2ecc_s::assign::{AssignedFq12, AssignedFq2, AssignedG2Affine, AssignedPoint};
 use halo2ecc_s::circuit::ecc_chip::EccBaseIntegerChipWrapper;
 use halo2ecc_s::circuit::{ecc_chip::EccChipBaseOps, pairing_chip::PairingChipOps};
 
@@ -34,7 +35,16 @@ use halo2ecc_s::{
     context::{Context, GeneralScalarEccContext},
 };
 
-use crate::utils::Limb;
+use crate::circuits::g1::{g1_add, g1_assign_identity, g1_msm, g1_mul_by_constant, g1_select};
+use crate::circuits::g2::{
+    g2_add, g2_assert_on_curve, g2_assert_psi_multiple, g2_assign_identity, g2_from_affine, g2_mul,
+    g2_select, g2_to_affine,
+};
+use crate::circuits::gt::{assert_gt_compressed, assert_gt_op, GT_OP_EQUAL, GT_OP_INVERT};
+use crate::circuits::map_to_curve::hash_to_g2_in_context;
+use crate::host::bls::{KZG_SETUP_G2, VERSIONED_HASH_VERSION_KZG};
+use crate::host::ForeignInst;
+use crate::utils::{bn_to_field, field_to_bn, Limb};
 use num_bigint::BigUint;
 use std::ops::{AddAssign, Mul};
 
@@ -335,8 +345,8 @@ impl Bls381PairChip<Fr> {
         let contex = Rc::new(RefCell::new(Context::new()));
         let mut ctx = GeneralScalarEccContext::<G1Affine, Fr>::new(contex);
 
-        let a_g1 = get_g1_from_cells(&mut ctx, a);
-        let b_g2 = get_g2_from_cells(&mut ctx, b);
+        let a_g1 = get_checked_g1_from_cells(&mut ctx, a);
+        let b_g2 = get_checked_g2_from_cells(&mut ctx, b);
 
         let ab_fq12_raw = ctx.pairing(&[(&a_g1, &b_g2)]);
         let ab_fq12 = ctx.fq12_reduce(&ab_fq12_raw);
@@ -418,7 +428,7 @@ impl Bls381SumChip<Fr> {
         let mut ais = vec![];
         let mut g1s = vec![];
         let mut sums = vec![];
-        let identity = ctx.assign_identity();
+        let identity = g1_assign_identity(&mut ctx);
         let mut sum = identity.clone();
         for group in ls.chunks_exact(22) {
             // using constraint to fix if to reset
@@ -429,10 +439,9 @@ impl Bls381SumChip<Fr> {
             };
             let a = get_scalar_from_cell(&mut ctx, &group.get(1..4).unwrap().to_vec());
             ais.push(a.clone());
-            let g = get_g1_from_cells(&mut ctx, &group.get(4..13).unwrap().to_vec());
-            let rhs = ctx.ecc_mul(&g, a);
-            let sum_ret = ctx.ecc_add(&lhs, &rhs);
-            let sum_ret = ctx.ecc_reduce(&sum_ret);
+            let g = get_checked_g1_from_cells(&mut ctx, &group.get(4..13).unwrap().to_vec());
+            let rhs = mul_g1(&mut ctx, &g, a);
+            let sum_ret = g1_add(&mut ctx, &lhs, &rhs);
             ctx.native_ctx.borrow_mut().enable_permute(&sum_ret.z.0);
             ctx.native_ctx
                 .borrow_mut()
@@ -458,7 +467,7 @@ impl Bls381SumChip<Fr> {
             ctx.native_ctx
                 .borrow_mut()
                 .enable_permute(&sum_ret.y.limbs_le[3]);
-            sum = ctx.to_point_with_curvature(sum_ret.clone());
+            sum = sum_ret.clone();
             g1s.push(g);
             sums.push(sum_ret);
         }
@@ -493,3 +502,1274 @@ impl Bls381SumChip<Fr> {
         Ok(())
     }
 }
+
+/// The number of (scalar, G1) pairs of an msm call, unused pairs are padded with zero scalars
+pub const BLS381_MSM_POINTS: usize = 16;
+
+pub struct Bls381MsmChip<N: FieldExt> {
+    config: Bls381ChipConfig,
+    base_chip: BaseChip<Fr>,
+    pub range_chip: RangeChip<Fr>,
+    point_select_chip: SelectChip<Fr>,
+    _marker: PhantomData<N>,
+}
+
+impl<N: FieldExt> Chip<N> for Bls381MsmChip<N> {
+    type Config = Bls381ChipConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl Bls381MsmChip<Fr> {
+    pub fn construct(config: <Self as Chip<Fr>>::Config) -> Self {
+        Self {
+            config: config.clone(),
+            point_select_chip: SelectChip::<Fr>::new(config.point_select_chip_config),
+            base_chip: BaseChip::new(config.base_chip_config),
+            range_chip: RangeChip::<Fr>::new(config.range_chip_config),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(cs: &mut ConstraintSystem<Fr>) -> <Self as Chip<Fr>>::Config {
+        Bls381ChipConfig {
+            base_chip_config: BaseChip::configure(cs),
+            range_chip_config: RangeChip::<Fr>::configure(cs),
+            point_select_chip_config: SelectChip::configure(cs),
+        }
+    }
+
+    /* A call pushes whether it starts a new sum and BLS381_MSM_POINTS pairs of a
+     * scalar and a point, and gets back the running sum of the session after the msm
+     * of its pairs (see g1_msm).
+     */
+    pub fn load_bls381_msm_circuit(
+        &self,
+        ls: &Vec<Limb<Fr>>, // n * (new, (fr, g1) * BLS381_MSM_POINTS, sum)
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let contex = Rc::new(RefCell::new(Context::new()));
+        let mut ctx = GeneralScalarEccContext::<G1Affine, Fr>::new(contex);
+
+        let size = 1 + 12 * BLS381_MSM_POINTS + 9;
+        let mut news = vec![];
+        let mut ais = vec![];
+        let mut g1s = vec![];
+        let mut sums = vec![];
+        let identity = g1_assign_identity(&mut ctx);
+        let mut sum = identity.clone();
+        for group in ls.chunks_exact(size) {
+            let new = ctx.native_ctx.borrow_mut().assign_bit(group[0].value);
+            let lhs = g1_select(&mut ctx, &new, &identity, &sum);
+            let mut points = vec![];
+            let mut bits = vec![];
+            for pair in group[1..size - 9].chunks_exact(12) {
+                let a = get_scalar_from_cell(&mut ctx, &pair[0..3].to_vec());
+                let g = get_checked_g1_from_cells(&mut ctx, &pair[3..12].to_vec());
+                bits.push(assign_scalar_bits(&mut ctx, &a));
+                points.push(g.clone());
+                ais.push(a);
+                g1s.push(g);
+            }
+            let product = g1_msm(&mut ctx, &points, &bits);
+            sum = g1_add(&mut ctx, &lhs, &product);
+            {
+                let mut native = ctx.native_ctx.borrow_mut();
+                native.enable_permute(&sum.z.0);
+                for limb in sum.x.limbs_le.iter().chain(sum.y.limbs_le.iter()) {
+                    native.enable_permute(limb);
+                }
+            }
+            news.push(new);
+            sums.push(sum.clone());
+        }
+
+        let records = Arc::try_unwrap(Into::<Context<Fr>>::into(ctx).records)
+            .unwrap()
+            .into_inner()
+            .unwrap();
+        layouter.assign_region(
+            || "base",
+            |mut region| {
+                let timer = start_timer!(|| "assign");
+                let cells = records.assign_all(
+                    &mut region,
+                    &self.base_chip,
+                    &self.range_chip,
+                    &self.point_select_chip,
+                )?;
+                for (i, new) in news.iter().enumerate() {
+                    let new_assigned = get_cell_of_ctx(&cells, &new.0.cell);
+                    region.constrain_equal(ls[size * i].get_the_cell().cell(), new_assigned.cell())?;
+                }
+                for (i, (a, g)) in ais.iter().zip(g1s.iter()).enumerate() {
+                    let start = size * (i / BLS381_MSM_POINTS) + 1 + 12 * (i % BLS381_MSM_POINTS);
+                    enable_fr_permute(&mut region, &cells, a, &ls[start..start + 3].to_vec())?;
+                    enable_g1affine_permute(&mut region, &cells, g, &ls[start + 3..start + 12].to_vec())?;
+                }
+                for (i, s) in sums.iter().enumerate() {
+                    enable_g1affine_permute(&mut region, &cells, s, &ls[size * (i + 1) - 9..size * (i + 1)].to_vec())?;
+                }
+                end_timer!(timer);
+                Ok(())
+            },
+        )?;
+        Ok(())
+    }
+}
+
+/// The maximal number of (G1, G2) pairs of a pairing check call. The missing pairs of a
+/// shorter call are identity pairs, the adaptor rejects a call of more pairs.
+pub const BLS381_PAIR_CHECK_PAIRS: usize = 4;
+
+pub struct Bls381PairCheckChip<N: FieldExt> {
+    config: Bls381ChipConfig,
+    base_chip: BaseChip<Fr>,
+    pub range_chip: RangeChip<Fr>,
+    point_select_chip: SelectChip<Fr>,
+    _marker: PhantomData<N>,
+}
+
+impl<N: FieldExt> Chip<N> for Bls381PairCheckChip<N> {
+    type Config = Bls381ChipConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+fn assign_g1_constant(
+    ctx: &mut GeneralScalarEccContext<G1Affine, Fr>,
+    p: G1,
+) -> AssignedPoint<G1Affine, Fr> {
+    let p = G1Affine::from(p);
+    let x = ctx.base_integer_chip().assign_int_constant(p.x);
+    let y = ctx.base_integer_chip().assign_int_constant(p.y);
+    let z = ctx.native_ctx.borrow_mut().assign_constant(Fr::zero());
+    AssignedPoint::new(x, y, AssignedCondition(z))
+}
+
+fn assign_g2_constant(
+    ctx: &mut GeneralScalarEccContext<G1Affine, Fr>,
+    q: G2,
+) -> AssignedG2Affine<G1Affine, Fr> {
+    let q = G2Affine::from(q);
+    let x1 = ctx.base_integer_chip().assign_int_constant(q.x.c0);
+    let x2 = ctx.base_integer_chip().assign_int_constant(q.x.c1);
+    let y1 = ctx.base_integer_chip().assign_int_constant(q.y.c0);
+    let y2 = ctx.base_integer_chip().assign_int_constant(q.y.c1);
+    let z = ctx.native_ctx.borrow_mut().assign_constant(Fr::zero());
+    AssignedG2Affine::new((x1, x2), (y1, y2), AssignedCondition(z))
+}
+
+// cond ? c : p, the result is never the identity
+fn select_g1(
+    ctx: &mut GeneralScalarEccContext<G1Affine, Fr>,
+    cond: &AssignedCondition<Fr>,
+    c: &AssignedPoint<G1Affine, Fr>,
+    p: &AssignedPoint<G1Affine, Fr>,
+) -> AssignedPoint<G1Affine, Fr> {
+    let x = ctx.base_integer_chip().bisec_int(cond, &c.x, &p.x);
+    let y = ctx.base_integer_chip().bisec_int(cond, &c.y, &p.y);
+    AssignedPoint::new(x, y, c.z.clone())
+}
+
+// cond ? c : q, the result is never the identity
+fn select_g2(
+    ctx: &mut GeneralScalarEccContext<G1Affine, Fr>,
+    cond: &AssignedCondition<Fr>,
+    c: &AssignedG2Affine<G1Affine, Fr>,
+    q: &AssignedG2Affine<G1Affine, Fr>,
+) -> AssignedG2Affine<G1Affine, Fr> {
+    let x1 = ctx.base_integer_chip().bisec_int(cond, &c.x.0, &q.x.0);
+    let x2 = ctx.base_integer_chip().bisec_int(cond, &c.x.1, &q.x.1);
+    let y1 = ctx.base_integer_chip().bisec_int(cond, &c.y.0, &q.y.0);
+    let y2 = ctx.base_integer_chip().bisec_int(cond, &c.y.1, &q.y.1);
+    AssignedG2Affine::new((x1, x2), (y1, y2), c.z.clone())
+}
+
+fn fq12_is_one(
+    ctx: &mut GeneralScalarEccContext<G1Affine, Fr>,
+    f: &AssignedFq12<Bls381Fq, Fr>,
+) -> AssignedCondition<Fr> {
+    let one = ctx.base_integer_chip().assign_int_constant(Bls381Fq::one());
+    let mut is_one = ctx.base_integer_chip().is_int_equal(&f.0 .0 .0, &one);
+    for c in [
+        &f.0 .0 .1, &f.0 .1 .0, &f.0 .1 .1, &f.0 .2 .0, &f.0 .2 .1,
+        &f.1 .0 .0, &f.1 .0 .1, &f.1 .1 .0, &f.1 .1 .1, &f.1 .2 .0, &f.1 .2 .1,
+    ] {
+        let is_zero = ctx.base_integer_chip().is_int_zero(c);
+        is_one = ctx.native_ctx.borrow_mut().and(&is_one, &is_zero);
+    }
+    is_one
+}
+
+/// Constrains a point other than the identity to be on y^2 = x^3 + 4
+fn assert_g1_on_curve(
+    ctx: &mut GeneralScalarEccContext<G1Affine, Fr>,
+    p: &AssignedPoint<G1Affine, Fr>,
+) {
+    let b = ctx.base_integer_chip().assign_int_constant(Bls381Fq::from(4u64));
+    let y2 = ctx.base_integer_chip().int_square(&p.y);
+    let x2 = ctx.base_integer_chip().int_square(&p.x);
+    let x3 = ctx.base_integer_chip().int_mul(&x2, &p.x);
+    let rhs = ctx.base_integer_chip().int_add(&x3, &b);
+    let rhs = ctx.base_integer_chip().bisec_int(&p.z, &y2, &rhs);
+    ctx.base_integer_chip().assert_int_equal(&y2, &rhs);
+}
+
+/// The absolute value of the parameter x = -0xd201000000010000 of the curve
+const BLS381_X_ABS: u128 = 0xd201000000010000;
+
+/* Constrains a point on the curve to be in G1. The endomorphism
+ * sigma(x, y) = (beta * x, y) for the cube root of unity beta acts on G1 as the
+ * multiplication by -x^2, and a point on the curve is in G1 iff [x^2]p = -sigma(p).
+ */
+fn assert_g1_in_subgroup(
+    ctx: &mut GeneralScalarEccContext<G1Affine, Fr>,
+    p: &AssignedPoint<G1Affine, Fr>,
+) {
+    let beta: Bls381Fq = bn_to_field(
+        &BigUint::parse_bytes(
+            b"5f19672fdf76ce51ba69c6076a0f77eaddb3a93be6f89688de17d813620a00022e01fffffffefffe",
+            16,
+        )
+        .unwrap(),
+    );
+    let product = g1_mul_by_constant(ctx, p, BLS381_X_ABS * BLS381_X_ABS);
+    let beta = ctx.base_integer_chip().assign_int_constant(beta);
+    let zero = ctx.base_integer_chip().assign_int_constant(Bls381Fq::zero());
+    let x = ctx.base_integer_chip().int_mul(&beta, &p.x);
+    let y = ctx.base_integer_chip().int_sub(&zero, &p.y);
+    let x = ctx.base_integer_chip().bisec_int(&p.z, &product.x, &x);
+    let y = ctx.base_integer_chip().bisec_int(&p.z, &product.y, &y);
+    ctx.base_integer_chip().assert_int_equal(&product.x, &x);
+    ctx.base_integer_chip().assert_int_equal(&product.y, &y);
+}
+
+/* Constrains a point on the twist to be in G2. The endomorphism psi acts on G2 as the
+ * multiplication by x, and a point on the twist is in G2 iff psi(p) = [x]p, that is
+ * [-x]p = (conj(p.x) * c_x, -conj(p.y) * c_y) with the constants of psi_in_context.
+ */
+fn assert_g2_in_subgroup(
+    ctx: &mut GeneralScalarEccContext<G1Affine, Fr>,
+    p: &AssignedG2Affine<G1Affine, Fr>,
+) {
+    let c = |s: &str| -> Bls381Fq { bn_to_field(&BigUint::parse_bytes(s.as_bytes(), 10).unwrap()) };
+    let c_x = ctx.fq2_assign_constant((
+        Bls381Fq::zero(),
+        c("4002409555221667392624310435006688643935503118305586438271171395842971157480381377015405980053539358417135540939437"),
+    ));
+    let minus_c_y = ctx.fq2_assign_constant((
+        c("1028732146235106349975324479215795277384839936929757896155643118032610843298655225875571310552543014690878354869257"),
+        c("2973677408986561043442465346520108879172042883009249989176415018091420807192182638567116318576472649347015917690530"),
+    ));
+    let b3 = assign_twist_b3(ctx);
+    g2_assert_psi_multiple(ctx, &b3, &c_x, &minus_c_y, BLS381_X_ABS, p);
+}
+
+/// get_g1_from_cells with the identity flag constrained to be a bit and the point in G1
+fn get_checked_g1_from_cells(
+    ctx: &mut GeneralScalarEccContext<G1Affine, Fr>,
+    a: &Vec<Limb<Fr>>, //G1 (4 * 2 + 1)
+) -> AssignedPoint<G1Affine, Fr> {
+    let mut p = get_g1_from_cells(ctx, a);
+    p.z = ctx.native_ctx.borrow_mut().assign_bit(a[8].value);
+    assert_g1_on_curve(ctx, &p);
+    assert_g1_in_subgroup(ctx, &p);
+    p
+}
+
+/// get_g2_from_cells with the identity flag constrained to be a bit and the point in
+/// G2 of the twist y^2 = x^3 + 4 * (1 + u)
+fn get_checked_g2_from_cells(
+    ctx: &mut GeneralScalarEccContext<G1Affine, Fr>,
+    b: &Vec<Limb<Fr>>, //G2 (4 * 4 + 1)
+) -> AssignedG2Affine<G1Affine, Fr> {
+    let mut q = get_g2_from_cells(ctx, b);
+    q.z = ctx.native_ctx.borrow_mut().assign_bit(b[16].value);
+    let twist_b = ctx.fq2_assign_constant((Bls381Fq::from(4u64), Bls381Fq::from(4u64)));
+    g2_assert_on_curve(ctx, &twist_b, &q);
+    assert_g2_in_subgroup(ctx, &q);
+    q
+}
+
+/// s * p where p may be the identity. The identity is replaced by the generator
+/// before the windowed multiplication and the flag of the product is set afterwards.
+fn mul_g1(
+    ctx: &mut GeneralScalarEccContext<G1Affine, Fr>,
+    p: &AssignedPoint<G1Affine, Fr>,
+    s: AssignedInteger<Scalar, Fr>,
+) -> AssignedPoint<G1Affine, Fr> {
+    let generator = assign_g1_constant(ctx, G1::generator());
+    let base = select_g1(ctx, &p.z, &generator, p);
+    let product = ctx.ecc_mul(&base, s);
+    let z = ctx.native_ctx.borrow_mut().or(&product.z, &p.z);
+    AssignedPoint::new(product.x, product.y, z)
+}
+
+/* Returns whether prod e(a_i, b_i) == 1 with one miller loop and one final
+ * exponentiation. The identity flags of the points have to be bits.
+ *
+ * A pair with an identity point contributes 1 and can not enter the miller loop,
+ * so the i-th of such pairs is replaced by (2^i * g1, g2). Two extra pairs
+ * (2^n * g1, g2) and (-(2^n + sum 2^i) * g1, g2) cancel the replaced pairs, where n
+ * is the number of pairs. The partial sums of the last point never meet the added
+ * multiple of g1 because they are all between 2^n and 2^(n+1), so the incomplete
+ * addition is safe.
+ */
+fn assign_pair_check(
+    ctx: &mut GeneralScalarEccContext<G1Affine, Fr>,
+    pairs: &[(AssignedPoint<G1Affine, Fr>, AssignedG2Affine<G1Affine, Fr>)],
+) -> AssignedCondition<Fr> {
+    let g2 = assign_g2_constant(ctx, G2::generator());
+    let shift = G1::generator() * Scalar::from(1u64 << pairs.len());
+    let mut compensation = assign_g1_constant(ctx, -shift);
+    let mut terms = vec![];
+    for (i, (a, b)) in pairs.iter().enumerate() {
+        let neutral = ctx.native_ctx.borrow_mut().or(&a.z, &b.z);
+
+        let c = G1::generator() * Scalar::from(1u64 << i);
+        let c_g1 = assign_g1_constant(ctx, c);
+        let a_term = select_g1(ctx, &neutral, &c_g1, a);
+        let b_term = select_g2(ctx, &neutral, &g2, b);
+        terms.push((a_term, b_term));
+
+        // compensation -= neutral ? c : identity
+        let neg_c = assign_g1_constant(ctx, -c);
+        let not_neutral = ctx.native_ctx.borrow_mut().not(&neutral);
+        let addend = AssignedPoint::new(neg_c.x, neg_c.y, not_neutral);
+        let lhs = ctx.to_point_with_curvature(compensation);
+        let sum = ctx.ecc_add(&lhs, &addend);
+        compensation = ctx.ecc_reduce(&sum);
+    }
+    terms.push((assign_g1_constant(ctx, shift), g2.clone()));
+    terms.push((compensation, g2));
+
+    let terms = terms.iter().map(|(a, b)| (a, b)).collect::<Vec<_>>();
+    let product_raw = ctx.pairing(&terms);
+    let product = ctx.fq12_reduce(&product_raw);
+    fq12_is_one(ctx, &product)
+}
+
+impl Bls381PairCheckChip<Fr> {
+    pub fn construct(config: <Self as Chip<Fr>>::Config) -> Self {
+        Self {
+            config: config.clone(),
+            point_select_chip: SelectChip::<Fr>::new(config.point_select_chip_config),
+            base_chip: BaseChip::new(config.base_chip_config),
+            range_chip: RangeChip::<Fr>::new(config.range_chip_config),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(cs: &mut ConstraintSystem<Fr>) -> <Self as Chip<Fr>>::Config {
+        Bls381ChipConfig {
+            base_chip_config: BaseChip::configure(cs),
+            range_chip_config: RangeChip::<Fr>::configure(cs),
+            point_select_chip_config: SelectChip::configure(cs),
+        }
+    }
+
+    /* A pair is (g1, g2) followed by the enable cells of its 26 operands and the call
+     * ends with the result and the opcode and enable cells of its result line, see
+     * load_bn256_pair_check_circuit.
+     */
+    pub fn load_bls381_pair_check_circuit(
+        &self,
+        ls: &Vec<Limb<Fr>>, // n * (g1, g2, enables) + (result, opcode, enable)
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let contex = Rc::new(RefCell::new(Context::new()));
+        let mut ctx = GeneralScalarEccContext::<G1Affine, Fr>::new(contex);
+
+        assert_eq!(ls.len(), 52 * BLS381_PAIR_CHECK_PAIRS + 3);
+        let result_line = &ls[52 * BLS381_PAIR_CHECK_PAIRS..];
+        let call_enable = ctx.native_ctx.borrow_mut().assign_bit(result_line[2].value);
+        let result_opcode = ctx
+            .native_ctx
+            .borrow_mut()
+            .assign_constant(Fr::from(ForeignInst::BlsPairCheckResult as u64));
+
+        let mut g1s = vec![];
+        let mut g2s = vec![];
+        let mut enables = vec![];
+        let mut pairs = vec![];
+        for pair in ls[0..52 * BLS381_PAIR_CHECK_PAIRS].chunks_exact(52) {
+            let g1 = get_checked_g1_from_cells(&mut ctx, &pair[0..9].to_vec());
+            let g2 = get_checked_g2_from_cells(&mut ctx, &pair[9..26].to_vec());
+            let mut native = ctx.native_ctx.borrow_mut();
+            let enable = native.assign_bit(pair[26].value);
+            let in_call = native.and(&enable, &call_enable);
+            native.assert_equal(&in_call.0, &enable.0);
+            let hole = native.not(&enable);
+            let neutral = native.or(&g1.z, &hole);
+            drop(native);
+            pairs.push((AssignedPoint::new(g1.x.clone(), g1.y.clone(), neutral), g2.clone()));
+            g1s.push(g1);
+            g2s.push(g2);
+            enables.push(enable);
+        }
+        let result = assign_pair_check(&mut ctx, &pairs);
+
+        let records = Arc::try_unwrap(Into::<Context<Fr>>::into(ctx).records)
+            .unwrap()
+            .into_inner()
+            .unwrap();
+        layouter.assign_region(
+            || "base",
+            |mut region| {
+                let timer = start_timer!(|| "assign");
+                let cells = records.assign_all(
+                    &mut region,
+                    &self.base_chip,
+                    &self.range_chip,
+                    &self.point_select_chip,
+                )?;
+                for (i, (a, b)) in g1s.iter().zip(g2s.iter()).enumerate() {
+                    enable_g1affine_permute(&mut region, &cells, a, &ls[52*i..52*i+9].to_vec())?;
+                    enable_g2affine_permute(&mut region, &cells, b, &ls[52*i+9..52*i+26].to_vec())?;
+                    let enable_assigned = get_cell_of_ctx(&cells, &enables[i].0.cell);
+                    for limb in ls[52*i+26..52*i+52].iter() {
+                        region.constrain_equal(limb.get_the_cell().cell(), enable_assigned.cell())?;
+                    }
+                }
+                let result_assigned = get_cell_of_ctx(&cells, &result.0.cell);
+                region.constrain_equal(result_line[0].get_the_cell().cell(), result_assigned.cell())?;
+                let opcode_assigned = get_cell_of_ctx(&cells, &result_opcode.cell);
+                region.constrain_equal(result_line[1].get_the_cell().cell(), opcode_assigned.cell())?;
+                let enable_assigned = get_cell_of_ctx(&cells, &call_enable.0.cell);
+                region.constrain_equal(result_line[2].get_the_cell().cell(), enable_assigned.cell())?;
+                end_timer!(timer);
+                Ok(())
+            },
+        )?;
+        Ok(())
+    }
+}
+
+/// Little endian bits of the limbs of a as they are pushed, 108 bits each and 57 bits
+/// for the top one, so the integer of the bits is below 2^381 but may not be reduced
+fn assign_fq_bits(
+    ctx: &mut GeneralScalarEccContext<G1Affine, Fr>,
+    a: &AssignedFq<Bls381Fq, Fr>,
+) -> Vec<AssignedCondition<Fr>> {
+    assign_fq_limb_bits(ctx, a, &[108, 108, 108, 57])
+}
+
+/// Little endian bits of the lower limbs of a with the given widths, the limbs above
+/// them are constrained to be zero
+fn assign_fq_limb_bits(
+    ctx: &mut GeneralScalarEccContext<G1Affine, Fr>,
+    a: &AssignedFq<Bls381Fq, Fr>,
+    widths: &[u64],
+) -> Vec<AssignedCondition<Fr>> {
+    let mut native = ctx.native_ctx.borrow_mut();
+    let zero = native.assign_constant(Fr::zero());
+    for limb in a.limbs_le.iter().skip(widths.len()) {
+        native.assert_equal(limb, &zero);
+    }
+    let mut bits = vec![];
+    for (i, width) in widths.iter().cloned().enumerate() {
+        let limb = a.limbs_le[i].clone();
+        let limb_bn = fr_to_bn(&limb.val);
+        let limb_bits = (0..width)
+            .map(|j| native.assign_bit(if limb_bn.bit(j) { Fr::one() } else { Fr::zero() }))
+            .collect::<Vec<_>>();
+        let sum = native.sum_with_constant(
+            limb_bits
+                .iter()
+                .enumerate()
+                .map(|(j, b)| (&b.0, Fr::from_u128(1u128 << j)))
+                .collect(),
+            None,
+        );
+        native.assert_equal(&sum, &limb);
+        bits.append(&mut limb_bits.clone());
+    }
+    bits
+}
+
+/// Whether the integer of the little endian bits is larger than the constant c, by
+/// comparing the bits from the top while they are equal to the bits of c
+fn bits_greater_than(
+    ctx: &mut GeneralScalarEccContext<G1Affine, Fr>,
+    bits: &[AssignedCondition<Fr>],
+    c: &BigUint,
+) -> AssignedCondition<Fr> {
+    assert!(c.bits() <= bits.len() as u64);
+    let mut native = ctx.native_ctx.borrow_mut();
+    let mut greater = AssignedCondition(native.assign_constant(Fr::zero()));
+    let mut equal = AssignedCondition(native.assign_constant(Fr::one()));
+    for (i, bit) in bits.iter().enumerate().rev() {
+        if c.bit(i as u64) {
+            equal = native.and(&equal, bit);
+        } else {
+            let above = native.and(&equal, bit);
+            greater = native.or(&greater, &above);
+            let not_bit = native.not(bit);
+            equal = native.and(&equal, &not_bit);
+        }
+    }
+    greater
+}
+
+/// The big endian words of 64 bits of the little endian bits, whose number is a
+/// multiple of 64
+fn bits_to_be_words(
+    ctx: &mut GeneralScalarEccContext<G1Affine, Fr>,
+    bits: &[AssignedCondition<Fr>],
+) -> Vec<AssignedValue<Fr>> {
+    let mut native = ctx.native_ctx.borrow_mut();
+    let words = bits
+        .chunks_exact(64)
+        .rev()
+        .map(|word| {
+            native.sum_with_constant(
+                word.iter()
+                    .enumerate()
+                    .map(|(j, b)| (&b.0, Fr::from_u128(1u128 << j)))
+                    .collect(),
+                None,
+            )
+        })
+        .collect();
+    words
+}
+
+/* OS2IP(bytes) mod p of the 64 uniform bytes that hash_to_field reduces to one Fq
+ * element, given as 8 big endian words. The bytes are split as hi * 2^256 + lo where
+ * hi and lo are below p, and the limbs of hi and lo are bound to the words by their
+ * bits. Returns the element and the words it was bound to.
+ */
+fn assign_fq_from_uniform_words(
+    ctx: &mut GeneralScalarEccContext<G1Affine, Fr>,
+    words: &[Limb<Fr>],
+) -> (AssignedFq<Bls381Fq, Fr>, Vec<AssignedValue<Fr>>) {
+    let word_bn = |ws: &[Limb<Fr>]| {
+        ws.iter()
+            .fold(BigUint::from(0u32), |acc, w| (acc << 64) + fr_to_bn(&w.value))
+    };
+    let hi = ctx.base_integer_chip().assign_w(&word_bn(&words[0..4]));
+    let lo = ctx.base_integer_chip().assign_w(&word_bn(&words[4..8]));
+    let mut assigned_words = vec![];
+    for half in [&hi, &lo] {
+        let bits = assign_fq_limb_bits(ctx, half, &[108, 108, 40]);
+        assigned_words.append(&mut bits_to_be_words(ctx, &bits));
+    }
+
+    let modulus = field_to_bn(&-Bls381Fq::one()) + BigUint::from(1u32);
+    let shift = bn_to_field::<Bls381Fq>(&((BigUint::from(1u32) << 256) % modulus));
+    let shift = ctx.base_integer_chip().assign_int_constant(shift);
+    let hi_shifted = ctx.base_integer_chip().int_mul(&hi, &shift);
+    let u = ctx.base_integer_chip().int_add(&hi_shifted, &lo);
+    (u, assigned_words)
+}
+
+/* The 6 big endian words of the 48 bytes of bls381_g1_to_compressed(p). The
+ * coordinates are constrained to be reduced, so the sign flag (0x20), which is set
+ * when y is larger than -y, comes from the bits of y. The coordinates of the identity
+ * are ignored and it is encoded as 0xc0 followed by zeros.
+ */
+fn assign_g1_compressed_words(
+    ctx: &mut GeneralScalarEccContext<G1Affine, Fr>,
+    p: &AssignedPoint<G1Affine, Fr>,
+) -> Vec<AssignedValue<Fr>> {
+    let modulus_minus_one = field_to_bn(&-Bls381Fq::one());
+    let half = &modulus_minus_one / BigUint::from(2u32);
+    let x_bits = assign_fq_bits(ctx, &p.x);
+    let y_bits = assign_fq_bits(ctx, &p.y);
+    for bits in [&x_bits, &y_bits] {
+        let out_of_field = bits_greater_than(ctx, bits, &modulus_minus_one);
+        let mut native = ctx.native_ctx.borrow_mut();
+        let reduced = native.not(&out_of_field);
+        native.assert_true(&reduced);
+    }
+    let larger = bits_greater_than(ctx, &y_bits, &half);
+
+    let mut native = ctx.native_ctx.borrow_mut();
+    let not_identity = native.not(&p.z);
+    let mut bits = x_bits
+        .iter()
+        .map(|b| native.and(b, &not_identity))
+        .collect::<Vec<_>>();
+    bits.push(native.and(&larger, &not_identity));
+    bits.push(p.z.clone());
+    bits.push(AssignedCondition(native.assign_constant(Fr::one())));
+    drop(native);
+    bits_to_be_words(ctx, &bits)
+}
+
+/// The first big endian word of a versioned hash as 2^56 * version + its lower 56 bits,
+/// which constrains its top byte to be the version of kzg commitments
+fn assign_versioned_hash_word(
+    ctx: &mut GeneralScalarEccContext<G1Affine, Fr>,
+    word: &Limb<Fr>,
+) -> AssignedValue<Fr> {
+    let word_bn = fr_to_bn(&word.value);
+    let mut native = ctx.native_ctx.borrow_mut();
+    let bits = (0..56)
+        .map(|j| native.assign_bit(if word_bn.bit(j) { Fr::one() } else { Fr::zero() }))
+        .collect::<Vec<_>>();
+    native.sum_with_constant(
+        bits.iter()
+            .enumerate()
+            .map(|(j, b)| (&b.0, Fr::from_u128(1u128 << j)))
+            .collect(),
+        Some(Fr::from((VERSIONED_HASH_VERSION_KZG as u64) << 56)),
+    )
+}
+
+pub struct Bls381KzgChip<N: FieldExt> {
+    config: Bls381ChipConfig,
+    base_chip: BaseChip<Fr>,
+    pub range_chip: RangeChip<Fr>,
+    point_select_chip: SelectChip<Fr>,
+    _marker: PhantomData<N>,
+}
+
+impl<N: FieldExt> Chip<N> for Bls381KzgChip<N> {
+    type Config = Bls381ChipConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl Bls381KzgChip<Fr> {
+    pub fn construct(config: <Self as Chip<Fr>>::Config) -> Self {
+        Self {
+            config: config.clone(),
+            point_select_chip: SelectChip::<Fr>::new(config.point_select_chip_config),
+            base_chip: BaseChip::new(config.base_chip_config),
+            range_chip: RangeChip::<Fr>::new(config.range_chip_config),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(cs: &mut ConstraintSystem<Fr>) -> <Self as Chip<Fr>>::Config {
+        Bls381ChipConfig {
+            base_chip_config: BaseChip::configure(cs),
+            range_chip_config: RangeChip::<Fr>::configure(cs),
+            point_select_chip_config: SelectChip::configure(cs),
+        }
+    }
+
+    /* Checks the opening p(z) == y of the commitment C with the proof pi as
+     *     e(C, g2) * e(-y * g1, g2) * e(z * pi, g2) * e(-pi, [tau]_2) == 1
+     * so that the points of the setup are constants and z and y only multiply g1
+     * points. Each term may be the identity, which the pair check takes care of.
+     * The first word of the versioned hash is constrained to start with the version
+     * byte. The compressed encoding of C is returned for the sha256 that the guest
+     * compares with the rest of the versioned hash.
+     */
+    pub fn load_bls381_kzg_circuit(
+        &self,
+        ls: &Vec<Limb<Fr>>, // n * (versioned hash, z, y, commitment, proof, result, compressed commitment)
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let contex = Rc::new(RefCell::new(Context::new()));
+        let mut ctx = GeneralScalarEccContext::<G1Affine, Fr>::new(contex);
+
+        let g2 = assign_g2_constant(&mut ctx, G2::generator());
+        let tau = assign_g2_constant(&mut ctx, G2::from(*KZG_SETUP_G2));
+        let neg_g1 = assign_g1_constant(&mut ctx, -G1::generator());
+
+        let mut versions = vec![];
+        let mut frs = vec![];
+        let mut g1s = vec![];
+        let mut results = vec![];
+        let mut compressed = vec![];
+        for args in ls.chunks_exact(35) {
+            versions.push(assign_versioned_hash_word(&mut ctx, &args[0]));
+            let z = get_scalar_from_cell(&mut ctx, &args[4..7].to_vec());
+            let y = get_scalar_from_cell(&mut ctx, &args[7..10].to_vec());
+            let commitment = get_checked_g1_from_cells(&mut ctx, &args[10..19].to_vec());
+            let proof = get_checked_g1_from_cells(&mut ctx, &args[19..28].to_vec());
+
+            let neg_y = ctx.ecc_mul(&neg_g1, y.clone());
+            let z_proof = mul_g1(&mut ctx, &proof, z.clone());
+            let neg_proof = ctx.ecc_neg(&proof);
+            let result = assign_pair_check(
+                &mut ctx,
+                &[
+                    (commitment.clone(), g2.clone()),
+                    (neg_y, g2.clone()),
+                    (z_proof, g2.clone()),
+                    (neg_proof, tau.clone()),
+                ],
+            );
+
+            compressed.push(assign_g1_compressed_words(&mut ctx, &commitment));
+            frs.push((z, y));
+            g1s.push((commitment, proof));
+            results.push(result);
+        }
+
+        let records = Arc::try_unwrap(Into::<Context<Fr>>::into(ctx).records)
+            .unwrap()
+            .into_inner()
+            .unwrap();
+        layouter.assign_region(
+            || "base",
+            |mut region| {
+                let timer = start_timer!(|| "assign");
+                let cells = records.assign_all(
+                    &mut region,
+                    &self.base_chip,
+                    &self.range_chip,
+                    &self.point_select_chip,
+                )?;
+                for (i, version) in versions.iter().enumerate() {
+                    let version_assigned = get_cell_of_ctx(&cells, &version.cell);
+                    region.constrain_equal(ls[35*i].get_the_cell().cell(), version_assigned.cell())?;
+                }
+                for (i, ((z, y), (commitment, proof))) in frs.iter().zip(g1s.iter()).enumerate() {
+                    enable_fr_permute(&mut region, &cells, z, &ls[35*i+4..35*i+7].to_vec())?;
+                    enable_fr_permute(&mut region, &cells, y, &ls[35*i+7..35*i+10].to_vec())?;
+                    enable_g1affine_permute(&mut region, &cells, commitment, &ls[35*i+10..35*i+19].to_vec())?;
+                    enable_g1affine_permute(&mut region, &cells, proof, &ls[35*i+19..35*i+28].to_vec())?;
+                }
+                for (i, result) in results.iter().enumerate() {
+                    let result_assigned = get_cell_of_ctx(&cells, &result.0.cell);
+                    region.constrain_equal(ls[35*i+28].get_the_cell().cell(), result_assigned.cell())?;
+                }
+                for (i, words) in compressed.iter().enumerate() {
+                    for (j, word) in words.iter().enumerate() {
+                        let word_assigned = get_cell_of_ctx(&cells, &word.cell);
+                        region.constrain_equal(ls[35*i+29+j].get_the_cell().cell(), word_assigned.cell())?;
+                    }
+                }
+                end_timer!(timer);
+                Ok(())
+            },
+        )?;
+        Ok(())
+    }
+}
+
+/// The number of public keys that a verification call pushes, unused keys are padded
+/// with the identity and more keys have to be summed by the guest first
+pub const BLS381_VERIFY_PUBKEYS: usize = 8;
+
+pub struct Bls381VerifyChip<N: FieldExt> {
+    config: Bls381ChipConfig,
+    base_chip: BaseChip<Fr>,
+    pub range_chip: RangeChip<Fr>,
+    point_select_chip: SelectChip<Fr>,
+    _marker: PhantomData<N>,
+}
+
+impl<N: FieldExt> Chip<N> for Bls381VerifyChip<N> {
+    type Config = Bls381ChipConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl Bls381VerifyChip<Fr> {
+    pub fn construct(config: <Self as Chip<Fr>>::Config) -> Self {
+        Self {
+            config: config.clone(),
+            point_select_chip: SelectChip::<Fr>::new(config.point_select_chip_config),
+            base_chip: BaseChip::new(config.base_chip_config),
+            range_chip: RangeChip::<Fr>::new(config.range_chip_config),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(cs: &mut ConstraintSystem<Fr>) -> <Self as Chip<Fr>>::Config {
+        Bls381ChipConfig {
+            base_chip_config: BaseChip::configure(cs),
+            range_chip_config: RangeChip::<Fr>::configure(cs),
+            point_select_chip_config: SelectChip::configure(cs),
+        }
+    }
+
+    /* Fast aggregate verify of a signature over G2, see host::bls:
+     *     e(sum pk_i, H(u)) * e(-g1, sig) == 1 and sum pk_i is not the identity
+     * where H maps the two Fq2 elements u = hash_to_field(m) to G2. A single
+     * signature is the case of one key and identity padding. The circuit verifies
+     * over the 256 uniform bytes that the guest pushes, which are reduced to u here.
+     * That they are expand_message_xmd(m) is not constrained, the guest computes them
+     * with the sha256 host calls.
+     */
+    pub fn load_bls381_verify_circuit(
+        &self,
+        ls: &Vec<Limb<Fr>>, // n * (pks, uniform bytes, sig, result)
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let contex = Rc::new(RefCell::new(Context::new()));
+        let mut ctx = GeneralScalarEccContext::<G1Affine, Fr>::new(contex);
+
+        let neg_g1 = assign_g1_constant(&mut ctx, -G1::generator());
+        let hash_start = 9 * BLS381_VERIFY_PUBKEYS;
+        let sig_start = hash_start + 32;
+        let size = sig_start + 17 + 1;
+
+        let mut pks = vec![];
+        let mut words = vec![];
+        let mut sigs = vec![];
+        let mut results = vec![];
+        for (k, args) in ls.chunks_exact(size).enumerate() {
+            let mut pk = g1_assign_identity(&mut ctx);
+            for i in 0..BLS381_VERIFY_PUBKEYS {
+                let p = get_checked_g1_from_cells(&mut ctx, &args[9 * i..9 * i + 9].to_vec());
+                pk = g1_add(&mut ctx, &pk, &p);
+                pks.push(p);
+            }
+
+            let mut u = vec![];
+            let mut bound = vec![];
+            for i in 0..4 {
+                let start = hash_start + 8 * i;
+                let (c, mut ws) = assign_fq_from_uniform_words(&mut ctx, &args[start..start + 8]);
+                u.push(c);
+                bound.append(&mut ws);
+            }
+            let (x, y) = hash_to_g2_in_context(
+                &mut ctx,
+                &[(u[0].clone(), u[1].clone()), (u[2].clone(), u[3].clone())],
+            );
+            let z = ctx.native_ctx.borrow_mut().assign_constant(Fr::zero());
+            let h = AssignedG2Affine::new(x, y, AssignedCondition(z));
+            words.push(bound);
+
+            let sig = get_checked_g2_from_cells(&mut ctx, &args[sig_start..sig_start + 17].to_vec());
+            let pair_check = assign_pair_check(&mut ctx, &[(pk.clone(), h), (neg_g1.clone(), sig.clone())]);
+            let not_identity = ctx.native_ctx.borrow_mut().not(&pk.z);
+            let result = ctx.native_ctx.borrow_mut().and(&pair_check, &not_identity);
+            sigs.push(sig);
+            results.push(result);
+        }
+
+        let records = Arc::try_unwrap(Into::<Context<Fr>>::into(ctx).records)
+            .unwrap()
+            .into_inner()
+            .unwrap();
+        layouter.assign_region(
+            || "base",
+            |mut region| {
+                let timer = start_timer!(|| "assign");
+                let cells = records.assign_all(
+                    &mut region,
+                    &self.base_chip,
+                    &self.range_chip,
+                    &self.point_select_chip,
+                )?;
+                for (i, pk) in pks.iter().enumerate() {
+                    let start = (i / BLS381_VERIFY_PUBKEYS) * size + 9 * (i % BLS381_VERIFY_PUBKEYS);
+                    enable_g1affine_permute(&mut region, &cells, pk, &ls[start..start + 9].to_vec())?;
+                }
+                for (k, ((ws, sig), result)) in words.iter().zip(sigs.iter()).zip(results.iter()).enumerate() {
+                    for (i, w) in ws.iter().enumerate() {
+                        let w_assigned = get_cell_of_ctx(&cells, &w.cell);
+                        region.constrain_equal(ls[k * size + hash_start + i].get_the_cell().cell(), w_assigned.cell())?;
+                    }
+                    let start = k * size + sig_start;
+                    enable_g2affine_permute(&mut region, &cells, sig, &ls[start..start + 17].to_vec())?;
+                    let result_assigned = get_cell_of_ctx(&cells, &result.0.cell);
+                    region.constrain_equal(ls[k * size + size - 1].get_the_cell().cell(), result_assigned.cell())?;
+                }
+                end_timer!(timer);
+                Ok(())
+            },
+        )?;
+        Ok(())
+    }
+}
+
+// 3 * b of the twist y^2 = x^3 + 4 * (1 + u)
+fn assign_twist_b3(ctx: &mut GeneralScalarEccContext<G1Affine, Fr>) -> AssignedFq2<Bls381Fq, Fr> {
+    ctx.fq2_assign_constant((Bls381Fq::from(12u64), Bls381Fq::from(12u64)))
+}
+
+/// Little endian bits of the canonical representation of a
+fn assign_scalar_bits(
+    ctx: &mut GeneralScalarEccContext<G1Affine, Fr>,
+    a: &AssignedInteger<Scalar, Fr>,
+) -> Vec<AssignedCondition<Fr>> {
+    let a_bn = field_to_bn(&ctx.scalar_integer_ctx.get_w(a));
+    let a_canonical = ctx.scalar_integer_ctx.assign_w(&a_bn);
+    ctx.scalar_integer_ctx.assert_int_equal(&a_canonical, a);
+    let mut native = ctx.native_ctx.borrow_mut();
+    let mut bits = vec![];
+    for (i, width) in [108, 108, 39].into_iter().enumerate() {
+        let limb = a_canonical.limbs_le[i].clone();
+        let limb_bn = fr_to_bn(&limb.val);
+        let limb_bits = (0..width)
+            .map(|j| native.assign_bit(if limb_bn.bit(j) { Fr::one() } else { Fr::zero() }))
+            .collect::<Vec<_>>();
+        let sum = native.sum_with_constant(
+            limb_bits
+                .iter()
+                .enumerate()
+                .map(|(j, b)| (&b.0, Fr::from_u128(1u128 << j)))
+                .collect(),
+            None,
+        );
+        native.assert_equal(&sum, &limb);
+        bits.append(&mut limb_bits.clone());
+    }
+    bits
+}
+
+pub struct Bls381SumG2Chip<N: FieldExt> {
+    config: Bls381ChipConfig,
+    base_chip: BaseChip<N>,
+    pub range_chip: RangeChip<N>,
+    point_select_chip: SelectChip<N>,
+    _marker: PhantomData<N>,
+}
+
+impl<N: FieldExt> Chip<N> for Bls381SumG2Chip<N> {
+    type Config = Bls381ChipConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl Bls381SumG2Chip<Fr> {
+    pub fn construct(config: <Self as Chip<Fr>>::Config) -> Self {
+        Self {
+            config: config.clone(),
+            point_select_chip: SelectChip::<Fr>::new(config.point_select_chip_config),
+            base_chip: BaseChip::new(config.base_chip_config),
+            range_chip: RangeChip::<Fr>::new(config.range_chip_config),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(cs: &mut ConstraintSystem<Fr>) -> <Self as Chip<Fr>>::Config {
+        Bls381ChipConfig {
+            base_chip_config: BaseChip::configure(cs),
+            range_chip_config: RangeChip::<Fr>::configure(cs),
+            point_select_chip_config: SelectChip::configure(cs),
+        }
+    }
+
+    pub fn load_bls381_sum_g2_circuit(
+        &self,
+        ls: &Vec<Limb<Fr>>,  // n * (new, fr, g2, sum)
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let contex = Rc::new(RefCell::new(Context::new()));
+        let mut ctx = GeneralScalarEccContext::<G1Affine, Fr>::new(contex);
+
+        let mut news = vec![];
+        let mut ais = vec![];
+        let mut g2s = vec![];
+        let mut sums = vec![];
+        let b3 = assign_twist_b3(&mut ctx);
+        let identity = g2_assign_identity(&mut ctx);
+        let mut sum = identity.clone();
+        for group in ls.chunks_exact(38) {
+            let new = ctx.native_ctx.borrow_mut().assign_bit(group[0].value);
+            let lhs = g2_select(&mut ctx, &new, &identity, &sum);
+            let a = get_scalar_from_cell(&mut ctx, &group[1..4].to_vec());
+            let bits = assign_scalar_bits(&mut ctx, &a);
+            let g = get_checked_g2_from_cells(&mut ctx, &group[4..21].to_vec());
+            let g_projective = g2_from_affine(&mut ctx, &g);
+            let rhs = g2_mul(&mut ctx, &b3, &g_projective, &bits);
+            sum = g2_add(&mut ctx, &b3, &lhs, &rhs);
+            let sum_affine: AssignedG2Affine<G1Affine, Fr> = g2_to_affine(&mut ctx, &sum);
+            news.push(new);
+            ais.push(a);
+            g2s.push(g);
+            sums.push(sum_affine);
+        }
+
+        let records = Arc::try_unwrap(Into::<Context<Fr>>::into(ctx).records)
+            .unwrap()
+            .into_inner()
+            .unwrap();
+        layouter.assign_region(
+            || "base",
+            |mut region| {
+                let timer = start_timer!(|| "assign");
+                let cells = records.assign_all(
+                    &mut region,
+                    &self.base_chip,
+                    &self.range_chip,
+                    &self.point_select_chip,
+                )?;
+                for (i, new) in news.iter().enumerate() {
+                    let new_assigned = get_cell_of_ctx(&cells, &new.0.cell);
+                    region.constrain_equal(ls[38 * i].get_the_cell().cell(), new_assigned.cell())?;
+                }
+                for (i, a) in ais.iter().enumerate() {
+                    enable_fr_permute(&mut region, &cells, a, &ls[38*i+1..38*i+4].to_vec())?;
+                }
+                for (i, g) in g2s.iter().enumerate() {
+                    enable_g2affine_permute(&mut region, &cells, g, &ls[38*i+4..38*i+21].to_vec())?;
+                }
+                for (i, s) in sums.iter().enumerate() {
+                    enable_g2affine_permute(&mut region, &cells, s, &ls[38*i+21..38*i+38].to_vec())?;
+                }
+                end_timer!(timer);
+                Ok(())
+            },
+        )?;
+        Ok(())
+    }
+}
+
+pub struct Bls381PairCompressedChip<N: FieldExt> {
+    config: Bls381ChipConfig,
+    base_chip: BaseChip<Fr>,
+    pub range_chip: RangeChip<Fr>,
+    point_select_chip: SelectChip<Fr>,
+    _marker: PhantomData<N>,
+}
+
+impl<N: FieldExt> Chip<N> for Bls381PairCompressedChip<N> {
+    type Config = Bls381ChipConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl Bls381PairCompressedChip<Fr> {
+    pub fn construct(config: <Self as Chip<Fr>>::Config) -> Self {
+        Self {
+            config: config.clone(),
+            point_select_chip: SelectChip::<Fr>::new(config.point_select_chip_config),
+            base_chip: BaseChip::new(config.base_chip_config),
+            range_chip: RangeChip::<Fr>::new(config.range_chip_config),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(cs: &mut ConstraintSystem<Fr>) -> <Self as Chip<Fr>>::Config {
+        Bls381ChipConfig {
+            base_chip_config: BaseChip::configure(cs),
+            range_chip_config: RangeChip::<Fr>::configure(cs),
+            point_select_chip_config: SelectChip::configure(cs),
+        }
+    }
+
+    pub fn load_bls381_pair_compressed_circuit(
+        &self,
+        a: &Vec<Limb<Fr>>,  //G1 (4 * 2 + 1)
+        b: &Vec<Limb<Fr>>,  //G2 (4 * 4 + 1)
+        ab: &Vec<Limb<Fr>>, // compressed Gt (4 * 6 + 1)
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let contex = Rc::new(RefCell::new(Context::new()));
+        let mut ctx = GeneralScalarEccContext::<G1Affine, Fr>::new(contex);
+
+        let a_g1 = get_checked_g1_from_cells(&mut ctx, a);
+        let b_g2 = get_checked_g2_from_cells(&mut ctx, b);
+        let ab_fq12_raw = ctx.pairing(&[(&a_g1, &b_g2)]);
+        let ab_fq12 = ctx.fq12_reduce(&ab_fq12_raw);
+
+        let cs = (0..6)
+            .map(|i| ctx.base_integer_chip().assign_w(&assigned_cells_to_bn381(ab, 4 * i)))
+            .collect::<Vec<_>>();
+        let is_one = ctx.native_ctx.borrow_mut().assign_bit(ab[24].value);
+        let c = (
+            (cs[0].clone(), cs[1].clone()),
+            (cs[2].clone(), cs[3].clone()),
+            (cs[4].clone(), cs[5].clone()),
+        );
+        // xi = 1 + u
+        let xi = ctx.fq2_assign_constant((Bls381Fq::one(), Bls381Fq::one()));
+        assert_gt_compressed(&mut ctx, &xi, &ab_fq12, &c, &is_one);
+
+        let records = Arc::try_unwrap(Into::<Context<Fr>>::into(ctx).records)
+            .unwrap()
+            .into_inner()
+            .unwrap();
+
+        layouter.assign_region(
+            || "base",
+            |mut region| {
+                let timer = start_timer!(|| "assign");
+                let cells = records.assign_all(
+                    &mut region,
+                    &self.base_chip,
+                    &self.range_chip,
+                    &self.point_select_chip,
+                )?;
+                enable_g1affine_permute(&mut region, &cells, &a_g1, a)?;
+                enable_g2affine_permute(&mut region, &cells, &b_g2, b)?;
+                for (i, c) in cs.iter().enumerate() {
+                    enable_fq_permute(&mut region, &cells, c, &ab[4 * i..4 * i + 4].to_vec())?;
+                }
+                let is_one_assigned = get_cell_of_ctx(&cells, &is_one.0.cell);
+                region.constrain_equal(ab[24].get_the_cell().cell(), is_one_assigned.cell())?;
+                end_timer!(timer);
+                Ok(())
+            },
+        )?;
+        Ok(())
+    }
+}
+
+fn get_fq12_from_cells(
+    ctx: &mut GeneralScalarEccContext<G1Affine, Fr>,
+    f: &Vec<Limb<Fr>>, // Fq_12 (4 * 12)
+) -> AssignedFq12<Bls381Fq, Fr> {
+    let cs = (0..12)
+        .map(|i| ctx.base_integer_chip().assign_w(&assigned_cells_to_bn381(f, 4 * i)))
+        .collect::<Vec<_>>();
+    (
+        (
+            (cs[0].clone(), cs[1].clone()),
+            (cs[2].clone(), cs[3].clone()),
+            (cs[4].clone(), cs[5].clone()),
+        ),
+        (
+            (cs[6].clone(), cs[7].clone()),
+            (cs[8].clone(), cs[9].clone()),
+            (cs[10].clone(), cs[11].clone()),
+        ),
+    )
+}
+
+pub struct Bls381GtChip<N: FieldExt> {
+    config: Bls381ChipConfig,
+    base_chip: BaseChip<Fr>,
+    pub range_chip: RangeChip<Fr>,
+    point_select_chip: SelectChip<Fr>,
+    _marker: PhantomData<N>,
+}
+
+impl<N: FieldExt> Chip<N> for Bls381GtChip<N> {
+    type Config = Bls381ChipConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl Bls381GtChip<Fr> {
+    pub fn construct(config: <Self as Chip<Fr>>::Config) -> Self {
+        Self {
+            config: config.clone(),
+            point_select_chip: SelectChip::<Fr>::new(config.point_select_chip_config),
+            base_chip: BaseChip::new(config.base_chip_config),
+            range_chip: RangeChip::<Fr>::new(config.range_chip_config),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(cs: &mut ConstraintSystem<Fr>) -> <Self as Chip<Fr>>::Config {
+        Bls381ChipConfig {
+            base_chip_config: BaseChip::configure(cs),
+            range_chip_config: RangeChip::<Fr>::configure(cs),
+            point_select_chip_config: SelectChip::configure(cs),
+        }
+    }
+
+    /* A call pushes an operation (see circuits::gt) and two elements of Fq12, and gets
+     * back an element of Fq12. The second operand of an inversion is ignored.
+     */
+    pub fn load_bls381_gt_circuit(
+        &self,
+        ls: &Vec<Limb<Fr>>, // n * (op, a, b, result)
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let contex = Rc::new(RefCell::new(Context::new()));
+        let mut ctx = GeneralScalarEccContext::<G1Affine, Fr>::new(contex);
+
+        let size = 1 + 3 * 48;
+        // xi = 1 + u
+        let xi = ctx.fq2_assign_constant((Bls381Fq::one(), Bls381Fq::one()));
+        let mut ops = vec![];
+        let mut fq12s = vec![];
+        for group in ls.chunks_exact(size) {
+            let op = group[0].value;
+            // op = is_invert + 2 * is_equal, and at most one of them is set
+            let (is_invert, is_equal, op_value) = {
+                let mut native = ctx.native_ctx.borrow_mut();
+                let is_invert = native.assign_bit(if op == Fr::from(GT_OP_INVERT) { Fr::one() } else { Fr::zero() });
+                let is_equal = native.assign_bit(if op == Fr::from(GT_OP_EQUAL) { Fr::one() } else { Fr::zero() });
+                let both = native.and(&is_invert, &is_equal);
+                let not_both = native.not(&both);
+                native.assert_true(&not_both);
+                let op_value = native.sum_with_constant(
+                    vec![(&is_invert.0, Fr::one()), (&is_equal.0, Fr::from(2u64))],
+                    None,
+                );
+                (is_invert, is_equal, op_value)
+            };
+            let a = get_fq12_from_cells(&mut ctx, &group[1..49].to_vec());
+            let b = get_fq12_from_cells(&mut ctx, &group[49..97].to_vec());
+            let result = get_fq12_from_cells(&mut ctx, &group[97..145].to_vec());
+            assert_gt_op(&mut ctx, &xi, &is_invert, &is_equal, &a, &b, &result);
+            ops.push(op_value);
+            fq12s.push((a, b, result));
+        }
+
+        let records = Arc::try_unwrap(Into::<Context<Fr>>::into(ctx).records)
+            .unwrap()
+            .into_inner()
+            .unwrap();
+        layouter.assign_region(
+            || "base",
+            |mut region| {
+                let timer = start_timer!(|| "assign");
+                let cells = records.assign_all(
+                    &mut region,
+                    &self.base_chip,
+                    &self.range_chip,
+                    &self.point_select_chip,
+                )?;
+                for (i, op) in ops.iter().enumerate() {
+                    let op_assigned = get_cell_of_ctx(&cells, &op.cell);
+                    region.constrain_equal(ls[size * i].get_the_cell().cell(), op_assigned.cell())?;
+                }
+                for (i, (a, b, result)) in fq12s.iter().enumerate() {
+                    let start = size * i + 1;
+                    enable_fq12_permute(&mut region, &cells, a, &ls[start..start + 48].to_vec())?;
+                    enable_fq12_permute(&mut region, &cells, b, &ls[start + 48..start + 96].to_vec())?;
+                    enable_fq12_permute(&mut region, &cells, result, &ls[start + 96..start + 144].to_vec())?;
+                }
+                end_timer!(timer);
+                Ok(())
+            },
+        )?;
+        Ok(())
+    }
+}
@@ -3,18 +3,20 @@ use halo2_proofs::pairing::bn256::Fr;
 use halo2_proofs::{
     arithmetic::{BaseExt, FieldExt},
     circuit::{AssignedCell, Chip, Layouter, Region},
-    pairing::bn256::G1Affine,
+    pairing::bn256::{Fq2, G1Affine, G2Affine, G1, G2},
+    pairing::group::Group,
     plonk::{ConstraintSystem, Error},
 };
 use halo2ecc_s::{circuit::{base_chip::BaseChipOps, ecc_chip::EccChipScalarOps}, assign::AssignedValue};
-use halo2ecc_s::circuit::fq12::Fq12ChipOps;
+use halo2ecc_s::circuit::integer_chip::IntegerChipOps;
+use halo2ecc_s::circuit::fq12::{Fq12ChipOps, Fq2ChipOps};
 use std::cell::RefCell;
 use std::marker::PhantomData;
 use std::rc::Rc;
 use std::sync::Arc;
 
 use halo2_proofs::pairing::bn256::Fq as Bn256Fq;
-use halo2ecc_s::assign::{AssignedCondition, AssignedFq, Cell as ContextCell};
+use halo2ecc_s::assign::{AssignedCondition, AssignedFq, AssignedFq2, Cell as ContextCell};
 use halo2ecc_s::circuit::ecc_chip::EccBaseIntegerChipWrapper;
 use halo2ecc_s::circuit::{ecc_chip::EccChipBaseOps, pairing_chip::PairingChipOps};
 
@@ -26,10 +28,18 @@ use halo2ecc_s::{
         range_chip::{RangeChip, RangeChipConfig},
         select_chip::{SelectChip, SelectChipConfig},
     },
-    context::{Context, IntegerContext, NativeScalarEccContext},
+    context::{Context, IntegerContext, NativeScalarEccContext, Records},
 };
 
-use crate::utils::Limb;
+use crate::circuits::g1::{g1_add, g1_assign_identity, g1_msm, g1_select};
+use crate::circuits::g2::{
+    g2_add, g2_assert_on_curve, g2_assert_psi_multiple, g2_assign_identity, g2_from_affine, g2_mul,
+    g2_select, g2_to_affine,
+};
+use crate::circuits::gt::{assert_gt_compressed, assert_gt_op, GT_OP_EQUAL, GT_OP_INVERT};
+use crate::host::ForeignInst;
+use crate::utils::{bn_to_field, Limb};
+use ff::Field;
 use num_bigint::BigUint;
 use std::ops::{AddAssign, Mul};
 
@@ -85,7 +95,7 @@ fn assigned_cells_to_bn256(
     bn
 }
 
-fn get_scalar_from_cell(
+pub(crate) fn get_scalar_from_cell(
     ctx: &mut NativeScalarEccContext<G1Affine>,
     a: Fr,
 ) -> AssignedValue<Fr> {
@@ -93,7 +103,7 @@ fn get_scalar_from_cell(
     v
 }
 
-fn get_g1_from_cells(
+pub(crate) fn get_g1_from_cells(
     ctx: &mut NativeScalarEccContext<G1Affine>,
     a: &Vec<Limb<Fr>>, //G1 (3 * 2 + 1)
 ) -> AssignedPoint<G1Affine, Fr> {
@@ -113,7 +123,7 @@ fn get_g1_from_cells(
     )
 }
 
-fn get_g2_from_cells(
+pub(crate) fn get_g2_from_cells(
     ctx: &mut NativeScalarEccContext<G1Affine>,
     b: &Vec<Limb<Fr>>, //G2 (3 * 4 + 1)
 ) -> AssignedG2Affine<G1Affine, Fr> {
@@ -137,7 +147,7 @@ fn get_g2_from_cells(
     )
 }
 
-fn get_cell_of_ctx(
+pub(crate) fn get_cell_of_ctx(
     cells: &Vec<Vec<Vec<Option<AssignedCell<Fr, Fr>>>>>,
     cell: &ContextCell,
 ) -> AssignedCell<Fr, Fr> {
@@ -146,7 +156,7 @@ fn get_cell_of_ctx(
         .unwrap()
 }
 
-fn enable_fr_permute(
+pub(crate) fn enable_fr_permute(
     region: &mut Region<'_, Fr>,
     cells: &Vec<Vec<Vec<Option<AssignedCell<Fr, Fr>>>>>,
     fr: &AssignedValue<Fr>,
@@ -172,7 +182,7 @@ fn enable_fq_permute(
     Ok(())
 }
 
-fn enable_g1affine_permute(
+pub(crate) fn enable_g1affine_permute(
     region: &mut Region<'_, Fr>,
     cells: &Vec<Vec<Vec<Option<AssignedCell<Fr, Fr>>>>>,
     point: &AssignedPoint<G1Affine, Fr>,
@@ -187,7 +197,7 @@ fn enable_g1affine_permute(
     Ok(())
 }
 
-fn enable_g2affine_permute(
+pub(crate) fn enable_g2affine_permute(
     region: &mut Region<'_, Fr>,
     cells: &Vec<Vec<Vec<Option<AssignedCell<Fr, Fr>>>>>,
     point: &AssignedG2Affine<G1Affine, Fr>,
@@ -316,8 +326,8 @@ impl Bn256PairChip<Fr> {
         let ctx = IntegerContext::<Bn256Fq, Fr>::new(context);
         let mut ctx = NativeScalarEccContext(ctx, 0);
 
-        let a_g1 = get_g1_from_cells(&mut ctx, a);
-        let b_g2 = get_g2_from_cells(&mut ctx, b);
+        let a_g1 = get_checked_g1_from_cells(&mut ctx, a);
+        let b_g2 = get_checked_g2_from_cells(&mut ctx, b);
         let ab_fq12_raw = ctx.pairing(&[(&a_g1, &b_g2)]);
         let ab_fq12 = ctx.fq12_reduce(&ab_fq12_raw);
         let records = Arc::try_unwrap(Into::<Context<Fr>>::into(ctx).records)
@@ -398,7 +408,7 @@ impl Bn256SumChip<Fr> {
         let mut ais = vec![];
         let mut g1s = vec![];
         let mut sums = vec![];
-        let identity = ctx.assign_identity();
+        let identity = g1_assign_identity(&mut ctx);
         let mut sum = identity.clone();
         for group in ls.chunks_exact(16) {
             // using constraint to fix if to reset
@@ -408,10 +418,9 @@ impl Bn256SumChip<Fr> {
                 sum
             };
             let a = get_scalar_from_cell(&mut ctx, group.get(1).unwrap().value);
-            let g = get_g1_from_cells(&mut ctx, &group.get(2..9).unwrap().to_vec());
-            let rhs = ctx.ecc_mul(&g, a);
-            let sum_ret = ctx.ecc_add(&lhs, &rhs);
-            let sum_ret = ctx.ecc_reduce(&sum_ret);
+            let g = get_checked_g1_from_cells(&mut ctx, &group.get(2..9).unwrap().to_vec());
+            let rhs = mul_g1(&mut ctx, &g, a);
+            let sum_ret = g1_add(&mut ctx, &lhs, &rhs);
             ctx.0.ctx.borrow_mut().enable_permute(&sum_ret.z.0);
             ctx.0
                 .ctx
@@ -437,7 +446,7 @@ impl Bn256SumChip<Fr> {
                 .ctx
                 .borrow_mut()
                 .enable_permute(&sum_ret.y.limbs_le[2]);
-            sum = ctx.to_point_with_curvature(sum_ret.clone());
+            sum = sum_ret.clone();
             ais.push(a);
             g1s.push(g);
             sums.push(sum_ret);
@@ -473,3 +482,828 @@ impl Bn256SumChip<Fr> {
         Ok(())
     }
 }
+
+/// The number of (scalar, G1) pairs of an msm call, unused pairs are padded with zero scalars
+pub const BN256_MSM_POINTS: usize = 16;
+
+pub struct Bn256MsmChip<N: FieldExt> {
+    config: Bn256ChipConfig,
+    base_chip: BaseChip<N>,
+    pub range_chip: RangeChip<N>,
+    point_select_chip: SelectChip<N>,
+    _marker: PhantomData<N>,
+}
+
+impl<N: FieldExt> Chip<N> for Bn256MsmChip<N> {
+    type Config = Bn256ChipConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl Bn256MsmChip<Fr> {
+    pub fn construct(config: <Self as Chip<Fr>>::Config) -> Self {
+        Self {
+            config: config.clone(),
+            point_select_chip: SelectChip::<Fr>::new(config.point_select_chip_config),
+            base_chip: BaseChip::new(config.base_chip_config),
+            range_chip: RangeChip::<Fr>::new(config.range_chip_config),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(cs: &mut ConstraintSystem<Fr>) -> <Self as Chip<Fr>>::Config {
+        Bn256ChipConfig {
+            base_chip_config: BaseChip::configure(cs),
+            range_chip_config: RangeChip::<Fr>::configure(cs),
+            point_select_chip_config: SelectChip::configure(cs),
+        }
+    }
+
+    /* A call pushes whether it starts a new sum and BN256_MSM_POINTS pairs of a scalar
+     * and a point, and gets back the running sum of the session after the msm of its
+     * pairs (see g1_msm). Unlike the sum circuit, the partial sums of the pairs of a
+     * call are never returned, so the pairs share their doublings.
+     */
+    pub fn load_bn256_msm_circuit(
+        &self,
+        ls: &Vec<Limb<Fr>>, // n * (new, (fr, g1) * BN256_MSM_POINTS, sum)
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let context = Rc::new(RefCell::new(Context::new()));
+        let ctx = IntegerContext::<Bn256Fq, Fr>::new(context);
+        let mut ctx = NativeScalarEccContext(ctx, 0);
+
+        let size = 1 + 8 * BN256_MSM_POINTS + 7;
+        let mut news = vec![];
+        let mut ais = vec![];
+        let mut g1s = vec![];
+        let mut sums = vec![];
+        let identity = g1_assign_identity(&mut ctx);
+        let mut sum = identity.clone();
+        for group in ls.chunks_exact(size) {
+            let new = ctx.0.ctx.borrow_mut().assign_bit(group[0].value);
+            let lhs = g1_select(&mut ctx, &new, &identity, &sum);
+            let mut points = vec![];
+            let mut bits = vec![];
+            for pair in group[1..size - 7].chunks_exact(8) {
+                let a = get_scalar_from_cell(&mut ctx, pair[0].value);
+                let g = get_checked_g1_from_cells(&mut ctx, &pair[1..8].to_vec());
+                bits.push(assign_scalar_bits(&mut ctx, &a));
+                points.push(g.clone());
+                ais.push(a);
+                g1s.push(g);
+            }
+            let product = g1_msm(&mut ctx, &points, &bits);
+            sum = g1_add(&mut ctx, &lhs, &product);
+            {
+                let mut native = ctx.0.ctx.borrow_mut();
+                native.enable_permute(&sum.z.0);
+                for limb in sum.x.limbs_le.iter().chain(sum.y.limbs_le.iter()) {
+                    native.enable_permute(limb);
+                }
+            }
+            news.push(new);
+            sums.push(sum.clone());
+        }
+
+        let records = Arc::try_unwrap(Into::<Context<Fr>>::into(ctx).records)
+            .unwrap()
+            .into_inner()
+            .unwrap();
+        layouter.assign_region(
+            || "base",
+            |mut region| {
+                let timer = start_timer!(|| "assign");
+                let cells = records.assign_all(
+                    &mut region,
+                    &self.base_chip,
+                    &self.range_chip,
+                    &self.point_select_chip,
+                )?;
+                for (i, new) in news.iter().enumerate() {
+                    let new_assigned = get_cell_of_ctx(&cells, &new.0.cell);
+                    region.constrain_equal(ls[size * i].get_the_cell().cell(), new_assigned.cell())?;
+                }
+                for (i, (a, g)) in ais.iter().zip(g1s.iter()).enumerate() {
+                    let start = size * (i / BN256_MSM_POINTS) + 1 + 8 * (i % BN256_MSM_POINTS);
+                    enable_fr_permute(&mut region, &cells, a, &ls[start..start + 1].to_vec())?;
+                    enable_g1affine_permute(&mut region, &cells, g, &ls[start + 1..start + 8].to_vec())?;
+                }
+                for (i, s) in sums.iter().enumerate() {
+                    enable_g1affine_permute(&mut region, &cells, s, &ls[size * (i + 1) - 7..size * (i + 1)].to_vec())?;
+                }
+                end_timer!(timer);
+                Ok(())
+            },
+        )?;
+        Ok(())
+    }
+}
+
+/// The maximal number of (G1, G2) pairs of a pairing check call. The missing pairs of a
+/// shorter call are identity pairs, the adaptor rejects a call of more pairs.
+pub const BN256_PAIR_CHECK_PAIRS: usize = 4;
+
+pub struct Bn256PairCheckChip<N: FieldExt> {
+    config: Bn256ChipConfig,
+    base_chip: BaseChip<N>,
+    pub range_chip: RangeChip<N>,
+    point_select_chip: SelectChip<N>,
+    _marker: PhantomData<N>,
+}
+
+impl<N: FieldExt> Chip<N> for Bn256PairCheckChip<N> {
+    type Config = Bn256ChipConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+fn assign_g1_constant(
+    ctx: &mut NativeScalarEccContext<G1Affine>,
+    p: G1,
+) -> AssignedPoint<G1Affine, Fr> {
+    let p = G1Affine::from(p);
+    let x = ctx.base_integer_chip().assign_int_constant(p.x);
+    let y = ctx.base_integer_chip().assign_int_constant(p.y);
+    let z = ctx.0.ctx.borrow_mut().assign_constant(Fr::zero());
+    AssignedPoint::new(x, y, AssignedCondition(z))
+}
+
+fn assign_g2_constant(
+    ctx: &mut NativeScalarEccContext<G1Affine>,
+    q: G2,
+) -> AssignedG2Affine<G1Affine, Fr> {
+    let q = G2Affine::from(q);
+    let x1 = ctx.base_integer_chip().assign_int_constant(q.x.c0);
+    let x2 = ctx.base_integer_chip().assign_int_constant(q.x.c1);
+    let y1 = ctx.base_integer_chip().assign_int_constant(q.y.c0);
+    let y2 = ctx.base_integer_chip().assign_int_constant(q.y.c1);
+    let z = ctx.0.ctx.borrow_mut().assign_constant(Fr::zero());
+    AssignedG2Affine::new((x1, x2), (y1, y2), AssignedCondition(z))
+}
+
+// cond ? c : p, the result is never the identity
+fn select_g1(
+    ctx: &mut NativeScalarEccContext<G1Affine>,
+    cond: &AssignedCondition<Fr>,
+    c: &AssignedPoint<G1Affine, Fr>,
+    p: &AssignedPoint<G1Affine, Fr>,
+) -> AssignedPoint<G1Affine, Fr> {
+    let x = ctx.base_integer_chip().bisec_int(cond, &c.x, &p.x);
+    let y = ctx.base_integer_chip().bisec_int(cond, &c.y, &p.y);
+    AssignedPoint::new(x, y, c.z.clone())
+}
+
+// cond ? c : q, the result is never the identity
+fn select_g2(
+    ctx: &mut NativeScalarEccContext<G1Affine>,
+    cond: &AssignedCondition<Fr>,
+    c: &AssignedG2Affine<G1Affine, Fr>,
+    q: &AssignedG2Affine<G1Affine, Fr>,
+) -> AssignedG2Affine<G1Affine, Fr> {
+    let x1 = ctx.base_integer_chip().bisec_int(cond, &c.x.0, &q.x.0);
+    let x2 = ctx.base_integer_chip().bisec_int(cond, &c.x.1, &q.x.1);
+    let y1 = ctx.base_integer_chip().bisec_int(cond, &c.y.0, &q.y.0);
+    let y2 = ctx.base_integer_chip().bisec_int(cond, &c.y.1, &q.y.1);
+    AssignedG2Affine::new((x1, x2), (y1, y2), c.z.clone())
+}
+
+fn fq12_is_one(
+    ctx: &mut NativeScalarEccContext<G1Affine>,
+    f: &AssignedFq12<Bn256Fq, Fr>,
+) -> AssignedCondition<Fr> {
+    let one = ctx.base_integer_chip().assign_int_constant(Bn256Fq::one());
+    let mut is_one = ctx.base_integer_chip().is_int_equal(&f.0 .0 .0, &one);
+    for c in [
+        &f.0 .0 .1, &f.0 .1 .0, &f.0 .1 .1, &f.0 .2 .0, &f.0 .2 .1,
+        &f.1 .0 .0, &f.1 .0 .1, &f.1 .1 .0, &f.1 .1 .1, &f.1 .2 .0, &f.1 .2 .1,
+    ] {
+        let is_zero = ctx.base_integer_chip().is_int_zero(c);
+        is_one = ctx.0.ctx.borrow_mut().and(&is_one, &is_zero);
+    }
+    is_one
+}
+
+/// Constrains a point other than the identity to be on y^2 = x^3 + 3
+fn assert_g1_on_curve(ctx: &mut NativeScalarEccContext<G1Affine>, p: &AssignedPoint<G1Affine, Fr>) {
+    let b = ctx.base_integer_chip().assign_int_constant(Bn256Fq::from(3u64));
+    let y2 = ctx.base_integer_chip().int_square(&p.y);
+    let x2 = ctx.base_integer_chip().int_square(&p.x);
+    let x3 = ctx.base_integer_chip().int_mul(&x2, &p.x);
+    let rhs = ctx.base_integer_chip().int_add(&x3, &b);
+    let rhs = ctx.base_integer_chip().bisec_int(&p.z, &y2, &rhs);
+    ctx.base_integer_chip().assert_int_equal(&y2, &rhs);
+}
+
+/// get_g1_from_cells with the identity flag constrained to be a bit and the point on
+/// the curve. The curve has no cofactor, so the point is also in the subgroup.
+pub(crate) fn get_checked_g1_from_cells(
+    ctx: &mut NativeScalarEccContext<G1Affine>,
+    a: &Vec<Limb<Fr>>, //G1 (3 * 2 + 1)
+) -> AssignedPoint<G1Affine, Fr> {
+    let mut p = get_g1_from_cells(ctx, a);
+    p.z = ctx.0.ctx.borrow_mut().assign_bit(a[6].value);
+    assert_g1_on_curve(ctx, &p);
+    p
+}
+
+/* Constrains a point on the twist to be in G2. The endomorphism psi acts on G2 as the
+ * multiplication by 6u^2 for the parameter u of the curve, and a point on the twist is
+ * in G2 iff psi(p) = [6u^2]p, where psi(x, y) = (conj(x) * c_x, conj(y) * c_y) with
+ * c_x = xi^((p - 1) / 3) and c_y = xi^((p - 1) / 2).
+ */
+fn assert_g2_in_subgroup(
+    ctx: &mut NativeScalarEccContext<G1Affine>,
+    p: &AssignedG2Affine<G1Affine, Fr>,
+) {
+    let c = |s: &str| -> Bn256Fq { bn_to_field(&BigUint::parse_bytes(s.as_bytes(), 10).unwrap()) };
+    let c_x = ctx.fq2_assign_constant((
+        c("21575463638280843010398324269430826099269044274347216827212613867836435027261"),
+        c("10307601595873709700152284273816112264069230130616436755625194854815875713954"),
+    ));
+    let c_y = ctx.fq2_assign_constant((
+        c("2821565182194536844548159561693502659359617185244120367078079554186484126554"),
+        c("3505843767911556378687030309984248845540243509899259641013678093033130930403"),
+    ));
+    let b3 = assign_twist_b3(ctx);
+    g2_assert_psi_multiple(ctx, &b3, &c_x, &c_y, 0x6f4d8248eeb859fbf83e9682e87cfd46, p);
+}
+
+/// get_g2_from_cells with the identity flag constrained to be a bit and the point in
+/// G2 of the twist
+pub(crate) fn get_checked_g2_from_cells(
+    ctx: &mut NativeScalarEccContext<G1Affine>,
+    b: &Vec<Limb<Fr>>, //G2 (3 * 4 + 1)
+) -> AssignedG2Affine<G1Affine, Fr> {
+    let mut q = get_g2_from_cells(ctx, b);
+    q.z = ctx.0.ctx.borrow_mut().assign_bit(b[12].value);
+    let twist_b = assign_twist_b(ctx);
+    g2_assert_on_curve(ctx, &twist_b, &q);
+    assert_g2_in_subgroup(ctx, &q);
+    q
+}
+
+/// s * p where p may be the identity. The identity is replaced by the generator
+/// before the windowed multiplication and the flag of the product is set afterwards.
+fn mul_g1(
+    ctx: &mut NativeScalarEccContext<G1Affine>,
+    p: &AssignedPoint<G1Affine, Fr>,
+    s: AssignedValue<Fr>,
+) -> AssignedPoint<G1Affine, Fr> {
+    let generator = assign_g1_constant(ctx, G1::generator());
+    let base = select_g1(ctx, &p.z, &generator, p);
+    let product = ctx.ecc_mul(&base, s);
+    let z = ctx.0.ctx.borrow_mut().or(&product.z, &p.z);
+    AssignedPoint::new(product.x, product.y, z)
+}
+
+/* Returns whether prod e(a_i, b_i) == 1 with one miller loop and one final
+ * exponentiation. The identity flags of the points have to be bits.
+ *
+ * A pair with an identity point contributes 1 and can not enter the miller loop,
+ * so the i-th of such pairs is replaced by (2^i * g1, g2). Two extra pairs
+ * (2^n * g1, g2) and (-(2^n + sum 2^i) * g1, g2) cancel the replaced pairs, where n
+ * is the number of pairs. The partial sums of the last point never meet the added
+ * multiple of g1 because they are all between 2^n and 2^(n+1), so the incomplete
+ * addition is safe.
+ */
+pub(crate) fn assign_pair_check(
+    ctx: &mut NativeScalarEccContext<G1Affine>,
+    pairs: &[(AssignedPoint<G1Affine, Fr>, AssignedG2Affine<G1Affine, Fr>)],
+) -> AssignedCondition<Fr> {
+    let g2 = assign_g2_constant(ctx, G2::generator());
+    let shift = G1::generator() * Fr::from(1u64 << pairs.len());
+    let mut compensation = assign_g1_constant(ctx, -shift);
+    let mut terms = vec![];
+    for (i, (a, b)) in pairs.iter().enumerate() {
+        let neutral = ctx.0.ctx.borrow_mut().or(&a.z, &b.z);
+
+        let c = G1::generator() * Fr::from(1u64 << i);
+        let c_g1 = assign_g1_constant(ctx, c);
+        let a_term = select_g1(ctx, &neutral, &c_g1, a);
+        let b_term = select_g2(ctx, &neutral, &g2, b);
+        terms.push((a_term, b_term));
+
+        // compensation -= neutral ? c : identity
+        let neg_c = assign_g1_constant(ctx, -c);
+        let not_neutral = ctx.0.ctx.borrow_mut().not(&neutral);
+        let addend = AssignedPoint::new(neg_c.x, neg_c.y, not_neutral);
+        let lhs = ctx.to_point_with_curvature(compensation);
+        let sum = ctx.ecc_add(&lhs, &addend);
+        compensation = ctx.ecc_reduce(&sum);
+    }
+    terms.push((assign_g1_constant(ctx, shift), g2.clone()));
+    terms.push((compensation, g2));
+
+    let terms = terms.iter().map(|(a, b)| (a, b)).collect::<Vec<_>>();
+    let product_raw = ctx.pairing(&terms);
+    let product = ctx.fq12_reduce(&product_raw);
+    fq12_is_one(ctx, &product)
+}
+
+impl Bn256PairCheckChip<Fr> {
+    pub fn construct(config: <Self as Chip<Fr>>::Config) -> Self {
+        Self {
+            config: config.clone(),
+            point_select_chip: SelectChip::<Fr>::new(config.point_select_chip_config),
+            base_chip: BaseChip::new(config.base_chip_config),
+            range_chip: RangeChip::<Fr>::new(config.range_chip_config),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(cs: &mut ConstraintSystem<Fr>) -> <Self as Chip<Fr>>::Config {
+        Bn256ChipConfig {
+            base_chip_config: BaseChip::configure(cs),
+            range_chip_config: RangeChip::<Fr>::configure(cs),
+            point_select_chip_config: SelectChip::configure(cs),
+        }
+    }
+
+    pub(crate) fn assign_records(
+        &self,
+        region: &mut Region<'_, Fr>,
+        records: &Records<Fr>,
+    ) -> Result<Vec<Vec<Vec<Option<AssignedCell<Fr, Fr>>>>>, Error> {
+        records.assign_all(region, &self.base_chip, &self.range_chip, &self.point_select_chip)
+    }
+
+    /* A pair is (g1, g2) followed by the enable cells of its 20 operands and the call
+     * ends with the result and the opcode and enable cells of its result line. The
+     * operands of a pair are enabled together and only in an enabled call, and a
+     * disabled pair (a hole) is an identity pair whatever its operands are. The result
+     * line carries the result opcode, so the enabled pairs of a call are the pairs that
+     * the guest pushed before its result.
+     */
+    pub fn load_bn256_pair_check_circuit(
+        &self,
+        ls: &Vec<Limb<Fr>>, // n * (g1, g2, enables) + (result, opcode, enable)
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let context = Rc::new(RefCell::new(Context::new()));
+        let ctx = IntegerContext::<Bn256Fq, Fr>::new(context);
+        let mut ctx = NativeScalarEccContext(ctx, 0);
+
+        assert_eq!(ls.len(), 40 * BN256_PAIR_CHECK_PAIRS + 3);
+        let result_line = &ls[40 * BN256_PAIR_CHECK_PAIRS..];
+        let call_enable = ctx.0.ctx.borrow_mut().assign_bit(result_line[2].value);
+        let result_opcode = ctx
+            .0
+            .ctx
+            .borrow_mut()
+            .assign_constant(Fr::from(ForeignInst::Bn254PairCheckResult as u64));
+
+        let mut g1s = vec![];
+        let mut g2s = vec![];
+        let mut enables = vec![];
+        let mut pairs = vec![];
+        for pair in ls[0..40 * BN256_PAIR_CHECK_PAIRS].chunks_exact(40) {
+            let g1 = get_checked_g1_from_cells(&mut ctx, &pair[0..7].to_vec());
+            let g2 = get_checked_g2_from_cells(&mut ctx, &pair[7..20].to_vec());
+            let mut native = ctx.0.ctx.borrow_mut();
+            let enable = native.assign_bit(pair[20].value);
+            let in_call = native.and(&enable, &call_enable);
+            native.assert_equal(&in_call.0, &enable.0);
+            let hole = native.not(&enable);
+            let neutral = native.or(&g1.z, &hole);
+            drop(native);
+            pairs.push((AssignedPoint::new(g1.x.clone(), g1.y.clone(), neutral), g2.clone()));
+            g1s.push(g1);
+            g2s.push(g2);
+            enables.push(enable);
+        }
+        let result = assign_pair_check(&mut ctx, &pairs);
+
+        let records = Arc::try_unwrap(Into::<Context<Fr>>::into(ctx).records)
+            .unwrap()
+            .into_inner()
+            .unwrap();
+        layouter.assign_region(
+            || "base",
+            |mut region| {
+                let timer = start_timer!(|| "assign");
+                let cells = self.assign_records(&mut region, &records)?;
+                for (i, (a, b)) in g1s.iter().zip(g2s.iter()).enumerate() {
+                    enable_g1affine_permute(&mut region, &cells, a, &ls[40*i..40*i+7].to_vec())?;
+                    enable_g2affine_permute(&mut region, &cells, b, &ls[40*i+7..40*i+20].to_vec())?;
+                    let enable_assigned = get_cell_of_ctx(&cells, &enables[i].0.cell);
+                    for limb in ls[40*i+20..40*i+40].iter() {
+                        region.constrain_equal(limb.get_the_cell().cell(), enable_assigned.cell())?;
+                    }
+                }
+                let result_assigned = get_cell_of_ctx(&cells, &result.0.cell);
+                region.constrain_equal(result_line[0].get_the_cell().cell(), result_assigned.cell())?;
+                let opcode_assigned = get_cell_of_ctx(&cells, &result_opcode.cell);
+                region.constrain_equal(result_line[1].get_the_cell().cell(), opcode_assigned.cell())?;
+                let enable_assigned = get_cell_of_ctx(&cells, &call_enable.0.cell);
+                region.constrain_equal(result_line[2].get_the_cell().cell(), enable_assigned.cell())?;
+                end_timer!(timer);
+                Ok(())
+            },
+        )?;
+        Ok(())
+    }
+}
+
+// b of the twist y^2 = x^3 + 3 / (9 + u)
+fn assign_twist_b(ctx: &mut NativeScalarEccContext<G1Affine>) -> AssignedFq2<Bn256Fq, Fr> {
+    let xi = Fq2 {
+        c0: Bn256Fq::from(9u64),
+        c1: Bn256Fq::one(),
+    };
+    let three = Fq2 {
+        c0: Bn256Fq::from(3u64),
+        c1: Bn256Fq::zero(),
+    };
+    let b = three * xi.invert().unwrap();
+    ctx.fq2_assign_constant((b.c0, b.c1))
+}
+
+// 3 * b of the twist y^2 = x^3 + 3 / (9 + u)
+fn assign_twist_b3(ctx: &mut NativeScalarEccContext<G1Affine>) -> AssignedFq2<Bn256Fq, Fr> {
+    let xi = Fq2 {
+        c0: Bn256Fq::from(9u64),
+        c1: Bn256Fq::one(),
+    };
+    let nine = Fq2 {
+        c0: Bn256Fq::from(9u64),
+        c1: Bn256Fq::zero(),
+    };
+    let b3 = nine * xi.invert().unwrap();
+    ctx.fq2_assign_constant((b3.c0, b3.c1))
+}
+
+/// Little endian bits of a. They are only constrained to add up to a modulo r, which
+/// gives the same multiple of points of order r.
+pub(crate) fn assign_scalar_bits(
+    ctx: &mut NativeScalarEccContext<G1Affine>,
+    a: &AssignedValue<Fr>,
+) -> Vec<AssignedCondition<Fr>> {
+    let a_bn = fr_to_bn(&a.val);
+    let mut native = ctx.0.ctx.borrow_mut();
+    let bits = (0..254)
+        .map(|i| native.assign_bit(if a_bn.bit(i) { Fr::one() } else { Fr::zero() }))
+        .collect::<Vec<_>>();
+    let mut coeff = Fr::one();
+    let mut terms = vec![];
+    for b in bits.iter() {
+        terms.push((&b.0, coeff));
+        coeff = coeff + coeff;
+    }
+    let sum = native.sum_with_constant(terms, None);
+    native.assert_equal(&sum, a);
+    bits
+}
+
+pub struct Bn256SumG2Chip<N: FieldExt> {
+    config: Bn256ChipConfig,
+    base_chip: BaseChip<N>,
+    pub range_chip: RangeChip<N>,
+    point_select_chip: SelectChip<N>,
+    _marker: PhantomData<N>,
+}
+
+impl<N: FieldExt> Chip<N> for Bn256SumG2Chip<N> {
+    type Config = Bn256ChipConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl Bn256SumG2Chip<Fr> {
+    pub fn construct(config: <Self as Chip<Fr>>::Config) -> Self {
+        Self {
+            config: config.clone(),
+            point_select_chip: SelectChip::<Fr>::new(config.point_select_chip_config),
+            base_chip: BaseChip::new(config.base_chip_config),
+            range_chip: RangeChip::<Fr>::new(config.range_chip_config),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(cs: &mut ConstraintSystem<Fr>) -> <Self as Chip<Fr>>::Config {
+        Bn256ChipConfig {
+            base_chip_config: BaseChip::configure(cs),
+            range_chip_config: RangeChip::<Fr>::configure(cs),
+            point_select_chip_config: SelectChip::configure(cs),
+        }
+    }
+
+    pub fn load_bn256_sum_g2_circuit(
+        &self,
+        ls: &Vec<Limb<Fr>>,  // n * (new, fr, g2, sum)
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let context = Rc::new(RefCell::new(Context::new()));
+        let ctx = IntegerContext::<Bn256Fq, Fr>::new(context);
+        let mut ctx = NativeScalarEccContext(ctx, 0);
+
+        let mut news = vec![];
+        let mut ais = vec![];
+        let mut g2s = vec![];
+        let mut sums = vec![];
+        let b3 = assign_twist_b3(&mut ctx);
+        let identity = g2_assign_identity(&mut ctx);
+        let mut sum = identity.clone();
+        for group in ls.chunks_exact(28) {
+            let new = ctx.0.ctx.borrow_mut().assign_bit(group[0].value);
+            let lhs = g2_select(&mut ctx, &new, &identity, &sum);
+            let a = get_scalar_from_cell(&mut ctx, group[1].value);
+            let bits = assign_scalar_bits(&mut ctx, &a);
+            let g = get_checked_g2_from_cells(&mut ctx, &group[2..15].to_vec());
+            let g_projective = g2_from_affine(&mut ctx, &g);
+            let rhs = g2_mul(&mut ctx, &b3, &g_projective, &bits);
+            sum = g2_add(&mut ctx, &b3, &lhs, &rhs);
+            let sum_affine: AssignedG2Affine<G1Affine, Fr> = g2_to_affine(&mut ctx, &sum);
+            news.push(new);
+            ais.push(a);
+            g2s.push(g);
+            sums.push(sum_affine);
+        }
+
+        let records = Arc::try_unwrap(Into::<Context<Fr>>::into(ctx).records)
+            .unwrap()
+            .into_inner()
+            .unwrap();
+        layouter.assign_region(
+            || "base",
+            |mut region| {
+                let timer = start_timer!(|| "assign");
+                let cells = records.assign_all(
+                    &mut region,
+                    &self.base_chip,
+                    &self.range_chip,
+                    &self.point_select_chip,
+                )?;
+                for (i, new) in news.iter().enumerate() {
+                    let new_assigned = get_cell_of_ctx(&cells, &new.0.cell);
+                    region.constrain_equal(ls[28 * i].get_the_cell().cell(), new_assigned.cell())?;
+                }
+                for (i, a) in ais.iter().enumerate() {
+                    enable_fr_permute(&mut region, &cells, a, &ls[28*i+1..28*i+2].to_vec())?;
+                }
+                for (i, g) in g2s.iter().enumerate() {
+                    enable_g2affine_permute(&mut region, &cells, g, &ls[28*i+2..28*i+15].to_vec())?;
+                }
+                for (i, s) in sums.iter().enumerate() {
+                    enable_g2affine_permute(&mut region, &cells, s, &ls[28*i+15..28*i+28].to_vec())?;
+                }
+                end_timer!(timer);
+                Ok(())
+            },
+        )?;
+        Ok(())
+    }
+}
+
+pub struct Bn256PairCompressedChip<N: FieldExt> {
+    config: Bn256ChipConfig,
+    base_chip: BaseChip<N>,
+    pub range_chip: RangeChip<N>,
+    point_select_chip: SelectChip<N>,
+    _marker: PhantomData<N>,
+}
+
+impl<N: FieldExt> Chip<N> for Bn256PairCompressedChip<N> {
+    type Config = Bn256ChipConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl Bn256PairCompressedChip<Fr> {
+    pub fn construct(config: <Self as Chip<Fr>>::Config) -> Self {
+        Self {
+            config: config.clone(),
+            point_select_chip: SelectChip::<Fr>::new(config.point_select_chip_config),
+            base_chip: BaseChip::new(config.base_chip_config),
+            range_chip: RangeChip::<Fr>::new(config.range_chip_config),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(cs: &mut ConstraintSystem<Fr>) -> <Self as Chip<Fr>>::Config {
+        Bn256ChipConfig {
+            base_chip_config: BaseChip::configure(cs),
+            range_chip_config: RangeChip::<Fr>::configure(cs),
+            point_select_chip_config: SelectChip::configure(cs),
+        }
+    }
+
+    pub fn load_bn256_pair_compressed_circuit(
+        &self,
+        a: &Vec<Limb<Fr>>,  //G1 (3 * 2 + 1)
+        b: &Vec<Limb<Fr>>,  //G2 (3 * 4 + 1)
+        ab: &Vec<Limb<Fr>>, // compressed Gt (3 * 6 + 1)
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let context = Rc::new(RefCell::new(Context::new()));
+        let ctx = IntegerContext::<Bn256Fq, Fr>::new(context);
+        let mut ctx = NativeScalarEccContext(ctx, 0);
+
+        let a_g1 = get_checked_g1_from_cells(&mut ctx, a);
+        let b_g2 = get_checked_g2_from_cells(&mut ctx, b);
+        let ab_fq12_raw = ctx.pairing(&[(&a_g1, &b_g2)]);
+        let ab_fq12 = ctx.fq12_reduce(&ab_fq12_raw);
+
+        let cs = (0..6)
+            .map(|i| ctx.base_integer_chip().assign_w(&assigned_cells_to_bn256(ab, 3 * i)))
+            .collect::<Vec<_>>();
+        let is_one = ctx.0.ctx.borrow_mut().assign_bit(ab[18].value);
+        let c = (
+            (cs[0].clone(), cs[1].clone()),
+            (cs[2].clone(), cs[3].clone()),
+            (cs[4].clone(), cs[5].clone()),
+        );
+        // xi = 9 + u
+        let xi = ctx.fq2_assign_constant((Bn256Fq::from(9u64), Bn256Fq::one()));
+        assert_gt_compressed(&mut ctx, &xi, &ab_fq12, &c, &is_one);
+
+        let records = Arc::try_unwrap(Into::<Context<Fr>>::into(ctx).records)
+            .unwrap()
+            .into_inner()
+            .unwrap();
+
+        layouter.assign_region(
+            || "base",
+            |mut region| {
+                let timer = start_timer!(|| "assign");
+                let cells = records.assign_all(
+                    &mut region,
+                    &self.base_chip,
+                    &self.range_chip,
+                    &self.point_select_chip,
+                )?;
+                enable_g1affine_permute(&mut region, &cells, &a_g1, a)?;
+                enable_g2affine_permute(&mut region, &cells, &b_g2, b)?;
+                for (i, c) in cs.iter().enumerate() {
+                    enable_fq_permute(&mut region, &cells, c, &ab[3 * i..3 * i + 3].to_vec())?;
+                }
+                let is_one_assigned = get_cell_of_ctx(&cells, &is_one.0.cell);
+                region.constrain_equal(ab[18].get_the_cell().cell(), is_one_assigned.cell())?;
+                end_timer!(timer);
+                Ok(())
+            },
+        )?;
+        Ok(())
+    }
+}
+
+fn get_fq12_from_cells(
+    ctx: &mut NativeScalarEccContext<G1Affine>,
+    f: &Vec<Limb<Fr>>, // Fq_12 (3 * 12)
+) -> AssignedFq12<Bn256Fq, Fr> {
+    let cs = (0..12)
+        .map(|i| ctx.base_integer_chip().assign_w(&assigned_cells_to_bn256(f, 3 * i)))
+        .collect::<Vec<_>>();
+    (
+        (
+            (cs[0].clone(), cs[1].clone()),
+            (cs[2].clone(), cs[3].clone()),
+            (cs[4].clone(), cs[5].clone()),
+        ),
+        (
+            (cs[6].clone(), cs[7].clone()),
+            (cs[8].clone(), cs[9].clone()),
+            (cs[10].clone(), cs[11].clone()),
+        ),
+    )
+}
+
+pub struct Bn256GtChip<N: FieldExt> {
+    config: Bn256ChipConfig,
+    base_chip: BaseChip<N>,
+    pub range_chip: RangeChip<N>,
+    point_select_chip: SelectChip<N>,
+    _marker: PhantomData<N>,
+}
+
+impl<N: FieldExt> Chip<N> for Bn256GtChip<N> {
+    type Config = Bn256ChipConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl Bn256GtChip<Fr> {
+    pub fn construct(config: <Self as Chip<Fr>>::Config) -> Self {
+        Self {
+            config: config.clone(),
+            point_select_chip: SelectChip::<Fr>::new(config.point_select_chip_config),
+            base_chip: BaseChip::new(config.base_chip_config),
+            range_chip: RangeChip::<Fr>::new(config.range_chip_config),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(cs: &mut ConstraintSystem<Fr>) -> <Self as Chip<Fr>>::Config {
+        Bn256ChipConfig {
+            base_chip_config: BaseChip::configure(cs),
+            range_chip_config: RangeChip::<Fr>::configure(cs),
+            point_select_chip_config: SelectChip::configure(cs),
+        }
+    }
+
+    /* A call pushes an operation (see circuits::gt) and two elements of Fq12, and gets
+     * back an element of Fq12. The second operand of an inversion is ignored.
+     */
+    pub fn load_bn256_gt_circuit(
+        &self,
+        ls: &Vec<Limb<Fr>>, // n * (op, a, b, result)
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let context = Rc::new(RefCell::new(Context::new()));
+        let ctx = IntegerContext::<Bn256Fq, Fr>::new(context);
+        let mut ctx = NativeScalarEccContext(ctx, 0);
+
+        let size = 1 + 3 * 36;
+        // xi = 9 + u
+        let xi = ctx.fq2_assign_constant((Bn256Fq::from(9u64), Bn256Fq::one()));
+        let mut ops = vec![];
+        let mut fq12s = vec![];
+        for group in ls.chunks_exact(size) {
+            let op = group[0].value;
+            // op = is_invert + 2 * is_equal, and at most one of them is set
+            let (is_invert, is_equal, op_value) = {
+                let mut native = ctx.0.ctx.borrow_mut();
+                let is_invert = native.assign_bit(if op == Fr::from(GT_OP_INVERT) { Fr::one() } else { Fr::zero() });
+                let is_equal = native.assign_bit(if op == Fr::from(GT_OP_EQUAL) { Fr::one() } else { Fr::zero() });
+                let both = native.and(&is_invert, &is_equal);
+                let not_both = native.not(&both);
+                native.assert_true(&not_both);
+                let op_value = native.sum_with_constant(
+                    vec![(&is_invert.0, Fr::one()), (&is_equal.0, Fr::from(2u64))],
+                    None,
+                );
+                (is_invert, is_equal, op_value)
+            };
+            let a = get_fq12_from_cells(&mut ctx, &group[1..37].to_vec());
+            let b = get_fq12_from_cells(&mut ctx, &group[37..73].to_vec());
+            let result = get_fq12_from_cells(&mut ctx, &group[73..109].to_vec());
+            assert_gt_op(&mut ctx, &xi, &is_invert, &is_equal, &a, &b, &result);
+            ops.push(op_value);
+            fq12s.push((a, b, result));
+        }
+
+        let records = Arc::try_unwrap(Into::<Context<Fr>>::into(ctx).records)
+            .unwrap()
+            .into_inner()
+            .unwrap();
+        layouter.assign_region(
+            || "base",
+            |mut region| {
+                let timer = start_timer!(|| "assign");
+                let cells = records.assign_all(
+                    &mut region,
+                    &self.base_chip,
+                    &self.range_chip,
+                    &self.point_select_chip,
+                )?;
+                for (i, op) in ops.iter().enumerate() {
+                    enable_fr_permute(&mut region, &cells, op, &ls[size * i..size * i + 1].to_vec())?;
+                }
+                for (i, (a, b, result)) in fq12s.iter().enumerate() {
+                    let start = size * i + 1;
+                    enable_fq12_permute(&mut region, &cells, a, &ls[start..start + 36].to_vec())?;
+                    enable_fq12_permute(&mut region, &cells, b, &ls[start + 36..start + 72].to_vec())?;
+                    enable_fq12_permute(&mut region, &cells, result, &ls[start + 72..start + 108].to_vec())?;
+                }
+                end_timer!(timer);
+                Ok(())
+            },
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+use halo2_proofs::arithmetic::BaseExt;
+use halo2_proofs::circuit::{AssignedCell, Region};
+use halo2_proofs::pairing::bn256::Fr;
+use halo2_proofs::plonk::Error;
+use halo2ecc_s::assign::{AssignedInteger, AssignedValue, Cell as ContextCell};
+use num_bigint::BigUint;
+use std::ops::{AddAssign, Mul};
+
+use crate::utils::Limb;
+
+/* Cell helpers shared by the ECDSA circuits over secp256k1 and secp256r1, whose
+ * field elements are pushed as 3 limbs of 108 bits.
+ */
+
+pub fn fr_to_bn(f: &Fr) -> BigUint {
+    let mut bytes: Vec<u8> = Vec::new();
+    f.write(&mut bytes).unwrap();
+    BigUint::from_bytes_le(&bytes[..])
+}
+
+pub fn fr_to_bool(f: &Fr) -> bool {
+    let mut bytes: Vec<u8> = Vec::new();
+    f.write(&mut bytes).unwrap();
+    return bytes[0] == 1u8;
+}
+
+pub(crate) fn assigned_cells_to_int(
+    a: &Vec<Limb<Fr>>, //Fp or Fq (3)
+    start: usize,
+) -> BigUint {
+    let mut bn = BigUint::from(0 as u64);
+    for i in start..start + 3 {
+        let shift = BigUint::from(2 as u32).pow(108 * (i - start) as u32);
+        bn.add_assign(fr_to_bn(&a[i].value).mul(shift.clone()));
+    }
+    bn
+}
+
+pub(crate) fn get_cell_of_ctx(
+    cells: &Vec<Vec<Vec<Option<AssignedCell<Fr, Fr>>>>>,
+    cell: &ContextCell,
+) -> AssignedCell<Fr, Fr> {
+    cells[cell.region as usize][cell.col][cell.row]
+        .clone()
+        .unwrap()
+}
+
+pub(crate) fn enable_value_permute(
+    region: &mut Region<'_, Fr>,
+    cells: &Vec<Vec<Vec<Option<AssignedCell<Fr, Fr>>>>>,
+    v: &AssignedValue<Fr>,
+    input: &Limb<Fr>,
+) -> Result<(), Error> {
+    let v_assigned = get_cell_of_ctx(cells, &v.cell);
+    region.constrain_equal(input.get_the_cell().cell(), v_assigned.cell())?;
+    Ok(())
+}
+
+pub(crate) fn enable_int_permute<W: BaseExt>(
+    region: &mut Region<'_, Fr>,
+    cells: &Vec<Vec<Vec<Option<AssignedCell<Fr, Fr>>>>>,
+    w: &AssignedInteger<W, Fr>,
+    input: &Vec<Limb<Fr>>,
+) -> Result<(), Error> {
+    for i in 0..3 {
+        enable_value_permute(region, cells, &w.limbs_le[i], &input[i])?;
+    }
+    Ok(())
+}
@@ -0,0 +1,168 @@
+use crate::circuits::babyjub::{AltJubChip, Point};
+use crate::circuits::poseidon::{PoseidonChip, PoseidonGateConfig};
+use crate::circuits::{CommonGateConfig, Limb};
+use crate::host::jubjub::BASE_TABLES;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Chip, Region},
+    plonk::{Advice, Column, ConstraintSystem, Error},
+};
+use poseidon::Spec;
+
+/* EdDSA-Poseidon signature verification over the alt BabyJubjub curve.
+ *
+ * For a public key A, a signature (R, s) and a message M the chip computes
+ * h = poseidon([R.x, R.y, A.x, A.y, M, 0, 0, 0]) and constrains the result bit to be
+ * (s * B == R + h * A). See host::jubjub::eddsa_verify for the native reference.
+ */
+pub struct AltJubEddsaChip<F: FieldExt> {
+    pub jub: AltJubChip<F>,
+    pub poseidon: PoseidonChip<F, 9, 8>,
+    state: EddsaState<F>,
+}
+
+pub struct EddsaState<F: FieldExt> {
+    zero: Limb<F>,
+    one: Limb<F>,
+}
+
+impl<F: FieldExt> Chip<F> for AltJubEddsaChip<F> {
+    type Config = CommonGateConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.jub.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt> AltJubEddsaChip<F> {
+    pub fn construct(
+        config: CommonGateConfig,
+        extend: PoseidonGateConfig,
+        spec: Spec<F, 9, 8>,
+    ) -> Self {
+        let state = EddsaState {
+            zero: Limb::new(None, F::zero()),
+            one: Limb::new(None, F::one()),
+        };
+        AltJubEddsaChip {
+            jub: AltJubChip::new(config.clone()),
+            poseidon: PoseidonChip::construct(config, extend, spec),
+            state,
+        }
+    }
+
+    pub fn configure(
+        cs: &mut ConstraintSystem<F>,
+        shared_advices: &Vec<Column<Advice>>,
+    ) -> (CommonGateConfig, PoseidonGateConfig) {
+        PoseidonChip::<F, 9, 8>::configure(cs, shared_advices)
+    }
+
+    pub fn initialize(
+        &mut self,
+        config: &CommonGateConfig,
+        region: &mut Region<F>,
+        offset: &mut usize,
+    ) -> Result<(), Error> {
+        // poseidon initialize resets the offset so it has to go first
+        self.poseidon.initialize(config, region, offset)?;
+        self.jub.initialize(config, region, offset)?;
+        self.state.zero = config.assign_constant(region, &mut (), offset, &F::zero())?;
+        self.state.one = config.assign_constant(region, &mut (), offset, &F::one())?;
+        Ok(())
+    }
+
+    fn assign_point_eq(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        lhs: &Point<F>,
+        rhs: &Point<F>,
+        result: &Limb<F>,
+    ) -> Result<(), Error> {
+        let config = &self.jub.config;
+        let diff_x = config.sum_with_constant(
+            region,
+            &mut (),
+            offset,
+            vec![(&lhs.x, F::one()), (&rhs.x, -F::one())],
+            None,
+        )?;
+        let diff_y = config.sum_with_constant(
+            region,
+            &mut (),
+            offset,
+            vec![(&lhs.y, F::one()), (&rhs.y, -F::one())],
+            None,
+        )?;
+        let eq_x = config.eq_constant(region, &mut (), offset, &diff_x, &F::zero())?;
+        let eq_y = config.eq_constant(region, &mut (), offset, &diff_y, &F::zero())?;
+        // result = eq_x * eq_y
+        config.assign_line(
+            region,
+            &mut (),
+            offset,
+            [
+                Some(eq_x),
+                None,
+                None,
+                Some(eq_y),
+                Some(result.clone()),
+                None,
+            ],
+            [
+                None,
+                None,
+                None,
+                None,
+                Some(-F::one()),
+                None,
+                Some(F::one()),
+                None,
+                None,
+            ],
+            0,
+        )?;
+        Ok(())
+    }
+
+    pub fn assign_eddsa_verify(
+        &mut self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        msg: &Limb<F>,
+        sig_r: &Point<F>,
+        sig_s: &Limb<F>,
+        pk: &Point<F>,
+        result: &Limb<F>,
+    ) -> Result<(), Error> {
+        let zero = self.state.zero.clone();
+        let h = self.poseidon.get_permute_result(
+            region,
+            offset,
+            &[
+                sig_r.x.clone(),
+                sig_r.y.clone(),
+                pk.x.clone(),
+                pk.y.clone(),
+                msg.clone(),
+                zero.clone(),
+                zero.clone(),
+                zero,
+            ],
+            &self.state.one,
+        )?;
+        let lhs = self
+            .jub
+            .mul_scalar_fixed(region, offset, sig_s, &BASE_TABLES)?;
+        let h_pk = self.jub.mul_scalar(region, offset, &h, pk)?;
+        let rhs = self.jub.add(region, offset, sig_r, &h_pk)?;
+        self.assign_point_eq(region, offset, &lhs, &rhs, result)
+    }
+}
@@ -0,0 +1,157 @@
+use halo2_proofs::arithmetic::{CurveAffine, FieldExt};
+use halo2ecc_s::assign::{AssignedCondition, AssignedPoint};
+use halo2ecc_s::circuit::base_chip::BaseChipOps;
+use halo2ecc_s::circuit::ecc_chip::EccChipBaseOps;
+use halo2ecc_s::circuit::integer_chip::IntegerChipOps;
+
+/* Arithmetic on G1 of BN254 and BLS12-381, shared by the contexts of both curves.
+ *
+ * Points are affine with an identity flag. The identity is kept with the coordinates
+ * (0, 0), which is how the adaptors push and read it.
+ */
+
+/// The identity with the coordinates (0, 0)
+pub fn g1_assign_identity<C: CurveAffine, N: FieldExt, T: EccChipBaseOps<C, N>>(
+    ctx: &mut T,
+) -> AssignedPoint<C, N> {
+    let zero = ctx.base_integer_chip().assign_int_constant(C::Base::zero());
+    let z = ctx.base_integer_chip().base_chip().assign_constant(N::one());
+    AssignedPoint::new(zero.clone(), zero, AssignedCondition(z))
+}
+
+/// cond ? a : b
+pub fn g1_select<C: CurveAffine, N: FieldExt, T: EccChipBaseOps<C, N>>(
+    ctx: &mut T,
+    cond: &AssignedCondition<N>,
+    a: &AssignedPoint<C, N>,
+    b: &AssignedPoint<C, N>,
+) -> AssignedPoint<C, N> {
+    let x = ctx.base_integer_chip().bisec_int(cond, &a.x, &b.x);
+    let y = ctx.base_integer_chip().bisec_int(cond, &a.y, &b.y);
+    let base_chip = ctx.base_integer_chip().base_chip();
+    let not_cond = base_chip.not(cond);
+    let a_z = base_chip.and(cond, &a.z);
+    let b_z = base_chip.and(&not_cond, &b.z);
+    let z = base_chip.or(&a_z, &b_z);
+    AssignedPoint::new(x, y, z)
+}
+
+/* p + q for points that are the identity or on the curve. ecc_add handles the
+ * identity and doublings but takes the chord of opposite points, so q is replaced by
+ * p for them, which keeps the chord well defined, and the sum is set to the identity.
+ */
+pub fn g1_add<C: CurveAffine, N: FieldExt, T: EccChipBaseOps<C, N>>(
+    ctx: &mut T,
+    p: &AssignedPoint<C, N>,
+    q: &AssignedPoint<C, N>,
+) -> AssignedPoint<C, N> {
+    let x_eq = ctx.base_integer_chip().is_int_equal(&p.x, &q.x);
+    let y_eq = ctx.base_integer_chip().is_int_equal(&p.y, &q.y);
+    let opposite = {
+        let base_chip = ctx.base_integer_chip().base_chip();
+        let y_ne = base_chip.not(&y_eq);
+        let either_identity = base_chip.or(&p.z, &q.z);
+        let neither_identity = base_chip.not(&either_identity);
+        let opposite = base_chip.and(&x_eq, &y_ne);
+        base_chip.and(&opposite, &neither_identity)
+    };
+    let x = ctx.base_integer_chip().bisec_int(&opposite, &p.x, &q.x);
+    let y = ctx.base_integer_chip().bisec_int(&opposite, &p.y, &q.y);
+    let q = AssignedPoint::new(x, y, q.z.clone());
+
+    let lhs = ctx.to_point_with_curvature(p.clone());
+    let sum = ctx.ecc_add(&lhs, &q);
+    let sum = ctx.ecc_reduce(&sum);
+    let z = ctx.base_integer_chip().base_chip().or(&sum.z, &opposite);
+    let zero = ctx.base_integer_chip().assign_int_constant(C::Base::zero());
+    let x = ctx.base_integer_chip().bisec_int(&z, &zero, &sum.x);
+    let y = ctx.base_integer_chip().bisec_int(&z, &zero, &sum.y);
+    AssignedPoint::new(x, y, z)
+}
+
+/// k * p for a constant k > 0 by double and add from the top bit of k
+pub fn g1_mul_by_constant<C: CurveAffine, N: FieldExt, T: EccChipBaseOps<C, N>>(
+    ctx: &mut T,
+    p: &AssignedPoint<C, N>,
+    k: u128,
+) -> AssignedPoint<C, N> {
+    assert!(k > 0);
+    let mut acc = p.clone();
+    for i in (0..127 - k.leading_zeros() as usize).rev() {
+        acc = g1_add(ctx, &acc, &acc);
+        if (k >> i) & 1 == 1 {
+            acc = g1_add(ctx, &acc, p);
+        }
+    }
+    acc
+}
+
+/// The number of scalar bits that one window of g1_msm consumes
+pub const G1_MSM_WINDOW_BITS: usize = 4;
+
+/// table[d] for the little endian bits of d
+fn g1_select_window<C: CurveAffine, N: FieldExt, T: EccChipBaseOps<C, N>>(
+    ctx: &mut T,
+    table: &[AssignedPoint<C, N>],
+    bits: &[AssignedCondition<N>],
+) -> AssignedPoint<C, N> {
+    let mut candidates = table[0..1 << bits.len()].to_vec();
+    for bit in bits.iter() {
+        candidates = candidates
+            .chunks_exact(2)
+            .map(|pair| g1_select(ctx, bit, &pair[1], &pair[0]))
+            .collect();
+    }
+    candidates.pop().unwrap()
+}
+
+/* sum s_i * p_i for the little endian bits of the scalars s_i, which all have the
+ * same length.
+ *
+ * The bucket method of Pippenger sorts the points into buckets by the digits of their
+ * scalars, but which bucket a point goes to depends on the witness while the layout of
+ * a circuit can not. So every point keeps the table of its multiples 0..2^w for
+ * windows of w bits instead, and the windows are processed from the top with the
+ * doublings of the accumulator shared by all points (Straus). A point costs 2^w - 2
+ * additions for its table plus one table selection and one addition per window, while
+ * the doublings cost the same for the whole batch.
+ */
+pub fn g1_msm<C: CurveAffine, N: FieldExt, T: EccChipBaseOps<C, N>>(
+    ctx: &mut T,
+    points: &[AssignedPoint<C, N>],
+    bits: &[Vec<AssignedCondition<N>>],
+) -> AssignedPoint<C, N> {
+    assert_eq!(points.len(), bits.len());
+    let identity = g1_assign_identity(ctx);
+    let tables = points
+        .iter()
+        .map(|p| {
+            let mut table = vec![identity.clone(), p.clone()];
+            for i in 2..1 << G1_MSM_WINDOW_BITS {
+                let multiple = g1_add(ctx, &table[i - 1], p);
+                table.push(multiple);
+            }
+            table
+        })
+        .collect::<Vec<_>>();
+
+    let windows = bits
+        .iter()
+        .map(|b| b.chunks(G1_MSM_WINDOW_BITS).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let window_count = windows.first().map_or(0, |w| w.len());
+
+    let mut acc = identity;
+    for w in (0..window_count).rev() {
+        if w + 1 != window_count {
+            for _ in 0..windows[0][w].len() {
+                acc = g1_add(ctx, &acc, &acc);
+            }
+        }
+        for (table, window) in tables.iter().zip(windows.iter()) {
+            let addend = g1_select_window(ctx, table, window[w]);
+            acc = g1_add(ctx, &acc, &addend);
+        }
+    }
+    acc
+}
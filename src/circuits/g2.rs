@@ -0,0 +1,255 @@
+use halo2_proofs::arithmetic::{BaseExt, CurveAffine, FieldExt};
+use halo2ecc_s::assign::{AssignedCondition, AssignedFq2, AssignedG2Affine};
+use halo2ecc_s::circuit::base_chip::BaseChipOps;
+use halo2ecc_s::circuit::ecc_chip::EccBaseIntegerChipWrapper;
+use halo2ecc_s::circuit::fq12::Fq2ChipOps;
+use halo2ecc_s::circuit::integer_chip::IntegerChipOps;
+
+/* Arithmetic on the G2 twists y^2 = x^3 + b of BN254 and BLS12-381, shared by the
+ * contexts of both curves.
+ *
+ * Points are kept in projective coordinates (X : Y : Z) with the identity (0 : 1 : 0)
+ * and added with the complete formulas of Renes, Costello and Batina (algorithm 7,
+ * a = 0). The formulas are complete since neither twist has a point of order two,
+ * so doublings, opposite points and the identity need no special case.
+ */
+#[derive(Clone, Debug)]
+pub struct AssignedG2Projective<W: BaseExt, N: FieldExt> {
+    pub x: AssignedFq2<W, N>,
+    pub y: AssignedFq2<W, N>,
+    pub z: AssignedFq2<W, N>,
+}
+
+fn fq2_select<W: BaseExt, N: FieldExt, T: EccBaseIntegerChipWrapper<W, N>>(
+    ctx: &mut T,
+    cond: &AssignedCondition<N>,
+    a: &AssignedFq2<W, N>,
+    b: &AssignedFq2<W, N>,
+) -> AssignedFq2<W, N> {
+    let c0 = ctx.base_integer_chip().bisec_int(cond, &a.0, &b.0);
+    let c1 = ctx.base_integer_chip().bisec_int(cond, &a.1, &b.1);
+    (c0, c1)
+}
+
+fn fq2_is_zero<W: BaseExt, N: FieldExt, T: EccBaseIntegerChipWrapper<W, N>>(
+    ctx: &mut T,
+    a: &AssignedFq2<W, N>,
+) -> AssignedCondition<N> {
+    let c0_zero = ctx.base_integer_chip().is_int_zero(&a.0);
+    let c1_zero = ctx.base_integer_chip().is_int_zero(&a.1);
+    ctx.base_integer_chip().base_chip().and(&c0_zero, &c1_zero)
+}
+
+pub fn g2_assign_identity<W: BaseExt, N: FieldExt, T: Fq2ChipOps<W, N>>(
+    ctx: &mut T,
+) -> AssignedG2Projective<W, N> {
+    AssignedG2Projective {
+        x: ctx.fq2_assign_constant((W::zero(), W::zero())),
+        y: ctx.fq2_assign_constant((W::one(), W::zero())),
+        z: ctx.fq2_assign_constant((W::zero(), W::zero())),
+    }
+}
+
+/// cond ? a : b
+pub fn g2_select<W: BaseExt, N: FieldExt, T: EccBaseIntegerChipWrapper<W, N>>(
+    ctx: &mut T,
+    cond: &AssignedCondition<N>,
+    a: &AssignedG2Projective<W, N>,
+    b: &AssignedG2Projective<W, N>,
+) -> AssignedG2Projective<W, N> {
+    AssignedG2Projective {
+        x: fq2_select(ctx, cond, &a.x, &b.x),
+        y: fq2_select(ctx, cond, &a.y, &b.y),
+        z: fq2_select(ctx, cond, &a.z, &b.z),
+    }
+}
+
+/// The affine coordinates are ignored if the identity flag is set
+pub fn g2_from_affine<
+    C: CurveAffine,
+    N: FieldExt,
+    T: EccBaseIntegerChipWrapper<C::Base, N> + Fq2ChipOps<C::Base, N>,
+>(
+    ctx: &mut T,
+    p: &AssignedG2Affine<C, N>,
+) -> AssignedG2Projective<C::Base, N> {
+    let identity = g2_assign_identity(ctx);
+    let point = AssignedG2Projective {
+        x: p.x.clone(),
+        y: p.y.clone(),
+        z: ctx.fq2_assign_constant((C::Base::one(), C::Base::zero())),
+    };
+    g2_select(ctx, &p.z, &identity, &point)
+}
+
+/// The identity is returned with the coordinates (0, 0) and its flag set
+pub fn g2_to_affine<
+    C: CurveAffine,
+    N: FieldExt,
+    T: EccBaseIntegerChipWrapper<C::Base, N> + Fq2ChipOps<C::Base, N>,
+>(
+    ctx: &mut T,
+    p: &AssignedG2Projective<C::Base, N>,
+) -> AssignedG2Affine<C, N> {
+    let is_identity = fq2_is_zero(ctx, &p.z);
+    let zero = ctx.fq2_assign_constant((C::Base::zero(), C::Base::zero()));
+    let one = ctx.fq2_assign_constant((C::Base::one(), C::Base::zero()));
+    let z = fq2_select(ctx, &is_identity, &one, &p.z);
+    let z_inv = ctx.fq2_unsafe_invert(&z);
+    let x = ctx.fq2_mul(&p.x, &z_inv);
+    let x = ctx.fq2_reduce(&x);
+    let y = ctx.fq2_mul(&p.y, &z_inv);
+    let y = ctx.fq2_reduce(&y);
+    let x = fq2_select(ctx, &is_identity, &zero, &x);
+    let y = fq2_select(ctx, &is_identity, &zero, &y);
+    AssignedG2Affine::new(x, y, is_identity)
+}
+
+/// Constrains a point other than the identity to be on the twist y^2 = x^3 + b
+pub fn g2_assert_on_curve<
+    C: CurveAffine,
+    N: FieldExt,
+    T: EccBaseIntegerChipWrapper<C::Base, N> + Fq2ChipOps<C::Base, N>,
+>(
+    ctx: &mut T,
+    b: &AssignedFq2<C::Base, N>,
+    p: &AssignedG2Affine<C, N>,
+) {
+    let y2 = ctx.fq2_square(&p.y);
+    let x2 = ctx.fq2_square(&p.x);
+    let x3 = ctx.fq2_mul(&x2, &p.x);
+    let rhs = ctx.fq2_add(&x3, b);
+    let rhs = fq2_select(ctx, &p.z, &y2, &rhs);
+    ctx.base_integer_chip().assert_int_equal(&y2.0, &rhs.0);
+    ctx.base_integer_chip().assert_int_equal(&y2.1, &rhs.1);
+}
+
+/// p + q where b3 is three times the b of the twist
+pub fn g2_add<W: BaseExt, N: FieldExt, T: Fq2ChipOps<W, N>>(
+    ctx: &mut T,
+    b3: &AssignedFq2<W, N>,
+    p: &AssignedG2Projective<W, N>,
+    q: &AssignedG2Projective<W, N>,
+) -> AssignedG2Projective<W, N> {
+    let (x1, y1, z1) = (&p.x, &p.y, &p.z);
+    let (x2, y2, z2) = (&q.x, &q.y, &q.z);
+    let mut t0 = ctx.fq2_mul(x1, x2);
+    let mut t1 = ctx.fq2_mul(y1, y2);
+    let mut t2 = ctx.fq2_mul(z1, z2);
+    let x1y1 = ctx.fq2_add(x1, y1);
+    let x2y2 = ctx.fq2_add(x2, y2);
+    let mut t3 = ctx.fq2_mul(&x1y1, &x2y2);
+    let mut t4 = ctx.fq2_add(&t0, &t1);
+    t3 = ctx.fq2_sub(&t3, &t4);
+    let y1z1 = ctx.fq2_add(y1, z1);
+    let y2z2 = ctx.fq2_add(y2, z2);
+    t4 = ctx.fq2_mul(&y1z1, &y2z2);
+    let mut x3 = ctx.fq2_add(&t1, &t2);
+    t4 = ctx.fq2_sub(&t4, &x3);
+    let x1z1 = ctx.fq2_add(x1, z1);
+    let x2z2 = ctx.fq2_add(x2, z2);
+    x3 = ctx.fq2_mul(&x1z1, &x2z2);
+    let mut y3 = ctx.fq2_add(&t0, &t2);
+    y3 = ctx.fq2_sub(&x3, &y3);
+    x3 = ctx.fq2_add(&t0, &t0);
+    t0 = ctx.fq2_add(&x3, &t0);
+    t2 = ctx.fq2_mul(b3, &t2);
+    let mut z3 = ctx.fq2_add(&t1, &t2);
+    t1 = ctx.fq2_sub(&t1, &t2);
+    y3 = ctx.fq2_mul(b3, &y3);
+    x3 = ctx.fq2_mul(&t4, &y3);
+    t2 = ctx.fq2_mul(&t3, &t1);
+    x3 = ctx.fq2_sub(&t2, &x3);
+    y3 = ctx.fq2_mul(&y3, &t0);
+    t1 = ctx.fq2_mul(&t1, &z3);
+    y3 = ctx.fq2_add(&t1, &y3);
+    t0 = ctx.fq2_mul(&t0, &t3);
+    z3 = ctx.fq2_mul(&z3, &t4);
+    z3 = ctx.fq2_add(&z3, &t0);
+    AssignedG2Projective {
+        x: x3,
+        y: y3,
+        z: z3,
+    }
+}
+
+/// s * p by double and add over the little endian bits of s
+pub fn g2_mul<
+    W: BaseExt,
+    N: FieldExt,
+    T: EccBaseIntegerChipWrapper<W, N> + Fq2ChipOps<W, N>,
+>(
+    ctx: &mut T,
+    b3: &AssignedFq2<W, N>,
+    p: &AssignedG2Projective<W, N>,
+    bits: &[AssignedCondition<N>],
+) -> AssignedG2Projective<W, N> {
+    let identity = g2_assign_identity(ctx);
+    let mut acc = identity.clone();
+    for bit in bits.iter().rev() {
+        acc = g2_add(ctx, b3, &acc, &acc);
+        let addend = g2_select(ctx, bit, p, &identity);
+        acc = g2_add(ctx, b3, &acc, &addend);
+    }
+    acc
+}
+
+/// k * p for a constant k by double and add from the top bit of k
+pub fn g2_mul_by_constant<W: BaseExt, N: FieldExt, T: Fq2ChipOps<W, N>>(
+    ctx: &mut T,
+    b3: &AssignedFq2<W, N>,
+    p: &AssignedG2Projective<W, N>,
+    k: u128,
+) -> AssignedG2Projective<W, N> {
+    let mut acc = g2_assign_identity(ctx);
+    for i in (0..128 - k.leading_zeros() as usize).rev() {
+        acc = g2_add(ctx, b3, &acc, &acc);
+        if (k >> i) & 1 == 1 {
+            acc = g2_add(ctx, b3, &acc, p);
+        }
+    }
+    acc
+}
+
+/* Constrains a point on the twist to satisfy psi(p) = k * p, where
+ * psi(x, y) = (conj(x) * c_x, conj(y) * c_y) is the untwist-Frobenius-twist
+ * endomorphism. For the k of BN254 and BLS12-381 this holds exactly for the points
+ * of G2, so it is the subgroup check of the decoded points. The identity passes.
+ *
+ * k * p is projective, so the coordinates of psi(p) are scaled by its Z instead of
+ * inverting it, which also rejects a product at infinity since its Y is not zero.
+ */
+pub fn g2_assert_psi_multiple<
+    C: CurveAffine,
+    N: FieldExt,
+    T: EccBaseIntegerChipWrapper<C::Base, N> + Fq2ChipOps<C::Base, N>,
+>(
+    ctx: &mut T,
+    b3: &AssignedFq2<C::Base, N>,
+    c_x: &AssignedFq2<C::Base, N>,
+    c_y: &AssignedFq2<C::Base, N>,
+    k: u128,
+    p: &AssignedG2Affine<C, N>,
+) {
+    let point = g2_from_affine(ctx, p);
+    let product = g2_mul_by_constant(ctx, b3, &point, k);
+    let zero = ctx.base_integer_chip().assign_int_constant(C::Base::zero());
+    let x_conj = (
+        p.x.0.clone(),
+        ctx.base_integer_chip().int_sub(&zero, &p.x.1),
+    );
+    let y_conj = (
+        p.y.0.clone(),
+        ctx.base_integer_chip().int_sub(&zero, &p.y.1),
+    );
+    let psi_x = ctx.fq2_mul(&x_conj, c_x);
+    let psi_y = ctx.fq2_mul(&y_conj, c_y);
+    let x = ctx.fq2_mul(&psi_x, &product.z);
+    let y = ctx.fq2_mul(&psi_y, &product.z);
+    let x = fq2_select(ctx, &p.z, &product.x, &x);
+    let y = fq2_select(ctx, &p.z, &product.y, &y);
+    ctx.base_integer_chip().assert_int_equal(&product.x.0, &x.0);
+    ctx.base_integer_chip().assert_int_equal(&product.x.1, &x.1);
+    ctx.base_integer_chip().assert_int_equal(&product.y.0, &y.0);
+    ctx.base_integer_chip().assert_int_equal(&product.y.1, &y.1);
+}
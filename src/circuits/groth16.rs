@@ -0,0 +1,227 @@
+use ark_std::{end_timer, start_timer};
+use halo2_proofs::pairing::bn256::Fr;
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Chip, Layouter, Region},
+    pairing::bn256::{Fq as Bn256Fq, G1Affine},
+    plonk::{Advice, Column, ConstraintSystem, Error},
+};
+use halo2ecc_s::assign::{AssignedCondition, AssignedG2Affine, AssignedPoint, AssignedValue};
+use halo2ecc_s::circuit::ecc_chip::EccChipBaseOps;
+use halo2ecc_s::context::{Context, IntegerContext, NativeScalarEccContext};
+use poseidon::Spec;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::circuits::bn256::{
+    assign_pair_check, assign_scalar_bits, enable_fr_permute, enable_g1affine_permute,
+    enable_g2affine_permute, get_cell_of_ctx, get_checked_g1_from_cells, get_checked_g2_from_cells,
+    get_scalar_from_cell, Bn256ChipConfig, Bn256PairCheckChip,
+};
+use crate::circuits::g1::{g1_add, g1_msm};
+use crate::circuits::poseidon::{PoseidonChip, PoseidonGateConfig};
+use crate::circuits::{CommonGateConfig, Limb};
+use crate::host::groth16::GROTH16_PUBLIC_INPUTS;
+
+/* Groth16 verification over BN254. See host::groth16 for the native reference.
+ *
+ * The arguments of a verification are the hash of the verifying key, the key, the
+ * public inputs, the proof (A, B, C) and the result. The hash is checked with the
+ * poseidon chip in a region of the common gates, while vk_x and the pairing check
+ *     e(-A, B) * e(alpha, beta) * e(vk_x, gamma) * e(C, delta) == 1
+ * share one halo2ecc-s context.
+ */
+pub const G1_LIMBS: usize = 7;
+pub const G2_LIMBS: usize = 13;
+pub const VK_LIMBS: usize = 3 * G2_LIMBS + (GROTH16_PUBLIC_INPUTS + 2) * G1_LIMBS;
+pub const PROOF_LIMBS: usize = G2_LIMBS + 2 * G1_LIMBS;
+// hash, key, inputs, proof and the result
+pub const GROTH16_LIMBS: usize = 1 + VK_LIMBS + GROTH16_PUBLIC_INPUTS + PROOF_LIMBS + 1;
+
+#[derive(Clone, Debug)]
+pub struct Groth16VerifyConfig {
+    pub pair_check: Bn256ChipConfig,
+    pub common: CommonGateConfig,
+    pub poseidon: PoseidonGateConfig,
+}
+
+pub struct Groth16VerifyChip<F: FieldExt> {
+    config: Groth16VerifyConfig,
+    pub pair_check: Bn256PairCheckChip<F>,
+    pub poseidon: PoseidonChip<F, 9, 8>,
+    state: Groth16State<F>,
+}
+
+pub struct Groth16State<F: FieldExt> {
+    zero: Limb<F>,
+    one: Limb<F>,
+}
+
+impl<F: FieldExt> Chip<F> for Groth16VerifyChip<F> {
+    type Config = Groth16VerifyConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl Groth16VerifyChip<Fr> {
+    pub fn construct(config: Groth16VerifyConfig, spec: Spec<Fr, 9, 8>) -> Self {
+        let state = Groth16State {
+            zero: Limb::new(None, Fr::zero()),
+            one: Limb::new(None, Fr::one()),
+        };
+        Groth16VerifyChip {
+            pair_check: Bn256PairCheckChip::construct(config.pair_check.clone()),
+            poseidon: PoseidonChip::construct(config.common.clone(), config.poseidon.clone(), spec),
+            config,
+            state,
+        }
+    }
+
+    pub fn configure(
+        cs: &mut ConstraintSystem<Fr>,
+        shared_advices: &Vec<Column<Advice>>,
+    ) -> Groth16VerifyConfig {
+        let (common, poseidon) = PoseidonChip::<Fr, 9, 8>::configure(cs, shared_advices);
+        Groth16VerifyConfig {
+            pair_check: Bn256PairCheckChip::<Fr>::configure(cs),
+            common,
+            poseidon,
+        }
+    }
+
+    pub fn initialize(
+        &mut self,
+        config: &CommonGateConfig,
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+    ) -> Result<(), Error> {
+        // poseidon initialize resets the offset so it has to go first
+        self.poseidon.initialize(config, region, offset)?;
+        self.state.zero = config.assign_constant(region, &mut (), offset, &Fr::zero())?;
+        self.state.one = config.assign_constant(region, &mut (), offset, &Fr::one())?;
+        Ok(())
+    }
+
+    /// hash == poseidon(vk), the limbs are padded with one followed by zeros
+    pub fn assign_vk_hash(
+        &mut self,
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        vk: &[Limb<Fr>],
+        hash: &Limb<Fr>,
+    ) -> Result<(), Error> {
+        let mut values = vk.to_vec();
+        values.push(self.state.one.clone());
+        values.resize((values.len() + 7) / 8 * 8, self.state.zero.clone());
+        let mut reset = self.state.one.clone();
+        let mut result = None;
+        for round in values.chunks_exact(8) {
+            let round: [Limb<Fr>; 8] = round.to_vec().try_into().unwrap();
+            result = Some(self.poseidon.get_permute_result(region, offset, &round, &reset)?);
+            reset = self.state.zero.clone();
+        }
+        // the adaptor has checked the hash natively and reported the call of a mismatch
+        let result = result.unwrap();
+        region.constrain_equal(
+            hash.get_the_cell().cell(),
+            result.get_the_cell().cell(),
+        )?;
+        Ok(())
+    }
+
+    pub fn load_groth16_verify_circuit(
+        &self,
+        ls: &Vec<Limb<Fr>>, // n * (hash, vk, inputs, proof, result)
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let context = Rc::new(RefCell::new(Context::new()));
+        let ctx = IntegerContext::<Bn256Fq, Fr>::new(context);
+        let mut ctx = NativeScalarEccContext(ctx, 0);
+
+        // the assigned values with the start of their limbs in ls
+        let mut g1s: Vec<(AssignedPoint<G1Affine, Fr>, usize)> = vec![];
+        let mut g2s: Vec<(AssignedG2Affine<G1Affine, Fr>, usize)> = vec![];
+        let mut xs: Vec<(AssignedValue<Fr>, usize)> = vec![];
+        let mut results: Vec<(AssignedCondition<Fr>, usize)> = vec![];
+
+        for (k, args) in ls.chunks_exact(GROTH16_LIMBS).enumerate() {
+            let vk_start = k * GROTH16_LIMBS + 1;
+            let inputs_start = vk_start + VK_LIMBS;
+            let proof_start = inputs_start + GROTH16_PUBLIC_INPUTS;
+            let mut g1 = |ctx: &mut NativeScalarEccContext<G1Affine>, start: usize| {
+                let p = get_checked_g1_from_cells(ctx, &ls[start..start + G1_LIMBS].to_vec());
+                g1s.push((p.clone(), start));
+                p
+            };
+            let alpha = g1(&mut ctx, vk_start);
+            let ics = (0..GROTH16_PUBLIC_INPUTS + 1)
+                .map(|i| g1(&mut ctx, vk_start + G1_LIMBS + 3 * G2_LIMBS + i * G1_LIMBS))
+                .collect::<Vec<_>>();
+            let a = g1(&mut ctx, proof_start);
+            let c = g1(&mut ctx, proof_start + G1_LIMBS + G2_LIMBS);
+
+            let mut g2 = |ctx: &mut NativeScalarEccContext<G1Affine>, start: usize| {
+                let q = get_checked_g2_from_cells(ctx, &ls[start..start + G2_LIMBS].to_vec());
+                g2s.push((q.clone(), start));
+                q
+            };
+            let beta = g2(&mut ctx, vk_start + G1_LIMBS);
+            let gamma = g2(&mut ctx, vk_start + G1_LIMBS + G2_LIMBS);
+            let delta = g2(&mut ctx, vk_start + G1_LIMBS + 2 * G2_LIMBS);
+            let b = g2(&mut ctx, proof_start + G1_LIMBS);
+
+            // vk_x = IC_0 + sum x_i * IC_i, where the IC of a shorter key are the identity
+            let mut bits = vec![];
+            for i in 0..GROTH16_PUBLIC_INPUTS {
+                let x = get_scalar_from_cell(&mut ctx, args[1 + VK_LIMBS + i].value);
+                bits.push(assign_scalar_bits(&mut ctx, &x));
+                xs.push((x, inputs_start + i));
+            }
+            let product = g1_msm(&mut ctx, &ics[1..], &bits);
+            let vk_x = g1_add(&mut ctx, &ics[0], &product);
+
+            let neg_a = ctx.ecc_neg(&a);
+            let result = assign_pair_check(
+                &mut ctx,
+                &[(neg_a, b), (alpha, beta), (vk_x, gamma), (c, delta)],
+            );
+            results.push((result, (k + 1) * GROTH16_LIMBS - 1));
+        }
+
+        let records = Arc::try_unwrap(Into::<Context<Fr>>::into(ctx).records)
+            .unwrap()
+            .into_inner()
+            .unwrap();
+        layouter.assign_region(
+            || "base",
+            |mut region| {
+                let timer = start_timer!(|| "assign");
+                let cells = self.pair_check.assign_records(&mut region, &records)?;
+                for (p, start) in g1s.iter() {
+                    enable_g1affine_permute(&mut region, &cells, p, &ls[*start..*start + G1_LIMBS].to_vec())?;
+                }
+                for (q, start) in g2s.iter() {
+                    enable_g2affine_permute(&mut region, &cells, q, &ls[*start..*start + G2_LIMBS].to_vec())?;
+                }
+                for (x, start) in xs.iter() {
+                    enable_fr_permute(&mut region, &cells, x, &ls[*start..*start + 1].to_vec())?;
+                }
+                for (result, index) in results.iter() {
+                    let result_assigned = get_cell_of_ctx(&cells, &result.0.cell);
+                    region.constrain_equal(ls[*index].get_the_cell().cell(), result_assigned.cell())?;
+                }
+                end_timer!(timer);
+                Ok(())
+            },
+        )?;
+        Ok(())
+    }
+}
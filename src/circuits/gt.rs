@@ -0,0 +1,189 @@
+use halo2_proofs::arithmetic::{BaseExt, FieldExt};
+use halo2ecc_s::assign::{AssignedCondition, AssignedFq, AssignedFq12, AssignedFq2, AssignedFq6};
+use halo2ecc_s::circuit::base_chip::BaseChipOps;
+use halo2ecc_s::circuit::ecc_chip::EccBaseIntegerChipWrapper;
+use halo2ecc_s::circuit::fq12::{Fq12ChipOps, Fq2ChipOps};
+use halo2ecc_s::circuit::integer_chip::IntegerChipOps;
+
+/* Torus compression and arithmetic of the elements of Gt, shared by BN254 and BLS12-381.
+ *
+ * Fq12 = Fq6[w] / (w^2 - v) and Fq6 = Fq2[v] / (v^3 - xi). An element f = g + h * w of
+ * Gt other than one has h != 0 and is compressed to c = (1 + g) / h, which gives back
+ * f = (c + w) / (c - w). One is sent as a flag since it has no compressed form.
+ */
+
+fn fq6_mul<W: BaseExt, N: FieldExt, T: Fq2ChipOps<W, N>>(
+    ctx: &mut T,
+    xi: &AssignedFq2<W, N>,
+    a: &AssignedFq6<W, N>,
+    b: &AssignedFq6<W, N>,
+) -> AssignedFq6<W, N> {
+    let a0b0 = ctx.fq2_mul(&a.0, &b.0);
+    let a0b1 = ctx.fq2_mul(&a.0, &b.1);
+    let a0b2 = ctx.fq2_mul(&a.0, &b.2);
+    let a1b0 = ctx.fq2_mul(&a.1, &b.0);
+    let a1b1 = ctx.fq2_mul(&a.1, &b.1);
+    let a1b2 = ctx.fq2_mul(&a.1, &b.2);
+    let a2b0 = ctx.fq2_mul(&a.2, &b.0);
+    let a2b1 = ctx.fq2_mul(&a.2, &b.1);
+    let a2b2 = ctx.fq2_mul(&a.2, &b.2);
+
+    // c0 = a0 * b0 + xi * (a1 * b2 + a2 * b1)
+    let t = ctx.fq2_add(&a1b2, &a2b1);
+    let t = ctx.fq2_mul(xi, &t);
+    let c0 = ctx.fq2_add(&a0b0, &t);
+    // c1 = a0 * b1 + a1 * b0 + xi * a2 * b2
+    let t = ctx.fq2_mul(xi, &a2b2);
+    let c1 = ctx.fq2_add(&a0b1, &a1b0);
+    let c1 = ctx.fq2_add(&c1, &t);
+    // c2 = a0 * b2 + a1 * b1 + a2 * b0
+    let c2 = ctx.fq2_add(&a0b2, &a1b1);
+    let c2 = ctx.fq2_add(&c2, &a2b0);
+    (c0, c1, c2)
+}
+
+fn fq6_components<W: BaseExt, N: FieldExt>(a: &AssignedFq6<W, N>) -> [&AssignedFq<W, N>; 6] {
+    [&a.0 .0, &a.0 .1, &a.1 .0, &a.1 .1, &a.2 .0, &a.2 .1]
+}
+
+/// Constrains (c, is_one) to be the compressed form of f, xi is the non residue of Fq6
+pub fn assert_gt_compressed<
+    W: BaseExt,
+    N: FieldExt,
+    T: EccBaseIntegerChipWrapper<W, N> + Fq2ChipOps<W, N>,
+>(
+    ctx: &mut T,
+    xi: &AssignedFq2<W, N>,
+    f: &AssignedFq12<W, N>,
+    c: &AssignedFq6<W, N>,
+    is_one: &AssignedCondition<N>,
+) {
+    let zero = ctx.base_integer_chip().assign_int_constant(W::zero());
+    let one = ctx.base_integer_chip().assign_int_constant(W::one());
+    let one_fq2 = ctx.fq2_assign_constant((W::one(), W::zero()));
+
+    // h * c == 1 + g determines c, and can not hold for f == 1 where h == 0
+    let hc = fq6_mul(ctx, xi, &f.1, c);
+    let g_plus_one = (ctx.fq2_add(&f.0 .0, &one_fq2), f.0 .1.clone(), f.0 .2.clone());
+
+    // is_one ? (h, g) == (0, 1) : (h * c, 1) == (1 + g, 1)
+    let lhs = fq6_components(&hc)
+        .into_iter()
+        .zip(fq6_components(&f.1).into_iter())
+        .map(|(hc, h)| ctx.base_integer_chip().bisec_int(is_one, h, hc))
+        .collect::<Vec<_>>();
+    let rhs = fq6_components(&g_plus_one)
+        .into_iter()
+        .map(|g| ctx.base_integer_chip().bisec_int(is_one, &zero, g))
+        .collect::<Vec<_>>();
+    for (l, r) in lhs.iter().zip(rhs.iter()) {
+        ctx.base_integer_chip().assert_int_equal(l, r);
+    }
+    for (i, g) in fq6_components(&f.0).into_iter().enumerate() {
+        let expected = if i == 0 { &one } else { &zero };
+        let g = ctx.base_integer_chip().bisec_int(is_one, g, expected);
+        ctx.base_integer_chip().assert_int_equal(&g, expected);
+    }
+}
+
+/// The operation of a gt op call, pushed before its operands
+pub const GT_OP_MUL: u64 = 0;
+pub const GT_OP_INVERT: u64 = 1;
+pub const GT_OP_EQUAL: u64 = 2;
+
+// (a0 + a1 * w) * (b0 + b1 * w) = a0 * b0 + v * a1 * b1 + (a0 * b1 + a1 * b0) * w
+fn fq12_mul<W: BaseExt, N: FieldExt, T: Fq2ChipOps<W, N>>(
+    ctx: &mut T,
+    xi: &AssignedFq2<W, N>,
+    a: &AssignedFq12<W, N>,
+    b: &AssignedFq12<W, N>,
+) -> AssignedFq12<W, N> {
+    let a0b0 = fq6_mul(ctx, xi, &a.0, &b.0);
+    let a0b1 = fq6_mul(ctx, xi, &a.0, &b.1);
+    let a1b0 = fq6_mul(ctx, xi, &a.1, &b.0);
+    let a1b1 = fq6_mul(ctx, xi, &a.1, &b.1);
+    // v * (c0 + c1 * v + c2 * v^2) = xi * c2 + c0 * v + c1 * v^2
+    let t = ctx.fq2_mul(xi, &a1b1.2);
+    let c0 = (
+        ctx.fq2_add(&a0b0.0, &t),
+        ctx.fq2_add(&a0b0.1, &a1b1.0),
+        ctx.fq2_add(&a0b0.2, &a1b1.1),
+    );
+    let c1 = (
+        ctx.fq2_add(&a0b1.0, &a1b0.0),
+        ctx.fq2_add(&a0b1.1, &a1b0.1),
+        ctx.fq2_add(&a0b1.2, &a1b0.2),
+    );
+    (c0, c1)
+}
+
+fn fq12_components<W: BaseExt, N: FieldExt>(a: &AssignedFq12<W, N>) -> Vec<&AssignedFq<W, N>> {
+    let mut r = fq6_components(&a.0).to_vec();
+    r.extend_from_slice(&fq6_components(&a.1));
+    r
+}
+
+/* Constrains result to be a * b, 1 / a or the boolean a == b as the element 1 or 0 of
+ * Fq12, for is_invert and is_equal at most one of which is set. The elements are
+ * any of Fq12, the chips do not check that they are in Gt. The inverse is pushed by
+ * the host and checked by a * result == 1, which a == 0 can not satisfy.
+ */
+pub fn assert_gt_op<
+    W: BaseExt,
+    N: FieldExt,
+    T: EccBaseIntegerChipWrapper<W, N> + Fq2ChipOps<W, N> + Fq12ChipOps<W, N>,
+>(
+    ctx: &mut T,
+    xi: &AssignedFq2<W, N>,
+    is_invert: &AssignedCondition<N>,
+    is_equal: &AssignedCondition<N>,
+    a: &AssignedFq12<W, N>,
+    b: &AssignedFq12<W, N>,
+    result: &AssignedFq12<W, N>,
+) {
+    let zero = ctx.base_integer_chip().assign_int_constant(W::zero());
+    let one = ctx.base_integer_chip().assign_int_constant(W::one());
+
+    // a * (is_invert ? result : b) == (is_invert ? 1 : result)
+    let rhs = fq12_components(result)
+        .into_iter()
+        .zip(fq12_components(b).into_iter())
+        .map(|(r, b)| ctx.base_integer_chip().bisec_int(is_invert, r, b))
+        .collect::<Vec<_>>();
+    let rhs = (
+        (
+            (rhs[0].clone(), rhs[1].clone()),
+            (rhs[2].clone(), rhs[3].clone()),
+            (rhs[4].clone(), rhs[5].clone()),
+        ),
+        (
+            (rhs[6].clone(), rhs[7].clone()),
+            (rhs[8].clone(), rhs[9].clone()),
+            (rhs[10].clone(), rhs[11].clone()),
+        ),
+    );
+    let product = fq12_mul(ctx, xi, a, &rhs);
+    let product = ctx.fq12_reduce(&product);
+
+    // a == b componentwise
+    let mut eq = ctx.base_integer_chip().is_int_equal(&a.0 .0 .0, &b.0 .0 .0);
+    for (a, b) in fq12_components(a).into_iter().zip(fq12_components(b).into_iter()).skip(1) {
+        let c = ctx.base_integer_chip().is_int_equal(a, b);
+        eq = ctx.base_integer_chip().base_chip().and(&eq, &c);
+    }
+    let eq_one = ctx.base_integer_chip().bisec_int(&eq, &one, &zero);
+
+    // is_equal ? (result, eq) : (product, is_invert ? 1 : result)
+    for (i, (p, r)) in fq12_components(&product)
+        .into_iter()
+        .zip(fq12_components(result).into_iter())
+        .enumerate()
+    {
+        let constant = if i == 0 { &one } else { &zero };
+        let expected = ctx.base_integer_chip().bisec_int(is_invert, constant, r);
+        let eq_value = if i == 0 { &eq_one } else { &zero };
+        let lhs = ctx.base_integer_chip().bisec_int(is_equal, r, p);
+        let rhs = ctx.base_integer_chip().bisec_int(is_equal, eq_value, &expected);
+        ctx.base_integer_chip().assert_int_equal(&lhs, &rhs);
+    }
+}
@@ -1,5 +1,5 @@
 use super::Limb;
-use crate::{utils::GateCell, adaptor::get_selected_entries, constant};
+use crate::{utils::GateCell, adaptor::get_selected_entries, adaptor::validate::HostCallError, constant};
 use halo2_proofs::pairing::bn256::Fr;
 use std::marker::PhantomData;
 
@@ -23,7 +23,8 @@ customized_circuits!(HostOpConfig, 2, 10, 2, 0,
 );
 
 impl HostOpConfig {
-    pub fn configure<F: FieldExt>(&self, cs: &mut ConstraintSystem<F>, opcodes: &Vec<F>) {
+    /// holes allows disabled lines between the enabled lines, see HostOpSelector::holes
+    pub fn configure<F: FieldExt>(&self, cs: &mut ConstraintSystem<F>, opcodes: &Vec<F>, holes: bool) {
         cs.lookup_any("filter-shared-ops", |meta| {
             let sopc = self.get_expr(meta, HostOpConfig::shared_opcode());
             let soper = self.get_expr(meta, HostOpConfig::shared_operand());
@@ -69,11 +70,17 @@ impl HostOpConfig {
             vec![sel.clone()*(shared_index.clone() - shared_index_n.clone() - constant_from!(1 as u64)) * (shared_index - shared_index_n)]
         });
 
+        /* with holes a disabled line keeps the index of the line before it */
         cs.create_gate("filtered_index decrease", |meta| {
             let filtered_index  = self.get_expr(meta, HostOpConfig::filtered_index());
             let filtered_index_n = self.get_expr(meta, HostOpConfig::filtered_index_n());
             let sel = self.get_expr(meta, HostOpConfig::sel());
-            vec![sel.clone() * (filtered_index - filtered_index_n.clone() - constant_from!(1 as u64)) * filtered_index_n]
+            let step = if holes {
+                self.get_expr(meta, HostOpConfig::enable_n())
+            } else {
+                constant_from!(1 as u64)
+            };
+            vec![sel.clone() * (filtered_index - filtered_index_n.clone() - step) * filtered_index_n]
         });
 
         cs.create_gate("merge operands in filtered columns", |meta| {
@@ -85,14 +92,30 @@ impl HostOpConfig {
         });
 
         /* enable is continuous with pattern 1,1,1,1,1,0,0,0,0 when sel is active */
-        cs.create_gate("enable consistant", |meta| {
-            let enable = self.get_expr(meta, HostOpConfig::enable());
-            let enable_n = self.get_expr(meta, HostOpConfig::enable_n());
-            let sel = self.get_expr(meta, HostOpConfig::sel());
-            vec![
-                (enable.clone() - constant_from!(1 as u64)) * enable_n * sel.clone(),
-            ]
-        });
+        if !holes {
+            cs.create_gate("enable consistant", |meta| {
+                let enable = self.get_expr(meta, HostOpConfig::enable());
+                let enable_n = self.get_expr(meta, HostOpConfig::enable_n());
+                let sel = self.get_expr(meta, HostOpConfig::sel());
+                vec![
+                    (enable.clone() - constant_from!(1 as u64)) * enable_n * sel.clone(),
+                ]
+            });
+        } else {
+            /* with holes enable is a bit and the merged lines of an operand are enabled
+             * together, the selector chip constrains the operands of the disabled lines
+             */
+            cs.create_gate("enable consistant", |meta| {
+                let enable = self.get_expr(meta, HostOpConfig::enable());
+                let enable_n = self.get_expr(meta, HostOpConfig::enable_n());
+                let indicator = self.get_expr(meta, HostOpConfig::indicator());
+                let sel = self.get_expr(meta, HostOpConfig::sel());
+                vec![
+                    (enable.clone() - constant_from!(1 as u64)) * enable.clone() * sel.clone(),
+                    (enable - enable_n) * indicator,
+                ]
+            });
+        }
     }
 
     pub fn assign_merged_operands(
@@ -103,6 +126,20 @@ impl HostOpConfig {
         indicator: Fr,
         enable: bool,
     ) -> Result<(Limb<Fr>, Limb<Fr>), Error> {
+        let (limb, op, _) =
+            self.assign_merged_operands_with_enable(region, offset, values, indicator, enable)?;
+        Ok((limb, op))
+    }
+
+    /// Same as assign_merged_operands but also returns the enable cell of the operand
+    pub fn assign_merged_operands_with_enable(
+        &self,
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        values: Vec<&((Fr, Fr), Fr)>,
+        indicator: Fr,
+        enable: bool,
+    ) -> Result<(Limb<Fr>, Limb<Fr>, Limb<Fr>), Error> {
         let mut rev = values.clone();
         let len = values.len();
         rev.reverse();
@@ -115,6 +152,7 @@ impl HostOpConfig {
         merged_ops.reverse();
         let mut ret = None;
         let mut op = None;
+        let mut en = None;
         for (i, (((operand, opcode), index), merged_op)) in
             values.into_iter().zip(merged_ops).enumerate()
         {
@@ -122,7 +160,7 @@ impl HostOpConfig {
             let opc =
                 self.assign_cell(region, *offset, &HostOpConfig::filtered_opcode(), *opcode)?;
             self.assign_cell(region, *offset, &HostOpConfig::filtered_index(), *index)?;
-            self.assign_cell(
+            let enc = self.assign_cell(
                 region,
                 *offset,
                 &HostOpConfig::enable(),
@@ -137,11 +175,12 @@ impl HostOpConfig {
                 if i == 0 {
                     ret = Some(limb);
                     op = Some(opc);
+                    en = Some(enc);
                 }
             }
             *offset += 1;
         }
-        Ok((ret.unwrap(), op.unwrap()))
+        Ok((ret.unwrap(), op.unwrap(), en.unwrap()))
     }
 
     pub fn assign_one_line(
@@ -155,12 +194,30 @@ impl HostOpConfig {
         ind: Fr,
         enable: bool,
     ) -> Result<(Limb<Fr>, Limb<Fr>), Error> {
+        let (r, op, _) = self.assign_one_line_with_enable(
+            region, offset, operand, opcode, index, merge, ind, enable,
+        )?;
+        Ok((r, op))
+    }
+
+    /// Same as assign_one_line but also returns the enable cell of the line
+    pub fn assign_one_line_with_enable(
+        &self,
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        operand: Fr,
+        opcode: Fr,
+        index: Fr,
+        merge: Fr,
+        ind: Fr,
+        enable: bool,
+    ) -> Result<(Limb<Fr>, Limb<Fr>, Limb<Fr>), Error> {
         let r = self.assign_cell(region, *offset, &HostOpConfig::filtered_operand(), operand)?;
         let op = self.assign_cell(region, *offset, &HostOpConfig::filtered_opcode(), opcode)?;
         self.assign_cell(region, *offset, &HostOpConfig::filtered_index(), index)?;
         self.assign_cell(region, *offset, &HostOpConfig::indicator(), ind)?;
         self.assign_cell(region, *offset, &HostOpConfig::merged_op(), merge)?;
-        self.assign_cell(
+        let en = self.assign_cell(
             region,
             *offset,
             &HostOpConfig::enable(),
@@ -168,7 +225,7 @@ impl HostOpConfig {
         )?;
         self.assign_cell(region, *offset, &HostOpConfig::sel(), Fr::one())?;
         *offset += 1;
-        Ok((r, op))
+        Ok((r, op, en))
     }
 }
 
@@ -177,6 +234,21 @@ pub trait HostOpSelector {
     fn configure(meta: &mut ConstraintSystem<Fr>, shared_advice: &Vec<Column<Advice>>) -> Self::Config;
     fn construct(c: Self::Config) -> Self;
     fn opcodes() -> Vec<Fr>;
+    /// Whether the chip lays disabled lines (holes) between its enabled lines. The
+    /// index does not move over a hole and its operand is free in the host op gates,
+    /// so a chip that returns true must constrain the operands of its holes or make
+    /// its result independent of them.
+    fn holes() -> bool {
+        false
+    }
+    /// Checks the calls of the op in the host call table before synthesis, a bad call
+    /// is reported with its op, its index and the argument that is wrong
+    fn validate(
+        _shared_operands: &Vec<Fr>,
+        _shared_opcodes: &Vec<Fr>,
+    ) -> Result<(), HostCallError> {
+        Ok(())
+    }
     fn assign(
         region: &mut Region<Fr>,
         offset: &mut usize,
@@ -242,7 +314,7 @@ impl<S: HostOpSelector> HostOpChip<Fr, S> {
         let selector = [];
 
         let config = HostOpConfig::new(witness, fixed, selector);
-        config.configure(cs, &S::opcodes());
+        config.configure(cs, &S::opcodes(), S::holes());
         config
     }
 
@@ -0,0 +1,575 @@
+// The constraint system matrix for keccak256.
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Chip, Region},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector, VirtualCells},
+    poly::Rotation,
+};
+
+use crate::constant_from;
+use crate::host::keccak256::{OUTPUT_LANES, RATE_LANES, ROTATION_CONSTANTS, ROUND_CONSTANTS};
+use crate::{
+    customized_circuits, customized_circuits_expand, item_count, table_item, value_for_assign,
+};
+use std::marker::PhantomData;
+
+use crate::utils::GateCell;
+use crate::utils::{field_to_u64, Limb};
+
+/*
+ * Each lane of the keccak state is kept as 16 nibbles (little endian) and all the
+ * bitwise operations are looked up nibble by nibble in a fixed table of
+ * (op, x, y, z, out):
+ *   XOR3:  out = x ^ y ^ z
+ *   XOR2:  out = x ^ y (z = 0)
+ *   NIBBLE: out = x (y = z = 0), only a range check of x
+ *   CHI:   out = x ^ (!y & z)
+ *   SPLITm: y = x >> (4 - m), out = (x % 2^(4-m)) << m (z = 0)
+ *
+ * A left rotation by 4q + m is a re-index of nibbles by q followed by a SPLITm of
+ * every nibble and p = out + q where q is the high part of the previous nibble.
+ * A lane is composed from its nibbles by acc = acc_n * acc_shift + y.
+ */
+#[rustfmt::skip]
+customized_circuits!(KeccakGateConfig, 2, 7, 13, 0,
+    | x   | y   | z   | out | acc   | p   | q   | op  | lookup_sel | acc_sel | acc_shift | rot_sel | const_sel | cy  | reset_sel | t_op | t_x | t_y | t_z | t_out
+    | nil | nil | nil | nil | acc_n | nil | nil | nil | nil        | nil     | nil       | nil     | nil       | nil | nil       | nil  | nil | nil | nil | nil
+);
+
+const OP_XOR3: u64 = 1;
+const OP_XOR2: u64 = 2;
+const OP_NIBBLE: u64 = 3;
+const OP_CHI: u64 = 4;
+// the split ops are OP_SPLIT + m for m in 1..4
+const OP_SPLIT: u64 = 4;
+
+fn nibble_op(op: u64, x: u64, y: u64, z: u64) -> u64 {
+    match op {
+        OP_XOR3 => x ^ y ^ z,
+        OP_XOR2 => x ^ y,
+        OP_NIBBLE => x,
+        OP_CHI => x ^ (!y & z & 0xf),
+        _ => {
+            let m = op - OP_SPLIT;
+            (x % (1 << (4 - m))) << m
+        }
+    }
+}
+
+fn lookup_table() -> Vec<[u64; 5]> {
+    let mut table = vec![];
+    for x in 0..16 {
+        for y in 0..16 {
+            for z in 0..16 {
+                table.push([OP_XOR3, x, y, z, nibble_op(OP_XOR3, x, y, z)]);
+                table.push([OP_CHI, x, y, z, nibble_op(OP_CHI, x, y, z)]);
+            }
+            table.push([OP_XOR2, x, y, 0, nibble_op(OP_XOR2, x, y, 0)]);
+        }
+        table.push([OP_NIBBLE, x, 0, 0, x]);
+        for m in 1..4 {
+            let op = OP_SPLIT + m;
+            table.push([op, x, x >> (4 - m), 0, nibble_op(op, x, 0, 0)]);
+        }
+    }
+    table
+}
+
+pub type Lane<F> = [Limb<F>; 16];
+
+pub struct KeccakChip<F: FieldExt> {
+    pub config: KeccakGateConfig,
+    state: Vec<Lane<F>>,
+    zero: Limb<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> Chip<F> for KeccakChip<F> {
+    type Config = KeccakGateConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt> KeccakChip<F> {
+    pub fn new(config: KeccakGateConfig) -> Self {
+        let zero = Limb::new(None, F::zero());
+        KeccakChip {
+            config,
+            state: vec![[0; 16].map(|_| zero.clone()); 25],
+            zero,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(cs: &mut ConstraintSystem<F>) -> KeccakGateConfig {
+        let witness = [0; 7].map(|_| cs.advice_column());
+        let fixed = [0; 13].map(|_| cs.fixed_column());
+        let selector = [];
+        witness.map(|x| cs.enable_equality(x));
+
+        let config = KeccakGateConfig::new(witness, fixed, selector);
+
+        cs.lookup_any("keccak nibble ops", |meta| {
+            let sel = config.get_expr(meta, KeccakGateConfig::lookup_sel());
+            let op = config.get_expr(meta, KeccakGateConfig::op());
+            let x = config.get_expr(meta, KeccakGateConfig::x());
+            let y = config.get_expr(meta, KeccakGateConfig::y());
+            let z = config.get_expr(meta, KeccakGateConfig::z());
+            let out = config.get_expr(meta, KeccakGateConfig::out());
+            vec![
+                (sel.clone() * op, config.get_expr(meta, KeccakGateConfig::t_op())),
+                (sel.clone() * x, config.get_expr(meta, KeccakGateConfig::t_x())),
+                (sel.clone() * y, config.get_expr(meta, KeccakGateConfig::t_y())),
+                (sel.clone() * z, config.get_expr(meta, KeccakGateConfig::t_z())),
+                (sel * out, config.get_expr(meta, KeccakGateConfig::t_out())),
+            ]
+        });
+
+        cs.create_gate("keccak compose lane", |meta| {
+            let sel = config.get_expr(meta, KeccakGateConfig::acc_sel());
+            let acc = config.get_expr(meta, KeccakGateConfig::acc());
+            let acc_n = config.get_expr(meta, KeccakGateConfig::acc_n());
+            let shift = config.get_expr(meta, KeccakGateConfig::acc_shift());
+            let y = config.get_expr(meta, KeccakGateConfig::y());
+            vec![sel * (acc - acc_n * shift - y)]
+        });
+
+        cs.create_gate("keccak rotate nibble", |meta| {
+            let sel = config.get_expr(meta, KeccakGateConfig::rot_sel());
+            let out = config.get_expr(meta, KeccakGateConfig::out());
+            let p = config.get_expr(meta, KeccakGateConfig::p());
+            let q = config.get_expr(meta, KeccakGateConfig::q());
+            vec![sel * (p - out - q)]
+        });
+
+        cs.create_gate("keccak constant", |meta| {
+            let sel = config.get_expr(meta, KeccakGateConfig::const_sel());
+            let y = config.get_expr(meta, KeccakGateConfig::y());
+            let cy = config.get_expr(meta, KeccakGateConfig::cy());
+            vec![sel * (y - cy)]
+        });
+
+        cs.create_gate("keccak reset state", |meta| {
+            let sel = config.get_expr(meta, KeccakGateConfig::reset_sel());
+            let x = config.get_expr(meta, KeccakGateConfig::x());
+            let p = config.get_expr(meta, KeccakGateConfig::p());
+            let q = config.get_expr(meta, KeccakGateConfig::q());
+            vec![sel * (x - p * (constant_from!(1) - q))]
+        });
+
+        config
+    }
+
+    /// assign the lookup table and the zero constant, the state is reset to zero
+    pub fn initialize(&mut self, region: &mut Region<F>, offset: &mut usize) -> Result<(), Error> {
+        for (i, row) in lookup_table().iter().enumerate() {
+            let cols = [
+                KeccakGateConfig::t_op(),
+                KeccakGateConfig::t_x(),
+                KeccakGateConfig::t_y(),
+                KeccakGateConfig::t_z(),
+                KeccakGateConfig::t_out(),
+            ];
+            for (col, v) in cols.iter().zip(row.iter()) {
+                self.config.assign_cell(region, i, col, F::from(*v))?;
+            }
+        }
+        self.zero = self.assign_constant(region, offset, 0)?;
+        self.state = vec![[0; 16].map(|_| self.zero.clone()); 25];
+        Ok(())
+    }
+
+    fn assign_constant(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        v: u64,
+    ) -> Result<Limb<F>, Error> {
+        self.config
+            .assign_cell(region, *offset, &KeccakGateConfig::const_sel(), F::one())?;
+        self.config
+            .assign_cell(region, *offset, &KeccakGateConfig::cy(), F::from(v))?;
+        let c = self
+            .config
+            .assign_cell(region, *offset, &KeccakGateConfig::y(), F::from(v))?;
+        *offset += 1;
+        Ok(c)
+    }
+
+    /// assign one looked up nibble operation at the current row without moving the offset
+    fn assign_op(
+        &self,
+        region: &mut Region<F>,
+        offset: usize,
+        op: u64,
+        x: &Limb<F>,
+        y: &Limb<F>,
+        z: &Limb<F>,
+    ) -> Result<[Limb<F>; 4], Error> {
+        let out = nibble_op(
+            op,
+            field_to_u64(&x.value),
+            field_to_u64(&y.value),
+            field_to_u64(&z.value),
+        );
+        self.config
+            .assign_cell(region, offset, &KeccakGateConfig::op(), F::from(op))?;
+        self.config
+            .assign_cell(region, offset, &KeccakGateConfig::lookup_sel(), F::one())?;
+        let x = self.config.bind_cell(region, offset, &KeccakGateConfig::x(), x)?;
+        let y = self.config.bind_cell(region, offset, &KeccakGateConfig::y(), y)?;
+        let z = self.config.bind_cell(region, offset, &KeccakGateConfig::z(), z)?;
+        let out = self
+            .config
+            .assign_cell(region, offset, &KeccakGateConfig::out(), F::from(out))?;
+        Ok([x, y, z, out])
+    }
+
+    fn lane_op(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        op: u64,
+        x: &Lane<F>,
+        y: &Lane<F>,
+        z: &Lane<F>,
+    ) -> Result<Lane<F>, Error> {
+        let mut r = vec![];
+        for i in 0..16 {
+            let [_, _, _, out] = self.assign_op(region, *offset, op, &x[i], &y[i], &z[i])?;
+            *offset += 1;
+            r.push(out);
+        }
+        Ok(r.try_into().unwrap())
+    }
+
+    fn zero_lane(&self) -> Lane<F> {
+        [0; 16].map(|_| self.zero.clone())
+    }
+
+    fn rotate_left(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        lane: &Lane<F>,
+        r: u32,
+    ) -> Result<Lane<F>, Error> {
+        let shift = (r / 4) as usize;
+        let lane: Lane<F> = [0; 16]
+            .iter()
+            .enumerate()
+            .map(|(i, _)| lane[(i + 16 - shift) % 16].clone())
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
+        let m = (r % 4) as u64;
+        if m == 0 {
+            return Ok(lane);
+        }
+        let start = *offset;
+        let mut split = vec![];
+        for i in 0..16 {
+            split.push(self.assign_op(
+                region,
+                start + i,
+                OP_SPLIT + m,
+                &lane[i],
+                &Limb::new(None, F::from(field_to_u64(&lane[i].value) >> (4 - m))),
+                &Limb::new(None, F::zero()),
+            )?);
+        }
+        let mut r = vec![];
+        for i in 0..16 {
+            let hi = &split[(i + 15) % 16][1];
+            let out = &split[i][3];
+            self.config
+                .assign_cell(region, start + i, &KeccakGateConfig::rot_sel(), F::one())?;
+            self.config
+                .bind_cell(region, start + i, &KeccakGateConfig::q(), hi)?;
+            r.push(self.config.assign_cell(
+                region,
+                start + i,
+                &KeccakGateConfig::p(),
+                out.value + hi.value,
+            )?);
+        }
+        *offset = start + 16;
+        Ok(r.try_into().unwrap())
+    }
+
+    /// compose the lane from the nibbles in column y of the rows start..start+16
+    fn compose_lane(
+        &self,
+        region: &mut Region<F>,
+        start: usize,
+        nibbles: &Lane<F>,
+        lane: &Limb<F>,
+    ) -> Result<(), Error> {
+        let mut acc = F::zero();
+        for i in (0..16).rev() {
+            let shift = if i == 15 { 0u64 } else { 16u64 };
+            acc = acc * F::from(shift) + nibbles[i].value;
+            self.config
+                .assign_cell(region, start + i, &KeccakGateConfig::acc_sel(), F::one())?;
+            self.config.assign_cell(
+                region,
+                start + i,
+                &KeccakGateConfig::acc_shift(),
+                F::from(shift),
+            )?;
+            let limb = self
+                .config
+                .assign_cell(region, start + i, &KeccakGateConfig::acc(), acc)?;
+            if i == 0 {
+                assert!(limb.value == lane.value);
+                if let Some(c) = lane.cell.as_ref() {
+                    region.constrain_equal(limb.get_the_cell().cell(), c.cell())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn lane_to_nibbles(lane: &Limb<F>) -> Lane<F> {
+        let v = field_to_u64(&lane.value);
+        [0; 16]
+            .iter()
+            .enumerate()
+            .map(|(i, _)| Limb::new(None, F::from((v >> (4 * i)) & 0xf)))
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap()
+    }
+
+    /// state = reset ? 0 : state, then xor the input lanes into the state
+    fn absorb(
+        &mut self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        values: &[Limb<F>; RATE_LANES],
+        reset: &Limb<F>,
+    ) -> Result<(), Error> {
+        for j in 0..25 {
+            let start = *offset;
+            let input = if j < RATE_LANES {
+                Self::lane_to_nibbles(&values[j])
+            } else {
+                self.zero_lane()
+            };
+            let mut lane = vec![];
+            for i in 0..16 {
+                let row = start + i;
+                let prev = &self.state[j][i];
+                let x = if reset.value == F::zero() {
+                    prev.value
+                } else {
+                    F::zero()
+                };
+                self.config
+                    .assign_cell(region, row, &KeccakGateConfig::reset_sel(), F::one())?;
+                self.config
+                    .bind_cell(region, row, &KeccakGateConfig::p(), prev)?;
+                self.config
+                    .bind_cell(region, row, &KeccakGateConfig::q(), reset)?;
+                let (op, y) = if j < RATE_LANES {
+                    (OP_XOR2, input[i].clone())
+                } else {
+                    (OP_NIBBLE, Limb::new(None, F::zero()))
+                };
+                let [_, _, _, out] = self.assign_op(
+                    region,
+                    row,
+                    op,
+                    &Limb::new(None, x),
+                    &y,
+                    &Limb::new(None, F::zero()),
+                )?;
+                lane.push(out);
+            }
+            if j < RATE_LANES {
+                self.compose_lane(region, start, &input, &values[j])?;
+            }
+            self.state[j] = lane.try_into().unwrap();
+            *offset = start + 16;
+        }
+        Ok(())
+    }
+
+    fn permute(&mut self, region: &mut Region<F>, offset: &mut usize) -> Result<(), Error> {
+        for rc in ROUND_CONSTANTS.iter() {
+            let a = self.state.clone();
+            // theta
+            let mut c = vec![];
+            for x in 0..5 {
+                let t = self.lane_op(region, offset, OP_XOR3, &a[x], &a[x + 5], &a[x + 10])?;
+                c.push(self.lane_op(region, offset, OP_XOR3, &t, &a[x + 15], &a[x + 20])?);
+            }
+            let mut c_rot = vec![];
+            for x in 0..5 {
+                c_rot.push(self.rotate_left(region, offset, &c[x], 1)?);
+            }
+            let mut a_theta = a.clone();
+            for x in 0..5 {
+                for y in 0..5 {
+                    a_theta[x + 5 * y] = self.lane_op(
+                        region,
+                        offset,
+                        OP_XOR3,
+                        &a[x + 5 * y],
+                        &c[(x + 4) % 5],
+                        &c_rot[(x + 1) % 5],
+                    )?;
+                }
+            }
+            // rho and pi
+            let mut b = a_theta.clone();
+            for x in 0..5 {
+                for y in 0..5 {
+                    b[y + 5 * ((2 * x + 3 * y) % 5)] = self.rotate_left(
+                        region,
+                        offset,
+                        &a_theta[x + 5 * y],
+                        ROTATION_CONSTANTS[x][y],
+                    )?;
+                }
+            }
+            // chi
+            for x in 0..5 {
+                for y in 0..5 {
+                    self.state[x + 5 * y] = self.lane_op(
+                        region,
+                        offset,
+                        OP_CHI,
+                        &b[x + 5 * y],
+                        &b[(x + 1) % 5 + 5 * y],
+                        &b[(x + 2) % 5 + 5 * y],
+                    )?;
+                }
+            }
+            // iota
+            let mut lane = vec![];
+            for i in 0..16 {
+                let rc_nibble = (rc >> (4 * i)) & 0xf;
+                let [_, _, _, out] = self.assign_op(
+                    region,
+                    *offset,
+                    OP_XOR2,
+                    &self.state[0][i],
+                    &Limb::new(None, F::from(rc_nibble)),
+                    &Limb::new(None, F::zero()),
+                )?;
+                self.config
+                    .assign_cell(region, *offset, &KeccakGateConfig::const_sel(), F::one())?;
+                self.config
+                    .assign_cell(region, *offset, &KeccakGateConfig::cy(), F::from(rc_nibble))?;
+                *offset += 1;
+                lane.push(out);
+            }
+            self.state[0] = lane.try_into().unwrap();
+        }
+        Ok(())
+    }
+
+    /// absorb one block of 17 lanes (restart the sponge if reset is 1) and constrain
+    /// the first 4 lanes of the permuted state to be equal to result
+    pub fn assign_permute(
+        &mut self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        values: &[Limb<F>; RATE_LANES],
+        reset: &Limb<F>,
+        result: &[Limb<F>; OUTPUT_LANES],
+    ) -> Result<(), Error> {
+        self.absorb(region, offset, values, reset)?;
+        self.permute(region, offset)?;
+        for j in 0..OUTPUT_LANES {
+            let start = *offset;
+            let lane = self.state[j].clone();
+            for i in 0..16 {
+                self.config
+                    .bind_cell(region, start + i, &KeccakGateConfig::y(), &lane[i])?;
+            }
+            self.compose_lane(region, start, &lane, &result[j])?;
+            *offset = start + 16;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pairing::bn256::Fr;
+
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+
+    use super::{KeccakChip, KeccakGateConfig};
+    use crate::host::keccak256::{keccak256_blocks, Keccak256};
+    use crate::utils::Limb;
+
+    #[derive(Clone, Debug, Default)]
+    struct TestCircuit {
+        message: Vec<u8>,
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = KeccakGateConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            KeccakChip::<Fr>::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let mut chip = KeccakChip::<Fr>::new(config);
+            layouter.assign_region(
+                || "keccak256 test",
+                |mut region| {
+                    let mut offset = 0;
+                    chip.initialize(&mut region, &mut offset)?;
+                    let mut hasher = Keccak256::new();
+                    for (i, block) in keccak256_blocks(&self.message).iter().enumerate() {
+                        let result = hasher.update_exact(block);
+                        chip.assign_permute(
+                            &mut region,
+                            &mut offset,
+                            &block.map(|x| Limb::new(None, Fr::from(x))),
+                            &Limb::new(None, Fr::from((i == 0) as u64)),
+                            &result.map(|x| Limb::new(None, Fr::from(x))),
+                        )?;
+                    }
+                    Ok(())
+                },
+            )?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_keccak256_circuit() {
+        let test_circuit = TestCircuit {
+            message: (0..200).map(|x| x as u8).collect(),
+        };
+        let prover = MockProver::run(18, &test_circuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+}
@@ -0,0 +1,438 @@
+use crate::circuits::merkle::{MerkleChip, MerkleGateConfig};
+use crate::circuits::CommonGateConfig;
+use crate::circuits::Limb;
+use crate::host::kvmap::{KVMapProbe, MAX_PROBE};
+use crate::host::ForeignInst::KVMapSet;
+use crate::utils::{bytes_to_field, field_to_u64};
+use ff::PrimeField;
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::{Chip, Region};
+use halo2_proofs::pairing::bn256::Fr;
+use halo2_proofs::plonk::{Advice, Column, ConstraintSystem, Error};
+
+/* A call of the key value map, see host::kvmap.
+ *
+ * Every call proves the MAX_PROBE leaves of the probe sequence of its key hash
+ * against the root. A leaf holds the hash of its entry (key hash, value) or zero.
+ * The probe goes on over a leaf while it holds the entry of another key, the first
+ * leaf it stops at is the final leaf and the probe has to stop within MAX_PROBE
+ * leaves. A get returns the value of the final leaf or zero, a set writes the entry
+ * of the key to the final leaf and its path gives the new root.
+ */
+pub struct KVMapChip<F: FieldExt, const D: usize> {
+    pub merkle: MerkleChip<F, D>,
+    zero: Limb<F>,
+    one: Limb<F>,
+}
+
+impl<F: FieldExt, const D: usize> Chip<F> for KVMapChip<F, D> {
+    type Config = CommonGateConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        self.merkle.config()
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+/// The field elements of the low and high 16 bytes of bytes
+fn bytes_to_halves(bytes: &[u8; 32]) -> [Fr; 2] {
+    [0, 16].map(|start| {
+        let mut half = [0u8; 32];
+        half[0..16].copy_from_slice(&bytes[start..start + 16]);
+        bytes_to_field(&half)
+    })
+}
+
+impl<const D: usize> KVMapChip<Fr, D> {
+    pub fn new(config: CommonGateConfig, extend: MerkleGateConfig) -> Self {
+        KVMapChip {
+            merkle: MerkleChip::new(config, extend),
+            zero: Limb::new(None, Fr::zero()),
+            one: Limb::new(None, Fr::one()),
+        }
+    }
+
+    pub fn configure(
+        cs: &mut ConstraintSystem<Fr>,
+        shared_advices: &Vec<Column<Advice>>,
+    ) -> (CommonGateConfig, MerkleGateConfig) {
+        MerkleChip::<Fr, D>::configure(cs, shared_advices)
+    }
+
+    pub fn initialize(
+        &mut self,
+        config: &CommonGateConfig,
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+    ) -> Result<(), Error> {
+        self.merkle.initialize(config, region, offset)?;
+        self.zero = config.assign_constant(region, &mut (), offset, &Fr::zero())?;
+        self.one = config.assign_constant(region, &mut (), offset, &Fr::one())?;
+        Ok(())
+    }
+
+    fn witness(
+        &self,
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        values: &[Fr],
+    ) -> Result<Vec<Limb<Fr>>, Error> {
+        let mut r = vec![];
+        for chunk in values.chunks(5) {
+            let mut v = chunk
+                .iter()
+                .map(|x| Some(Limb::new(None, *x)))
+                .collect::<Vec<_>>();
+            v.resize_with(5, || None);
+            let mut limbs = self.merkle.config.assign_witness(
+                region,
+                &mut (),
+                offset,
+                v.try_into().unwrap(),
+                0,
+            )?;
+            r.append(&mut limbs);
+        }
+        Ok(r)
+    }
+
+    fn assign_bit(
+        &self,
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        bit: bool,
+    ) -> Result<Limb<Fr>, Error> {
+        let b = self.witness(region, offset, &[Fr::from(bit as u64)])?[0].clone();
+        // b * b - b = 0
+        self.merkle.config.assign_line(
+            region,
+            &mut (),
+            offset,
+            [Some(b.clone()), Some(b.clone()), Some(b.clone()), None, None, None],
+            [
+                Some(-Fr::one()),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some(Fr::one()),
+                None,
+            ],
+            0,
+        )?;
+        Ok(b)
+    }
+
+    fn mul(
+        &self,
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        a: &Limb<Fr>,
+        b: &Limb<Fr>,
+    ) -> Result<Limb<Fr>, Error> {
+        let l = self.merkle.config.assign_line(
+            region,
+            &mut (),
+            offset,
+            [
+                Some(a.clone()),
+                None,
+                None,
+                Some(b.clone()),
+                Some(Limb::new(None, a.value * b.value)),
+                None,
+            ],
+            [
+                None,
+                None,
+                None,
+                None,
+                Some(-Fr::one()),
+                None,
+                Some(Fr::one()),
+                None,
+                None,
+            ],
+            0,
+        )?;
+        Ok(l[2].clone())
+    }
+
+    /// The weighted sum of the inputs, folded in lines of at most three terms so that
+    /// every line is constrained on its own
+    fn sum(
+        &self,
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        inputs: Vec<(&Limb<Fr>, Fr)>,
+        constant: Option<Fr>,
+    ) -> Result<Limb<Fr>, Error> {
+        let config = &self.merkle.config;
+        let (head, tail) = inputs.split_at(inputs.len().min(3));
+        let mut acc = config.sum_with_constant(region, &mut (), offset, head.to_vec(), constant)?;
+        for chunk in tail.chunks(2) {
+            let mut terms = vec![(&acc, Fr::one())];
+            terms.extend_from_slice(chunk);
+            let next = config.sum_with_constant(region, &mut (), offset, terms, None)?;
+            acc = next;
+        }
+        Ok(acc)
+    }
+
+    fn select(
+        &self,
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        cond: &Limb<Fr>,
+        f: &Limb<Fr>,
+        t: &Limb<Fr>,
+    ) -> Result<Limb<Fr>, Error> {
+        self.merkle.config.select(region, &mut (), offset, cond, f, t, 0)
+    }
+
+    fn assert_equal(
+        &self,
+        region: &mut Region<Fr>,
+        a: &Limb<Fr>,
+        b: &Limb<Fr>,
+    ) -> Result<(), Error> {
+        assert_eq!(a.value, b.value);
+        region.constrain_equal(a.get_the_cell().cell(), b.get_the_cell().cell())
+    }
+
+    /// 1 if a equals b else 0
+    fn is_equal(
+        &self,
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        a: &Limb<Fr>,
+        b: &Limb<Fr>,
+    ) -> Result<Limb<Fr>, Error> {
+        let diff = self.sum(region, offset, vec![(a, Fr::one()), (b, -Fr::one())], None)?;
+        self.merkle
+            .config
+            .eq_constant(region, &mut (), offset, &diff, &Fr::zero())
+    }
+
+    /// The little endian bits of a value of bits bits
+    fn assign_bits(
+        &self,
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        a: &Limb<Fr>,
+        bits: usize,
+    ) -> Result<Vec<Limb<Fr>>, Error> {
+        let repr = a.value.to_repr();
+        let mut r = vec![];
+        for i in 0..bits {
+            r.push(self.assign_bit(region, offset, (repr[i / 8] >> (i % 8)) & 1 == 1)?);
+        }
+        let sum = self.sum(
+            region,
+            offset,
+            r.iter()
+                .enumerate()
+                .map(|(i, b)| (b, Fr::from_u128(1u128 << i)))
+                .collect(),
+            None,
+        )?;
+        self.assert_equal(region, &sum, a)?;
+        Ok(r)
+    }
+
+    /// The field element of its low and high halves of 16 bytes
+    fn join_halves(
+        &self,
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        lo: &Limb<Fr>,
+        hi: &Limb<Fr>,
+    ) -> Result<Limb<Fr>, Error> {
+        self.sum(
+            region,
+            offset,
+            vec![(lo, Fr::one()), (hi, Fr::from_u128(1u128 << 64) * Fr::from_u128(1u128 << 64))],
+            None,
+        )
+    }
+
+    /// Prove a call of the map: root, the 4 words of the key hash, the two halves of
+    /// the value, the new root and the opcode of the value, with the probed leaves of
+    /// the key hash below root
+    pub fn assign_call(
+        &mut self,
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        args: &[Limb<Fr>; 9],
+        probes: &Vec<KVMapProbe<D>>,
+    ) -> Result<(), Error> {
+        let [root, w0, w1, w2, w3, value_lo, value_hi, new_root, opcode] = args;
+        assert_eq!(probes.len(), MAX_PROBE);
+        let config = self.merkle.config.clone();
+        let is_set = config.eq_constant(region, &mut (), offset, opcode, &Fr::from(KVMapSet as u64))?;
+        let shift = Fr::from_u128(1u128 << 64);
+        let key_lo = self.sum(region, offset, vec![(w0, Fr::one()), (w1, shift)], None)?;
+        let key_hi = self.sum(region, offset, vec![(w2, Fr::one()), (w3, shift)], None)?;
+
+        // the first probed offset is given by the low D bits of the first word
+        let bits = self.assign_bits(region, offset, w0, 64)?;
+        let start = self.sum(
+            region,
+            offset,
+            bits[0..D]
+                .iter()
+                .enumerate()
+                .map(|(i, b)| (b, Fr::from_u128(1u128 << i)))
+                .collect(),
+            None,
+        )?;
+
+        let mut active = self.one.clone();
+        let mut selected: Option<(Vec<Limb<Fr>>, Vec<Limb<Fr>>)> = None;
+        for (j, probe) in probes.iter().enumerate() {
+            // the offset of the leaf wraps around the leaf layer
+            let next = field_to_u64(&start.value) + j as u64;
+            let wrap = self.assign_bit(region, offset, next >> D == 1)?;
+            let address = self.sum(
+                region,
+                offset,
+                vec![(&start, Fr::one()), (&wrap, -Fr::from_u128(1u128 << D))],
+                Some(Fr::from(j as u64)),
+            )?;
+            let mut positions = self.assign_bits(region, offset, &address, D)?;
+            positions.reverse();
+
+            let assist = probe
+                .proof
+                .assist
+                .iter()
+                .map(|x| bytes_to_field(x))
+                .collect::<Vec<_>>();
+            let assist = self.witness(region, offset, &assist)?;
+            let leaf = self.witness(region, offset, &bytes_to_halves(&probe.data))?;
+            let leaf_hash =
+                self.merkle
+                    .hash_data(region, offset, &leaf, &self.one.clone(), &self.zero.clone())?;
+            let leaf_root = self.merkle.hash_path(
+                region,
+                offset,
+                leaf_hash,
+                &positions,
+                &assist,
+                &self.one.clone(),
+            )?;
+            self.assert_equal(region, &leaf_root, root)?;
+
+            // an active leaf holds zero or the hash of its entry
+            let (key, value) = probe.entry.unwrap_or(([0; 32], [0; 32]));
+            let occupied = self.assign_bit(region, offset, probe.entry.is_some())?;
+            let mut entry = bytes_to_halves(&key).to_vec();
+            entry.append(&mut bytes_to_halves(&value).to_vec());
+            let entry = self.witness(region, offset, &entry)?;
+            let entry_hash =
+                self.merkle
+                    .hash_data(region, offset, &entry, &self.one.clone(), &self.zero.clone())?;
+            let leaf_data = self.join_halves(region, offset, &leaf[0], &leaf[1])?;
+            let stored = self.mul(region, offset, &occupied, &entry_hash)?;
+            let diff = self.sum(
+                region,
+                offset,
+                vec![(&leaf_data, Fr::one()), (&stored, -Fr::one())],
+                None,
+            )?;
+            let diff = self.mul(region, offset, &active, &diff)?;
+            self.assert_equal(region, &diff, &self.zero.clone())?;
+
+            // the probe goes on over the entry of another key
+            let eq_lo = self.is_equal(region, offset, &entry[0], &key_lo)?;
+            let eq_hi = self.is_equal(region, offset, &entry[1], &key_hi)?;
+            let same_key = self.mul(region, offset, &eq_lo, &eq_hi)?;
+            let other_key = self.sum(region, offset, vec![(&same_key, -Fr::one())], Some(Fr::one()))?;
+            let skip = self.mul(region, offset, &active, &occupied)?;
+            let skip = self.mul(region, offset, &skip, &other_key)?;
+            let is_final = self.sum(
+                region,
+                offset,
+                vec![(&active, Fr::one()), (&skip, -Fr::one())],
+                None,
+            )?;
+
+            // the leaf, entry and path of the final leaf
+            let mut current = vec![leaf[0].clone(), leaf[1].clone(), occupied];
+            current.append(&mut entry[2..4].to_vec());
+            let mut path = positions;
+            path.append(&mut assist.clone());
+            selected = Some(match selected {
+                None => (current, path),
+                Some((leaf_acc, path_acc)) => {
+                    let mut r = vec![];
+                    for (acc, x) in leaf_acc.iter().zip(current.iter()) {
+                        r.push(self.select(region, offset, &is_final, acc, x)?);
+                    }
+                    let mut p = vec![];
+                    for (acc, x) in path_acc.iter().zip(path.iter()) {
+                        p.push(self.select(region, offset, &is_final, acc, x)?);
+                    }
+                    (r, p)
+                }
+            });
+            active = skip;
+        }
+        // the probe stops within MAX_PROBE leaves
+        self.assert_equal(region, &active, &self.zero.clone())?;
+        let (leaf, path) = selected.unwrap();
+        let (leaf_lo, leaf_hi, occupied) = (&leaf[0], &leaf[1], &leaf[2]);
+        let (stored_lo, stored_hi) = (&leaf[3], &leaf[4]);
+
+        // a get returns the stored value or zero
+        let is_get = self.sum(region, offset, vec![(&is_set, -Fr::one())], Some(Fr::one()))?;
+        for (value, stored) in [(value_lo, stored_lo), (value_hi, stored_hi)] {
+            let found = self.mul(region, offset, occupied, stored)?;
+            let diff = self.sum(
+                region,
+                offset,
+                vec![(value, Fr::one()), (&found, -Fr::one())],
+                None,
+            )?;
+            let diff = self.mul(region, offset, &is_get, &diff)?;
+            self.assert_equal(region, &diff, &self.zero.clone())?;
+        }
+
+        // a set writes the hash of the entry of the key to the final leaf
+        let entry_hash = self.merkle.hash_data(
+            region,
+            offset,
+            &[key_lo, key_hi, value_lo.clone(), value_hi.clone()],
+            &self.one.clone(),
+            &self.zero.clone(),
+        )?;
+        let halves = self.witness(region, offset, &bytes_to_halves(&entry_hash.value.to_repr()))?;
+        let joined = self.join_halves(region, offset, &halves[0], &halves[1])?;
+        self.assert_equal(region, &joined, &entry_hash)?;
+        let new_lo = self.select(region, offset, &is_set, leaf_lo, &halves[0])?;
+        let new_hi = self.select(region, offset, &is_set, leaf_hi, &halves[1])?;
+        let leaf_hash = self.merkle.hash_data(
+            region,
+            offset,
+            &[new_lo, new_hi],
+            &self.one.clone(),
+            &self.zero.clone(),
+        )?;
+        let root = self.merkle.hash_path(
+            region,
+            offset,
+            leaf_hash,
+            &path[0..D],
+            &path[D..2 * D],
+            &self.one.clone(),
+        )?;
+        self.assert_equal(region, &root, new_root)?;
+        Ok(())
+    }
+}
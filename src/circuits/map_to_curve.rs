@@ -485,6 +485,144 @@ fn isogeny_map_in_context(
     (x, y)
 }
 
+// An affine point of E2 in the computation trace, whose coordinates are
+// elements of Fq2.  The formulas below are incomplete: they assume that the
+// points are not the identity and that the points added are neither equal
+// nor opposite, which holds with overwhelming probability for hashed inputs.
+type AssignedFq2Point = (AssignedFq2<Fq, Fr>, AssignedFq2<Fq, Fr>);
+
+fn g2_add_in_context(
+    gseccc: &mut GeneralScalarEccContext<G1Affine, Fr>,
+    a: &AssignedFq2Point,
+    b: &AssignedFq2Point,
+) -> AssignedFq2Point {
+    // lambda = (y_b - y_a) / (x_b - x_a)
+    let dy: AssignedFq2<Fq, Fr> = gseccc.fq2_sub(&b.1, &a.1);
+    let dx: AssignedFq2<Fq, Fr> = gseccc.fq2_sub(&b.0, &a.0);
+    let dx_inverse: AssignedFq2<Fq, Fr> = gseccc.fq2_unsafe_invert(&dx);
+    let lambda: AssignedFq2<Fq, Fr> = gseccc.fq2_mul(&dy, &dx_inverse);
+    g2_add_with_slope(gseccc, a, &b.0, &lambda)
+}
+
+fn g2_double_in_context(
+    gseccc: &mut GeneralScalarEccContext<G1Affine, Fr>,
+    a: &AssignedFq2Point,
+) -> AssignedFq2Point {
+    // lambda = 3 * x^2 / (2 * y)
+    let x_squared: AssignedFq2<Fq, Fr> = gseccc.fq2_square(&a.0);
+    let two_x_squared: AssignedFq2<Fq, Fr> = gseccc.fq2_add(&x_squared, &x_squared);
+    let three_x_squared: AssignedFq2<Fq, Fr> = gseccc.fq2_add(&two_x_squared, &x_squared);
+    let two_y: AssignedFq2<Fq, Fr> = gseccc.fq2_add(&a.1, &a.1);
+    let two_y_inverse: AssignedFq2<Fq, Fr> = gseccc.fq2_unsafe_invert(&two_y);
+    let lambda: AssignedFq2<Fq, Fr> = gseccc.fq2_mul(&three_x_squared, &two_y_inverse);
+    g2_add_with_slope(gseccc, a, &a.0, &lambda)
+}
+
+// x_c = lambda^2 - x_a - x_b, y_c = lambda * (x_a - x_c) - y_a
+fn g2_add_with_slope(
+    gseccc: &mut GeneralScalarEccContext<G1Affine, Fr>,
+    a: &AssignedFq2Point,
+    x_b: &AssignedFq2<Fq, Fr>,
+    lambda: &AssignedFq2<Fq, Fr>,
+) -> AssignedFq2Point {
+    let lambda_squared: AssignedFq2<Fq, Fr> = gseccc.fq2_square(lambda);
+    let x: AssignedFq2<Fq, Fr> = gseccc.fq2_sub(&lambda_squared, &a.0);
+    let x: AssignedFq2<Fq, Fr> = gseccc.fq2_sub(&x, x_b);
+    let x_a_minus_x: AssignedFq2<Fq, Fr> = gseccc.fq2_sub(&a.0, &x);
+    let y: AssignedFq2<Fq, Fr> = gseccc.fq2_mul(lambda, &x_a_minus_x);
+    let y: AssignedFq2<Fq, Fr> = gseccc.fq2_sub(&y, &a.1);
+    (x, y)
+}
+
+fn g2_neg_in_context(
+    gseccc: &mut GeneralScalarEccContext<G1Affine, Fr>,
+    a: &AssignedFq2Point,
+) -> AssignedFq2Point {
+    (a.0.clone(), gseccc.fq2_neg(&a.1))
+}
+
+// Multiplies by the BLS parameter x = -0xd201000000010000 with the
+// square-and-multiply algorithm on the constant |x|.
+fn g2_mul_by_x_in_context(
+    gseccc: &mut GeneralScalarEccContext<G1Affine, Fr>,
+    a: &AssignedFq2Point,
+) -> AssignedFq2Point {
+    let abs_x: u64 = 0xd201000000010000;
+    let mut r = a.clone();
+    for i in (0..63).rev() {
+        r = g2_double_in_context(gseccc, &r);
+        if (abs_x >> i) & 1 == 1 {
+            r = g2_add_in_context(gseccc, &r, a);
+        }
+    }
+    g2_neg_in_context(gseccc, &r)
+}
+
+// psi(x, y) = (conj(x) * c_x, conj(y) * c_y), the untwist-Frobenius-twist
+// endomorphism, where c_x = 1 / (1 + i)^((p - 1) / 3) and c_y = 1 / (1 + i)^((p - 1) / 2).
+fn psi_in_context(
+    gseccc: &mut GeneralScalarEccContext<G1Affine, Fr>,
+    a: &AssignedFq2Point,
+) -> AssignedFq2Point {
+    let c_x_im_bn = BigUint::from_str("4002409555221667392624310435006688643935503118305586438271171395842971157480381377015405980053539358417135540939437").unwrap();
+    let c_x_im_fq: Fq = bn_to_field(&c_x_im_bn);
+    let c_x: AssignedFq2<Fq, Fr> = gseccc.fq2_assign_constant((Fq::zero(), c_x_im_fq));
+
+    let c_y_re_bn = BigUint::from_str("2973677408986561043442465346520108879172042883009249989176415018091420807192182638567116318576472649347015917690530").unwrap();
+    let c_y_im_bn = BigUint::from_str("1028732146235106349975324479215795277384839936929757896155643118032610843298655225875571310552543014690878354869257").unwrap();
+    let c_y_re_fq: Fq = bn_to_field(&c_y_re_bn);
+    let c_y_im_fq: Fq = bn_to_field(&c_y_im_bn);
+    let c_y: AssignedFq2<Fq, Fr> = gseccc.fq2_assign_constant((c_y_re_fq, c_y_im_fq));
+
+    let zero = gseccc.base_integer_ctx.assign_int_constant(Fq::zero());
+    let x_conjugate: AssignedFq2<Fq, Fr> =
+        (a.0 .0.clone(), gseccc.base_integer_ctx.int_sub(&zero, &a.0 .1));
+    let y_conjugate: AssignedFq2<Fq, Fr> =
+        (a.1 .0.clone(), gseccc.base_integer_ctx.int_sub(&zero, &a.1 .1));
+    (
+        gseccc.fq2_mul(&x_conjugate, &c_x),
+        gseccc.fq2_mul(&y_conjugate, &c_y),
+    )
+}
+
+// h_eff * P = [x^2 - x - 1]P + [x - 1]psi(P) + psi^2(2P), following the
+// straight-line program of clear_cofactor in the reference document.
+fn clear_cofactor_in_context(
+    gseccc: &mut GeneralScalarEccContext<G1Affine, Fr>,
+    p: &AssignedFq2Point,
+) -> AssignedFq2Point {
+    let t1 = g2_mul_by_x_in_context(gseccc, p);
+    let mut t2 = psi_in_context(gseccc, p);
+    let mut t3 = g2_double_in_context(gseccc, p);
+    t3 = psi_in_context(gseccc, &t3);
+    t3 = psi_in_context(gseccc, &t3);
+    let minus_t2 = g2_neg_in_context(gseccc, &t2);
+    t3 = g2_add_in_context(gseccc, &t3, &minus_t2);
+    t2 = g2_add_in_context(gseccc, &t1, &t2);
+    t2 = g2_mul_by_x_in_context(gseccc, &t2);
+    t3 = g2_add_in_context(gseccc, &t3, &t2);
+    let minus_t1 = g2_neg_in_context(gseccc, &t1);
+    t3 = g2_add_in_context(gseccc, &t3, &minus_t1);
+    let minus_p = g2_neg_in_context(gseccc, p);
+    g2_add_in_context(gseccc, &t3, &minus_p)
+}
+
+/// Constrains the output to be hash_to_curve of the reference document
+/// for the two elements u = hash_to_field(msg, 2) of Fq2.  The
+/// hash_to_field step (expand_message_xmd with SHA-256) is not part of
+/// the circuit.
+pub fn hash_to_g2_in_context(
+    gseccc: &mut GeneralScalarEccContext<G1Affine, Fr>,
+    u: &[AssignedFq2<Fq, Fr>; 2],
+) -> AssignedFq2Point {
+    let (x0_prime, y0_prime) = simplified_swu_in_context(gseccc, &u[0]);
+    let q0 = isogeny_map_in_context(gseccc, &x0_prime, &y0_prime);
+    let (x1_prime, y1_prime) = simplified_swu_in_context(gseccc, &u[1]);
+    let q1 = isogeny_map_in_context(gseccc, &x1_prime, &y1_prime);
+    let r = g2_add_in_context(gseccc, &q0, &q1);
+    clear_cofactor_in_context(gseccc, &r)
+}
+
 #[test]
 fn simplified_swu_outputs_correct_test_vector() {
     let mut gseccc =
@@ -570,3 +708,39 @@ fn isogeny_map_outputs_correct_test_vector() {
     assert_eq!(should_be_y_re_fq, y_re_fq);
     assert_eq!(should_be_y_im_fq, y_im_fq);
 }
+
+#[test]
+fn hash_to_g2_outputs_correct_test_vector() {
+    let mut gseccc =
+        GeneralScalarEccContext::<G1Affine, Fr>::new(Rc::new(RefCell::new(Context::new())));
+
+    // From the reference document's test vectors for msg = "".
+    let u0_re_bn = BigUint::from_str("593868448310005448561172252387029516360409945786457439875974315031640021389835649561235021338510064922970633805048").unwrap();
+    let u0_im_bn = BigUint::from_str("867375309489067512797459860887365951877054038763818448057326190302701649888849997836339069389536967202878289851290").unwrap();
+    let u1_re_bn = BigUint::from_str("457889704519948843474026022562641969443315715595459159112874498082953431971323809145630315884223143822925947137684").unwrap();
+    let u1_im_bn = BigUint::from_str("3132697209754082586339430915081913810572071485832539443682634025529375380328136128542015469873094481703191673087029").unwrap();
+    let u: [AssignedFq2<Fq, Fr>; 2] = [
+        (
+            gseccc.base_integer_ctx.assign_w(&u0_re_bn),
+            gseccc.base_integer_ctx.assign_w(&u0_im_bn),
+        ),
+        (
+            gseccc.base_integer_ctx.assign_w(&u1_re_bn),
+            gseccc.base_integer_ctx.assign_w(&u1_im_bn),
+        ),
+    ];
+
+    let (x, y) = hash_to_g2_in_context(&mut gseccc, &u);
+
+    let should_be = [
+        "193548053368451749411421515628510806626565736652086807419354395577367693778571452628423727082668900187036482254730",
+        "891930009643099423308102777951250899694559203647724988361022851024990473423938537113948850338098230396747396259901",
+        "771717272055834152378281705972671257005357145478800908373659404991537354153455452961747174765859335819766715637138",
+        "2810310118582126634041133454180705304393079139103252956502404531123692847658283858246402311867775854528543237781718",
+    ];
+    let coordinates = [&x.0, &x.1, &y.0, &y.1];
+    for (c, should_be_bn) in coordinates.iter().zip(should_be.iter()) {
+        let should_be_fq: Fq = bn_to_field(&BigUint::from_str(should_be_bn).unwrap());
+        assert_eq!(should_be_fq, gseccc.base_integer_ctx.get_w(c));
+    }
+}
@@ -1,5 +1,7 @@
 use crate::circuits::poseidon::PoseidonChip;
 use crate::circuits::poseidon::PoseidonGateConfig;
+#[cfg(feature = "merkle-poseidon2")]
+use crate::circuits::poseidon2::{Poseidon2Chip, Poseidon2GateConfig};
 use crate::circuits::CommonGateConfig;
 use crate::utils::bytes_to_field;
 use crate::utils::field_to_bytes;
@@ -13,6 +15,7 @@ use std::marker::PhantomData;
 
 use crate::circuits::Limb;
 use crate::host::merkle::MerkleProof;
+#[cfg(not(feature = "merkle-poseidon2"))]
 use crate::host::poseidon::MERKLE_HASHER_SPEC;
 use crate::host::poseidon::POSEIDON_HASHER_SPEC;
 use crate::host::ForeignInst::MerkleSet;
@@ -48,11 +51,25 @@ impl<F: FieldExt, const D: usize> MerkleProofState<F, D> {
     }
 }
 
+/* The inner nodes are hashed by poseidon by default, or by the two to one
+ * compression of poseidon2 with the merkle-poseidon2 feature which must match the
+ * hasher of MongoMerkle. The leaf data is always hashed by poseidon.
+ */
+#[cfg(not(feature = "merkle-poseidon2"))]
+pub type MerkleGateConfig = PoseidonGateConfig;
+#[cfg(not(feature = "merkle-poseidon2"))]
+type MerkleHasherChip<F> = PoseidonChip<F, 3, 2>;
+
+#[cfg(feature = "merkle-poseidon2")]
+pub type MerkleGateConfig = Poseidon2GateConfig;
+#[cfg(feature = "merkle-poseidon2")]
+type MerkleHasherChip<F> = Poseidon2Chip<F>;
+
 pub struct MerkleChip<F: FieldExt, const D: usize> {
     pub config: CommonGateConfig,
-    pub extend: PoseidonGateConfig,
+    pub extend: MerkleGateConfig,
     data_hasher_chip: PoseidonChip<F, 9, 8>,
-    merkle_hasher_chip: PoseidonChip<F, 3, 2>,
+    merkle_hasher_chip: MerkleHasherChip<F>,
     state: MerkleProofState<F, D>,
     _marker: PhantomData<F>,
 }
@@ -71,10 +88,16 @@ impl<F: FieldExt, const D: usize> Chip<F> for MerkleChip<F, D> {
 }
 
 impl<const D: usize> MerkleChip<Fr, D> {
-    pub fn new(config: CommonGateConfig, extend: PoseidonGateConfig) -> Self {
+    pub fn new(config: CommonGateConfig, extend: MerkleGateConfig) -> Self {
+        #[cfg(not(feature = "merkle-poseidon2"))]
+        let merkle_hasher_chip = PoseidonChip::construct(config.clone(), extend.clone(), MERKLE_HASHER_SPEC.clone());
+        #[cfg(feature = "merkle-poseidon2")]
+        let merkle_hasher_chip = Poseidon2Chip::construct(config.clone(), extend.clone());
+        // the data hasher has rate 8 thus never enables the permute helper gate
+        let data_extend = PoseidonGateConfig::new(config.witness, config.fixed, config.selector);
         MerkleChip {
-            merkle_hasher_chip: PoseidonChip::construct(config.clone(), extend.clone(), MERKLE_HASHER_SPEC.clone()),
-            data_hasher_chip: PoseidonChip::construct(config.clone(), extend.clone(), POSEIDON_HASHER_SPEC.clone()),
+            merkle_hasher_chip,
+            data_hasher_chip: PoseidonChip::construct(config.clone(), data_extend, POSEIDON_HASHER_SPEC.clone()),
             config,
             extend,
             state: MerkleProofState::default(),
@@ -92,16 +115,82 @@ impl<const D: usize> MerkleChip<Fr, D> {
         region: &mut Region<Fr>,
         offset: &mut usize,
     ) -> Result<(), Error> {
-        self.merkle_hasher_chip.initialize(config, region, offset)?;
-        self.data_hasher_chip.initialize(config, region, offset)
+        // the poseidon state starts its constants from offset 0
+        self.data_hasher_chip.initialize(config, region, offset)?;
+        self.merkle_hasher_chip.initialize(config, region, offset)
     }
 
-    pub fn configure(cs: &mut ConstraintSystem<Fr>, shared_advices: &Vec<Column<Advice>>) -> (CommonGateConfig, PoseidonGateConfig) {
+    pub fn configure(cs: &mut ConstraintSystem<Fr>, shared_advices: &Vec<Column<Advice>>) -> (CommonGateConfig, MerkleGateConfig) {
         let config = CommonGateConfig::configure(cs, &(), shared_advices);
-        let extend = PoseidonGateConfig::configure(cs, &config);
+        let extend = MerkleGateConfig::configure(cs, &config);
         (config, extend)
     }
 
+    #[cfg(not(feature = "merkle-poseidon2"))]
+    fn hash_pair(
+        chip: &mut MerkleHasherChip<Fr>,
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        left: Limb<Fr>,
+        right: Limb<Fr>,
+        reset: &Limb<Fr>,
+    ) -> Result<Limb<Fr>, Error> {
+        chip.get_permute_result(region, offset, &[left, right], reset)
+    }
+
+    #[cfg(feature = "merkle-poseidon2")]
+    fn hash_pair(
+        chip: &mut MerkleHasherChip<Fr>,
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        left: Limb<Fr>,
+        right: Limb<Fr>,
+        _reset: &Limb<Fr>,
+    ) -> Result<Limb<Fr>, Error> {
+        chip.compress(region, offset, &left, &right)
+    }
+
+    /// The hash of a message of at most 7 elements padded with one and zeros, the same
+    /// as update(message) then squeeze() of POSEIDON_HASHER
+    pub(crate) fn hash_data(
+        &mut self,
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        message: &[Limb<Fr>],
+        one: &Limb<Fr>,
+        zero: &Limb<Fr>,
+    ) -> Result<Limb<Fr>, Error> {
+        let mut inputs = message.to_vec();
+        inputs.push(one.clone());
+        inputs.resize(8, zero.clone());
+        self.data_hasher_chip
+            .get_permute_result(region, offset, &inputs.try_into().unwrap(), one)
+    }
+
+    /// The root of the path from the node hash along the big endian positions of its
+    /// address, assist holds the siblings from the top of the tree down
+    pub(crate) fn hash_path(
+        &mut self,
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        hash: Limb<Fr>,
+        positions: &[Limb<Fr>],
+        assist: &[Limb<Fr>],
+        one: &Limb<Fr>,
+    ) -> Result<Limb<Fr>, Error> {
+        let mut acc = hash;
+        for (position, assist) in positions.iter().rev().zip(assist.iter().rev()) {
+            let left = self
+                .config
+                .select(region, &mut (), offset, &position, &acc, &assist, 0)?;
+            let right = self
+                .config
+                .select(region, &mut (), offset, &position, &assist, &acc, 0)?;
+            acc = Self::hash_pair(&mut self.merkle_hasher_chip, region, offset, left, right, one)?;
+        }
+        Ok(acc)
+    }
+
     pub fn assign_proof(
         &mut self,
         region: &mut Region<Fr>,
@@ -163,42 +252,14 @@ impl<const D: usize> MerkleChip<Fr, D> {
             .decompose_limb(region, &mut (), offset, &address, &mut positions, D)?;
 
         // position = 0 means assist is at right else assist is at left
-        let initial_hash = self.data_hasher_chip.get_permute_result(
-            region,
-            offset,
-            &[
-                value[0].clone(),
-                value[1].clone(),
-                self.state.one.clone(),
-                self.state.zero.clone(),
-                self.state.zero.clone(),
-                self.state.zero.clone(),
-                self.state.zero.clone(),
-                self.state.zero.clone(),
-            ],
-            &self.state.one.clone(),
-        )?;
+        let one = self.state.one.clone();
+        let zero = self.state.zero.clone();
+        let initial_hash =
+            self.hash_data(region, offset, &[value[0].clone(), value[1].clone()], &one, &zero)?;
         assert_eq!(field_to_bytes(&initial_hash.value), proof.source);
 
-        let final_hash = positions.iter().rev().zip(compare_assist.iter().rev()).fold(
-            initial_hash,
-            |acc, (position, assist)| {
-                let left = self
-                    .config
-                    .select(region, &mut (), offset, &position, &acc, &assist, 0)
-                    .unwrap();
-                let right = self
-                    .config
-                    .select(region, &mut (), offset, &position, &assist, &acc, 0)
-                    .unwrap();
-                let hash = self
-                    .merkle_hasher_chip
-                    .get_permute_result(region, offset, &[left, right], &self.state.one.clone())
-                    .unwrap();
-                //println!("position check: {} {:?} {:?}", position.value, acc.clone().value, assist.clone().value);
-                hash
-            },
-        );
+        let final_hash =
+            self.hash_path(region, offset, initial_hash, &positions, &compare_assist, &one)?;
 
         let desired_root = self.config.select(region, &mut (), offset, &is_set, root, new_root, 0)?;
         assert_eq!(desired_root.value, final_hash.value);
@@ -1,12 +1,26 @@
 pub mod babyjub;
+pub mod blake2;
 pub mod bls;
 pub mod bn256;
+pub mod ecdsa;
+pub mod eddsa;
+pub mod g1;
+pub mod g2;
+pub mod groth16;
+pub mod gt;
 pub mod host;
+pub mod keccak256;
+pub mod kvmap;
+pub mod nibble;
 pub mod merkle;
 pub mod modexp;
+pub mod pedersen;
 pub mod poseidon;
+pub mod poseidon2;
 pub mod range;
 pub mod rmd160;
+pub mod secp256k1;
+pub mod secp256r1;
 pub mod anemoi;
 pub mod map_to_curve;
 pub mod sgn0;
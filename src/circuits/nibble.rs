@@ -0,0 +1,513 @@
+// Lookup assisted bitwise operations over nibbles for the blake2 chips.
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::Region,
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector, VirtualCells},
+    poly::Rotation,
+};
+
+use crate::constant_from;
+use crate::{
+    customized_circuits, customized_circuits_expand, item_count, table_item, value_for_assign,
+};
+use std::marker::PhantomData;
+
+use crate::utils::GateCell;
+use crate::utils::{field_to_u64, Limb};
+
+/*
+ * A word is kept as a list of nibbles (little endian) and the bitwise operations are
+ * looked up nibble by nibble in a fixed table of (op, x, y, z, out):
+ *   XOR3:  out = x ^ y ^ z
+ *   XOR2:  out = x ^ y (z = 0)
+ *   NIBBLE: out = x (y = z = 0), only a range check of x
+ *   CHI:   out = x ^ (!y & z)
+ *   SPLITm: y = x >> (4 - m), out = (x % 2^(4-m)) << m (z = 0)
+ * Any looked up row also range checks the y column.
+ *
+ * A left rotation by 4q + m is a re-index of nibbles by q followed by a SPLITm of
+ * every nibble and p = out + q where q is the high part of the previous nibble.
+ * A word is composed from its nibbles by acc = acc_n * acc_shift + y.
+ *
+ * The other gates are:
+ *   constant: y = cy
+ *   reset: x = reset ? cy : p with reset in column q
+ *   add: x + y + z = p + q * cy with carry q in [0, 2] and cy = 2^bits, the same
+ *        carry approach as the u32 sums of the rmd160 chip.
+ *
+ * The u32 words of rmd160 are only split into bytes for its sums and rotations and
+ * its boolean functions are not looked up, so the xors of blake2 need this table.
+ */
+#[rustfmt::skip]
+customized_circuits!(NibbleGateConfig, 2, 7, 14, 0,
+    | x   | y   | z   | out | acc   | p   | q   | op  | lookup_sel | acc_sel | acc_shift | rot_sel | const_sel | cy  | reset_sel | add_sel | t_op | t_x | t_y | t_z | t_out
+    | nil | nil | nil | nil | acc_n | nil | nil | nil | nil        | nil     | nil       | nil     | nil       | nil | nil       | nil     | nil  | nil | nil | nil | nil
+);
+
+pub const OP_XOR3: u64 = 1;
+pub const OP_XOR2: u64 = 2;
+pub const OP_NIBBLE: u64 = 3;
+pub const OP_CHI: u64 = 4;
+// the split ops are OP_SPLIT + m for m in 1..4
+const OP_SPLIT: u64 = 4;
+
+pub type Word<F> = Vec<Limb<F>>;
+
+fn nibble_op(op: u64, x: u64, y: u64, z: u64) -> u64 {
+    match op {
+        OP_XOR3 => x ^ y ^ z,
+        OP_XOR2 => x ^ y,
+        OP_NIBBLE => x,
+        OP_CHI => x ^ (!y & z & 0xf),
+        _ => {
+            let m = op - OP_SPLIT;
+            (x % (1 << (4 - m))) << m
+        }
+    }
+}
+
+fn lookup_table() -> Vec<[u64; 5]> {
+    let mut table = vec![];
+    for x in 0..16 {
+        for y in 0..16 {
+            for z in 0..16 {
+                table.push([OP_XOR3, x, y, z, nibble_op(OP_XOR3, x, y, z)]);
+                table.push([OP_CHI, x, y, z, nibble_op(OP_CHI, x, y, z)]);
+            }
+            table.push([OP_XOR2, x, y, 0, nibble_op(OP_XOR2, x, y, 0)]);
+        }
+        table.push([OP_NIBBLE, x, 0, 0, x]);
+        for m in 1..4 {
+            let op = OP_SPLIT + m;
+            table.push([op, x, x >> (4 - m), 0, nibble_op(op, x, 0, 0)]);
+        }
+    }
+    table
+}
+
+/// split the lowest n nibbles of v (little endian)
+pub fn u64_to_nibbles(v: u64, n: usize) -> Vec<u64> {
+    (0..n).map(|i| (v >> (4 * i)) & 0xf).collect()
+}
+
+pub struct NibbleChip<F: FieldExt> {
+    pub config: NibbleGateConfig,
+    pub zero: Limb<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> NibbleChip<F> {
+    pub fn new(config: NibbleGateConfig) -> Self {
+        NibbleChip {
+            config,
+            zero: Limb::new(None, F::zero()),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(cs: &mut ConstraintSystem<F>) -> NibbleGateConfig {
+        let witness = [0; 7].map(|_| cs.advice_column());
+        let fixed = [0; 14].map(|_| cs.fixed_column());
+        let selector = [];
+        witness.map(|x| cs.enable_equality(x));
+
+        let config = NibbleGateConfig::new(witness, fixed, selector);
+
+        cs.lookup_any("nibble ops", |meta| {
+            let sel = config.get_expr(meta, NibbleGateConfig::lookup_sel());
+            let op = config.get_expr(meta, NibbleGateConfig::op());
+            let x = config.get_expr(meta, NibbleGateConfig::x());
+            let y = config.get_expr(meta, NibbleGateConfig::y());
+            let z = config.get_expr(meta, NibbleGateConfig::z());
+            let out = config.get_expr(meta, NibbleGateConfig::out());
+            vec![
+                (
+                    sel.clone() * op,
+                    config.get_expr(meta, NibbleGateConfig::t_op()),
+                ),
+                (
+                    sel.clone() * x,
+                    config.get_expr(meta, NibbleGateConfig::t_x()),
+                ),
+                (
+                    sel.clone() * y,
+                    config.get_expr(meta, NibbleGateConfig::t_y()),
+                ),
+                (
+                    sel.clone() * z,
+                    config.get_expr(meta, NibbleGateConfig::t_z()),
+                ),
+                (sel * out, config.get_expr(meta, NibbleGateConfig::t_out())),
+            ]
+        });
+
+        cs.create_gate("nibble compose word", |meta| {
+            let sel = config.get_expr(meta, NibbleGateConfig::acc_sel());
+            let acc = config.get_expr(meta, NibbleGateConfig::acc());
+            let acc_n = config.get_expr(meta, NibbleGateConfig::acc_n());
+            let shift = config.get_expr(meta, NibbleGateConfig::acc_shift());
+            let y = config.get_expr(meta, NibbleGateConfig::y());
+            vec![sel * (acc - acc_n * shift - y)]
+        });
+
+        cs.create_gate("nibble rotate", |meta| {
+            let sel = config.get_expr(meta, NibbleGateConfig::rot_sel());
+            let out = config.get_expr(meta, NibbleGateConfig::out());
+            let p = config.get_expr(meta, NibbleGateConfig::p());
+            let q = config.get_expr(meta, NibbleGateConfig::q());
+            vec![sel * (p - out - q)]
+        });
+
+        cs.create_gate("nibble constant", |meta| {
+            let sel = config.get_expr(meta, NibbleGateConfig::const_sel());
+            let y = config.get_expr(meta, NibbleGateConfig::y());
+            let cy = config.get_expr(meta, NibbleGateConfig::cy());
+            vec![sel * (y - cy)]
+        });
+
+        cs.create_gate("nibble reset", |meta| {
+            let sel = config.get_expr(meta, NibbleGateConfig::reset_sel());
+            let x = config.get_expr(meta, NibbleGateConfig::x());
+            let p = config.get_expr(meta, NibbleGateConfig::p());
+            let q = config.get_expr(meta, NibbleGateConfig::q());
+            let cy = config.get_expr(meta, NibbleGateConfig::cy());
+            vec![sel * (x - p * (constant_from!(1) - q.clone()) - cy * q)]
+        });
+
+        cs.create_gate("word add with carry", |meta| {
+            let sel = config.get_expr(meta, NibbleGateConfig::add_sel());
+            let x = config.get_expr(meta, NibbleGateConfig::x());
+            let y = config.get_expr(meta, NibbleGateConfig::y());
+            let z = config.get_expr(meta, NibbleGateConfig::z());
+            let p = config.get_expr(meta, NibbleGateConfig::p());
+            let q = config.get_expr(meta, NibbleGateConfig::q());
+            let cy = config.get_expr(meta, NibbleGateConfig::cy());
+            vec![
+                sel.clone() * (x + y + z - p - q.clone() * cy),
+                sel * q.clone() * (q.clone() - constant_from!(1)) * (q - constant_from!(2)),
+            ]
+        });
+
+        config
+    }
+
+    /// assign the lookup table and the zero constant
+    pub fn initialize(&mut self, region: &mut Region<F>, offset: &mut usize) -> Result<(), Error> {
+        let cols = [
+            NibbleGateConfig::t_op(),
+            NibbleGateConfig::t_x(),
+            NibbleGateConfig::t_y(),
+            NibbleGateConfig::t_z(),
+            NibbleGateConfig::t_out(),
+        ];
+        for (i, row) in lookup_table().iter().enumerate() {
+            for (col, v) in cols.iter().zip(row.iter()) {
+                self.config.assign_cell(region, i, col, F::from(*v))?;
+            }
+        }
+        self.zero = self.assign_constant(region, offset, 0)?;
+        Ok(())
+    }
+
+    pub fn zero_word(&self, n: usize) -> Word<F> {
+        vec![self.zero.clone(); n]
+    }
+
+    pub fn assign_constant(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        v: u64,
+    ) -> Result<Limb<F>, Error> {
+        self.config
+            .assign_cell(region, *offset, &NibbleGateConfig::const_sel(), F::one())?;
+        self.config
+            .assign_cell(region, *offset, &NibbleGateConfig::cy(), F::from(v))?;
+        let c = self
+            .config
+            .assign_cell(region, *offset, &NibbleGateConfig::y(), F::from(v))?;
+        *offset += 1;
+        Ok(c)
+    }
+
+    pub fn assign_constant_word(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        v: u64,
+        n: usize,
+    ) -> Result<Word<F>, Error> {
+        u64_to_nibbles(v, n)
+            .into_iter()
+            .map(|x| self.assign_constant(region, offset, x))
+            .collect()
+    }
+
+    /// constrain y at the given row to be the constant v
+    pub fn assign_constant_y(
+        &self,
+        region: &mut Region<F>,
+        row: usize,
+        v: u64,
+    ) -> Result<(), Error> {
+        self.config
+            .assign_cell(region, row, &NibbleGateConfig::const_sel(), F::one())?;
+        self.config
+            .assign_cell(region, row, &NibbleGateConfig::cy(), F::from(v))?;
+        Ok(())
+    }
+
+    /// assign one looked up nibble operation at the given row
+    pub fn assign_op(
+        &self,
+        region: &mut Region<F>,
+        row: usize,
+        op: u64,
+        x: &Limb<F>,
+        y: &Limb<F>,
+        z: &Limb<F>,
+    ) -> Result<[Limb<F>; 4], Error> {
+        let out = nibble_op(
+            op,
+            field_to_u64(&x.value),
+            field_to_u64(&y.value),
+            field_to_u64(&z.value),
+        );
+        self.config
+            .assign_cell(region, row, &NibbleGateConfig::op(), F::from(op))?;
+        self.config
+            .assign_cell(region, row, &NibbleGateConfig::lookup_sel(), F::one())?;
+        let x = self
+            .config
+            .bind_cell(region, row, &NibbleGateConfig::x(), x)?;
+        let y = self
+            .config
+            .bind_cell(region, row, &NibbleGateConfig::y(), y)?;
+        let z = self
+            .config
+            .bind_cell(region, row, &NibbleGateConfig::z(), z)?;
+        let out = self
+            .config
+            .assign_cell(region, row, &NibbleGateConfig::out(), F::from(out))?;
+        Ok([x, y, z, out])
+    }
+
+    pub fn word_op(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        op: u64,
+        x: &[Limb<F>],
+        y: &[Limb<F>],
+        z: &[Limb<F>],
+    ) -> Result<Word<F>, Error> {
+        let mut r = vec![];
+        for i in 0..x.len() {
+            let [_, _, _, out] = self.assign_op(region, *offset, op, &x[i], &y[i], &z[i])?;
+            *offset += 1;
+            r.push(out);
+        }
+        Ok(r)
+    }
+
+    pub fn rotate_left(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        word: &[Limb<F>],
+        r: u32,
+    ) -> Result<Word<F>, Error> {
+        let n = word.len();
+        let shift = (r / 4) as usize;
+        let word = (0..n)
+            .map(|i| word[(i + n - shift) % n].clone())
+            .collect::<Vec<_>>();
+        let m = (r % 4) as u64;
+        if m == 0 {
+            return Ok(word);
+        }
+        let start = *offset;
+        let mut split = vec![];
+        for i in 0..n {
+            split.push(self.assign_op(
+                region,
+                start + i,
+                OP_SPLIT + m,
+                &word[i],
+                &Limb::new(None, F::from(field_to_u64(&word[i].value) >> (4 - m))),
+                &Limb::new(None, F::zero()),
+            )?);
+        }
+        let mut r = vec![];
+        for i in 0..n {
+            let hi = &split[(i + n - 1) % n][1];
+            let out = &split[i][3];
+            self.config
+                .assign_cell(region, start + i, &NibbleGateConfig::rot_sel(), F::one())?;
+            self.config
+                .bind_cell(region, start + i, &NibbleGateConfig::q(), hi)?;
+            r.push(self.config.assign_cell(
+                region,
+                start + i,
+                &NibbleGateConfig::p(),
+                out.value + hi.value,
+            )?);
+        }
+        *offset = start + n;
+        Ok(r)
+    }
+
+    /// compose the nibbles already in column y of the rows start..start+n,
+    /// the composed value is constrained to be equal to value if it is given
+    pub fn assign_compose(
+        &self,
+        region: &mut Region<F>,
+        start: usize,
+        nibbles: &[Limb<F>],
+        value: Option<&Limb<F>>,
+    ) -> Result<Limb<F>, Error> {
+        let n = nibbles.len();
+        let mut acc = F::zero();
+        let mut acc_limb = None;
+        for i in (0..n).rev() {
+            let shift = if i == n - 1 { 0u64 } else { 16u64 };
+            acc = acc * F::from(shift) + nibbles[i].value;
+            self.config
+                .assign_cell(region, start + i, &NibbleGateConfig::acc_sel(), F::one())?;
+            self.config.assign_cell(
+                region,
+                start + i,
+                &NibbleGateConfig::acc_shift(),
+                F::from(shift),
+            )?;
+            acc_limb =
+                Some(
+                    self.config
+                        .assign_cell(region, start + i, &NibbleGateConfig::acc(), acc)?,
+                );
+        }
+        let acc_limb = acc_limb.unwrap();
+        if let Some(value) = value {
+            assert!(acc_limb.value == value.value);
+            if let Some(c) = value.cell.as_ref() {
+                region.constrain_equal(acc_limb.get_the_cell().cell(), c.cell())?;
+            }
+        }
+        Ok(acc_limb)
+    }
+
+    /// compose nibbles that are already range checked
+    pub fn compose(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        nibbles: &[Limb<F>],
+        value: Option<&Limb<F>>,
+    ) -> Result<Limb<F>, Error> {
+        let start = *offset;
+        for (i, nibble) in nibbles.iter().enumerate() {
+            self.config
+                .bind_cell(region, start + i, &NibbleGateConfig::y(), nibble)?;
+        }
+        *offset = start + nibbles.len();
+        self.assign_compose(region, start, nibbles, value)
+    }
+
+    /// decompose value into n range checked nibbles
+    pub fn decompose(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        value: &Limb<F>,
+        n: usize,
+    ) -> Result<Word<F>, Error> {
+        let start = *offset;
+        let nibbles = u64_to_nibbles(field_to_u64(&value.value), n)
+            .into_iter()
+            .map(|x| Limb::new(None, F::from(x)))
+            .collect::<Vec<_>>();
+        let mut r = vec![];
+        for i in 0..n {
+            let [_, y, _, _] = self.assign_op(
+                region,
+                start + i,
+                OP_XOR2,
+                &Limb::new(None, F::zero()),
+                &nibbles[i],
+                &Limb::new(None, F::zero()),
+            )?;
+            r.push(y);
+        }
+        self.assign_compose(region, start, &nibbles, Some(value))?;
+        *offset = start + n;
+        Ok(r)
+    }
+
+    /// fill the reset gate of the given row: x = reset ? init : prev, returns the value of x
+    pub fn assign_reset(
+        &self,
+        region: &mut Region<F>,
+        row: usize,
+        prev: &Limb<F>,
+        reset: &Limb<F>,
+        init: u64,
+    ) -> Result<F, Error> {
+        self.config
+            .assign_cell(region, row, &NibbleGateConfig::reset_sel(), F::one())?;
+        self.config
+            .assign_cell(region, row, &NibbleGateConfig::cy(), F::from(init))?;
+        self.config
+            .bind_cell(region, row, &NibbleGateConfig::p(), prev)?;
+        self.config
+            .bind_cell(region, row, &NibbleGateConfig::q(), reset)?;
+        Ok(if reset.value == F::zero() {
+            prev.value
+        } else {
+            F::from(init)
+        })
+    }
+
+    /// (a + b + c) mod 2^bits, returns the sum and its nibbles
+    pub fn add(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        a: &Limb<F>,
+        b: &Limb<F>,
+        c: Option<&Limb<F>>,
+        bits: usize,
+    ) -> Result<(Limb<F>, Word<F>), Error> {
+        let c = c.unwrap_or(&self.zero);
+        let sum = field_to_u64(&a.value) as u128
+            + field_to_u64(&b.value) as u128
+            + field_to_u64(&c.value) as u128;
+        let modulus = 1u128 << bits;
+        let row = *offset;
+        self.config
+            .assign_cell(region, row, &NibbleGateConfig::add_sel(), F::one())?;
+        self.config
+            .assign_cell(region, row, &NibbleGateConfig::cy(), F::from_u128(modulus))?;
+        self.config
+            .bind_cell(region, row, &NibbleGateConfig::x(), a)?;
+        self.config
+            .bind_cell(region, row, &NibbleGateConfig::y(), b)?;
+        self.config
+            .bind_cell(region, row, &NibbleGateConfig::z(), c)?;
+        self.config.assign_cell(
+            region,
+            row,
+            &NibbleGateConfig::q(),
+            F::from_u128(sum / modulus),
+        )?;
+        let result = self.config.assign_cell(
+            region,
+            row,
+            &NibbleGateConfig::p(),
+            F::from_u128(sum % modulus),
+        )?;
+        *offset += 1;
+        let nibbles = self.decompose(region, offset, &result, bits / 4)?;
+        Ok((result, nibbles))
+    }
+}
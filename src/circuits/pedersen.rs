@@ -0,0 +1,148 @@
+use crate::circuits::babyjub::{host_point_to_field, AltJubChip, Point};
+use crate::circuits::{CommonGateConfig, Limb};
+use crate::host::jubjub::Point as HostPoint;
+use crate::host::pedersen::{
+    HASH_WINDOWS, HASH_WINDOW_BITS, PEDERSEN_G_TABLES, PEDERSEN_H_TABLES, PEDERSEN_HASH_TABLES,
+};
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Chip, Region},
+    plonk::{Advice, Column, ConstraintSystem, Error},
+};
+
+/* Pedersen commitments and the windowed Pedersen hash over the alt BabyJubjub curve.
+ * See host::pedersen for the generators and the native reference.
+ *
+ * The commitment multiplies the fixed generators G and H with
+ * AltJubChip::mul_scalar_fixed. The hash takes the message as 4 u64 words and every
+ * window of 4 bits selects m * P_j from the table of P_j with AltJubChip::assign_window.
+ *
+ * HASH selects which of the two host op families the chip serves.
+ */
+pub struct AltJubPedersenChip<F: FieldExt, const HASH: bool> {
+    pub jub: AltJubChip<F>,
+    state: PedersenState<F>,
+}
+
+pub type AltJubPedersenCommitChip<F> = AltJubPedersenChip<F, false>;
+pub type AltJubPedersenHashChip<F> = AltJubPedersenChip<F, true>;
+
+pub const HASH_WORDS: usize = 4;
+
+pub struct PedersenState<F: FieldExt> {
+    identity: Point<F>,
+}
+
+impl<F: FieldExt, const HASH: bool> Chip<F> for AltJubPedersenChip<F, HASH> {
+    type Config = CommonGateConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.jub.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<F: FieldExt, const HASH: bool> AltJubPedersenChip<F, HASH> {
+    pub fn construct(config: CommonGateConfig) -> Self {
+        let identity = Point {
+            x: Limb::new(None, F::zero()),
+            y: Limb::new(None, F::one()),
+        };
+        let state = PedersenState { identity };
+        AltJubPedersenChip {
+            jub: AltJubChip::new(config),
+            state,
+        }
+    }
+
+    pub fn configure(
+        cs: &mut ConstraintSystem<F>,
+        shared_advices: &Vec<Column<Advice>>,
+    ) -> CommonGateConfig {
+        AltJubChip::<F>::configure(cs, shared_advices)
+    }
+
+    fn assign_host_point(
+        config: &CommonGateConfig,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        p: &HostPoint,
+    ) -> Result<Point<F>, Error> {
+        let (x, y) = host_point_to_field::<F>(p);
+        Ok(Point {
+            x: config.assign_constant(region, &mut (), offset, &x)?,
+            y: config.assign_constant(region, &mut (), offset, &y)?,
+        })
+    }
+
+    pub fn initialize(
+        &mut self,
+        config: &CommonGateConfig,
+        region: &mut Region<F>,
+        offset: &mut usize,
+    ) -> Result<(), Error> {
+        self.jub.initialize(config, region, offset)?;
+        self.state.identity = Self::assign_host_point(config, region, offset, &HostPoint::identity())?;
+        Ok(())
+    }
+
+    fn constrain_point_eq(
+        region: &mut Region<F>,
+        lhs: &Point<F>,
+        rhs: &Point<F>,
+    ) -> Result<(), Error> {
+        assert!(lhs.x.value == rhs.x.value && lhs.y.value == rhs.y.value);
+        region.constrain_equal(lhs.x.get_the_cell().cell(), rhs.x.get_the_cell().cell())?;
+        region.constrain_equal(lhs.y.get_the_cell().cell(), rhs.y.get_the_cell().cell())?;
+        Ok(())
+    }
+
+    /// result = v * G + r * H
+    pub fn assign_commit(
+        &mut self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        v: &Limb<F>,
+        r: &Limb<F>,
+        result: &Point<F>,
+    ) -> Result<(), Error> {
+        let v_g = self.jub.mul_scalar_fixed(region, offset, v, &PEDERSEN_G_TABLES)?;
+        let r_h = self.jub.mul_scalar_fixed(region, offset, r, &PEDERSEN_H_TABLES)?;
+        let commitment = self.jub.add(region, offset, &v_g, &r_h)?;
+        Self::constrain_point_eq(region, &commitment, result)
+    }
+
+    /// result = sum m_j * P_j over the 4 bit windows m_j of the little endian words
+    pub fn assign_hash(
+        &mut self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        words: &[Limb<F>],
+        result: &Point<F>,
+    ) -> Result<(), Error> {
+        assert!(words.len() * 64 == HASH_WINDOWS * HASH_WINDOW_BITS);
+        let mut bits = vec![];
+        for word in words.iter() {
+            let mut word_bits = vec![];
+            self.jub
+                .config
+                .decompose_limb(region, &mut (), offset, word, &mut word_bits, 64)?;
+            // decompose_limb returns the bits from the highest
+            word_bits.reverse();
+            bits.append(&mut word_bits);
+        }
+        let mut acc = self.state.identity.clone();
+        for (j, window) in bits.chunks_exact(HASH_WINDOW_BITS).enumerate() {
+            let p = self
+                .jub
+                .assign_window(region, offset, &PEDERSEN_HASH_TABLES[j], window)?;
+            acc = self.jub.add(region, offset, &acc, &p)?;
+        }
+        Self::constrain_point_eq(region, &acc, result)
+    }
+}
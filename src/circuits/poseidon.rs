@@ -1,6 +1,7 @@
 use crate::host::poseidon::PREFIX_CHALLENGE;
 use crate::host::poseidon::PREFIX_POINT;
 use crate::host::poseidon::PREFIX_SCALAR;
+use crate::host::poseidon::PoseidonFamily;
 use halo2_proofs::arithmetic::FieldExt;
 use poseidon::SparseMDSMatrix;
 use poseidon::Spec;
@@ -30,6 +31,16 @@ pub struct PoseidonState<F: FieldExt, const T: usize> {
     state: [Limb<F>; T],
     default: [Limb<F>; T],
     prefix: Vec<Limb<F>>,
+    family: PoseidonFamily,
+}
+
+/// A call group of the host op table: its merged operand, its opcode and whether
+/// it is enabled (in the table) or a hole padding the sponge rounds
+#[derive(Clone, Debug)]
+pub struct SpongeCall<F: FieldExt> {
+    pub operand: Limb<F>,
+    pub opcode: Limb<F>,
+    pub enable: Limb<F>,
 }
 
 pub struct PoseidonChip<F: FieldExt, const T: usize, const RATE: usize> {
@@ -41,6 +52,12 @@ pub struct PoseidonChip<F: FieldExt, const T: usize, const RATE: usize> {
     _marker: PhantomData<F>,
 }
 
+/// The poseidon chip of the variable-length sponge opcodes, it absorbs the rounds of
+/// the host op table with assign_sponge_round instead of assign_permute
+pub struct PoseidonSpongeChip<F: FieldExt, const T: usize, const RATE: usize>(
+    pub PoseidonChip<F, T, RATE>,
+);
+
 impl PoseidonGateConfig {
     // configured as an extend of CommonGateConfig
     pub fn configure<F:FieldExt>(cs: &mut ConstraintSystem<F>, config: &CommonGateConfig) -> PoseidonGateConfig {
@@ -169,11 +186,21 @@ impl PoseidonGateConfig {
 
 impl<F: FieldExt, const T: usize, const RATE: usize> PoseidonChip<F, T, RATE> {
     pub fn construct(config: CommonGateConfig, extend: PoseidonGateConfig, spec: Spec<F, T, RATE>) -> Self {
+        Self::construct_with_family(config, extend, spec, PoseidonFamily::Sponge)
+    }
+
+    pub fn construct_with_family(
+        config: CommonGateConfig,
+        extend: PoseidonGateConfig,
+        spec: Spec<F, T, RATE>,
+        family: PoseidonFamily,
+    ) -> Self {
         let state = [0u32; T].map(|_| Limb::new(None, F::zero()));
         let state = PoseidonState {
             default: state.clone(),
             state,
             prefix: vec![],
+            family,
         };
 
         PoseidonChip {
@@ -230,7 +257,7 @@ impl<F: FieldExt, const T: usize, const RATE: usize> PoseidonChip<F, T, RATE> {
         self.poseidon_state.state = new_state.try_into().unwrap();
         self.poseidon_state
             .permute(&self.config, &self.extend, &self.spec, region, offset, values)?;
-        Ok(self.poseidon_state.state[1].clone())
+        Ok(self.poseidon_state.state[self.poseidon_state.family.result_index()].clone())
     }
 
     pub fn assign_permute(
@@ -249,6 +276,157 @@ impl<F: FieldExt, const T: usize, const RATE: usize> PoseidonChip<F, T, RATE> {
         )?;
         Ok(())
     }
+
+    /// constrain cond * (a - b) == 0, b is the constant c if it is None
+    fn assert_equal_if(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        cond: &Limb<F>,
+        a: &Limb<F>,
+        b: Option<&Limb<F>>,
+        c: F,
+    ) -> Result<(), Error> {
+        let v = b.map_or(c, |b| b.value);
+        self.config.assign_line(
+            region,
+            &mut (),
+            offset,
+            [
+                Some(cond.clone()),
+                b.map(|_| cond.clone()),
+                b.cloned(),
+                Some(a.clone()),
+                None,
+                None,
+            ],
+            [
+                Some(-c),
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some(F::one()),
+                b.map(|_| -F::one()),
+                None,
+            ],
+            0,
+        )?;
+        assert!(cond.value == F::zero() || a.value == v);
+        Ok(())
+    }
+
+    /// Absorb one round of a variable-length sponge given by the calls of the host op
+    /// table, see SpongeCall. The round is padded in circuit: the enabled values are
+    /// a prefix of the round, the first hole after them is 1 and the others are 0.
+    ///
+    /// live and last tell whether the previous round was part of a message and whether
+    /// it was the last round of it. A round is live if it starts a message (new is
+    /// enabled) or continues one, it is the last round of the message if it is live
+    /// and has a hole and only then the result is enabled and bound to the permute
+    /// result. The rounds after the last message are holes only.
+    pub fn assign_sponge_round(
+        &mut self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        new: &SpongeCall<F>,
+        values: &[SpongeCall<F>; RATE],
+        result: &SpongeCall<F>,
+        opcodes: [F; 3],
+        live: &Limb<F>,
+        last: &Limb<F>,
+    ) -> Result<(Limb<F>, Limb<F>), Error> {
+        let [new_op, push_op, finalize_op] = opcodes;
+        // a message can only start after the last round of the previous one
+        let continuing = self.config.sum_with_constant(
+            region,
+            &mut (),
+            offset,
+            vec![(live, F::one()), (last, -F::one())],
+            None,
+        )?;
+        self.assert_equal_if(region, offset, &new.enable, &continuing, None, F::zero())?;
+        self.assert_equal_if(region, offset, &new.enable, &new.opcode, None, new_op)?;
+        let one_minus_enable = self.config.sum_with_constant(
+            region,
+            &mut (),
+            offset,
+            vec![(&new.enable, -F::one())],
+            Some(F::one()),
+        )?;
+        // the restart flag of a hole is 0
+        self.assert_equal_if(region, offset, &one_minus_enable, &new.operand, None, F::zero())?;
+        let live = self.config.sum_with_constant(
+            region,
+            &mut (),
+            offset,
+            vec![(&new.enable, F::one()), (&continuing, F::one())],
+            None,
+        )?;
+
+        let mut prev = live.clone();
+        for value in values.iter() {
+            // enabled values come first: value.enable <= prev
+            self.assert_equal_if(region, offset, &value.enable, &prev, None, F::one())?;
+            self.assert_equal_if(region, offset, &value.enable, &value.opcode, None, push_op)?;
+            // a hole is 1 right after the last enabled value and 0 after that
+            let hole = self.config.sum_with_constant(
+                region,
+                &mut (),
+                offset,
+                vec![(&value.enable, -F::one())],
+                Some(F::one()),
+            )?;
+            self.assert_equal_if(region, offset, &hole, &value.operand, Some(&prev), F::zero())?;
+            prev = value.enable.clone();
+        }
+
+        // the result is enabled if and only if the round is live and padded
+        let finalize = self.config.sum_with_constant(
+            region,
+            &mut (),
+            offset,
+            vec![(&live, F::one()), (&prev, -F::one())],
+            None,
+        )?;
+        self.config.assign_line(
+            region,
+            &mut (),
+            offset,
+            [
+                Some(result.enable.clone()),
+                Some(finalize.clone()),
+                None,
+                None,
+                None,
+                None,
+            ],
+            [
+                Some(F::one()),
+                Some(-F::one()),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            ],
+            0,
+        )?;
+        self.assert_equal_if(region, offset, &finalize, &result.opcode, None, finalize_op)?;
+
+        let inputs: [Limb<F>; RATE] = values
+            .iter()
+            .map(|x| x.operand.clone())
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
+        let r = self.get_permute_result(region, offset, &inputs, &new.operand)?;
+        self.assert_equal_if(region, offset, &finalize, &result.operand, Some(&r), F::zero())?;
+        Ok((live, finalize))
+    }
 }
 
 impl<F: FieldExt, const T: usize> PoseidonState<F, T> {
@@ -261,7 +439,12 @@ impl<F: FieldExt, const T: usize> PoseidonState<F, T> {
         *offset = 0;
         let zero = config.assign_constant(region, &mut (), offset, &F::zero())?;
         let mut state = [0u32; T].map(|_| zero.clone());
-        state[0] = config.assign_constant(region, &mut (), offset, &F::from_u128(1u128 << 64))?;
+        state[0] = match self.family {
+            PoseidonFamily::Sponge => {
+                config.assign_constant(region, &mut (), offset, &F::from_u128(1u128 << 64))?
+            }
+            PoseidonFamily::Circom => zero.clone(),
+        };
         self.default = state.clone();
         self.state = state;
         self.prefix = vec![
@@ -272,7 +455,7 @@ impl<F: FieldExt, const T: usize> PoseidonState<F, T> {
         Ok(())
     }
 
-    fn x_power5_with_constant(
+    pub(crate) fn x_power5_with_constant(
         config: &CommonGateConfig,
         region: &mut Region<F>,
         offset: &mut usize,
@@ -555,7 +738,8 @@ mod tests {
         plonk::{Advice, Circuit, Column, ConstraintSystem, Error},
     };
 
-    use super::{Limb, PoseidonChip};
+    use super::{Limb, PoseidonChip, SpongeCall};
+    use crate::host::ForeignInst;
 
     #[derive(Clone, Debug)]
     pub struct HelperChipConfig {
@@ -746,4 +930,129 @@ mod tests {
         let prover = MockProver::run(16, &test_circuit, vec![]).unwrap();
         assert_eq!(prover.verify(), Ok(()));
     }
+
+    #[derive(Clone, Debug, Default)]
+    struct TestSpongeCircuit {
+        message: Vec<Fr>,
+    }
+
+    fn sponge_opcodes() -> [Fr; 3] {
+        [
+            Fr::from(ForeignInst::PoseidonSpongeNew as u64),
+            Fr::from(ForeignInst::PoseidonSpongePush as u64),
+            Fr::from(ForeignInst::PoseidonSpongeFinalize as u64),
+        ]
+    }
+
+    /// (operand, opcode, enable) of the calls of the padded rounds of the message
+    /// followed by a round of holes
+    fn sponge_calls(message: &Vec<Fr>) -> Vec<Vec<[Fr; 3]>> {
+        let [new_op, push_op, finalize_op] = sponge_opcodes();
+        let mut hasher = crate::host::poseidon::POSEIDON_HASHER.clone();
+        hasher.update(message);
+        let result = hasher.squeeze();
+        let total_rounds = message.len() / 8 + 1;
+        let bit = |b: bool| if b { Fr::one() } else { Fr::zero() };
+        let mut rounds = vec![];
+        for k in 0..total_rounds + 1 {
+            let live = k < total_rounds;
+            let mut calls = vec![[bit(k == 0), new_op, bit(k == 0)]];
+            for j in 0..8 {
+                let e = k * 8 + j;
+                if e < message.len() {
+                    calls.push([message[e], push_op, Fr::one()]);
+                } else {
+                    calls.push([bit(live && e == message.len()), push_op, Fr::zero()]);
+                }
+            }
+            let last = k == total_rounds - 1;
+            calls.push([if last { result } else { Fr::zero() }, finalize_op, bit(last)]);
+            rounds.push(calls);
+        }
+        rounds
+    }
+
+    impl Circuit<Fr> for TestSpongeCircuit {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(cs: &mut ConstraintSystem<Fr>) -> Self::Config {
+            TestCircuit::configure(cs)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let mut poseidonchip = PoseidonChip::<Fr, 9, 8>::construct(
+                config.clone().commonconfig,
+                config.clone().poseidonconfig,
+                POSEIDON_HASHER_SPEC.clone(),
+            );
+            let helperchip = HelperChip::new(config.clone().helperconfig);
+            layouter.assign_region(
+                || "assign poseidon sponge test",
+                |mut region| {
+                    let mut offset = 0;
+                    let mut rounds = vec![];
+                    for calls in sponge_calls(&self.message).iter() {
+                        let mut round = vec![];
+                        for call in calls.iter() {
+                            let cells =
+                                helperchip.assign_inputs(&mut region, &mut offset, &call.to_vec())?;
+                            round.push(SpongeCall {
+                                operand: cells[0].clone(),
+                                opcode: cells[1].clone(),
+                                enable: cells[2].clone(),
+                            });
+                        }
+                        rounds.push(round);
+                    }
+                    offset = 0;
+                    poseidonchip.poseidon_state.initialize(
+                        &config.commonconfig,
+                        &mut region,
+                        &mut offset,
+                    )?;
+                    let zero = config.commonconfig.assign_constant(
+                        &mut region,
+                        &mut (),
+                        &mut offset,
+                        &Fr::zero(),
+                    )?;
+                    let mut live = zero.clone();
+                    let mut last = zero;
+                    for round in rounds.iter() {
+                        (live, last) = poseidonchip.assign_sponge_round(
+                            &mut region,
+                            &mut offset,
+                            &round[0],
+                            &round[1..9].to_vec().try_into().unwrap(),
+                            &round[9],
+                            sponge_opcodes(),
+                            &live,
+                            &last,
+                        )?;
+                    }
+                    Ok(())
+                },
+            )?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_poseidon_sponge_circuit() {
+        for len in [0, 11, 16] {
+            let message = (0..len).map(|x| Fr::from(x as u64 + 1)).collect();
+            let test_circuit = TestSpongeCircuit { message };
+            let prover = MockProver::run(16, &test_circuit, vec![]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+        }
+    }
 }
@@ -0,0 +1,388 @@
+use crate::circuits::poseidon::PoseidonState;
+use crate::circuits::{CommonGateConfig, Limb};
+use crate::host::poseidon2::{INTERNAL_DIAG, POSEIDON2_SPEC, R_F, T};
+use crate::{
+    customized_circuits, customized_circuits_expand, item_count, table_item, value_for_assign,
+};
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::pairing::bn256::Fr;
+
+use crate::utils::GateCell;
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    circuit::Region,
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector, VirtualCells},
+    poly::Rotation,
+};
+
+#[rustfmt::skip]
+customized_circuits!(Poseidon2GateConfig, 2, 5, 13, 0,
+    | x0   | x1    | x2   | x0_2   | x0_5   |  c0  | c1  | c2  | c3  | rc  | cdn | c   | c03  | c12  | lookup_hint | lookup_ind  | common_sel | internal_sel
+    | x0_n | x1_n  | x2_n | nil    | nil    |  nil | nil | nil | nil | nil | nil | nil | nil  | nil  | nil         | nil         |    nil     |      nil
+);
+
+/* Poseidon2 (width 3) on top of CommonGateConfig.
+ *
+ * The external rounds are built from the common gate: the round constants of the
+ * next round are added together with the external matrix, so every full sbox is a
+ * plain x^5. The internal rounds are chained on the extension gate, one row for
+ * each round, since the internal matrix only depends on the sum of the state.
+ *
+ * The extension gate shares the extension selector with PoseidonGateConfig, thus
+ * they can not be configured on the same CommonGateConfig.
+ */
+pub struct Poseidon2Chip<F: FieldExt> {
+    pub config: CommonGateConfig,
+    pub extend: Poseidon2GateConfig,
+    zero: Limb<F>,
+    _marker: PhantomData<F>,
+}
+
+impl Poseidon2GateConfig {
+    // configured as an extend of CommonGateConfig
+    pub fn configure<F: FieldExt>(
+        cs: &mut ConstraintSystem<F>,
+        config: &CommonGateConfig,
+    ) -> Poseidon2GateConfig {
+        let extend = Poseidon2GateConfig {
+            fixed: config.fixed,
+            selector: config.selector,
+            witness: config.witness,
+        };
+
+        cs.create_gate("poseidon2 internal round constraint", |meta| {
+            let x0 = config.get_expr(meta, Self::x0());
+            let x1 = config.get_expr(meta, Self::x1());
+            let x2 = config.get_expr(meta, Self::x2());
+            let x0_2 = config.get_expr(meta, Self::x0_2());
+            let x0_5 = config.get_expr(meta, Self::x0_5());
+
+            let x0_next = config.get_expr(meta, Self::x0_n());
+            let x1_next = config.get_expr(meta, Self::x1_n());
+            let x2_next = config.get_expr(meta, Self::x2_n());
+
+            let rc = config.get_expr(meta, Self::rc());
+            let sel = config.get_expr(meta, Self::internal_sel());
+
+            let x0_c = x0 + rc;
+            let sum = x0_5.clone() + x1.clone() + x2.clone();
+            let d = INTERNAL_DIAG.map(|d| Expression::Constant(F::from(d)));
+
+            vec![
+                sel.clone() * (x0_c.clone() * x0_c.clone() - x0_2.clone()),
+                sel.clone() * (x0_2.clone() * x0_2 * x0_c - x0_5.clone()),
+                sel.clone() * (x0_next - (sum.clone() + x0_5 * d[0].clone())),
+                sel.clone() * (x1_next - (sum.clone() + x1 * d[1].clone())),
+                sel * (x2_next - (sum + x2 * d[2].clone())),
+            ]
+        });
+
+        extend
+    }
+
+    fn assign_internal_line<F: FieldExt>(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        state: &mut [Limb<F>; T],
+        rc: F,
+        is_first_line: bool,
+        is_last_line: bool,
+    ) -> Result<(), Error> {
+        let x = [Self::x0(), Self::x1(), Self::x2()];
+
+        self.assign_cell(region, *offset, &Self::internal_sel(), F::one())?;
+        self.assign_cell(region, *offset, &Self::rc(), rc)?;
+
+        // only assign state at the 1st line of the internal rounds
+        if is_first_line {
+            for i in 0..T {
+                let x = self.assign_cell(region, *offset, &x[i], state[i].value)?;
+                region.constrain_equal(x.get_the_cell().cell(), state[i].get_the_cell().cell())?;
+            }
+        }
+
+        let x0_c = state[0].value + rc;
+        let x0_2 = self.assign_cell(region, *offset, &Self::x0_2(), x0_c.square())?;
+        let x0_5 = self.assign_cell(region, *offset, &Self::x0_5(), x0_2.value.square() * x0_c)?;
+
+        let sum = x0_5.value + state[1].value + state[2].value;
+        let inputs = [x0_5.value, state[1].value, state[2].value];
+        for i in 0..T {
+            state[i] = self.assign_cell(
+                region,
+                *offset + 1,
+                &x[i],
+                sum + inputs[i] * F::from(INTERNAL_DIAG[i]),
+            )?;
+        }
+
+        if is_last_line {
+            *offset = *offset + 2;
+        } else {
+            *offset = *offset + 1;
+        }
+
+        Ok(())
+    }
+}
+
+impl<F: FieldExt> Poseidon2Chip<F> {
+    pub fn construct(config: CommonGateConfig, extend: Poseidon2GateConfig) -> Self {
+        Poseidon2Chip {
+            config,
+            extend,
+            zero: Limb::new(None, F::zero()),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        cs: &mut ConstraintSystem<F>,
+        shared_advices: &Vec<Column<Advice>>,
+    ) -> (CommonGateConfig, Poseidon2GateConfig) {
+        let config = CommonGateConfig::configure(cs, &(), shared_advices);
+        let extend = Poseidon2GateConfig::configure(cs, &config);
+        (config, extend)
+    }
+
+    pub fn initialize(
+        &mut self,
+        config: &CommonGateConfig,
+        region: &mut Region<F>,
+        offset: &mut usize,
+    ) -> Result<(), Error> {
+        self.zero = config.assign_constant(region, &mut (), offset, &F::zero())?;
+        Ok(())
+    }
+
+    /// state_i = sum(state) + state_i + constants_i
+    fn apply_external(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        state: &mut [Limb<F>; T],
+        constants: &[F; T],
+    ) -> Result<(), Error> {
+        let res = (0..T)
+            .map(|i| {
+                let a = state
+                    .iter()
+                    .enumerate()
+                    .map(|(j, x)| (x, if i == j { F::from(2u64) } else { F::one() }))
+                    .collect::<Vec<_>>();
+                self.config
+                    .sum_with_constant(region, &mut (), offset, a, Some(constants[i]))
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        *state = res.try_into().unwrap();
+        Ok(())
+    }
+
+    fn sbox_full(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        state: &mut [Limb<F>; T],
+    ) -> Result<(), Error> {
+        for x in state.iter_mut() {
+            *x = PoseidonState::<F, T>::x_power5_with_constant(
+                &self.config,
+                region,
+                offset,
+                x,
+                F::zero(),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Apply the external rounds with the constants, the constants of every round
+    /// are added to the state by the previous external matrix
+    fn external_rounds(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        state: &mut [Limb<F>; T],
+        constants: &[[F; T]],
+    ) -> Result<(), Error> {
+        for next in constants.iter().skip(1) {
+            self.sbox_full(region, offset, state)?;
+            self.apply_external(region, offset, state, next)?;
+        }
+        self.sbox_full(region, offset, state)?;
+        self.apply_external(region, offset, state, &[F::zero(); T])
+    }
+
+    pub fn permute(
+        &self,
+        region: &mut Region<F>,
+        offset: &mut usize,
+        state: &mut [Limb<F>; T],
+        external: &[[F; T]],
+        internal: &[F],
+    ) -> Result<(), Error> {
+        let (start, end) = external.split_at(R_F / 2);
+        self.apply_external(region, offset, state, &start[0])?;
+        self.external_rounds(region, offset, state, start)?;
+
+        for (idx, rc) in internal.iter().enumerate() {
+            self.extend.assign_internal_line(
+                region,
+                offset,
+                state,
+                *rc,
+                idx == 0,
+                idx == internal.len() - 1,
+            )?;
+        }
+
+        for (x, c) in state.iter_mut().zip(end[0].iter()) {
+            *x = self.config.sum_with_constant(
+                region,
+                &mut (),
+                offset,
+                vec![(x, F::one())],
+                Some(*c),
+            )?;
+        }
+        self.external_rounds(region, offset, state, end)
+    }
+}
+
+impl Poseidon2Chip<Fr> {
+    /// The two to one compression of host::poseidon2::poseidon2_compress
+    pub fn compress(
+        &self,
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        a: &Limb<Fr>,
+        b: &Limb<Fr>,
+    ) -> Result<Limb<Fr>, Error> {
+        let mut state = [a.clone(), b.clone(), self.zero.clone()];
+        self.permute(
+            region,
+            offset,
+            &mut state,
+            &POSEIDON2_SPEC.external,
+            &POSEIDON2_SPEC.internal,
+        )?;
+        Ok(state[0].clone())
+    }
+
+    pub fn assign_compress(
+        &self,
+        region: &mut Region<Fr>,
+        offset: &mut usize,
+        a: &Limb<Fr>,
+        b: &Limb<Fr>,
+        result: &Limb<Fr>,
+    ) -> Result<(), Error> {
+        let r = self.compress(region, offset, a, b)?;
+        assert!(r.value == result.value);
+        region.constrain_equal(
+            result.cell.as_ref().unwrap().cell(),
+            r.cell.as_ref().unwrap().cell(),
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::circuits::poseidon2::{Poseidon2Chip, Poseidon2GateConfig};
+    use crate::circuits::CommonGateConfig;
+    use crate::host::poseidon2::{poseidon2_compress, POSEIDON2_SPEC};
+    use crate::utils::Limb;
+    use crate::value_for_assign;
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pairing::bn256::Fr;
+
+    use halo2_proofs::{
+        circuit::{Layouter, Region, SimpleFloorPlanner},
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error},
+    };
+
+    #[derive(Clone, Debug, Default)]
+    struct TestCircuit {
+        inputs: [Fr; 2],
+        result: Fr,
+    }
+
+    #[derive(Clone, Debug)]
+    struct TestConfig {
+        poseidon2config: Poseidon2GateConfig,
+        commonconfig: CommonGateConfig,
+        limb: Column<Advice>,
+    }
+
+    fn assign_limb(
+        region: &mut Region<Fr>,
+        limb: Column<Advice>,
+        offset: &mut usize,
+        v: Fr,
+    ) -> Result<Limb<Fr>, Error> {
+        let c = region.assign_advice(|| "assign input", limb, *offset, || value_for_assign!(v))?;
+        *offset += 1;
+        Ok(Limb::new(Some(c), v))
+    }
+
+    impl Circuit<Fr> for TestCircuit {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(cs: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let witness = (0..5).map(|_| cs.advice_column()).collect::<Vec<_>>();
+            let (commonconfig, poseidon2config) = Poseidon2Chip::<Fr>::configure(cs, &witness);
+            let limb = cs.advice_column();
+            cs.enable_equality(limb);
+            Self::Config {
+                commonconfig,
+                poseidon2config,
+                limb,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let mut chip = Poseidon2Chip::<Fr>::construct(
+                config.commonconfig.clone(),
+                config.poseidon2config.clone(),
+            );
+            layouter.assign_region(
+                || "assign poseidon2 test",
+                |mut region| {
+                    let mut offset = 0;
+                    let a = assign_limb(&mut region, config.limb, &mut offset, self.inputs[0])?;
+                    let b = assign_limb(&mut region, config.limb, &mut offset, self.inputs[1])?;
+                    let result = assign_limb(&mut region, config.limb, &mut offset, self.result)?;
+                    offset = 0;
+                    chip.initialize(&config.commonconfig, &mut region, &mut offset)?;
+                    chip.assign_compress(&mut region, &mut offset, &a, &b, &result)?;
+                    // chained compressions share the initialized zero
+                    chip.compress(&mut region, &mut offset, &result, &a)?;
+                    Ok(())
+                },
+            )?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_poseidon2_circuit() {
+        let inputs = [Fr::from(1u64), Fr::from(2u64)];
+        let result = poseidon2_compress(&POSEIDON2_SPEC, inputs[0], inputs[1]);
+        let test_circuit = TestCircuit { inputs, result };
+        let prover = MockProver::run(12, &test_circuit, vec![]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+}
@@ -0,0 +1,498 @@
+use ark_std::{end_timer, start_timer};
+use halo2_proofs::pairing::bn256::Fr;
+use halo2_proofs::pairing::secp256k1::Fq as Scalar;
+use halo2_proofs::{
+    arithmetic::{CurveAffine, FieldExt},
+    circuit::{AssignedCell, Chip, Layouter, Region},
+    pairing::group::Curve,
+    pairing::secp256k1::Secp256k1Affine,
+    plonk::{ConstraintSystem, Error},
+};
+use halo2ecc_s::circuit::base_chip::BaseChipOps;
+use halo2ecc_s::circuit::ecc_chip::{EccChipBaseOps, EccChipScalarOps};
+use halo2ecc_s::circuit::integer_chip::IntegerChipOps;
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use halo2_proofs::pairing::secp256k1::Fp as Secp256k1Fp;
+use halo2ecc_s::assign::{
+    AssignedCondition, AssignedFq, AssignedInteger, AssignedPoint,
+};
+
+use halo2ecc_s::{
+    circuit::{
+        base_chip::{BaseChip, BaseChipConfig},
+        range_chip::{RangeChip, RangeChipConfig},
+        select_chip::{SelectChip, SelectChipConfig},
+    },
+    context::{Context, GeneralScalarEccContext},
+};
+
+use crate::circuits::ecdsa::{
+    assigned_cells_to_int, enable_int_permute, enable_value_permute, fr_to_bn,
+};
+use crate::utils::{field_to_bn, Limb};
+
+/* Both the base field and the scalar field of secp256k1 are 256 bits wide and are
+ * passed in as 3 limbs of 108 bits, the same way as the base field of bn254.
+ *
+ * ECDSA (msg, r, s, pk) is checked by computing R = msg/s * G + r/s * pk and
+ * comparing R.x with r. Signatures whose R.x is not smaller than the group order
+ * are rejected, see host::secp256k1.
+ *
+ * The key of a recovery is computed as pk = s/r * R - msg/r * G from the point R
+ * with R.x = r and the parity v of R.y. A recovery fails if r or s is zero, if r is
+ * not the x coordinate of a point or if pk is the identity, in which case the result
+ * is zero and the key is the identity with zero coordinates.
+ */
+
+#[derive(Clone, Debug)]
+pub struct Secp256k1ChipConfig {
+    base_chip_config: BaseChipConfig,
+    range_chip_config: RangeChipConfig,
+    point_select_chip_config: SelectChipConfig,
+}
+
+pub struct Secp256k1VerifyChip<N: FieldExt> {
+    config: Secp256k1ChipConfig,
+    base_chip: BaseChip<N>,
+    pub range_chip: RangeChip<N>,
+    point_select_chip: SelectChip<N>,
+    _marker: PhantomData<N>,
+}
+
+impl<N: FieldExt> Chip<N> for Secp256k1VerifyChip<N> {
+    type Config = Secp256k1ChipConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+pub struct Secp256k1RecoverChip<N: FieldExt> {
+    config: Secp256k1ChipConfig,
+    base_chip: BaseChip<N>,
+    pub range_chip: RangeChip<N>,
+    point_select_chip: SelectChip<N>,
+    _marker: PhantomData<N>,
+}
+
+impl<N: FieldExt> Chip<N> for Secp256k1RecoverChip<N> {
+    type Config = Secp256k1ChipConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+fn get_scalar_from_cells(
+    ctx: &mut GeneralScalarEccContext<Secp256k1Affine, Fr>,
+    a: &Vec<Limb<Fr>>, //Fq (3)
+) -> AssignedInteger<Scalar, Fr> {
+    let bn = assigned_cells_to_int(a, 0);
+    ctx.scalar_integer_ctx.assign_w(&bn)
+}
+
+fn get_fp_from_cells(
+    ctx: &mut GeneralScalarEccContext<Secp256k1Affine, Fr>,
+    a: &Vec<Limb<Fr>>, //Fp (3)
+) -> AssignedFq<Secp256k1Fp, Fr> {
+    let bn = assigned_cells_to_int(a, 0);
+    ctx.base_integer_ctx.assign_w(&bn)
+}
+
+/// The point with its identity flag constrained to be a bit and, unless it is the
+/// identity, on y^2 = x^3 + 7. The curve has no cofactor, so it is also in the group.
+fn get_point_from_cells(
+    ctx: &mut GeneralScalarEccContext<Secp256k1Affine, Fr>,
+    a: &Vec<Limb<Fr>>, //Point (3 * 2 + 1)
+) -> AssignedPoint<Secp256k1Affine, Fr> {
+    let x_bn = assigned_cells_to_int(a, 0);
+    let y_bn = assigned_cells_to_int(a, 3);
+    let x = ctx.base_integer_chip().assign_w(&x_bn);
+    let y = ctx.base_integer_chip().assign_w(&y_bn);
+    let z = ctx.native_ctx.borrow_mut().assign_bit(a[6].value);
+    let p = AssignedPoint::new(x, y, z);
+
+    let b = ctx.base_integer_chip().assign_int_constant(Secp256k1Fp::from(7u64));
+    let y2 = ctx.base_integer_chip().int_square(&p.y);
+    let x2 = ctx.base_integer_chip().int_square(&p.x);
+    let x3 = ctx.base_integer_chip().int_mul(&x2, &p.x);
+    let rhs = ctx.base_integer_chip().int_add(&x3, &b);
+    let rhs = ctx.base_integer_chip().bisec_int(&p.z, &y2, &rhs);
+    ctx.base_integer_chip().assert_int_equal(&y2, &rhs);
+    p
+}
+
+fn enable_point_permute(
+    region: &mut Region<'_, Fr>,
+    cells: &Vec<Vec<Vec<Option<AssignedCell<Fr, Fr>>>>>,
+    point: &AssignedPoint<Secp256k1Affine, Fr>,
+    input: &Vec<Limb<Fr>>,
+) -> Result<(), Error> {
+    let mut inputs = input.chunks(3);
+    enable_int_permute(region, cells, &point.x, &inputs.next().unwrap().to_vec())?;
+    enable_int_permute(region, cells, &point.y, &inputs.next().unwrap().to_vec())?;
+    enable_value_permute(region, cells, &point.z.0, &input[6])?;
+    Ok(())
+}
+
+/// The assigned operands of one ECDSA check
+struct EcdsaAssigned {
+    msg: AssignedInteger<Scalar, Fr>,
+    r: AssignedInteger<Scalar, Fr>,
+    r_fp: AssignedFq<Secp256k1Fp, Fr>,
+    s: AssignedInteger<Scalar, Fr>,
+    pk: AssignedPoint<Secp256k1Affine, Fr>,
+    valid: AssignedCondition<Fr>,
+}
+
+/* Constrains valid == (s != 0) && (r != 0) && (pk != O) && (R != O) && (R.x == r)
+ * where R = msg/s * G + r/s * pk.
+ */
+fn assign_ecdsa(
+    ctx: &mut GeneralScalarEccContext<Secp256k1Affine, Fr>,
+    msg: &Vec<Limb<Fr>>,
+    r: &Vec<Limb<Fr>>,
+    s: &Vec<Limb<Fr>>,
+    pk: &Vec<Limb<Fr>>,
+) -> EcdsaAssigned {
+    let msg = get_scalar_from_cells(ctx, msg);
+    let r_fp = get_fp_from_cells(ctx, r);
+    let r = get_scalar_from_cells(ctx, r);
+    let s = get_scalar_from_cells(ctx, s);
+    let pk = get_point_from_cells(ctx, pk);
+
+    let (s_zero, u1) = ctx.scalar_integer_ctx.int_div(&msg, &s);
+    let (_, u2) = ctx.scalar_integer_ctx.int_div(&r, &s);
+    let r_zero = ctx.scalar_integer_ctx.is_int_zero(&r);
+
+    let generator = ctx.assign_constant_point(&Secp256k1Affine::generator().to_curve());
+    let p1 = ctx.ecc_mul(&generator, u1);
+    let p1 = ctx.to_point_with_curvature(p1);
+    let p2 = ctx.ecc_mul(&pk, u2);
+    let nonce = ctx.ecc_add(&p1, &p2);
+    let nonce = ctx.ecc_reduce(&nonce);
+    let x_eq = ctx.base_integer_ctx.is_int_equal(&nonce.x, &r_fp);
+
+    let mut native = ctx.native_ctx.borrow_mut();
+    let degenerate = native.or(&s_zero, &r_zero);
+    let degenerate = native.or(&degenerate, &pk.z);
+    let degenerate = native.or(&degenerate, &nonce.z);
+    let well_formed = native.not(&degenerate);
+    let valid = native.and(&well_formed, &x_eq);
+    drop(native);
+
+    EcdsaAssigned {
+        msg,
+        r,
+        r_fp,
+        s,
+        pk,
+        valid,
+    }
+}
+
+/// The assigned operands of one recovery
+struct RecoverAssigned {
+    msg: AssignedInteger<Scalar, Fr>,
+    r: AssignedInteger<Scalar, Fr>,
+    r_fp: AssignedFq<Secp256k1Fp, Fr>,
+    s: AssignedInteger<Scalar, Fr>,
+    parity: AssignedCondition<Fr>,
+    pk: AssignedPoint<Secp256k1Affine, Fr>,
+    valid: AssignedCondition<Fr>,
+}
+
+/* Constrains valid == (s != 0) && (r != 0) && (R exists) && (pk' != O) and the pushed
+ * pk to be pk' if valid and the identity with zero coordinates otherwise, where
+ * pk' = s/r * R - msg/r * G and R = (r, y) with the parity of y given by v.
+ *
+ * r^3 + 7 is not zero since the curve has no point of order two and -1 is not a
+ * square mod p, so exactly one of r^3 + 7 and -(r^3 + 7) has a square root y. The
+ * second case proves that no R exists, and G stands in for R to keep the
+ * multiplication well defined.
+ */
+fn assign_recover(
+    ctx: &mut GeneralScalarEccContext<Secp256k1Affine, Fr>,
+    msg: &Vec<Limb<Fr>>,
+    r: &Vec<Limb<Fr>>,
+    s: &Vec<Limb<Fr>>,
+    v: &Limb<Fr>,
+    pk: &Vec<Limb<Fr>>,
+) -> RecoverAssigned {
+    let msg = get_scalar_from_cells(ctx, msg);
+    let r_fp = get_fp_from_cells(ctx, r);
+    let r = get_scalar_from_cells(ctx, r);
+    let s = get_scalar_from_cells(ctx, s);
+    let pk = get_point_from_cells(ctx, pk);
+
+    let x = ctx.base_integer_ctx.get_w(&r_fp);
+    let rhs = x.square() * x + Secp256k1Fp::from(7u64);
+    let root: Option<Secp256k1Fp> = rhs.sqrt().into();
+    let exists = root.is_some();
+    let y = root.unwrap_or_else(|| Option::from((-rhs).sqrt()).unwrap());
+    let y = if field_to_bn(&y).bit(0) == (v.value == Fr::one()) {
+        y
+    } else {
+        -y
+    };
+    let exists =
+        ctx.native_ctx
+            .borrow_mut()
+            .assign_bit(if exists { Fr::one() } else { Fr::zero() });
+    let y = ctx.base_integer_ctx.assign_w(&field_to_bn(&y));
+    let b = ctx.base_integer_chip().assign_int_constant(Secp256k1Fp::from(7u64));
+    let zero = ctx.base_integer_chip().assign_int_constant(Secp256k1Fp::zero());
+    let y2 = ctx.base_integer_chip().int_square(&y);
+    let x2 = ctx.base_integer_chip().int_square(&r_fp);
+    let x3 = ctx.base_integer_chip().int_mul(&x2, &r_fp);
+    let rhs = ctx.base_integer_chip().int_add(&x3, &b);
+    let neg_rhs = ctx.base_integer_chip().int_sub(&zero, &rhs);
+    let rhs = ctx.base_integer_chip().bisec_int(&exists, &rhs, &neg_rhs);
+    ctx.base_integer_chip().assert_int_equal(&y2, &rhs);
+    let parity = assign_parity(ctx, &y);
+
+    let generator = ctx.assign_constant_point(&Secp256k1Affine::generator().to_curve());
+    let nonce_x = ctx.base_integer_chip().bisec_int(&exists, &r_fp, &generator.x);
+    let nonce_y = ctx.base_integer_chip().bisec_int(&exists, &y, &generator.y);
+    let nonce = AssignedPoint::new(nonce_x, nonce_y, generator.z.clone());
+
+    let (r_zero, u1) = ctx.scalar_integer_ctx.int_div(&s, &r);
+    let (_, u2) = ctx.scalar_integer_ctx.int_div(&msg, &r);
+    let s_zero = ctx.scalar_integer_ctx.is_int_zero(&s);
+    let p1 = ctx.ecc_mul(&nonce, u1);
+    let p1 = ctx.to_point_with_curvature(p1);
+    let p2 = ctx.ecc_mul(&generator, u2);
+    let p2 = ctx.ecc_neg(&p2);
+    let recovered = ctx.ecc_add(&p1, &p2);
+    let recovered = ctx.ecc_reduce(&recovered);
+
+    let mut native = ctx.native_ctx.borrow_mut();
+    let degenerate = native.or(&r_zero, &s_zero);
+    let missing = native.not(&exists);
+    let degenerate = native.or(&degenerate, &missing);
+    let degenerate = native.or(&degenerate, &recovered.z);
+    let valid = native.not(&degenerate);
+    native.assert_equal(&pk.z.0, &degenerate.0);
+    drop(native);
+    let x = ctx.base_integer_chip().bisec_int(&valid, &recovered.x, &zero);
+    let y = ctx.base_integer_chip().bisec_int(&valid, &recovered.y, &zero);
+    ctx.base_integer_chip().assert_int_equal(&pk.x, &x);
+    ctx.base_integer_chip().assert_int_equal(&pk.y, &y);
+
+    RecoverAssigned {
+        msg,
+        r,
+        r_fp,
+        s,
+        parity,
+        pk,
+        valid,
+    }
+}
+
+/// Constrains the lowest bit of the canonical representation of y, whose limbs of
+/// 108, 108 and 40 bits are decomposed and compared with p from the top
+fn assign_parity(
+    ctx: &mut GeneralScalarEccContext<Secp256k1Affine, Fr>,
+    y: &AssignedFq<Secp256k1Fp, Fr>,
+) -> AssignedCondition<Fr> {
+    let y_bn = field_to_bn(&ctx.base_integer_ctx.get_w(y));
+    let y_canonical = ctx.base_integer_ctx.assign_w(&y_bn);
+    ctx.base_integer_ctx.assert_int_equal(&y_canonical, y);
+    let mut native = ctx.native_ctx.borrow_mut();
+    let mut bits = vec![];
+    for (i, width) in [108, 108, 40].into_iter().enumerate() {
+        let limb = y_canonical.limbs_le[i].clone();
+        let limb_bn = fr_to_bn(&limb.val);
+        let limb_bits = (0..width)
+            .map(|j| native.assign_bit(if limb_bn.bit(j) { Fr::one() } else { Fr::zero() }))
+            .collect::<Vec<_>>();
+        let sum = native.sum_with_constant(
+            limb_bits
+                .iter()
+                .enumerate()
+                .map(|(j, b)| (&b.0, Fr::from_u128(1u128 << j)))
+                .collect(),
+            None,
+        );
+        native.assert_equal(&sum, &limb);
+        bits.append(&mut limb_bits.clone());
+    }
+
+    // y <= p - 1, compared with the bits of p - 1 from the top while they are equal
+    let modulus = field_to_bn(&-Secp256k1Fp::one());
+    let mut greater = AssignedCondition(native.assign_constant(Fr::zero()));
+    let mut equal = AssignedCondition(native.assign_constant(Fr::one()));
+    for (i, bit) in bits.iter().enumerate().rev() {
+        if modulus.bit(i as u64) {
+            equal = native.and(&equal, bit);
+        } else {
+            let above = native.and(&equal, bit);
+            greater = native.or(&greater, &above);
+            let not_bit = native.not(bit);
+            equal = native.and(&equal, &not_bit);
+        }
+    }
+    let canonical = native.not(&greater);
+    native.assert_true(&canonical);
+    bits[0].clone()
+}
+
+impl Secp256k1VerifyChip<Fr> {
+    pub fn construct(config: <Self as Chip<Fr>>::Config) -> Self {
+        Self {
+            config: config.clone(),
+            point_select_chip: SelectChip::<Fr>::new(config.point_select_chip_config),
+            base_chip: BaseChip::new(config.base_chip_config),
+            range_chip: RangeChip::<Fr>::new(config.range_chip_config),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(cs: &mut ConstraintSystem<Fr>) -> <Self as Chip<Fr>>::Config {
+        Secp256k1ChipConfig {
+            base_chip_config: BaseChip::configure(cs),
+            range_chip_config: RangeChip::<Fr>::configure(cs),
+            point_select_chip_config: SelectChip::configure(cs),
+        }
+    }
+
+    pub fn load_secp256k1_verify_circuit(
+        &self,
+        ls: &Vec<Limb<Fr>>, // n * (msg, r, s, pk, result)
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let contex = Rc::new(RefCell::new(Context::new()));
+        let mut ctx = GeneralScalarEccContext::<Secp256k1Affine, Fr>::new(contex);
+
+        let mut checks = vec![];
+        for group in ls.chunks_exact(17) {
+            let check = assign_ecdsa(
+                &mut ctx,
+                &group[0..3].to_vec(),
+                &group[3..6].to_vec(),
+                &group[6..9].to_vec(),
+                &group[9..16].to_vec(),
+            );
+            checks.push(check);
+        }
+
+        let records = Arc::try_unwrap(Into::<Context<Fr>>::into(ctx).records)
+            .unwrap()
+            .into_inner()
+            .unwrap();
+        layouter.assign_region(
+            || "base",
+            |mut region| {
+                let timer = start_timer!(|| "assign");
+                let cells = records.assign_all(
+                    &mut region,
+                    &self.base_chip,
+                    &self.range_chip,
+                    &self.point_select_chip,
+                )?;
+                for (i, check) in checks.iter().enumerate() {
+                    let group = &ls[17 * i..17 * (i + 1)];
+                    enable_int_permute(&mut region, &cells, &check.msg, &group[0..3].to_vec())?;
+                    enable_int_permute(&mut region, &cells, &check.r, &group[3..6].to_vec())?;
+                    enable_int_permute(&mut region, &cells, &check.r_fp, &group[3..6].to_vec())?;
+                    enable_int_permute(&mut region, &cells, &check.s, &group[6..9].to_vec())?;
+                    enable_point_permute(&mut region, &cells, &check.pk, &group[9..16].to_vec())?;
+                    enable_value_permute(&mut region, &cells, &check.valid.0, &group[16])?;
+                }
+                end_timer!(timer);
+                Ok(())
+            },
+        )?;
+        Ok(())
+    }
+}
+
+impl Secp256k1RecoverChip<Fr> {
+    pub fn construct(config: <Self as Chip<Fr>>::Config) -> Self {
+        Self {
+            config: config.clone(),
+            point_select_chip: SelectChip::<Fr>::new(config.point_select_chip_config),
+            base_chip: BaseChip::new(config.base_chip_config),
+            range_chip: RangeChip::<Fr>::new(config.range_chip_config),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(cs: &mut ConstraintSystem<Fr>) -> <Self as Chip<Fr>>::Config {
+        Secp256k1ChipConfig {
+            base_chip_config: BaseChip::configure(cs),
+            range_chip_config: RangeChip::<Fr>::configure(cs),
+            point_select_chip_config: SelectChip::configure(cs),
+        }
+    }
+
+    /* The recovered key pk is computed from (msg, r, s, v), see assign_recover, and
+     * the result is whether the recovery succeeded.
+     */
+    pub fn load_secp256k1_recover_circuit(
+        &self,
+        ls: &Vec<Limb<Fr>>, // n * (msg, r, s, v, pk, result)
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let contex = Rc::new(RefCell::new(Context::new()));
+        let mut ctx = GeneralScalarEccContext::<Secp256k1Affine, Fr>::new(contex);
+
+        let mut checks = vec![];
+        for group in ls.chunks_exact(18) {
+            let check = assign_recover(
+                &mut ctx,
+                &group[0..3].to_vec(),
+                &group[3..6].to_vec(),
+                &group[6..9].to_vec(),
+                &group[9],
+                &group[10..17].to_vec(),
+            );
+            checks.push(check);
+        }
+
+        let records = Arc::try_unwrap(Into::<Context<Fr>>::into(ctx).records)
+            .unwrap()
+            .into_inner()
+            .unwrap();
+        layouter.assign_region(
+            || "base",
+            |mut region| {
+                let timer = start_timer!(|| "assign");
+                let cells = records.assign_all(
+                    &mut region,
+                    &self.base_chip,
+                    &self.range_chip,
+                    &self.point_select_chip,
+                )?;
+                for (i, check) in checks.iter().enumerate() {
+                    let group = &ls[18 * i..18 * (i + 1)];
+                    enable_int_permute(&mut region, &cells, &check.msg, &group[0..3].to_vec())?;
+                    enable_int_permute(&mut region, &cells, &check.r, &group[3..6].to_vec())?;
+                    enable_int_permute(&mut region, &cells, &check.r_fp, &group[3..6].to_vec())?;
+                    enable_int_permute(&mut region, &cells, &check.s, &group[6..9].to_vec())?;
+                    enable_value_permute(&mut region, &cells, &check.parity.0, &group[9])?;
+                    enable_point_permute(&mut region, &cells, &check.pk, &group[10..17].to_vec())?;
+                    enable_value_permute(&mut region, &cells, &check.valid.0, &group[17])?;
+                }
+                end_timer!(timer);
+                Ok(())
+            },
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,348 @@
+use ark_std::{end_timer, start_timer};
+use halo2_proofs::pairing::bn256::Fr;
+use halo2_proofs::pairing::secp256r1::Fq as Scalar;
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Chip, Layouter},
+    plonk::{ConstraintSystem, Error},
+};
+use halo2ecc_s::circuit::base_chip::BaseChipOps;
+use halo2ecc_s::circuit::integer_chip::IntegerChipOps;
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use halo2_proofs::pairing::secp256r1::Fp as Secp256r1Fp;
+use halo2ecc_s::assign::{AssignedCondition, AssignedFq, AssignedInteger};
+
+use halo2ecc_s::{
+    circuit::{
+        base_chip::{BaseChip, BaseChipConfig},
+        range_chip::{RangeChip, RangeChipConfig},
+        select_chip::{SelectChip, SelectChipConfig},
+    },
+    context::{Context, IntegerContext},
+};
+
+use crate::circuits::ecdsa::{
+    assigned_cells_to_int, enable_int_permute, enable_value_permute, fr_to_bn, fr_to_bool,
+};
+use crate::host::secp256r1::{p256_b, P256Point};
+use crate::utils::{field_to_bn, Limb};
+
+/* ECDSA verification over secp256r1 (P-256).
+ *
+ * The ecc chip of halo2ecc-s only supports curves with a = 0, so the point
+ * arithmetic is done here on top of its integer chips, using the complete
+ * projective addition of host::secp256r1. The two scalar multiplications are
+ * merged into a single double-and-add loop over the bits of u1 = msg/s and u2 = r/s.
+ */
+
+#[derive(Clone, Debug)]
+pub struct Secp256r1ChipConfig {
+    base_chip_config: BaseChipConfig,
+    range_chip_config: RangeChipConfig,
+    point_select_chip_config: SelectChipConfig,
+}
+
+pub struct Secp256r1VerifyChip<N: FieldExt> {
+    config: Secp256r1ChipConfig,
+    base_chip: BaseChip<N>,
+    pub range_chip: RangeChip<N>,
+    point_select_chip: SelectChip<N>,
+    _marker: PhantomData<N>,
+}
+
+impl<N: FieldExt> Chip<N> for Secp256r1VerifyChip<N> {
+    type Config = Secp256r1ChipConfig;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+type BaseContext = IntegerContext<Secp256r1Fp, Fr>;
+type ScalarContext = IntegerContext<Scalar, Fr>;
+
+#[derive(Clone)]
+struct AssignedP256Point {
+    x: AssignedFq<Secp256r1Fp, Fr>,
+    y: AssignedFq<Secp256r1Fp, Fr>,
+    z: AssignedFq<Secp256r1Fp, Fr>,
+}
+
+fn assign_constant_point(base: &mut BaseContext, p: &P256Point) -> AssignedP256Point {
+    AssignedP256Point {
+        x: base.assign_int_constant(p.x),
+        y: base.assign_int_constant(p.y),
+        z: base.assign_int_constant(p.z),
+    }
+}
+
+/// Same operations as P256Point::add
+fn p256_add(
+    base: &mut BaseContext,
+    b: &AssignedFq<Secp256r1Fp, Fr>,
+    p: &AssignedP256Point,
+    q: &AssignedP256Point,
+) -> AssignedP256Point {
+    let (x1, y1, z1) = (&p.x, &p.y, &p.z);
+    let (x2, y2, z2) = (&q.x, &q.y, &q.z);
+    let mut t0 = base.int_mul(x1, x2);
+    let mut t1 = base.int_mul(y1, y2);
+    let mut t2 = base.int_mul(z1, z2);
+    let x1y1 = base.int_add(x1, y1);
+    let x2y2 = base.int_add(x2, y2);
+    let mut t3 = base.int_mul(&x1y1, &x2y2);
+    let mut t4 = base.int_add(&t0, &t1);
+    t3 = base.int_sub(&t3, &t4);
+    let y1z1 = base.int_add(y1, z1);
+    let y2z2 = base.int_add(y2, z2);
+    t4 = base.int_mul(&y1z1, &y2z2);
+    let mut x3 = base.int_add(&t1, &t2);
+    t4 = base.int_sub(&t4, &x3);
+    let x1z1 = base.int_add(x1, z1);
+    let x2z2 = base.int_add(x2, z2);
+    x3 = base.int_mul(&x1z1, &x2z2);
+    let mut y3 = base.int_add(&t0, &t2);
+    y3 = base.int_sub(&x3, &y3);
+    let mut z3 = base.int_mul(b, &t2);
+    x3 = base.int_sub(&y3, &z3);
+    z3 = base.int_add(&x3, &x3);
+    x3 = base.int_add(&x3, &z3);
+    z3 = base.int_sub(&t1, &x3);
+    x3 = base.int_add(&t1, &x3);
+    y3 = base.int_mul(b, &y3);
+    t1 = base.int_add(&t2, &t2);
+    t2 = base.int_add(&t1, &t2);
+    y3 = base.int_sub(&y3, &t2);
+    y3 = base.int_sub(&y3, &t0);
+    t1 = base.int_add(&y3, &y3);
+    y3 = base.int_add(&t1, &y3);
+    t1 = base.int_add(&t0, &t0);
+    t0 = base.int_add(&t1, &t0);
+    t0 = base.int_sub(&t0, &t2);
+    t1 = base.int_mul(&t4, &y3);
+    t2 = base.int_mul(&t0, &y3);
+    y3 = base.int_mul(&x3, &z3);
+    y3 = base.int_add(&y3, &t2);
+    x3 = base.int_mul(&t3, &x3);
+    x3 = base.int_sub(&x3, &t1);
+    z3 = base.int_mul(&t4, &z3);
+    t1 = base.int_mul(&t3, &t0);
+    z3 = base.int_add(&z3, &t1);
+    AssignedP256Point {
+        x: x3,
+        y: y3,
+        z: z3,
+    }
+}
+
+fn p256_select(
+    base: &mut BaseContext,
+    cond: &AssignedCondition<Fr>,
+    a: &AssignedP256Point,
+    b: &AssignedP256Point,
+) -> AssignedP256Point {
+    AssignedP256Point {
+        x: base.bisec_int(cond, &a.x, &b.x),
+        y: base.bisec_int(cond, &a.y, &b.y),
+        z: base.bisec_int(cond, &a.z, &b.z),
+    }
+}
+
+/// Little endian bits of the canonical representation of u
+fn assign_scalar_bits(
+    scalar: &mut ScalarContext,
+    u: &AssignedInteger<Scalar, Fr>,
+) -> Vec<AssignedCondition<Fr>> {
+    let u_bn = field_to_bn(&scalar.get_w(u));
+    let u_canonical = scalar.assign_w(&u_bn);
+    scalar.assert_int_equal(&u_canonical, u);
+    let mut native = scalar.ctx.borrow_mut();
+    let mut bits = vec![];
+    for (i, width) in [108, 108, 40].into_iter().enumerate() {
+        let limb = u_canonical.limbs_le[i].clone();
+        let limb_bn = fr_to_bn(&limb.val);
+        let limb_bits = (0..width)
+            .map(|j| native.assign_bit(if limb_bn.bit(j) { Fr::one() } else { Fr::zero() }))
+            .collect::<Vec<_>>();
+        let sum = native.sum_with_constant(
+            limb_bits
+                .iter()
+                .enumerate()
+                .map(|(j, b)| (&b.0, Fr::from_u128(1u128 << j)))
+                .collect(),
+            None,
+        );
+        native.assert_equal(&sum, &limb);
+        bits.append(&mut limb_bits.clone());
+    }
+    bits
+}
+
+struct EcdsaAssigned {
+    msg: AssignedInteger<Scalar, Fr>,
+    r: AssignedInteger<Scalar, Fr>,
+    r_fp: AssignedFq<Secp256r1Fp, Fr>,
+    s: AssignedInteger<Scalar, Fr>,
+    pk_x: AssignedFq<Secp256r1Fp, Fr>,
+    pk_y: AssignedFq<Secp256r1Fp, Fr>,
+    pk_identity: AssignedCondition<Fr>,
+    valid: AssignedCondition<Fr>,
+}
+
+/* Constrains valid == (s != 0) && (r != 0) && (pk != O) && (R != O) && (R.x == r)
+ * where R = msg/s * G + r/s * pk.
+ */
+fn assign_ecdsa(
+    base: &mut BaseContext,
+    scalar: &mut ScalarContext,
+    ls: &[Limb<Fr>], // msg, r, s, pk (x, y, is_identity)
+) -> EcdsaAssigned {
+    let msg = scalar.assign_w(&assigned_cells_to_int(&ls[0..3].to_vec(), 0));
+    let r = scalar.assign_w(&assigned_cells_to_int(&ls[3..6].to_vec(), 0));
+    let r_fp = base.assign_w(&assigned_cells_to_int(&ls[3..6].to_vec(), 0));
+    let s = scalar.assign_w(&assigned_cells_to_int(&ls[6..9].to_vec(), 0));
+    let pk_x = base.assign_w(&assigned_cells_to_int(&ls[9..12].to_vec(), 0));
+    let pk_y = base.assign_w(&assigned_cells_to_int(&ls[12..15].to_vec(), 0));
+    let pk_identity = scalar
+        .ctx
+        .borrow_mut()
+        .assign_bit(if fr_to_bool(&ls[15].value) { Fr::one() } else { Fr::zero() });
+
+    // unless pk is the identity, y^2 = x^3 - 3x + b
+    let b = base.assign_int_constant(p256_b());
+    let y2 = base.int_square(&pk_y);
+    let x2 = base.int_square(&pk_x);
+    let x3 = base.int_mul(&x2, &pk_x);
+    let x_2 = base.int_add(&pk_x, &pk_x);
+    let x_3 = base.int_add(&x_2, &pk_x);
+    let rhs = base.int_sub(&x3, &x_3);
+    let rhs = base.int_add(&rhs, &b);
+    let rhs = base.bisec_int(&pk_identity, &y2, &rhs);
+    base.assert_int_equal(&y2, &rhs);
+
+    let (s_zero, u1) = scalar.int_div(&msg, &s);
+    let (_, u2) = scalar.int_div(&r, &s);
+    let r_zero = scalar.is_int_zero(&r);
+    let u1_bits = assign_scalar_bits(scalar, &u1);
+    let u2_bits = assign_scalar_bits(scalar, &u2);
+
+    let identity = assign_constant_point(base, &P256Point::identity());
+    let generator = assign_constant_point(base, &P256Point::generator());
+    let one = base.assign_int_constant(Secp256r1Fp::one());
+    let pk = AssignedP256Point {
+        x: pk_x.clone(),
+        y: pk_y.clone(),
+        z: one,
+    };
+    let pk = p256_select(base, &pk_identity, &identity, &pk);
+    let generator_pk = p256_add(base, &b, &generator, &pk);
+
+    let mut acc = identity.clone();
+    for i in (0..256).rev() {
+        acc = p256_add(base, &b, &acc, &acc);
+        let g_or_identity = p256_select(base, &u1_bits[i], &generator, &identity);
+        let gpk_or_pk = p256_select(base, &u1_bits[i], &generator_pk, &pk);
+        let addend = p256_select(base, &u2_bits[i], &gpk_or_pk, &g_or_identity);
+        acc = p256_add(base, &b, &acc, &addend);
+    }
+
+    let nonce_identity = base.is_int_zero(&acc.z);
+    let r_z = base.int_mul(&r_fp, &acc.z);
+    let x_eq = base.is_int_equal(&acc.x, &r_z);
+
+    let mut native = base.ctx.borrow_mut();
+    let degenerate = native.or(&s_zero, &r_zero);
+    let degenerate = native.or(&degenerate, &pk_identity);
+    let degenerate = native.or(&degenerate, &nonce_identity);
+    let well_formed = native.not(&degenerate);
+    let valid = native.and(&well_formed, &x_eq);
+    drop(native);
+
+    EcdsaAssigned {
+        msg,
+        r,
+        r_fp,
+        s,
+        pk_x,
+        pk_y,
+        pk_identity,
+        valid,
+    }
+}
+
+impl Secp256r1VerifyChip<Fr> {
+    pub fn construct(config: <Self as Chip<Fr>>::Config) -> Self {
+        Self {
+            config: config.clone(),
+            point_select_chip: SelectChip::<Fr>::new(config.point_select_chip_config),
+            base_chip: BaseChip::new(config.base_chip_config),
+            range_chip: RangeChip::<Fr>::new(config.range_chip_config),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(cs: &mut ConstraintSystem<Fr>) -> <Self as Chip<Fr>>::Config {
+        Secp256r1ChipConfig {
+            base_chip_config: BaseChip::configure(cs),
+            range_chip_config: RangeChip::<Fr>::configure(cs),
+            point_select_chip_config: SelectChip::configure(cs),
+        }
+    }
+
+    pub fn load_secp256r1_verify_circuit(
+        &self,
+        ls: &Vec<Limb<Fr>>, // n * (msg, r, s, pk, result)
+        layouter: &mut impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let context = Rc::new(RefCell::new(Context::new()));
+        let mut base = BaseContext::new(context.clone());
+        let mut scalar = ScalarContext::new(context.clone());
+
+        let mut checks = vec![];
+        for group in ls.chunks_exact(17) {
+            checks.push(assign_ecdsa(&mut base, &mut scalar, &group[0..16]));
+        }
+
+        drop(base);
+        drop(scalar);
+        let records = Arc::try_unwrap(Rc::try_unwrap(context).unwrap().into_inner().records)
+            .unwrap()
+            .into_inner()
+            .unwrap();
+        layouter.assign_region(
+            || "base",
+            |mut region| {
+                let timer = start_timer!(|| "assign");
+                let cells = records.assign_all(
+                    &mut region,
+                    &self.base_chip,
+                    &self.range_chip,
+                    &self.point_select_chip,
+                )?;
+                for (i, check) in checks.iter().enumerate() {
+                    let group = &ls[17 * i..17 * (i + 1)];
+                    enable_int_permute(&mut region, &cells, &check.msg, &group[0..3].to_vec())?;
+                    enable_int_permute(&mut region, &cells, &check.r, &group[3..6].to_vec())?;
+                    enable_int_permute(&mut region, &cells, &check.r_fp, &group[3..6].to_vec())?;
+                    enable_int_permute(&mut region, &cells, &check.s, &group[6..9].to_vec())?;
+                    enable_int_permute(&mut region, &cells, &check.pk_x, &group[9..12].to_vec())?;
+                    enable_int_permute(&mut region, &cells, &check.pk_y, &group[12..15].to_vec())?;
+                    enable_value_permute(&mut region, &cells, &check.pk_identity.0, &group[15])?;
+                    enable_value_permute(&mut region, &cells, &check.valid.0, &group[16])?;
+                }
+                end_timer!(timer);
+                Ok(())
+            },
+        )?;
+        Ok(())
+    }
+}
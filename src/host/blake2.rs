@@ -0,0 +1,244 @@
+/* Native reference of the blake2s and blake2b host calls.
+ *
+ * Both variants share the same compression with words of 32 or 64 bits kept in u64.
+ * The hash is unkeyed with the default parameter block and a digest of 32 bytes for
+ * blake2s and 64 bytes for blake2b. Every compression takes a block of 16 words,
+ * the byte counter t (less than 2^64) and the last block flag.
+ */
+
+pub const BLOCK_WORDS: usize = 16;
+pub const STATE_WORDS: usize = 8;
+
+pub const SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+pub struct Blake2Params {
+    pub bits: usize,
+    pub rounds: usize,
+    /// the right rotations of the mixing function G
+    pub rotations: [u32; 4],
+    pub iv: [u64; STATE_WORDS],
+    pub digest_bytes: usize,
+}
+
+impl Blake2Params {
+    pub fn block_bytes(&self) -> usize {
+        BLOCK_WORDS * self.bits / 8
+    }
+
+    pub fn mask(&self) -> u64 {
+        u64::MAX >> (64 - self.bits)
+    }
+
+    /// the initial state of the unkeyed hash
+    pub fn init_state(&self) -> [u64; STATE_WORDS] {
+        let mut h = self.iv;
+        h[0] ^= 0x01010000 ^ self.digest_bytes as u64;
+        h
+    }
+}
+
+pub const BLAKE2S: Blake2Params = Blake2Params {
+    bits: 32,
+    rounds: 10,
+    rotations: [16, 12, 8, 7],
+    iv: [
+        0x6A09E667, 0xBB67AE85, 0x3C6EF372, 0xA54FF53A, 0x510E527F, 0x9B05688C, 0x1F83D9AB,
+        0x5BE0CD19,
+    ],
+    digest_bytes: 32,
+};
+
+pub const BLAKE2B: Blake2Params = Blake2Params {
+    bits: 64,
+    rounds: 12,
+    rotations: [32, 24, 16, 63],
+    iv: [
+        0x6A09E667F3BCC908,
+        0xBB67AE8584CAA73B,
+        0x3C6EF372FE94F82B,
+        0xA54FF53A5F1D36F1,
+        0x510E527FADE682D1,
+        0x9B05688C2B3E6C1F,
+        0x1F83D9ABFB41BD6B,
+        0x5BE0CD19137E2179,
+    ],
+    digest_bytes: 64,
+};
+
+fn rotr(p: &Blake2Params, x: u64, r: u32) -> u64 {
+    ((x >> r) | (x << (p.bits as u32 - r))) & p.mask()
+}
+
+fn g(p: &Blake2Params, v: &mut [u64; 16], i: [usize; 4], x: u64, y: u64) {
+    let [a, b, c, d] = i;
+    let mask = p.mask();
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x) & mask;
+    v[d] = rotr(p, v[d] ^ v[a], p.rotations[0]);
+    v[c] = v[c].wrapping_add(v[d]) & mask;
+    v[b] = rotr(p, v[b] ^ v[c], p.rotations[1]);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y) & mask;
+    v[d] = rotr(p, v[d] ^ v[a], p.rotations[2]);
+    v[c] = v[c].wrapping_add(v[d]) & mask;
+    v[b] = rotr(p, v[b] ^ v[c], p.rotations[3]);
+}
+
+/// the (a, b, c, d) indices of the eight G calls of a round
+pub const G_INDICES: [[usize; 4]; 8] = [
+    [0, 4, 8, 12],
+    [1, 5, 9, 13],
+    [2, 6, 10, 14],
+    [3, 7, 11, 15],
+    [0, 5, 10, 15],
+    [1, 6, 11, 12],
+    [2, 7, 8, 13],
+    [3, 4, 9, 14],
+];
+
+pub fn blake2_compress(
+    p: &Blake2Params,
+    h: &mut [u64; STATE_WORDS],
+    m: &[u64; BLOCK_WORDS],
+    t: u64,
+    last: bool,
+) {
+    let mut v = [0u64; 16];
+    v[0..8].copy_from_slice(h);
+    v[8..16].copy_from_slice(&p.iv);
+    v[12] ^= t & p.mask();
+    v[13] ^= ((t as u128) >> p.bits) as u64;
+    if last {
+        v[14] ^= p.mask();
+    }
+    for r in 0..p.rounds {
+        let s = SIGMA[r % 10];
+        for (k, i) in G_INDICES.iter().enumerate() {
+            g(p, &mut v, *i, m[s[2 * k]], m[s[2 * k + 1]]);
+        }
+    }
+    for i in 0..STATE_WORDS {
+        h[i] ^= v[i] ^ v[i + 8];
+    }
+}
+
+/// Split the message into blocks of little endian words together with the
+/// byte counter and the last block flag of each block
+pub fn blake2_blocks(p: &Blake2Params, bytes: &[u8]) -> Vec<([u64; BLOCK_WORDS], u64, bool)> {
+    let block_bytes = p.block_bytes();
+    let word_bytes = p.bits / 8;
+    let total = std::cmp::max(1, (bytes.len() + block_bytes - 1) / block_bytes);
+    (0..total)
+        .map(|i| {
+            let end = std::cmp::min(bytes.len(), (i + 1) * block_bytes);
+            let mut block = bytes[i * block_bytes..end].to_vec();
+            block.resize(block_bytes, 0);
+            let words = block
+                .chunks_exact(word_bytes)
+                .map(|w| {
+                    let mut buf = [0u8; 8];
+                    buf[0..word_bytes].copy_from_slice(w);
+                    u64::from_le_bytes(buf)
+                })
+                .collect::<Vec<_>>()
+                .try_into()
+                .unwrap();
+            (words, end as u64, i == total - 1)
+        })
+        .collect()
+}
+
+pub struct Blake2 {
+    params: &'static Blake2Params,
+    state: [u64; STATE_WORDS],
+}
+
+impl Blake2 {
+    pub fn new(params: &'static Blake2Params) -> Self {
+        Blake2 {
+            params,
+            state: params.init_state(),
+        }
+    }
+
+    /// Compress one block and return the chained state
+    pub fn update_exact(
+        &mut self,
+        block: &[u64; BLOCK_WORDS],
+        t: u64,
+        last: bool,
+    ) -> [u64; STATE_WORDS] {
+        blake2_compress(self.params, &mut self.state, block, t, last);
+        self.state
+    }
+}
+
+pub fn blake2(params: &'static Blake2Params, bytes: &[u8]) -> Vec<u8> {
+    let mut hasher = Blake2::new(params);
+    let mut result = [0u64; STATE_WORDS];
+    for (block, t, last) in blake2_blocks(params, bytes).iter() {
+        result = hasher.update_exact(block, *t, *last);
+    }
+    result
+        .iter()
+        .flat_map(|x| x.to_le_bytes()[0..params.bits / 8].to_vec())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{blake2, blake2_blocks, BLAKE2B, BLAKE2S};
+
+    fn hex(bytes: Vec<u8>) -> String {
+        bytes.iter().map(|x| format!("{:02x}", x)).collect()
+    }
+
+    #[test]
+    fn test_blake2s() {
+        assert_eq!(
+            hex(blake2(&BLAKE2S, b"")),
+            "69217a3079908094e11121d042354a7c1f55b6482ca1a51e1b250dfd1ed0eef9"
+        );
+        assert_eq!(
+            hex(blake2(&BLAKE2S, b"abc")),
+            "508c5e8c327c14e2e1a72ba34eeb452f37458b209ed63a294d999b4c86675982"
+        );
+        let bytes = (0..200).map(|x| x as u8).collect::<Vec<_>>();
+        assert_eq!(blake2_blocks(&BLAKE2S, &bytes).len(), 4);
+        assert_eq!(
+            hex(blake2(&BLAKE2S, &bytes)),
+            "6d244e1a06ce4ef578dd0f63aff0936706735119ca9c8d22d86c801414ab9741"
+        );
+        // a full block is the last block without any extra padding block
+        assert_eq!(blake2_blocks(&BLAKE2S, &[0u8; 64]).len(), 1);
+        assert_eq!(
+            hex(blake2(&BLAKE2S, &[0u8; 64])),
+            "ae09db7cd54f42b490ef09b6bc541af688e4959bb8c53f359a6f56e38ab454a3"
+        );
+    }
+
+    #[test]
+    fn test_blake2b() {
+        assert_eq!(
+            hex(blake2(&BLAKE2B, b"abc")),
+            "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d1\
+             7d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"
+        );
+        let bytes = (0..200).map(|x| x as u8).collect::<Vec<_>>();
+        assert_eq!(blake2_blocks(&BLAKE2B, &bytes).len(), 2);
+        assert_eq!(
+            hex(blake2(&BLAKE2B, &bytes)),
+            "fb3c1f0f56a56f8e316fdf5d853c8c872c39635d083634c3904fc3ac07d1b578\
+             e85ff0e480e92d44ade33b62e893ee32343e79ddf6ef292e89b582d312502314"
+        );
+    }
+}
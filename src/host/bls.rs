@@ -1,11 +1,352 @@
+/* KZG point evaluation of EIP-4844 over BLS12-381.
+ *
+ * A proof of p(z) == y for the commitment C = [p(tau)]_1 is the commitment of the
+ * quotient, pi = [(p(tau) - y) / (tau - z)]_1, and it is valid if
+ *     e(C - [y]_1 + z * pi, g2) == e(pi, [tau]_2)
+ * which moves z to G1 so that [tau]_2 is the only point of the setup involved.
+ * The precompile also checks that the versioned hash is the one of C. The host call
+ * takes the versioned hash, whose version byte the circuit checks, and returns the
+ * compressed encoding of C. The guest hashes it with sha256 and compares the digest
+ * with the rest of the versioned hash.
+ */
+use crate::circuits::gt::{GT_OP_EQUAL, GT_OP_INVERT, GT_OP_MUL};
+use crate::utils::{bn_to_field, field_to_bn};
+use ff::Field;
+use halo2_proofs::arithmetic::CurveAffine;
+use halo2_proofs::pairing::bls12_381::{
+    pairing, Fq, Fq12, Fq2, Fq6, Fr, G1Affine, G2Affine, Gt, G1, G2,
+};
+use halo2_proofs::pairing::group::Group;
+use lazy_static::lazy_static;
+use num_bigint::BigUint;
+use sha2::{Digest, Sha256};
+
+pub const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+
+fn fq_from_hex(s: &str) -> Fq {
+    bn_to_field(&BigUint::parse_bytes(s.as_bytes(), 16).unwrap())
+}
+
+lazy_static! {
+    /// [tau]_2 of the Ethereum KZG ceremony, the second G2 point of trusted_setup.txt
+    pub static ref KZG_SETUP_G2: G2Affine = G2Affine::from_xy(
+        Fq2 {
+            c0: fq_from_hex("185cbfee53492714734429b7b38608e23926c911cceceac9a36851477ba4c60b087041de621000edc98edada20c1def2"),
+            c1: fq_from_hex("15bfd7dd8cdeb128843bc287230af38926187075cbfbefa81009a2ce615ac53d2914e5870cb452d2afaaab24f3499f72"),
+        },
+        Fq2 {
+            c0: fq_from_hex("014353bdb96b626dd7d5ee8599d1fca2131569490e28de18e82451a496a9c9794ce26d105941f383ee689bfbbb832a99"),
+            c1: fq_from_hex("1666c54b0a32529503432fcae0181b4bef79de09fc63671fda5ed1ba9bfa07899495346f3d7ac9cd23048ef30d0a154f"),
+        },
+    )
+    .unwrap();
+}
+
+/// The 48 bytes compressed encoding of the zcash serialization that the KZG ceremony uses
+pub fn bls381_g1_to_compressed(p: &G1Affine) -> [u8; 48] {
+    let mut r = [0u8; 48];
+    if bool::from(p.is_identity()) {
+        r[0] = 0xc0;
+        return r;
+    }
+    let x = field_to_bn(&p.x).to_bytes_be();
+    r[48 - x.len()..].copy_from_slice(&x);
+    // the sign bit is set when y is the larger one of y and -y
+    let half = field_to_bn(&-Fq::one()) / BigUint::from(2u32);
+    r[0] |= 0x80;
+    if field_to_bn(&p.y) > half {
+        r[0] |= 0x20;
+    }
+    r
+}
+
+/// The words of the compressed encoding that the kzg host call returns
+pub const KZG_COMPRESSED_WORDS: usize = 6;
+
+/// The compressed encoding of a commitment as big endian words
+pub fn bls381_g1_compressed_words(p: &G1Affine) -> [u64; KZG_COMPRESSED_WORDS] {
+    let bytes = bls381_g1_to_compressed(p);
+    let mut r = [0u64; KZG_COMPRESSED_WORDS];
+    for (w, chunk) in r.iter_mut().zip(bytes.chunks_exact(8)) {
+        *w = u64::from_be_bytes(chunk.try_into().unwrap());
+    }
+    r
+}
+
+/// The words of the versioned hash that the kzg host call takes
+pub const KZG_VERSIONED_HASH_WORDS: usize = 4;
+
+/// The versioned hash as big endian words, so that the version byte is the top byte
+/// of the first word
+pub fn kzg_versioned_hash_words(hash: &[u8; 32]) -> [u64; KZG_VERSIONED_HASH_WORDS] {
+    let mut r = [0u64; KZG_VERSIONED_HASH_WORDS];
+    for (w, chunk) in r.iter_mut().zip(hash.chunks_exact(8)) {
+        *w = u64::from_be_bytes(chunk.try_into().unwrap());
+    }
+    r
+}
+
+pub fn kzg_to_versioned_hash(commitment: &G1Affine) -> [u8; 32] {
+    let mut hash: [u8; 32] = Sha256::digest(bls381_g1_to_compressed(commitment)).into();
+    hash[0] = VERSIONED_HASH_VERSION_KZG;
+    hash
+}
+
+pub fn verify_kzg_proof(commitment: &G1Affine, z: &Fr, y: &Fr, proof: &G1Affine) -> bool {
+    let p = G1::from(*commitment) - G1::generator() * *y + G1::from(*proof) * *z;
+    pairing(&G1Affine::from(p), &G2Affine::generator()) == pairing(proof, &KZG_SETUP_G2)
+}
+
+/// The point evaluation precompile without the decoding of its 192 bytes of input
+pub fn kzg_point_evaluation(
+    versioned_hash: &[u8; 32],
+    z: &Fr,
+    y: &Fr,
+    commitment: &G1Affine,
+    proof: &G1Affine,
+) -> bool {
+    kzg_to_versioned_hash(commitment) == *versioned_hash && verify_kzg_proof(commitment, z, y, proof)
+}
+
+/* BLS signatures of the minimal-pubkey-size variant, public keys in G1 and
+ * signatures in G2, with the proof of possession ciphersuite so that fast
+ * aggregate verify is safe for keys of the same message:
+ *     e(sum pk_i, H(m)) == e(g1, sig)
+ * H is hash_to_curve with BLS12381G2_XMD:SHA-256_SSWU_RO_. The host op verifies over
+ * the 256 uniform bytes of expand_message_xmd(m) instead of m and the circuit reduces
+ * them to the two Fq2 elements u = hash_to_field(m). The circuit does not constrain
+ * expand_message_xmd, the guest computes it with the sha256 host calls.
+ */
+pub const BLS_SIG_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+// h_eff of the reference document, clears the cofactor of E2
+const BLS381_G2_H_EFF: &str = "bc69f08f2ee75b3584c6a0ea91b352888e2a8e9145ad7689986ff031508ffe1329c2f178731db956d82bf015d1212b02ec0ec69d7477c1ae954cbc06689f6a359894c0adebbf6b4e8020005aaa95551";
+
+// the coefficients of x_num, x_den, y_num and y_den of the 3-isogeny map, lowest degree first
+const BLS381_ISO3_X_NUM: [(&str, &str); 4] = [
+    ("889424345604814976315064405719089812568196182208668418962679585805340366775741747653930584250892369786198727235542", "889424345604814976315064405719089812568196182208668418962679585805340366775741747653930584250892369786198727235542"),
+    ("0", "2668273036814444928945193217157269437704588546626005256888038757416021100327225242961791752752677109358596181706522"),
+    ("2668273036814444928945193217157269437704588546626005256888038757416021100327225242961791752752677109358596181706526", "1334136518407222464472596608578634718852294273313002628444019378708010550163612621480895876376338554679298090853261"),
+    ("3557697382419259905260257622876359250272784728834673675850718343221361467102966990615722337003569479144794908942033", "0"),
+];
+const BLS381_ISO3_X_DEN: [(&str, &str); 3] = [
+    ("0", "4002409555221667393417789825735904156556882819939007885332058136124031650490837864442687629129015664037894272559715"),
+    ("12", "4002409555221667393417789825735904156556882819939007885332058136124031650490837864442687629129015664037894272559775"),
+    ("1", "0"),
+];
+const BLS381_ISO3_Y_NUM: [(&str, &str); 4] = [
+    ("3261222600550988246488569487636662646083386001431784202863158481286248011511053074731078808919938689216061999863558", "3261222600550988246488569487636662646083386001431784202863158481286248011511053074731078808919938689216061999863558"),
+    ("0", "889424345604814976315064405719089812568196182208668418962679585805340366775741747653930584250892369786198727235518"),
+    ("2668273036814444928945193217157269437704588546626005256888038757416021100327225242961791752752677109358596181706524", "1334136518407222464472596608578634718852294273313002628444019378708010550163612621480895876376338554679298090853263"),
+    ("2816510427748580758331037284777117739799287910327449993381818688383577828123182200904113516794492504322962636245776", "0"),
+];
+const BLS381_ISO3_Y_DEN: [(&str, &str); 4] = [
+    ("4002409555221667393417789825735904156556882819939007885332058136124031650490837864442687629129015664037894272559355", "4002409555221667393417789825735904156556882819939007885332058136124031650490837864442687629129015664037894272559355"),
+    ("0", "4002409555221667393417789825735904156556882819939007885332058136124031650490837864442687629129015664037894272559571"),
+    ("18", "4002409555221667393417789825735904156556882819939007885332058136124031650490837864442687629129015664037894272559769"),
+    ("1", "0"),
+];
+
+fn fq2_from_decimal(c: &(&str, &str)) -> Fq2 {
+    Fq2 {
+        c0: bn_to_field(&BigUint::parse_bytes(c.0.as_bytes(), 10).unwrap()),
+        c1: bn_to_field(&BigUint::parse_bytes(c.1.as_bytes(), 10).unwrap()),
+    }
+}
+
+fn fq2_polynomial(coeffs: &[(&str, &str)], x: &Fq2) -> Fq2 {
+    coeffs
+        .iter()
+        .rev()
+        .fold(Fq2::zero(), |acc, c| acc * x + fq2_from_decimal(c))
+}
+
+fn fq2_sgn0(a: &Fq2) -> bool {
+    let c0 = field_to_bn(&a.c0);
+    let c1 = field_to_bn(&a.c1);
+    c0.bit(0) || (c0 == BigUint::from(0u32) && c1.bit(0))
+}
+
+fn expand_message_xmd(msg: &[u8], dst: &[u8], len: usize) -> Vec<u8> {
+    let ell = (len + 31) / 32;
+    let mut dst_prime = dst.to_vec();
+    dst_prime.push(dst.len() as u8);
+    let b0 = Sha256::new()
+        .chain_update([0u8; 64])
+        .chain_update(msg)
+        .chain_update((len as u16).to_be_bytes())
+        .chain_update([0u8])
+        .chain_update(&dst_prime)
+        .finalize();
+    let mut bs = vec![Sha256::new()
+        .chain_update(b0)
+        .chain_update([1u8])
+        .chain_update(&dst_prime)
+        .finalize()];
+    for i in 2..=ell {
+        let xor = b0
+            .iter()
+            .zip(bs[i - 2].iter())
+            .map(|(x, y)| x ^ y)
+            .collect::<Vec<_>>();
+        bs.push(
+            Sha256::new()
+                .chain_update(xor)
+                .chain_update([i as u8])
+                .chain_update(&dst_prime)
+                .finalize(),
+        );
+    }
+    bs.iter().flatten().take(len).cloned().collect()
+}
+
+/// The words of the uniform bytes of hash_to_field that the verify host call takes
+pub const BLS_UNIFORM_WORDS: usize = 32;
+
+/// expand_message_xmd(msg) of hash_to_field as big endian words
+pub fn bls381_expand_message(msg: &[u8], dst: &[u8]) -> [u64; BLS_UNIFORM_WORDS] {
+    let bytes = expand_message_xmd(msg, dst, BLS_UNIFORM_WORDS * 8);
+    let mut r = [0u64; BLS_UNIFORM_WORDS];
+    for (w, chunk) in r.iter_mut().zip(bytes.chunks_exact(8)) {
+        *w = u64::from_be_bytes(chunk.try_into().unwrap());
+    }
+    r
+}
+
+pub fn bls381_hash_to_field(msg: &[u8], dst: &[u8]) -> [Fq2; 2] {
+    bls381_uniform_words_to_field(&bls381_expand_message(msg, dst))
+}
+
+/// The reduction of hash_to_field of the uniform bytes, 64 bytes for each Fq element
+pub fn bls381_uniform_words_to_field(words: &[u64; BLS_UNIFORM_WORDS]) -> [Fq2; 2] {
+    let bytes = words.iter().flat_map(|w| w.to_be_bytes()).collect::<Vec<_>>();
+    let modulus = field_to_bn(&-Fq::one()) + BigUint::from(1u32);
+    let fq = |i: usize| -> Fq {
+        bn_to_field(&(BigUint::from_bytes_be(&bytes[64 * i..64 * (i + 1)]) % &modulus))
+    };
+    [Fq2 { c0: fq(0), c1: fq(1) }, Fq2 { c0: fq(2), c1: fq(3) }]
+}
+
+// simplified SWU onto the curve E2' isogenous to E2
+fn map_to_curve_simple_swu(u: &Fq2) -> (Fq2, Fq2) {
+    let a = Fq2 { c0: Fq::zero(), c1: Fq::from(240u64) };
+    let b = Fq2 { c0: Fq::from(1012u64), c1: Fq::from(1012u64) };
+    let z = -Fq2 { c0: Fq::from(2u64), c1: Fq::one() };
+    let tv1 = z.square() * u.square().square() + z * u.square();
+    let x1 = if bool::from(tv1.is_zero()) {
+        b * (z * a).invert().unwrap()
+    } else {
+        -b * a.invert().unwrap() * (Fq2::one() + tv1.invert().unwrap())
+    };
+    let gx = |x: Fq2| x.square() * x + a * x + b;
+    let (x, y) = match Option::<Fq2>::from(gx(x1).sqrt()) {
+        Some(y) => (x1, y),
+        None => {
+            let x2 = z * u.square() * x1;
+            (x2, gx(x2).sqrt().unwrap())
+        }
+    };
+    if fq2_sgn0(u) != fq2_sgn0(&y) {
+        (x, -y)
+    } else {
+        (x, y)
+    }
+}
+
+fn iso_map(x: &Fq2, y: &Fq2) -> G2 {
+    let x_num = fq2_polynomial(&BLS381_ISO3_X_NUM, x);
+    let x_den = fq2_polynomial(&BLS381_ISO3_X_DEN, x);
+    let y_num = fq2_polynomial(&BLS381_ISO3_Y_NUM, x);
+    let y_den = fq2_polynomial(&BLS381_ISO3_Y_DEN, x);
+    G2::from(
+        G2Affine::from_xy(
+            x_num * x_den.invert().unwrap(),
+            *y * y_num * y_den.invert().unwrap(),
+        )
+        .unwrap(),
+    )
+}
+
+/// hash_to_curve of the two elements u = hash_to_field(msg)
+pub fn bls381_map_to_g2(u: &[Fq2; 2]) -> G2Affine {
+    let (x0, y0) = map_to_curve_simple_swu(&u[0]);
+    let (x1, y1) = map_to_curve_simple_swu(&u[1]);
+    let q = iso_map(&x0, &y0) + iso_map(&x1, &y1);
+    // the sum is not in the subgroup yet, so h_eff multiplies it bit by bit
+    let h_eff = BigUint::parse_bytes(BLS381_G2_H_EFF.as_bytes(), 16).unwrap();
+    let mut r = G2::identity();
+    for i in (0..h_eff.bits()).rev() {
+        r = r.double();
+        if h_eff.bit(i) {
+            r = r + q;
+        }
+    }
+    G2Affine::from(r)
+}
+
+pub fn bls381_hash_to_g2(msg: &[u8], dst: &[u8]) -> G2Affine {
+    bls381_map_to_g2(&bls381_hash_to_field(msg, dst))
+}
+
+/// Fast aggregate verify of the keys that signed the same message, the keys are
+/// assumed to come with proofs of possession and an identity aggregate never verifies
+pub fn bls_fast_aggregate_verify(pks: &[G1Affine], u: &[Fq2; 2], sig: &G2Affine) -> bool {
+    let pk = pks.iter().fold(G1::identity(), |acc, pk| acc + G1::from(*pk));
+    !bool::from(pk.is_identity())
+        && pairing(&G1Affine::from(pk), &bls381_map_to_g2(u)) == pairing(&G1Affine::generator(), sig)
+}
+
+pub fn bls_verify(pk: &G1Affine, u: &[Fq2; 2], sig: &G2Affine) -> bool {
+    bls_fast_aggregate_verify(&[*pk], u, sig)
+}
+
+/* Torus compression of pairing results, see circuits::gt. An element f = g + h * w
+ * of Gt other than one is sent as c = (1 + g) / h in Fq6, which halves the words of a
+ * pairing result.
+ */
+
+/// The compressed form of f, or None if f is one
+pub fn bls381_gt_compress(f: &Gt) -> Option<Fq6> {
+    Option::<Fq6>::from(f.0.c1.invert()).map(|h_inv| (f.0.c0 + Fq6::one()) * h_inv)
+}
+
+/// f = (c + w) / (c - w)
+pub fn bls381_gt_decompress(c: &Option<Fq6>) -> Gt {
+    match c {
+        None => Gt::identity(),
+        Some(c) => {
+            let num = Fq12 { c0: *c, c1: Fq6::one() };
+            let den = Fq12 { c0: *c, c1: -Fq6::one() };
+            Gt(num * den.invert().unwrap())
+        }
+    }
+}
+
+/// The result of a gt op call of op (see circuits::gt), zero is its own inverse here
+pub fn bls381_gt_op(op: u64, a: &Fq12, b: &Fq12) -> Fq12 {
+    match op {
+        GT_OP_MUL => *a * *b,
+        GT_OP_INVERT => Option::<Fq12>::from(a.invert()).unwrap_or(Fq12::zero()),
+        GT_OP_EQUAL => if a == b { Fq12::one() } else { Fq12::zero() },
+        _ => panic!("unknown gt op {}", op),
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::{
+        bls381_expand_message, bls381_g1_compressed_words, bls381_g1_to_compressed,
+        bls381_gt_compress, bls381_gt_decompress, bls381_hash_to_field, bls381_hash_to_g2,
+        bls381_map_to_g2, bls_fast_aggregate_verify, bls_verify, bls381_gt_op, kzg_point_evaluation, kzg_to_versioned_hash, kzg_versioned_hash_words, verify_kzg_proof, BLS_SIG_DST,
+    };
+    use crate::circuits::gt::{GT_OP_EQUAL, GT_OP_INVERT, GT_OP_MUL};
+    use crate::circuits::bls::{BLS381_MSM_POINTS, BLS381_VERIFY_PUBKEYS};
     use crate::host::{ExternalHostCallEntry, ExternalHostCallEntryTable, ForeignInst};
     use crate::utils::field_to_bn;
     use halo2_proofs::pairing::bls12_381::pairing;
     use ff::Field;
     use halo2_proofs::pairing::bls12_381::{
-        Fq as Bls381Fq, G1Affine, G2Affine, Gt as Bls381Gt, G1, G2, Fr
+        Fq as Bls381Fq, Fq12, Fq2, G1Affine, G2Affine, Gt as Bls381Gt, G1, G2, Fr
     };
     use halo2_proofs::pairing::group::Group;
     use num_bigint::BigUint;
@@ -80,9 +421,15 @@ mod tests {
         .collect()
     }
 
+    // the identity is pushed with the coordinates (0, 0) like the sum chips return it
     fn bls381_g1_to_args(g: G1Affine, op: ForeignInst) -> Vec<ExternalHostCallEntry> {
-        let mut a = bls381_fq_to_args(g.x, op);
-        let mut b = bls381_fq_to_args(g.y, op);
+        let (x, y) = if bool::from(g.is_identity()) {
+            (Bls381Fq::zero(), Bls381Fq::zero())
+        } else {
+            (g.x, g.y)
+        };
+        let mut a = bls381_fq_to_args(x, op);
+        let mut b = bls381_fq_to_args(y, op);
         let z: u64 = g.is_identity().unwrap_u8() as u64;
         a.append(&mut b);
         a.append(&mut vec![ExternalHostCallEntry {
@@ -93,14 +440,19 @@ mod tests {
         a
     }
 
-    fn bls381_g2_to_pair_args(g: G2Affine) -> Vec<ExternalHostCallEntry> {
-        let x0 = bls381_fq_to_args(g.x.c0, ForeignInst::BlsPairG2);
-        let x1 = bls381_fq_to_args(g.x.c1, ForeignInst::BlsPairG2);
-        let y0 = bls381_fq_to_args(g.y.c0, ForeignInst::BlsPairG2);
-        let y1 = bls381_fq_to_args(g.y.c1, ForeignInst::BlsPairG2);
+    fn bls381_g2_to_args(g: G2Affine, op: ForeignInst) -> Vec<ExternalHostCallEntry> {
+        let (x, y) = if bool::from(g.is_identity()) {
+            (Fq2::zero(), Fq2::zero())
+        } else {
+            (g.x, g.y)
+        };
+        let x0 = bls381_fq_to_args(x.c0, op);
+        let x1 = bls381_fq_to_args(x.c1, op);
+        let y0 = bls381_fq_to_args(y.c0, op);
+        let y1 = bls381_fq_to_args(y.c1, op);
         let z: u64 = g.is_identity().unwrap_u8() as u64;
         let zentry = ExternalHostCallEntry {
-            op: ForeignInst::BlsPairG2 as usize,
+            op: op as usize,
             value: z,
             is_ret: false,
         };
@@ -110,10 +462,32 @@ mod tests {
             .collect()
     }
 
+    fn bls381_gt_to_compressed_args(f: Bls381Gt, op: ForeignInst) -> Vec<ExternalHostCallEntry> {
+        let c = bls381_gt_compress(&f);
+        let mut r = vec![];
+        for x in c.map_or(vec![Bls381Fq::zero(); 6], |c| vec![c.c0.c0, c.c0.c1, c.c1.c0, c.c1.c1, c.c2.c0, c.c2.c1]) {
+            r.append(&mut bls381_fq_to_args(x, op));
+        }
+        r.push(ExternalHostCallEntry {
+            op: op as usize,
+            value: c.is_none() as u64,
+            is_ret: false,
+        });
+        r
+    }
+
+    fn create_bls_pair_compressed_table(a: G1Affine, b: G2Affine) -> ExternalHostCallEntryTable {
+        let ab: Bls381Gt = pairing(&a, &b);
+        let mut r = bls381_g1_to_args(a, ForeignInst::BlsPairCompressedG1);
+        r.append(&mut bls381_g2_to_args(b, ForeignInst::BlsPairCompressedG2));
+        r.append(&mut bls381_gt_to_compressed_args(ab, ForeignInst::BlsPairCompressedGt));
+        ExternalHostCallEntryTable(r)
+    }
+
     pub fn create_bls_pair_shared_table(a: G1Affine, b: G2Affine) -> ExternalHostCallEntryTable {
         let ab: Bls381Gt = pairing(&a, &b);
         let g1_args = bls381_g1_to_args(a, ForeignInst::BlsPairG1);
-        let g2_args = bls381_g2_to_pair_args(b);
+        let g2_args = bls381_g2_to_args(b, ForeignInst::BlsPairG2);
         let ab_args = bls381_gt_to_pair_args(ab);
         let table = ExternalHostCallEntryTable(
             vec![g1_args, g2_args, ab_args]
@@ -124,6 +498,74 @@ mod tests {
         table
     }
 
+    fn create_bls_pair_check_input(pairs: Vec<(G1Affine, G2Affine)>) -> Vec<ExternalHostCallEntry> {
+        let mut r = vec![];
+        let mut product = Bls381Gt::identity();
+        for (a, b) in pairs.into_iter() {
+            product = product + pairing(&a, &b);
+            r.append(&mut bls381_g1_to_args(a, ForeignInst::BlsPairCheckG1));
+            r.append(&mut bls381_g2_to_args(b, ForeignInst::BlsPairCheckG2));
+        }
+        r.push(ExternalHostCallEntry {
+            op: ForeignInst::BlsPairCheckResult as usize,
+            value: (product == Bls381Gt::identity()) as u64,
+            is_ret: false,
+        });
+        r
+    }
+
+    fn create_bls_kzg_input(z: Fr, y: Fr, commitment: G1Affine, proof: G1Affine) -> Vec<ExternalHostCallEntry> {
+        let mut r = vec![];
+        for word in kzg_versioned_hash_words(&kzg_to_versioned_hash(&commitment)) {
+            r.push(ExternalHostCallEntry {
+                op: ForeignInst::BlsKzgPointEvalVersionedHash as usize,
+                value: word,
+                is_ret: false,
+            });
+        }
+        r.append(&mut bls381_fr_to_args(z, ForeignInst::BlsKzgPointEvalZ));
+        r.append(&mut bls381_fr_to_args(y, ForeignInst::BlsKzgPointEvalY));
+        r.append(&mut bls381_g1_to_args(commitment, ForeignInst::BlsKzgPointEvalCommitment));
+        r.append(&mut bls381_g1_to_args(proof, ForeignInst::BlsKzgPointEvalProof));
+        r.push(ExternalHostCallEntry {
+            op: ForeignInst::BlsKzgPointEvalResult as usize,
+            value: verify_kzg_proof(&commitment, &z, &y, &proof) as u64,
+            is_ret: false,
+        });
+        for word in bls381_g1_compressed_words(&commitment) {
+            r.push(ExternalHostCallEntry {
+                op: ForeignInst::BlsKzgPointEvalCompressed as usize,
+                value: word,
+                is_ret: true,
+            });
+        }
+        r
+    }
+
+    fn create_bls_verify_input(pks: Vec<G1Affine>, msg: &[u8], sig: G2Affine) -> Vec<ExternalHostCallEntry> {
+        let u = bls381_hash_to_field(msg, BLS_SIG_DST);
+        let mut r = vec![];
+        let mut padded = pks.clone();
+        padded.resize(BLS381_VERIFY_PUBKEYS, G1Affine::identity());
+        for pk in padded.into_iter() {
+            r.append(&mut bls381_g1_to_args(pk, ForeignInst::BlsVerifyPubkey));
+        }
+        for word in bls381_expand_message(msg, BLS_SIG_DST) {
+            r.push(ExternalHostCallEntry {
+                op: ForeignInst::BlsVerifyUniformBytes as usize,
+                value: word,
+                is_ret: false,
+            });
+        }
+        r.append(&mut bls381_g2_to_args(sig, ForeignInst::BlsVerifySignature));
+        r.push(ExternalHostCallEntry {
+            op: ForeignInst::BlsVerifyResult as usize,
+            value: bls_fast_aggregate_verify(&pks, &u, &sig) as u64,
+            is_ret: false,
+        });
+        r
+    }
+
     fn create_bls_sum_input(new: u32, a: Fr, g: G1Affine, sum: G1Affine) -> Vec<ExternalHostCallEntry> {
         let mut r = vec![];
         r.append(&mut vec![ExternalHostCallEntry {
@@ -137,6 +579,60 @@ mod tests {
         r
     }
 
+    fn bls381_fq12_to_args(f: Fq12, op: ForeignInst) -> Vec<ExternalHostCallEntry> {
+        let mut r = vec![];
+        for fq6 in [f.c0, f.c1] {
+            for fq2 in [fq6.c0, fq6.c1, fq6.c2] {
+                r.append(&mut bls381_fq_to_args(fq2.c0, op));
+                r.append(&mut bls381_fq_to_args(fq2.c1, op));
+            }
+        }
+        r
+    }
+
+    fn create_bls381_gt_input(op: u64, a: Fq12, b: Fq12) -> Vec<ExternalHostCallEntry> {
+        let mut r = vec![ExternalHostCallEntry {
+            op: ForeignInst::BlsGtOp as usize,
+            value: op,
+            is_ret: false,
+        }];
+        r.append(&mut bls381_fq12_to_args(a, ForeignInst::BlsGtA));
+        r.append(&mut bls381_fq12_to_args(b, ForeignInst::BlsGtB));
+        r.append(&mut bls381_fq12_to_args(bls381_gt_op(op, &a, &b), ForeignInst::BlsGtResult));
+        r
+    }
+
+    // a call of the batched msm, padded with zero scalars of the identity
+    fn create_bls_msm_input(new: u32, pairs: &[(Fr, G1)], sum: G1) -> Vec<ExternalHostCallEntry> {
+        assert!(pairs.len() <= BLS381_MSM_POINTS);
+        let mut r = vec![];
+        r.append(&mut vec![ExternalHostCallEntry {
+            op: ForeignInst::BlsMsmNew as usize,
+            value: new as u64,
+            is_ret: false,
+        }]);
+        for i in 0..BLS381_MSM_POINTS {
+            let (a, g) = pairs.get(i).cloned().unwrap_or((Fr::zero(), G1::identity()));
+            r.append(&mut bls381_fr_to_args(a, ForeignInst::BlsMsmScalar));
+            r.append(&mut bls381_g1_to_args(G1Affine::from(g), ForeignInst::BlsMsmG1));
+        }
+        r.append(&mut bls381_g1_to_args(G1Affine::from(sum), ForeignInst::BlsMsmResult));
+        r
+    }
+
+    fn create_bls_sum_g2_input(new: u32, a: Fr, g: G2Affine, sum: G2Affine) -> Vec<ExternalHostCallEntry> {
+        let mut r = vec![];
+        r.append(&mut vec![ExternalHostCallEntry {
+            op: ForeignInst::BlsSumG2New as usize,
+            value: new as u64,
+            is_ret: false,
+        }]);
+        r.append(&mut bls381_fr_to_args(a, ForeignInst::BlsSumG2Scalar));
+        r.append(&mut bls381_g2_to_args(g, ForeignInst::BlsSumG2));
+        r.append(&mut bls381_g2_to_args(sum, ForeignInst::BlsSumG2Result));
+        r
+    }
+
     #[test]
     fn generate_bls_pair_input() {
         let a: G1Affine = G1::random(&mut OsRng).into();
@@ -146,6 +642,138 @@ mod tests {
         serde_json::to_writer_pretty(file, &table).expect("can not write to file");
     }
 
+    #[test]
+    fn test_bls_gt_compress() {
+        let a: G1Affine = G1::random(&mut OsRng).into();
+        let b: G2Affine = G2Affine::from(G2::random(&mut OsRng));
+        let ab = pairing(&a, &b);
+        let c = bls381_gt_compress(&ab);
+        assert!(c.is_some());
+        assert_eq!(bls381_gt_decompress(&c), ab);
+        assert!(bls381_gt_compress(&Bls381Gt::identity()).is_none());
+        assert_eq!(bls381_gt_decompress(&None), Bls381Gt::identity());
+    }
+
+    #[test]
+    fn generate_bls_pair_compressed_input() {
+        let a: G1Affine = G1::random(&mut OsRng).into();
+        let b: G2Affine = G2Affine::from(G2::random(&mut OsRng));
+        let table = create_bls_pair_compressed_table(a, b);
+        let file = File::create("blspaircompressed.json").expect("can not create file");
+        serde_json::to_writer_pretty(file, &table).expect("can not write to file");
+    }
+
+    #[test]
+    fn generate_bls_pair_check_input() {
+        // e(s * a, b) * e(-a, s * b) == 1 with an identity pair, the fourth pair of the
+        // circuit is a hole
+        let a = G1::random(&mut OsRng);
+        let b = G2::random(&mut OsRng);
+        let s = Fr::random(&mut OsRng);
+        let pairs = vec![
+            (G1Affine::from(a * s), G2Affine::from(b)),
+            (G1Affine::from(-a), G2Affine::from(b * s)),
+            (G1Affine::identity(), G2Affine::generator()),
+        ];
+        let table = ExternalHostCallEntryTable(create_bls_pair_check_input(pairs));
+        let file = File::create("blspaircheck.json").expect("can not create file");
+        serde_json::to_writer_pretty(file, &table).expect("can not write to file");
+    }
+
+    #[test]
+    fn test_kzg_point_evaluation() {
+        assert_eq!(
+            hex::encode(bls381_g1_to_compressed(&G1Affine::generator())),
+            "97f1d3a73197d7942695638c4fa9ac0fc3688c4f9774b905a14e3a3f171bac586c55e83ff97a1aeffb3af00adb22c6bb"
+        );
+        assert_eq!(bls381_g1_compressed_words(&G1Affine::generator())[0], 0x97f1d3a73197d794);
+        // the commitment of the zero blob
+        let zero = G1Affine::identity();
+        assert_eq!(bls381_g1_compressed_words(&zero), [0xc0 << 56, 0, 0, 0, 0, 0]);
+        let zero_hash = kzg_to_versioned_hash(&zero);
+        assert_eq!(
+            hex::encode(zero_hash),
+            "010657f37554c781402a22917dee2f75def7ab966d7b770905398eba3c444014"
+        );
+        assert_eq!(kzg_versioned_hash_words(&zero_hash)[0], 0x010657f37554c781);
+        let z = Fr::random(&mut OsRng);
+        assert!(kzg_point_evaluation(&zero_hash, &z, &Fr::zero(), &zero, &zero));
+
+        // a constant polynomial opens to itself everywhere with the identity as its proof
+        let c = Fr::random(&mut OsRng);
+        let commitment = G1Affine::from(G1::generator() * c);
+        let hash = kzg_to_versioned_hash(&commitment);
+        assert!(kzg_point_evaluation(&hash, &z, &c, &commitment, &zero));
+        assert!(!kzg_point_evaluation(&hash, &z, &(c + Fr::one()), &commitment, &zero));
+        assert!(!kzg_point_evaluation(&zero_hash, &z, &c, &commitment, &zero));
+        assert!(!verify_kzg_proof(&commitment, &z, &c, &G1Affine::generator()));
+    }
+
+    #[test]
+    fn generate_bls_kzg_input() {
+        let z = Fr::random(&mut OsRng);
+        let c = Fr::random(&mut OsRng);
+        let commitment = G1Affine::from(G1::generator() * c);
+        let table = ExternalHostCallEntryTable(create_bls_kzg_input(z, c, commitment, G1Affine::identity()));
+        let file = File::create("blskzg.json").expect("can not create file");
+        serde_json::to_writer_pretty(file, &table).expect("can not write to file");
+    }
+
+    #[test]
+    fn test_bls_hash_to_g2() {
+        // the test vector of the reference document for msg = ""
+        let dst = b"QUUX-V01-CS02-with-BLS12381G2_XMD:SHA-256_SSWU_RO_";
+        let p = bls381_hash_to_g2(b"", dst);
+        let should_be = [
+            "193548053368451749411421515628510806626565736652086807419354395577367693778571452628423727082668900187036482254730",
+            "891930009643099423308102777951250899694559203647724988361022851024990473423938537113948850338098230396747396259901",
+            "771717272055834152378281705972671257005357145478800908373659404991537354153455452961747174765859335819766715637138",
+            "2810310118582126634041133454180705304393079139103252956502404531123692847658283858246402311867775854528543237781718",
+        ];
+        for (c, should_be) in [p.x.c0, p.x.c1, p.y.c0, p.y.c1].iter().zip(should_be.iter()) {
+            assert_eq!(field_to_bn(c).to_string(), *should_be);
+        }
+    }
+
+    #[test]
+    fn test_bls_verify() {
+        let msg = b"zkwasm host circuits";
+        let u = bls381_hash_to_field(msg, BLS_SIG_DST);
+        let h = G2::from(bls381_map_to_g2(&u));
+        let sks = [(); 3].map(|_| Fr::random(&mut OsRng));
+        let pks = sks
+            .iter()
+            .map(|sk| G1Affine::from(G1::generator() * *sk))
+            .collect::<Vec<_>>();
+
+        let sig = G2Affine::from(h * sks[0]);
+        assert!(bls_verify(&pks[0], &u, &sig));
+        assert!(!bls_verify(&pks[1], &u, &sig));
+        let other = bls381_hash_to_field(b"another message", BLS_SIG_DST);
+        assert!(!bls_verify(&pks[0], &other, &sig));
+
+        let aggregate = G2Affine::from(h * (sks[0] + sks[1] + sks[2]));
+        assert!(bls_fast_aggregate_verify(&pks, &u, &aggregate));
+        assert!(!bls_fast_aggregate_verify(&pks[0..2], &u, &aggregate));
+        // the identity aggregate never verifies
+        assert!(!bls_fast_aggregate_verify(&[], &u, &G2Affine::identity()));
+    }
+
+    #[test]
+    fn generate_bls_verify_input() {
+        let msg = b"zkwasm host circuits";
+        let h = G2::from(bls381_hash_to_g2(msg, BLS_SIG_DST));
+        let sks = [(); 3].map(|_| Fr::random(&mut OsRng));
+        let pks = sks
+            .iter()
+            .map(|sk| G1Affine::from(G1::generator() * *sk))
+            .collect::<Vec<_>>();
+        let sig = G2Affine::from(h * (sks[0] + sks[1] + sks[2]));
+        let table = ExternalHostCallEntryTable(create_bls_verify_input(pks, msg, sig));
+        let file = File::create("blsverify.json").expect("can not create file");
+        serde_json::to_writer_pretty(file, &table).expect("can not write to file");
+    }
+
     #[test]
     fn generate_bls_sum_input() {
         let l = [2, 4, 3];
@@ -165,4 +793,108 @@ mod tests {
         let file = File::create("blssumtest.json").expect("can not create file");
         serde_json::to_writer_pretty(file, &table).expect("can not write to file");
     }
+
+    #[test]
+    fn generate_bls_sum_identity_input() {
+        // an identity point, a zero scalar, a doubling and sums that cancel
+        let a = Fr::random(&mut OsRng);
+        let g = G1::random(&mut OsRng);
+        let h = G1::random(&mut OsRng);
+        let steps = vec![
+            (1, a, G1::identity()),
+            (0, Fr::zero(), g),
+            (0, a, g),
+            (0, a, g),
+            (0, -a - a, g),
+            (1, a, h),
+            (0, a, -h),
+        ];
+        let mut inputs = vec![];
+        let mut z = G1::identity();
+        for (new, a_j, g_j) in steps.into_iter() {
+            if new == 1 {
+                z = G1::identity();
+            }
+            z = z.add(g_j * a_j);
+            inputs.append(&mut create_bls_sum_input(new, a_j, G1Affine::from(g_j), G1Affine::from(z)));
+        }
+        let table = ExternalHostCallEntryTable(inputs);
+        let file = File::create("blssumidentity.json").expect("can not create file");
+        serde_json::to_writer_pretty(file, &table).expect("can not write to file");
+    }
+
+    #[test]
+    fn generate_bls_msm_input() {
+        // a session over two calls, then one whose pairs cancel
+        let g = G1::random(&mut OsRng);
+        let a = Fr::random(&mut OsRng);
+        let calls = vec![
+            (1, (0..BLS381_MSM_POINTS).map(|_| (Fr::random(&mut OsRng), G1::random(&mut OsRng))).collect::<Vec<_>>()),
+            (0, (0..5).map(|_| (Fr::random(&mut OsRng), G1::random(&mut OsRng))).collect::<Vec<_>>()),
+            (1, vec![(a, g), (Fr::zero(), G1::random(&mut OsRng)), (a, -g), (a, G1::identity())]),
+        ];
+        let mut inputs = vec![];
+        let mut z = G1::identity();
+        for (new, pairs) in calls.into_iter() {
+            if new == 1 {
+                z = G1::identity();
+            }
+            for (a_j, g_j) in pairs.iter() {
+                z = z.add(*g_j * *a_j);
+            }
+            inputs.append(&mut create_bls_msm_input(new, &pairs, z));
+        }
+        let table = ExternalHostCallEntryTable(inputs);
+        let file = File::create("blsmsm.json").expect("can not create file");
+        serde_json::to_writer_pretty(file, &table).expect("can not write to file");
+    }
+
+    #[test]
+    fn generate_bls_sum_g2_input() {
+        let l = [2, 1];
+        let mut inputs = vec![];
+        for i in 0..2 {
+            let mut z = G2::identity();
+            for j in 0..l[i] {
+                let new = if j == 0 {1} else {0};
+                let a_j = Fr::random(&mut OsRng);
+                let g_j = G2::random(&mut OsRng);
+                let r = g_j * a_j;
+                z = z.add(r.clone());
+                inputs.append(&mut create_bls_sum_g2_input(new, a_j, G2Affine::from(g_j), G2Affine::from(z)));
+            }
+        }
+        let table = ExternalHostCallEntryTable(inputs);
+        let file = File::create("blssumg2test.json").expect("can not create file");
+        serde_json::to_writer_pretty(file, &table).expect("can not write to file");
+    }
+
+    #[test]
+    fn generate_bls_gt_input() {
+        // e(a, b) * e(a, b)^-1 == 1 and the comparison of pairing results
+        let p: Bls381Gt = pairing(
+            &G1Affine::from(G1::random(&mut OsRng)),
+            &G2Affine::from(G2::random(&mut OsRng)),
+        );
+        let q: Bls381Gt = pairing(
+            &G1Affine::from(G1::random(&mut OsRng)),
+            &G2Affine::from(G2::random(&mut OsRng)),
+        );
+        let p_inv = bls381_gt_op(GT_OP_INVERT, &p.0, &Fq12::zero());
+        let calls = vec![
+            (GT_OP_MUL, p.0, q.0),
+            (GT_OP_INVERT, p.0, Fq12::zero()),
+            (GT_OP_MUL, p.0, p_inv),
+            (GT_OP_EQUAL, p.0, p.0),
+            (GT_OP_EQUAL, p.0, q.0),
+        ];
+        let mut inputs = vec![];
+        for (op, a, b) in calls.into_iter() {
+            inputs.append(&mut create_bls381_gt_input(op, a, b));
+        }
+        assert_eq!(bls381_gt_op(GT_OP_MUL, &p.0, &p_inv), Fq12::one());
+        let table = ExternalHostCallEntryTable(inputs);
+        let file = File::create("blsgt.json").expect("can not create file");
+        serde_json::to_writer_pretty(file, &table).expect("can not write to file");
+    }
 }
@@ -1,11 +1,52 @@
+/* Torus compression of pairing results, see circuits::gt. An element f = g + h * w
+ * of Gt other than one is sent as c = (1 + g) / h in Fq6, which halves the words of a
+ * pairing result. The gt op calls combine pairing results in Fq12.
+ */
+use crate::circuits::gt::{GT_OP_EQUAL, GT_OP_INVERT, GT_OP_MUL};
+use ff::Field;
+use halo2_proofs::pairing::bn256::{Fq12, Fq6, Gt};
+use halo2_proofs::pairing::group::Group;
+
+/// The compressed form of f, or None if f is one
+pub fn bn256_gt_compress(f: &Gt) -> Option<Fq6> {
+    Option::<Fq6>::from(f.0.c1.invert()).map(|h_inv| (f.0.c0 + Fq6::one()) * h_inv)
+}
+
+/// f = (c + w) / (c - w)
+pub fn bn256_gt_decompress(c: &Option<Fq6>) -> Gt {
+    match c {
+        None => Gt::identity(),
+        Some(c) => {
+            let num = Fq12 { c0: *c, c1: Fq6::one() };
+            let den = Fq12 { c0: *c, c1: -Fq6::one() };
+            Gt(num * den.invert().unwrap())
+        }
+    }
+}
+
+/// The result of a gt op call of op (see circuits::gt), zero is its own inverse here
+pub fn bn256_gt_op(op: u64, a: &Fq12, b: &Fq12) -> Fq12 {
+    match op {
+        GT_OP_MUL => *a * *b,
+        GT_OP_INVERT => Option::<Fq12>::from(a.invert()).unwrap_or(Fq12::zero()),
+        GT_OP_EQUAL => if a == b { Fq12::one() } else { Fq12::zero() },
+        _ => panic!("unknown gt op {}", op),
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::{bn256_gt_compress, bn256_gt_decompress, bn256_gt_op};
+    use crate::circuits::gt::{GT_OP_EQUAL, GT_OP_INVERT, GT_OP_MUL};
+    use crate::circuits::bn256::BN256_MSM_POINTS;
     use crate::host::{ExternalHostCallEntry, ExternalHostCallEntryTable, ForeignInst};
     use crate::utils::field_to_bn;
     use halo2_proofs::pairing::bn256::pairing;
     use halo2_proofs::pairing::bn256::Fr;
     use ff::Field;
-    use halo2_proofs::pairing::bn256::{Fq as Bn256Fq, G1Affine, G2Affine, Gt as Bn256Gt, G1, G2};
+    use halo2_proofs::pairing::bn256::{
+        Fq as Bn256Fq, Fq12, Fq2, G1Affine, G2Affine, Gt as Bn256Gt, G1, G2,
+    };
     use halo2_proofs::pairing::group::Group;
     use num_bigint::BigUint;
     use rand::rngs::OsRng;
@@ -79,10 +120,16 @@ mod tests {
         .collect()
     }
 
+    // the identity is pushed with the coordinates (0, 0) like the sum chips return it
     fn bn256_g1_to_args(g: G1, op: ForeignInst) -> Vec<ExternalHostCallEntry> {
-        let g_af = G1Affine::from(g);
-        let mut a = bn256_fq_to_args(g_af.x, op);
-        let mut b = bn256_fq_to_args(g_af.y, op);
+        let (x, y) = if bool::from(g.is_identity()) {
+            (Bn256Fq::zero(), Bn256Fq::zero())
+        } else {
+            let g_af = G1Affine::from(g);
+            (g_af.x, g_af.y)
+        };
+        let mut a = bn256_fq_to_args(x, op);
+        let mut b = bn256_fq_to_args(y, op);
         let z: u64 = g.is_identity().unwrap_u8() as u64;
         a.append(&mut b);
         a.append(&mut vec![ExternalHostCallEntry {
@@ -93,15 +140,20 @@ mod tests {
         a
     }
 
-    fn bn256_g2_to_pair_args(g: G2) -> Vec<ExternalHostCallEntry> {
-        let g_af = G2Affine::from(g);
-        let x0 = bn256_fq_to_args(g_af.x.c0, ForeignInst::Bn254PairG2);
-        let x1 = bn256_fq_to_args(g_af.x.c1, ForeignInst::Bn254PairG2);
-        let y0 = bn256_fq_to_args(g_af.y.c0, ForeignInst::Bn254PairG2);
-        let y1 = bn256_fq_to_args(g_af.y.c1, ForeignInst::Bn254PairG2);
+    fn bn256_g2_to_args(g: G2, op: ForeignInst) -> Vec<ExternalHostCallEntry> {
+        let (x, y) = if bool::from(g.is_identity()) {
+            (Fq2::zero(), Fq2::zero())
+        } else {
+            let g_af = G2Affine::from(g);
+            (g_af.x, g_af.y)
+        };
+        let x0 = bn256_fq_to_args(x.c0, op);
+        let x1 = bn256_fq_to_args(x.c1, op);
+        let y0 = bn256_fq_to_args(y.c0, op);
+        let y1 = bn256_fq_to_args(y.c1, op);
         let z: u64 = g.is_identity().unwrap_u8() as u64;
         let zentry = ExternalHostCallEntry {
-            op: ForeignInst::Bn254PairG2 as usize,
+            op: op as usize,
             value: z,
             is_ret: false,
         };
@@ -111,12 +163,34 @@ mod tests {
             .collect()
     }
 
+    fn bn256_gt_to_compressed_args(f: Bn256Gt, op: ForeignInst) -> Vec<ExternalHostCallEntry> {
+        let c = bn256_gt_compress(&f);
+        let mut r = vec![];
+        for x in c.map_or(vec![Bn256Fq::zero(); 6], |c| vec![c.c0.c0, c.c0.c1, c.c1.c0, c.c1.c1, c.c2.c0, c.c2.c1]) {
+            r.append(&mut bn256_fq_to_args(x, op));
+        }
+        r.push(ExternalHostCallEntry {
+            op: op as usize,
+            value: c.is_none() as u64,
+            is_ret: false,
+        });
+        r
+    }
+
+    fn create_bn256_pair_compressed_table(a: G1, b: G2) -> ExternalHostCallEntryTable {
+        let ab: Bn256Gt = pairing(&G1Affine::from(a), &G2Affine::from(b));
+        let mut r = bn256_g1_to_args(a, ForeignInst::Bn254PairCompressedG1);
+        r.append(&mut bn256_g2_to_args(b, ForeignInst::Bn254PairCompressedG2));
+        r.append(&mut bn256_gt_to_compressed_args(ab, ForeignInst::Bn254PairCompressedGt));
+        ExternalHostCallEntryTable(r)
+    }
+
     fn create_bn256_pair_shared_table(a: G1, b: G2) -> ExternalHostCallEntryTable {
         let a_af = G1Affine::from(a);
         let b_af = G2Affine::from(b);
         let ab: Bn256Gt = pairing(&a_af, &b_af);
         let g1_args = bn256_g1_to_args(a, ForeignInst::Bn254PairG1);
-        let g2_args = bn256_g2_to_pair_args(b);
+        let g2_args = bn256_g2_to_args(b, ForeignInst::Bn254PairG2);
         let ab_args = bn256_gt_to_pair_args(ab);
         let table = ExternalHostCallEntryTable(
             vec![g1_args, g2_args, ab_args]
@@ -127,6 +201,22 @@ mod tests {
         table
     }
 
+    fn create_bn256_pair_check_input(pairs: Vec<(G1, G2)>) -> Vec<ExternalHostCallEntry> {
+        let mut r = vec![];
+        let mut product = Bn256Gt::identity();
+        for (a, b) in pairs.into_iter() {
+            product = product + pairing(&G1Affine::from(a), &G2Affine::from(b));
+            r.append(&mut bn256_g1_to_args(a, ForeignInst::Bn254PairCheckG1));
+            r.append(&mut bn256_g2_to_args(b, ForeignInst::Bn254PairCheckG2));
+        }
+        r.push(ExternalHostCallEntry {
+            op: ForeignInst::Bn254PairCheckResult as usize,
+            value: (product == Bn256Gt::identity()) as u64,
+            is_ret: false,
+        });
+        r
+    }
+
     fn create_bn256_sum_input(new: u32, a: Fr, g: G1, sum: G1) -> Vec<ExternalHostCallEntry> {
         let mut r = vec![];
         r.append(&mut vec![ExternalHostCallEntry {
@@ -139,6 +229,60 @@ mod tests {
         r.append(&mut bn256_g1_to_args(sum, ForeignInst::Bn254SumResult));
         r
     }
+    fn bn256_fq12_to_args(f: Fq12, op: ForeignInst) -> Vec<ExternalHostCallEntry> {
+        let mut r = vec![];
+        for fq6 in [f.c0, f.c1] {
+            for fq2 in [fq6.c0, fq6.c1, fq6.c2] {
+                r.append(&mut bn256_fq_to_args(fq2.c0, op));
+                r.append(&mut bn256_fq_to_args(fq2.c1, op));
+            }
+        }
+        r
+    }
+
+    fn create_bn256_gt_input(op: u64, a: Fq12, b: Fq12) -> Vec<ExternalHostCallEntry> {
+        let mut r = vec![ExternalHostCallEntry {
+            op: ForeignInst::Bn254GtOp as usize,
+            value: op,
+            is_ret: false,
+        }];
+        r.append(&mut bn256_fq12_to_args(a, ForeignInst::Bn254GtA));
+        r.append(&mut bn256_fq12_to_args(b, ForeignInst::Bn254GtB));
+        r.append(&mut bn256_fq12_to_args(bn256_gt_op(op, &a, &b), ForeignInst::Bn254GtResult));
+        r
+    }
+
+    // a call of the batched msm, padded with zero scalars of the identity
+    fn create_bn256_msm_input(new: u32, pairs: &[(Fr, G1)], sum: G1) -> Vec<ExternalHostCallEntry> {
+        assert!(pairs.len() <= BN256_MSM_POINTS);
+        let mut r = vec![];
+        r.append(&mut vec![ExternalHostCallEntry {
+            op: ForeignInst::Bn254MsmNew as usize,
+            value: new as u64,
+            is_ret: false,
+        }]);
+        for i in 0..BN256_MSM_POINTS {
+            let (a, g) = pairs.get(i).cloned().unwrap_or((Fr::zero(), G1::identity()));
+            r.append(&mut bn256_fr_to_args(a, ForeignInst::Bn254MsmScalar));
+            r.append(&mut bn256_g1_to_args(g, ForeignInst::Bn254MsmG1));
+        }
+        r.append(&mut bn256_g1_to_args(sum, ForeignInst::Bn254MsmResult));
+        r
+    }
+
+    fn create_bn256_sum_g2_input(new: u32, a: Fr, g: G2, sum: G2) -> Vec<ExternalHostCallEntry> {
+        let mut r = vec![];
+        r.append(&mut vec![ExternalHostCallEntry {
+            op: ForeignInst::Bn254SumG2New as usize,
+            value: new as u64,
+            is_ret: false,
+        }]);
+        r.append(&mut bn256_fr_to_args(a, ForeignInst::Bn254SumG2Scalar));
+        r.append(&mut bn256_g2_to_args(g, ForeignInst::Bn254SumG2));
+        r.append(&mut bn256_g2_to_args(sum, ForeignInst::Bn254SumG2Result));
+        r
+    }
+
     #[test]
     fn generate_bn256_pair_input() {
         let a = G1::random(&mut OsRng);
@@ -148,6 +292,40 @@ mod tests {
         serde_json::to_writer_pretty(file, &table).expect("can not write to file");
     }
 
+    #[test]
+    fn test_bn256_gt_compress() {
+        let a = G1Affine::from(G1::random(&mut OsRng));
+        let b = G2Affine::from(G2::random(&mut OsRng));
+        let ab = pairing(&a, &b);
+        let c = bn256_gt_compress(&ab);
+        assert!(c.is_some());
+        assert_eq!(bn256_gt_decompress(&c), ab);
+        assert!(bn256_gt_compress(&Bn256Gt::identity()).is_none());
+        assert_eq!(bn256_gt_decompress(&None), Bn256Gt::identity());
+    }
+
+    #[test]
+    fn generate_bn256_pair_compressed_input() {
+        let a = G1::random(&mut OsRng);
+        let b = G2::random(&mut OsRng);
+        let table = create_bn256_pair_compressed_table(a, b);
+        let file = File::create("bn256paircompressed.json").expect("can not create file");
+        serde_json::to_writer_pretty(file, &table).expect("can not write to file");
+    }
+
+    #[test]
+    fn generate_bn256_pair_check_input() {
+        // e(s * a, b) * e(-a, s * b) == 1 with an identity pair, the fourth pair of the
+        // circuit is a hole
+        let a = G1::random(&mut OsRng);
+        let b = G2::random(&mut OsRng);
+        let s = Fr::random(&mut OsRng);
+        let pairs = vec![(a * s, b), (-a, b * s), (G1::identity(), G2::generator())];
+        let table = ExternalHostCallEntryTable(create_bn256_pair_check_input(pairs));
+        let file = File::create("bn256paircheck.json").expect("can not create file");
+        serde_json::to_writer_pretty(file, &table).expect("can not write to file");
+    }
+
     #[test]
     fn generate_bn256_sum_input() {
         let l = [2, 4, 3];
@@ -167,4 +345,108 @@ mod tests {
         let file = File::create("bn256sumtest.json").expect("can not create file");
         serde_json::to_writer_pretty(file, &table).expect("can not write to file");
     }
+
+    #[test]
+    fn generate_bn256_sum_identity_input() {
+        // an identity point, a zero scalar, a doubling and sums that cancel
+        let a = Fr::random(&mut OsRng);
+        let g = G1::random(&mut OsRng);
+        let h = G1::random(&mut OsRng);
+        let steps = vec![
+            (1, a, G1::identity()),
+            (0, Fr::zero(), g),
+            (0, a, g),
+            (0, a, g),
+            (0, -a - a, g),
+            (1, a, h),
+            (0, a, -h),
+        ];
+        let mut inputs = vec![];
+        let mut z = G1::identity();
+        for (new, a_j, g_j) in steps.into_iter() {
+            if new == 1 {
+                z = G1::identity();
+            }
+            z = z.add(g_j * a_j);
+            inputs.append(&mut create_bn256_sum_input(new, a_j, g_j, z));
+        }
+        let table = ExternalHostCallEntryTable(inputs);
+        let file = File::create("bn256sumidentity.json").expect("can not create file");
+        serde_json::to_writer_pretty(file, &table).expect("can not write to file");
+    }
+
+    #[test]
+    fn generate_bn256_sum_g2_input() {
+        let l = [2, 1];
+        let mut inputs = vec![];
+        for i in 0..2 {
+            let mut z = G2::identity();
+            for j in 0..l[i] {
+                let new = if j == 0 {1} else {0};
+                let a_j = Fr::random(&mut OsRng);
+                let g_j = G2::random(&mut OsRng);
+                let r = g_j * a_j;
+                z = z.add(r.clone());
+                inputs.append(&mut create_bn256_sum_g2_input(new, a_j, g_j, z));
+            }
+        }
+        let table = ExternalHostCallEntryTable(inputs);
+        let file = File::create("bn256sumg2test.json").expect("can not create file");
+        serde_json::to_writer_pretty(file, &table).expect("can not write to file");
+    }
+
+    #[test]
+    fn generate_bn256_msm_input() {
+        // a session over two calls, then one whose pairs cancel
+        let g = G1::random(&mut OsRng);
+        let a = Fr::random(&mut OsRng);
+        let calls = vec![
+            (1, (0..BN256_MSM_POINTS).map(|_| (Fr::random(&mut OsRng), G1::random(&mut OsRng))).collect::<Vec<_>>()),
+            (0, (0..5).map(|_| (Fr::random(&mut OsRng), G1::random(&mut OsRng))).collect::<Vec<_>>()),
+            (1, vec![(a, g), (Fr::zero(), G1::random(&mut OsRng)), (a, -g), (a, G1::identity())]),
+        ];
+        let mut inputs = vec![];
+        let mut z = G1::identity();
+        for (new, pairs) in calls.into_iter() {
+            if new == 1 {
+                z = G1::identity();
+            }
+            for (a_j, g_j) in pairs.iter() {
+                z = z.add(*g_j * *a_j);
+            }
+            inputs.append(&mut create_bn256_msm_input(new, &pairs, z));
+        }
+        let table = ExternalHostCallEntryTable(inputs);
+        let file = File::create("bn256msm.json").expect("can not create file");
+        serde_json::to_writer_pretty(file, &table).expect("can not write to file");
+    }
+
+    #[test]
+    fn generate_bn256_gt_input() {
+        // e(a, b) * e(a, b)^-1 == 1 and the comparison of pairing results
+        let p: Bn256Gt = pairing(
+            &G1Affine::from(G1::random(&mut OsRng)),
+            &G2Affine::from(G2::random(&mut OsRng)),
+        );
+        let q: Bn256Gt = pairing(
+            &G1Affine::from(G1::random(&mut OsRng)),
+            &G2Affine::from(G2::random(&mut OsRng)),
+        );
+        let p_inv = bn256_gt_op(GT_OP_INVERT, &p.0, &Fq12::zero());
+        let calls = vec![
+            (GT_OP_MUL, p.0, q.0),
+            (GT_OP_INVERT, p.0, Fq12::zero()),
+            (GT_OP_MUL, p.0, p_inv),
+            (GT_OP_EQUAL, p.0, p.0),
+            (GT_OP_EQUAL, p.0, q.0),
+        ];
+        let mut inputs = vec![];
+        for (op, a, b) in calls.into_iter() {
+            inputs.append(&mut create_bn256_gt_input(op, a, b));
+        }
+        assert_eq!(bn256_gt_op(GT_OP_MUL, &p.0, &p_inv), Fq12::one());
+        let table = ExternalHostCallEntryTable(inputs);
+        let file = File::create("bn256gt.json").expect("can not create file");
+        serde_json::to_writer_pretty(file, &table).expect("can not write to file");
+    }
 }
@@ -3,6 +3,8 @@ use halo2_proofs::pairing::bn256::Fr;
 //use lazy_static;
 use crate::host::cache::DATA_CACHE;
 use crate::host::db::{MongoDB, TreeDB};
+use crate::host::merkle::MerkleError;
+use crate::host::mongomerkle::MongoMerkle;
 use crate::host::poseidon::POSEIDON_HASHER;
 use mongodb::bson::doc;
 use mongodb::bson::{spec::BinarySubtype, Bson};
@@ -11,6 +13,7 @@ use serde::{
     Deserialize, Deserializer, Serialize, Serializer,
 };
 use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 
 fn deserialize_u256_from_binary<'de, D>(deserializer: D) -> Result<[u8; 32], D::Error>
@@ -92,6 +95,62 @@ impl MongoDataHash {
         }
     }
 
+    /// Count, for every data hash, how many distinct merkle leaves reachable from
+    /// the live roots hold it as their data. Hashes that are not referenced are absent.
+    pub fn collect_references<const DEPTH: usize>(
+        merkle: &MongoMerkle<DEPTH>,
+        live_roots: &Vec<[u8; 32]>,
+    ) -> Result<HashMap<[u8; 32], u64>, MerkleError> {
+        let first = (1u64 << DEPTH) - 1;
+        let mut leaves = HashSet::new();
+        for root in live_roots.iter() {
+            for leaf in merkle.leaves_in_range(root, first..first + (1u64 << DEPTH)) {
+                leaves.insert(leaf?);
+            }
+        }
+        let mut references = HashMap::new();
+        for (_, data) in leaves.into_iter() {
+            *references.entry(data).or_insert(0) += 1;
+        }
+        Ok(references)
+    }
+
+    /// Delete every data record whose hash is not in references.
+    /// Returns the number of records removed.
+    ///
+    /// The references have to be collected from every live root, see garbage_collect.
+    pub fn prune(
+        &mut self,
+        references: &HashMap<[u8; 32], u64>,
+    ) -> Result<usize, mongodb::error::Error> {
+        let hashes = self.db.borrow().get_data_hashes()?;
+        let mut cache = DATA_CACHE.lock().unwrap();
+        let mut pruned = 0;
+        for hash in hashes.iter() {
+            if references.get(hash).map_or(true, |x| *x == 0) {
+                self.db.borrow_mut().delete_data_record(hash)?;
+                cache.pop(hash);
+                pruned += 1;
+            }
+        }
+        Ok(pruned)
+    }
+
+    /// Mark and sweep: drop all data records not referenced from any leaf under live_roots.
+    ///
+    /// live_roots has to hold every root that can still be read, including the pending
+    /// roots of updates that are not committed yet. update_record stores a record before
+    /// the leaf that references it is committed, so a record that is only referenced
+    /// from a root missing from live_roots is deleted.
+    pub fn garbage_collect<const DEPTH: usize>(
+        &mut self,
+        merkle: &MongoMerkle<DEPTH>,
+        live_roots: &Vec<[u8; 32]>,
+    ) -> Result<usize, MerkleError> {
+        let references = Self::collect_references(merkle, live_roots)?;
+        Ok(self.prune(&references).expect("Unexpected DB Error"))
+    }
+
     /* We always insert new record as there might be uncommitted update to the merkle tree */
     pub fn update_record(&mut self, record: DataHashRecord) -> Result<(), mongodb::error::Error> {
         let r: Option<DataHashRecord> = self.get_record(&record.hash)?;
@@ -122,6 +181,11 @@ pub struct DataHashRecord {
 
 impl DataHashRecord {
     pub fn new(&mut self, data: &Vec<u8>) -> Self {
+        Self::from_data(data)
+    }
+
+    /// Hash the data in 16 bytes chunks with POSEIDON_HASHER and wrap it as a record
+    pub fn from_data(data: &Vec<u8>) -> Self {
         let mut hasher = POSEIDON_HASHER.clone();
         let batchdata = data
             .chunks(16)
@@ -150,4 +214,81 @@ impl DataHashRecord {
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::{DataHashRecord, MongoDataHash};
+    use crate::host::db::{MongoDB, TreeDB};
+    use crate::host::merkle::{MerkleNode, MerkleTree};
+    use crate::host::mongomerkle::{MongoMerkle, DEFAULT_HASH_VEC};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    /* Store two blobs, reference one of them from a leaf and check that
+     * garbage collection only removes the unreferenced one.
+     */
+    fn test_datahash_garbage_collect() {
+        const DEPTH: usize = 32;
+        const TEST_ADDR: [u8; 32] = [10; 32];
+        let db: Rc<RefCell<dyn TreeDB>> = Rc::new(RefCell::new(MongoDB::new(TEST_ADDR)));
+        let mut store = MongoDataHash::construct(TEST_ADDR, Some(db.clone()));
+        let live = DataHashRecord::from_data(&[1u8; 48].to_vec());
+        let dead = DataHashRecord::from_data(&[2u8; 48].to_vec());
+        store.update_record(live.clone()).unwrap();
+        store.update_record(dead.clone()).unwrap();
+
+        let mut mt =
+            MongoMerkle::<DEPTH>::construct(TEST_ADDR, DEFAULT_HASH_VEC[DEPTH], Some(db.clone()));
+        let (mut leaf, _) = mt.get_leaf_with_proof((1u64 << DEPTH) - 1).unwrap();
+        leaf.set(&live.hash.to_vec());
+        mt.set_leaf_with_proof(&leaf).unwrap();
+
+        let roots = vec![mt.get_root_hash()];
+        let references = MongoDataHash::collect_references(&mt, &roots).unwrap();
+        assert_eq!(references.get(&live.hash), Some(&1));
+        assert_eq!(references.get(&dead.hash), None);
+
+        store.garbage_collect(&mt, &roots).unwrap();
+        assert!(store.get_record(&live.hash).unwrap().is_some());
+        assert!(store.get_record(&dead.hash).unwrap().is_none());
+    }
+
+    #[test]
+    /* A blob that is only referenced from the root of an uncommitted update survives
+     * garbage collection as long as that root is passed as a live root.
+     */
+    fn test_datahash_garbage_collect_pending_root() {
+        const DEPTH: usize = 32;
+        const TEST_ADDR: [u8; 32] = [11; 32];
+        let db: Rc<RefCell<dyn TreeDB>> = Rc::new(RefCell::new(MongoDB::new(TEST_ADDR)));
+        let mut store = MongoDataHash::construct(TEST_ADDR, Some(db.clone()));
+        let committed = DataHashRecord::from_data(&[3u8; 48].to_vec());
+        let pending = DataHashRecord::from_data(&[4u8; 48].to_vec());
+        let dead = DataHashRecord::from_data(&[5u8; 48].to_vec());
+        store.update_record(committed.clone()).unwrap();
+        store.update_record(dead.clone()).unwrap();
+
+        let mut mt =
+            MongoMerkle::<DEPTH>::construct(TEST_ADDR, DEFAULT_HASH_VEC[DEPTH], Some(db.clone()));
+        let (mut leaf, _) = mt.get_leaf_with_proof((1u64 << DEPTH) - 1).unwrap();
+        leaf.set(&committed.hash.to_vec());
+        mt.set_leaf_with_proof(&leaf).unwrap();
+        let committed_root = mt.get_root_hash();
+
+        // an update whose record is stored before its root is committed
+        store.update_record(pending.clone()).unwrap();
+        let (mut leaf, _) = mt.get_leaf_with_proof(1u64 << DEPTH).unwrap();
+        leaf.set(&pending.hash.to_vec());
+        mt.set_leaf_with_proof(&leaf).unwrap();
+        let pending_root = mt.get_root_hash();
+
+        let references = MongoDataHash::collect_references(&mt, &vec![committed_root]).unwrap();
+        assert_eq!(references.get(&pending.hash), None);
+
+        store
+            .garbage_collect(&mt, &vec![committed_root, pending_root])
+            .unwrap();
+        assert!(store.get_record(&committed.hash).unwrap().is_some());
+        assert!(store.get_record(&pending.hash).unwrap().is_some());
+        assert!(store.get_record(&dead.hash).unwrap().is_none());
+    }
+}
@@ -19,6 +19,15 @@ lazy_static::lazy_static! {
     };
 }
 
+/// The error of the maintenance methods of a TreeDB that does not implement them
+pub fn unsupported(method: &str) -> mongodb::error::Error {
+    mongodb::error::Error::custom(format!("TreeDB::{} is not supported", method))
+}
+
+/* The maintenance methods that fsck and the pruning of data records need have default
+ * bodies which return the unsupported error, so that stores which only implement the
+ * lookups still compile.
+ */
 pub trait TreeDB {
     fn get_merkle_record(
         &self,
@@ -33,12 +42,35 @@ pub trait TreeDB {
         records: &Vec<MerkleRecord>,
     ) -> Result<(), mongodb::error::Error>;
 
+    fn get_merkle_records_by_index(
+        &self,
+        _index: u64,
+    ) -> Result<Vec<MerkleRecord>, mongodb::error::Error> {
+        Err(unsupported("get_merkle_records_by_index"))
+    }
+
+    fn delete_merkle_record(
+        &mut self,
+        _index: u64,
+        _hash: &[u8; 32],
+    ) -> Result<(), mongodb::error::Error> {
+        Err(unsupported("delete_merkle_record"))
+    }
+
     fn get_data_record(
         &self,
         hash: &[u8; 32],
     ) -> Result<Option<DataHashRecord>, mongodb::error::Error>;
 
     fn set_data_record(&mut self, record: DataHashRecord) -> Result<(), mongodb::error::Error>;
+
+    fn get_data_hashes(&self) -> Result<Vec<[u8; 32]>, mongodb::error::Error> {
+        Err(unsupported("get_data_hashes"))
+    }
+
+    fn delete_data_record(&mut self, _hash: &[u8; 32]) -> Result<(), mongodb::error::Error> {
+        Err(unsupported("delete_data_record"))
+    }
 }
 
 #[derive(Clone)]
@@ -92,6 +124,29 @@ impl TreeDB for MongoDB {
         Ok(())
     }
 
+    fn get_merkle_records_by_index(
+        &self,
+        index: u64,
+    ) -> Result<Vec<MerkleRecord>, mongodb::error::Error> {
+        let collection = self.merkel_collection()?;
+        let mut filter = doc! {};
+        filter.insert("index", u64_to_bson(index));
+        collection.find(filter, None)?.collect()
+    }
+
+    fn delete_merkle_record(
+        &mut self,
+        index: u64,
+        hash: &[u8; 32],
+    ) -> Result<(), mongodb::error::Error> {
+        let collection = self.merkel_collection()?;
+        let mut filter = doc! {};
+        filter.insert("index", u64_to_bson(index));
+        filter.insert("hash", u256_to_bson(hash));
+        collection.delete_many(filter, None)?;
+        Ok(())
+    }
+
     fn get_data_record(
         &self,
         hash: &[u8; 32],
@@ -107,6 +162,22 @@ impl TreeDB for MongoDB {
         collection.insert_one(record, None)?;
         Ok(())
     }
+
+    fn get_data_hashes(&self) -> Result<Vec<[u8; 32]>, mongodb::error::Error> {
+        let collection = self.data_collection()?;
+        collection
+            .find(doc! {}, None)?
+            .map(|x| x.map(|record| record.hash))
+            .collect()
+    }
+
+    fn delete_data_record(&mut self, hash: &[u8; 32]) -> Result<(), mongodb::error::Error> {
+        let collection = self.data_collection()?;
+        let mut filter = doc! {};
+        filter.insert("hash", u256_to_bson(hash));
+        collection.delete_many(filter, None)?;
+        Ok(())
+    }
 }
 
 pub fn get_collection<T>(
@@ -0,0 +1,219 @@
+/* Native reference of the Groth16 verifier over BN254.
+ *
+ * A proof (A, B, C) of the public inputs x_i is valid for the verifying key
+ * (alpha, beta, gamma, delta, IC) if
+ *     e(A, B) == e(alpha, beta) * e(vk_x, gamma) * e(C, delta)
+ * where vk_x = IC_0 + sum x_i * IC_i.
+ *
+ * The host op takes a fixed number of public inputs, so shorter keys are padded with
+ * identity points in IC and zero inputs. The padded points are part of the hash of
+ * the key, so the inputs beyond those of the key do not change the result. Keys with
+ * more than GROTH16_PUBLIC_INPUTS inputs are not supported. The verifying key is committed by the
+ * poseidon hash of the limbs the circuit reads it as: every coordinate is split into
+ * 5 words of 54 bits which are merged in pairs (2, 2, 1) and every point ends with
+ * its identity flag.
+ */
+use crate::host::poseidon::POSEIDON_HASHER;
+use crate::utils::field_to_bn;
+use halo2_proofs::arithmetic::BaseExt;
+use halo2_proofs::pairing::bn256::{pairing, Fr, G1Affine, G2Affine, Gt, G1, G2};
+use halo2_proofs::pairing::group::{Curve, Group};
+use num_bigint::BigUint;
+
+pub const GROTH16_PUBLIC_INPUTS: usize = 8;
+
+/// the words of a coordinate
+pub const FQ_WORDS: usize = 5;
+pub const G1_WORDS: usize = 2 * FQ_WORDS + 1;
+pub const G2_WORDS: usize = 4 * FQ_WORDS + 1;
+pub const VK_WORDS: usize = 3 * G2_WORDS + (GROTH16_PUBLIC_INPUTS + 2) * G1_WORDS;
+pub const PROOF_WORDS: usize = G2_WORDS + 2 * G1_WORDS;
+
+#[derive(Clone, Debug)]
+pub struct Groth16VerifyingKey {
+    pub alpha: G1Affine,
+    pub beta: G2Affine,
+    pub gamma: G2Affine,
+    pub delta: G2Affine,
+    /// IC_0 followed by one point for each public input
+    pub ic: Vec<G1Affine>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Groth16Proof {
+    pub a: G1Affine,
+    pub b: G2Affine,
+    pub c: G1Affine,
+}
+
+impl Groth16VerifyingKey {
+    /// The key with IC padded to GROTH16_PUBLIC_INPUTS inputs
+    pub fn padded(&self) -> Self {
+        assert!(self.ic.len() <= GROTH16_PUBLIC_INPUTS + 1);
+        let mut vk = self.clone();
+        vk.ic.resize(GROTH16_PUBLIC_INPUTS + 1, G1::identity().to_affine());
+        vk
+    }
+
+    pub fn to_words(&self) -> Vec<u64> {
+        let vk = self.padded();
+        let mut r = g1_to_words(&vk.alpha);
+        r.append(&mut g2_to_words(&vk.beta));
+        r.append(&mut g2_to_words(&vk.gamma));
+        r.append(&mut g2_to_words(&vk.delta));
+        for ic in vk.ic.iter() {
+            r.append(&mut g1_to_words(ic));
+        }
+        r
+    }
+
+    /// The limbs of the padded key in the order of to_words
+    pub fn to_limbs(&self) -> Vec<Fr> {
+        let words = self.to_words();
+        let mut start = 0;
+        let mut r = vec![];
+        for size in vk_point_words() {
+            r.append(&mut point_words_to_limbs(&words[start..start + size]));
+            start += size;
+        }
+        r
+    }
+
+    pub fn hash(&self) -> Fr {
+        vk_limbs_hash(&self.to_limbs())
+    }
+}
+
+impl Groth16Proof {
+    pub fn to_words(&self) -> Vec<u64> {
+        let mut r = g1_to_words(&self.a);
+        r.append(&mut g2_to_words(&self.b));
+        r.append(&mut g1_to_words(&self.c));
+        r
+    }
+}
+
+fn fq_to_words<F: BaseExt>(f: &F) -> Vec<u64> {
+    let mut bn = field_to_bn(f);
+    let mut r = vec![];
+    for _ in 0..FQ_WORDS {
+        let d = BigUint::from(1u64 << 54);
+        r.push((bn.clone() % d.clone()).to_u64_digits().first().cloned().unwrap_or(0));
+        bn = bn / d;
+    }
+    r
+}
+
+pub fn g1_to_words(p: &G1Affine) -> Vec<u64> {
+    let mut r = fq_to_words(&p.x);
+    r.append(&mut fq_to_words(&p.y));
+    r.push(G1::from(*p).is_identity().unwrap_u8() as u64);
+    r
+}
+
+pub fn g2_to_words(q: &G2Affine) -> Vec<u64> {
+    let mut r = fq_to_words(&q.x.c0);
+    r.append(&mut fq_to_words(&q.x.c1));
+    r.append(&mut fq_to_words(&q.y.c0));
+    r.append(&mut fq_to_words(&q.y.c1));
+    r.push(G2::from(*q).is_identity().unwrap_u8() as u64);
+    r
+}
+
+/// The words of alpha, beta, gamma, delta and every point of the padded IC
+pub fn vk_point_words() -> Vec<usize> {
+    let mut sizes = vec![G1_WORDS, G2_WORDS, G2_WORDS, G2_WORDS];
+    sizes.resize(4 + GROTH16_PUBLIC_INPUTS + 1, G1_WORDS);
+    sizes
+}
+
+/// The limbs of the words of a g1 or g2 point as the circuit merges them
+pub fn point_words_to_limbs(words: &[u64]) -> Vec<Fr> {
+    let words = words.iter().map(|w| Fr::from(*w)).collect::<Vec<_>>();
+    point_operands_to_limbs(&words)
+}
+
+/// point_words_to_limbs of the operands of host calls, which are not reduced to words
+pub fn point_operands_to_limbs(words: &[Fr]) -> Vec<Fr> {
+    let shift = Fr::from(1u64 << 54);
+    let nb_fq = words.len() / FQ_WORDS;
+    let mut r = vec![];
+    for fq in words[0..nb_fq * FQ_WORDS].chunks_exact(FQ_WORDS) {
+        r.push(fq[0] + fq[1] * shift);
+        r.push(fq[2] + fq[3] * shift);
+        r.push(fq[4]);
+    }
+    r.push(words[nb_fq * FQ_WORDS]);
+    r
+}
+
+/// The poseidon hash that commits to the limbs of a verifying key
+pub fn vk_limbs_hash(limbs: &[Fr]) -> Fr {
+    let mut hasher = POSEIDON_HASHER.clone();
+    hasher.update(limbs);
+    hasher.squeeze()
+}
+
+pub fn groth16_verify(vk: &Groth16VerifyingKey, inputs: &[Fr], proof: &Groth16Proof) -> bool {
+    let vk = vk.padded();
+    assert!(inputs.len() <= GROTH16_PUBLIC_INPUTS);
+    let vk_x = inputs
+        .iter()
+        .zip(vk.ic.iter().skip(1))
+        .fold(G1::from(vk.ic[0]), |acc, (x, ic)| acc + ic * x);
+    let lhs = pairing(&proof.a, &proof.b);
+    let rhs: Gt = pairing(&vk.alpha, &vk.beta)
+        + pairing(&vk_x.to_affine(), &vk.gamma)
+        + pairing(&proof.c, &vk.delta);
+    lhs == rhs
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{groth16_verify, Groth16Proof, Groth16VerifyingKey, GROTH16_PUBLIC_INPUTS};
+    use ff::Field;
+    use halo2_proofs::pairing::bn256::{Fr, G1, G2};
+    use halo2_proofs::pairing::group::{Curve, Group};
+    use rand::rngs::OsRng;
+
+    /// A valid instance made from known discrete logs of the key
+    pub(crate) fn random_instance(
+        nb_inputs: usize,
+    ) -> (Groth16VerifyingKey, Vec<Fr>, Groth16Proof) {
+        let [a, b, c, d, r, s] = [(); 6].map(|_| Fr::random(&mut OsRng));
+        let ks = (0..nb_inputs + 1)
+            .map(|_| Fr::random(&mut OsRng))
+            .collect::<Vec<_>>();
+        let inputs = (0..nb_inputs)
+            .map(|_| Fr::random(&mut OsRng))
+            .collect::<Vec<_>>();
+        let vk_x = inputs
+            .iter()
+            .zip(ks.iter().skip(1))
+            .fold(ks[0], |acc, (x, k)| acc + *x * k);
+        let vk = Groth16VerifyingKey {
+            alpha: (G1::generator() * a).to_affine(),
+            beta: (G2::generator() * b).to_affine(),
+            gamma: (G2::generator() * c).to_affine(),
+            delta: (G2::generator() * d).to_affine(),
+            ic: ks.iter().map(|k| (G1::generator() * k).to_affine()).collect(),
+        };
+        let proof = Groth16Proof {
+            a: (G1::generator() * r).to_affine(),
+            b: (G2::generator() * s).to_affine(),
+            c: (G1::generator() * ((r * s - a * b - vk_x * c) * d.invert().unwrap())).to_affine(),
+        };
+        (vk, inputs, proof)
+    }
+
+    #[test]
+    fn test_groth16_verify() {
+        let (vk, mut inputs, proof) = random_instance(3);
+        assert!(groth16_verify(&vk, &inputs, &proof));
+        // the padded key commits to the same points
+        assert_eq!(vk.hash(), vk.padded().hash());
+        assert_eq!(vk.padded().ic.len(), GROTH16_PUBLIC_INPUTS + 1);
+        inputs[1] = inputs[1] + Fr::one();
+        assert!(!groth16_verify(&vk, &inputs, &proof));
+    }
+}
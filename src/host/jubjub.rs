@@ -1,4 +1,5 @@
-use crate::utils::bn_to_field;
+use crate::host::poseidon::POSEIDON_HASHER;
+use crate::utils::{bn_to_field, field_to_bn};
 use ff::Field;
 use halo2_proofs::pairing::bn256::Fr;
 use lazy_static::lazy_static;
@@ -6,13 +7,13 @@ use num_bigint::BigUint;
 use std::ops::{AddAssign, MulAssign, SubAssign};
 
 lazy_static! {
-    static ref D_BIG: BigUint = BigUint::parse_bytes(
+    pub(crate) static ref D_BIG: BigUint = BigUint::parse_bytes(
         b"12181644023421730124874158521699555681764249180949974110617291017600649128846",
         10
     )
     .unwrap();
-    static ref D: Fr = bn_to_field(&(D_BIG));
-    static ref A_BIG: BigUint = BigUint::parse_bytes(
+    pub(crate) static ref D: Fr = bn_to_field(&(D_BIG));
+    pub(crate) static ref A_BIG: BigUint = BigUint::parse_bytes(
         b"21888242871839275222246405745257275088548364400416034343698204186575808495616",
         10
     )
@@ -23,8 +24,31 @@ lazy_static! {
         10
     )
     .unwrap();
+    /// order of the prime order subgroup generated by BASE
+    pub static ref SUBGROUP_ORDER: BigUint = BigUint::parse_bytes(
+        b"2736030358979909402780800718157159386076813972158567259200215660948447373041",
+        10
+    )
+    .unwrap();
+    /// base point of the EdDSA signatures
+    pub static ref BASE: Point = Point {
+        x: bn_to_field(&BigUint::parse_bytes(
+            b"2ef3f9b423a2c8c74e9803958f6c320e854a1c1c06cd5cc8fd221dc052d76df7",
+            16
+        )
+        .unwrap()),
+        y: bn_to_field(&BigUint::parse_bytes(
+            b"05a01167ea785d3f784224644a68e4067532c815f5f6d57d984b5c0e9c6c94b7",
+            16
+        )
+        .unwrap()),
+    };
+    pub static ref BASE_TABLES: Vec<Vec<Point>> = fixed_base_tables(&BASE);
 }
 
+pub const FIXED_BASE_WINDOW_BITS: usize = 4;
+pub const FIXED_BASE_WINDOWS: usize = 256 / FIXED_BASE_WINDOW_BITS;
+
 #[derive(Clone, Debug)]
 pub struct PointProjective {
     pub x: Fr,
@@ -189,17 +213,83 @@ impl Point {
         }
         r.affine()
     }
+
+    /// Whether a * x^2 + y^2 == 1 + d * x^2 * y^2
+    pub fn is_on_curve(&self) -> bool {
+        let x2 = self.x.square();
+        let y2 = self.y.square();
+        *A * x2 + y2 == Fr::one() + *D * x2 * y2
+    }
+
+    /// Whether the point is in the subgroup generated by BASE, assuming it is on the curve
+    pub fn is_in_subgroup(&self) -> bool {
+        self.mul_scalar(&SUBGROUP_ORDER) == Point::identity()
+    }
+}
+
+/// The window tables of a base known in advance: tables[j][m] = m * 16^j * base,
+/// so that n * base is the sum of tables[j][m_j] over the 4 bit windows m_j of n
+pub fn fixed_base_tables(base: &Point) -> Vec<Vec<Point>> {
+    let mut tables = vec![];
+    let mut window_base = base.projective();
+    for _ in 0..FIXED_BASE_WINDOWS {
+        let mut table = vec![Point::identity().projective()];
+        for m in 1..(1 << FIXED_BASE_WINDOW_BITS) {
+            table.push(table[m - 1].add(&window_base));
+        }
+        for _ in 0..FIXED_BASE_WINDOW_BITS {
+            window_base = window_base.double();
+        }
+        tables.push(table.iter().map(|p| p.affine()).collect());
+    }
+    tables
 }
 
 pub fn test_bit(b: &[u8], i: usize) -> bool {
     b[i / 8] & (1 << (i % 8)) != 0
 }
 
+/* EdDSA-Poseidon signatures over the alt BabyJubjub curve.
+ *
+ * The challenge is H(R, A, M) = poseidon([R.x, R.y, A.x, A.y, M, 0, 0, 0]) which fits in
+ * a single permutation of POSEIDON_HASHER. A signature (R, s) of M under the public key
+ * A is valid iff s * B == R + H(R, A, M) * A. Same as the circuit, s is not required to
+ * be reduced modulo the subgroup order.
+ */
+pub fn eddsa_hash(r: &Point, pk: &Point, msg: &Fr) -> Fr {
+    let mut hasher = POSEIDON_HASHER.clone();
+    hasher.update_exact(&[
+        r.x,
+        r.y,
+        pk.x,
+        pk.y,
+        *msg,
+        Fr::zero(),
+        Fr::zero(),
+        Fr::zero(),
+    ])
+}
+
+/// Sign msg with secret key sk and nonce k. Returns (R, s).
+pub fn eddsa_sign(sk: &BigUint, msg: &Fr, k: &BigUint) -> (Point, BigUint) {
+    let pk = BASE.mul_scalar(sk);
+    let r = BASE.mul_scalar(k);
+    let h = field_to_bn(&eddsa_hash(&r, &pk, msg));
+    let s = (k + h * sk) % &*SUBGROUP_ORDER;
+    (r, s)
+}
+
+pub fn eddsa_verify(pk: &Point, r: &Point, s: &BigUint, msg: &Fr) -> bool {
+    let h = field_to_bn(&eddsa_hash(r, pk, msg));
+    BASE.mul_scalar(s) == r.add(&pk.mul_scalar(&h))
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Point;
+    use super::{eddsa_sign, eddsa_verify, Point, BASE, BASE_TABLES, SUBGROUP_ORDER};
     use crate::utils::bn_to_field;
     use crate::utils::field_to_bn;
+    use ff::Field;
     use halo2_proofs::pairing::bn256::Fr;
     use num_bigint::BigUint;
     use std::str::FromStr;
@@ -304,4 +394,50 @@ mod tests {
         // assert_eq!(lhs,rhs)
         assert_eq!(Point::identity(),rst);
     }
+
+    #[test]
+    fn test_point_checks() {
+        assert!(BASE.is_on_curve() && BASE.is_in_subgroup());
+        assert!(Point::identity().is_on_curve() && Point::identity().is_in_subgroup());
+        // (0, -1) has order two
+        let p = Point {
+            x: Fr::zero(),
+            y: -Fr::one(),
+        };
+        assert!(p.is_on_curve() && !p.is_in_subgroup());
+        let q = Point {
+            x: BASE.x,
+            y: BASE.x,
+        };
+        assert!(!q.is_on_curve());
+    }
+
+    #[test]
+    fn test_eddsa_sign_verify() {
+        let sk = BigUint::from(0x1234567890abcdefu64);
+        let k = BigUint::from(0xfedcba0987654321u64);
+        let msg = Fr::from(42u64);
+        let pk = BASE.mul_scalar(&sk);
+        assert_eq!(BASE.mul_scalar(&SUBGROUP_ORDER), Point::identity());
+        let (r, s) = eddsa_sign(&sk, &msg, &k);
+        assert!(eddsa_verify(&pk, &r, &s, &msg));
+        assert!(!eddsa_verify(&pk, &r, &s, &(msg + Fr::one())));
+        assert!(!eddsa_verify(&BASE, &r, &s, &msg));
+        assert!(!eddsa_verify(&pk, &r, &(s + 1u64), &msg));
+    }
+
+    #[test]
+    fn test_fixed_base_tables() {
+        let n = BigUint::parse_bytes(
+            b"f0e1d2c3b4a5968778695a4b3c2d1e0f0123456789abcdeffedcba9876543210",
+            16,
+        )
+        .unwrap();
+        let digits = n.to_radix_le(16);
+        let sum = digits
+            .iter()
+            .enumerate()
+            .fold(Point::identity(), |acc, (j, m)| acc.add(&BASE_TABLES[j][*m as usize]));
+        assert_eq!(sum, BASE.mul_scalar(&n));
+    }
 }
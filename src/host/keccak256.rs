@@ -0,0 +1,164 @@
+/* Native reference of the keccak256 host calls.
+ *
+ * The state is 25 u64 lanes indexed by x + 5 * y. Messages are padded with the
+ * keccak pad10*1 rule (0x01 ... 0x80) and absorbed in blocks of 17 lanes (136 bytes).
+ * After each block the first 4 lanes of the state are the squeezed 256 bit result.
+ */
+
+pub const RATE_LANES: usize = 17;
+pub const RATE_BYTES: usize = RATE_LANES * 8;
+pub const OUTPUT_LANES: usize = 4;
+
+pub const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808A,
+    0x8000000080008000,
+    0x000000000000808B,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008A,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000A,
+    0x000000008000808B,
+    0x800000000000008B,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800A,
+    0x800000008000000A,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+/// rotation offsets of the rho step, indexed by [x][y]
+pub const ROTATION_CONSTANTS: [[u32; 5]; 5] = [
+    [0, 36, 3, 41, 18],
+    [1, 44, 10, 45, 2],
+    [62, 6, 43, 15, 61],
+    [28, 55, 25, 21, 56],
+    [27, 20, 39, 8, 14],
+];
+
+pub fn keccak_f(a: &mut [u64; 25]) {
+    for rc in ROUND_CONSTANTS.iter() {
+        // theta
+        let mut c = [0u64; 5];
+        for x in 0..5 {
+            c[x] = a[x] ^ a[x + 5] ^ a[x + 10] ^ a[x + 15] ^ a[x + 20];
+        }
+        for x in 0..5 {
+            let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+            for y in 0..5 {
+                a[x + 5 * y] ^= d;
+            }
+        }
+        // rho and pi
+        let mut b = [0u64; 25];
+        for x in 0..5 {
+            for y in 0..5 {
+                b[y + 5 * ((2 * x + 3 * y) % 5)] =
+                    a[x + 5 * y].rotate_left(ROTATION_CONSTANTS[x][y]);
+            }
+        }
+        // chi
+        for x in 0..5 {
+            for y in 0..5 {
+                a[x + 5 * y] =
+                    b[x + 5 * y] ^ (!b[(x + 1) % 5 + 5 * y] & b[(x + 2) % 5 + 5 * y]);
+            }
+        }
+        // iota
+        a[0] ^= rc;
+    }
+}
+
+/// Pad the message and split it into blocks of little endian lanes
+pub fn keccak256_blocks(bytes: &[u8]) -> Vec<[u64; RATE_LANES]> {
+    let mut padded = bytes.to_vec();
+    padded.push(0x01);
+    padded.resize((padded.len() + RATE_BYTES - 1) / RATE_BYTES * RATE_BYTES, 0);
+    *padded.last_mut().unwrap() |= 0x80;
+    padded
+        .chunks_exact(RATE_BYTES)
+        .map(|block| {
+            block
+                .chunks_exact(8)
+                .map(|x| u64::from_le_bytes(x.try_into().unwrap()))
+                .collect::<Vec<_>>()
+                .try_into()
+                .unwrap()
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Keccak256 {
+    state: [u64; 25],
+}
+
+impl Keccak256 {
+    pub fn new() -> Self {
+        Keccak256 { state: [0u64; 25] }
+    }
+
+    /// Absorb one padded block and return the squeezed lanes
+    pub fn update_exact(&mut self, block: &[u64; RATE_LANES]) -> [u64; OUTPUT_LANES] {
+        for (lane, value) in self.state.iter_mut().zip(block.iter()) {
+            *lane ^= value;
+        }
+        keccak_f(&mut self.state);
+        self.state[0..OUTPUT_LANES].try_into().unwrap()
+    }
+}
+
+pub fn keccak256(bytes: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    let mut result = [0u64; OUTPUT_LANES];
+    for block in keccak256_blocks(bytes).iter() {
+        result = hasher.update_exact(block);
+    }
+    result
+        .iter()
+        .flat_map(|x| x.to_le_bytes())
+        .collect::<Vec<_>>()
+        .try_into()
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{keccak256, keccak256_blocks};
+
+    fn hex(bytes: [u8; 32]) -> String {
+        bytes.iter().map(|x| format!("{:02x}", x)).collect()
+    }
+
+    #[test]
+    fn test_keccak256() {
+        assert_eq!(
+            hex(keccak256(b"")),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+        assert_eq!(
+            hex(keccak256(b"abc")),
+            "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+        );
+        // a full block of zeros is followed by a block of padding
+        assert_eq!(keccak256_blocks(&[0u8; 136]).len(), 2);
+        assert_eq!(
+            hex(keccak256(&[0u8; 136])),
+            "3a5912a7c5faa06ee4fe906253e339467a9ce87d533c65be3c15cb231cdb25f9"
+        );
+        let bytes = (0..200).map(|x| x as u8).collect::<Vec<_>>();
+        assert_eq!(
+            hex(keccak256(&bytes)),
+            "bfb0aa97863e797943cf7c33bb7e880bb4543f3d2703c0923c6901c2af57b890"
+        );
+    }
+}
@@ -0,0 +1,261 @@
+use crate::host::datahash::{DataHashRecord, MongoDataHash};
+use crate::host::db::TreeDB;
+use crate::host::merkle::{MerkleError, MerkleErrorCode, MerkleNode, MerkleProof, MerkleTree};
+use crate::host::mongomerkle::MongoMerkle;
+use crate::host::poseidon::POSEIDON_HASHER;
+use ff::PrimeField;
+use halo2_proofs::pairing::bn256::Fr;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/* A sparse key value map on top of MongoMerkle.
+ *
+ * A key is hashed with POSEIDON_HASHER into its key hash and the lower DEPTH bits of
+ * the first u64 word of the key hash give the first leaf offset to probe. A populated
+ * leaf stores the hash of the DataHashRecord of its entry, so that the key hash of an
+ * occupied leaf can always be recovered. Collisions are resolved by linear probing
+ * over at most MAX_PROBE consecutive leaves, wrapping around the leaf layer.
+ *
+ * Entry encoding: key hash (32 bytes) | value (32 bytes)
+ *
+ * The calling convention of the host opcodes is
+ * KVMapSetRoot (4 * u64)
+ * KVMapKey (4 * u64), the key hash
+ * KVMapSet (4 * u64) / KVMapGet (4 * u64)
+ * KVMapGetRoot (4 * u64)
+ * A get of an absent key returns zero. The kvmap circuit proves every leaf of the
+ * probe sequence against the root, see KVMapChip.
+ */
+pub const MAX_PROBE: usize = 8;
+
+pub struct MongoKVMap<const DEPTH: usize> {
+    merkle: MongoMerkle<DEPTH>,
+    datahash: MongoDataHash,
+}
+
+/// A probed leaf of the probe sequence of a key: its proof, its data and its entry
+/// if it has one
+pub struct KVMapProbe<const DEPTH: usize> {
+    pub proof: MerkleProof<[u8; 32], DEPTH>,
+    pub data: [u8; 32],
+    pub entry: Option<([u8; 32], [u8; 32])>,
+}
+
+fn bytes_to_fields(bytes: &[u8]) -> Vec<Fr> {
+    bytes
+        .chunks(16)
+        .map(|x| {
+            let mut v = x.to_vec();
+            v.resize(32, 0);
+            Fr::from_repr(v.try_into().unwrap()).unwrap()
+        })
+        .collect::<Vec<Fr>>()
+}
+
+/// The poseidon hash of the length and the 16 bytes chunks of key
+pub fn key_hash(key: &[u8]) -> [u8; 32] {
+    let mut hasher = POSEIDON_HASHER.clone();
+    let mut inputs = vec![Fr::from(key.len() as u64)];
+    inputs.append(&mut bytes_to_fields(key));
+    hasher.update(&inputs);
+    hasher.squeeze().to_repr()
+}
+
+pub fn encode_entry(key_hash: &[u8; 32], value: &[u8; 32]) -> Vec<u8> {
+    let mut data = key_hash.to_vec();
+    data.extend_from_slice(value);
+    data
+}
+
+pub fn decode_entry(data: &[u8]) -> Option<([u8; 32], [u8; 32])> {
+    if data.len() != 64 {
+        return None;
+    }
+    Some((
+        data[0..32].try_into().unwrap(),
+        data[32..64].try_into().unwrap(),
+    ))
+}
+
+impl<const DEPTH: usize> MongoKVMap<DEPTH> {
+    pub fn construct(
+        addr: [u8; 32],
+        root: [u8; 32],
+        db: Option<Rc<RefCell<dyn TreeDB>>>,
+    ) -> Self {
+        MongoKVMap {
+            merkle: MongoMerkle::construct(addr, root, db.clone()),
+            datahash: MongoDataHash::construct(addr, db),
+        }
+    }
+
+    pub fn get_root_hash(&self) -> [u8; 32] {
+        self.merkle.get_root_hash()
+    }
+
+    fn offset_mask() -> u64 {
+        assert!(DEPTH < 64, "the leaf offsets of the map must fit in a u64");
+        (1u64 << DEPTH) - 1
+    }
+
+    /// The offset (within the leaf layer) of the first slot probed for key_hash
+    pub fn key_to_offset(key_hash: &[u8; 32]) -> u64 {
+        u64::from_le_bytes(key_hash[0..8].try_into().unwrap()) & Self::offset_mask()
+    }
+
+    fn offset_to_index(offset: u64) -> u64 {
+        Self::offset_mask() + (offset & Self::offset_mask())
+    }
+
+    /// Probe the MAX_PROBE leaves of the probe sequence of key_hash and return them
+    /// with the position of the leaf that holds key_hash, or of the first empty leaf.
+    /// The leaves after that position only have an entry if their record decodes.
+    pub fn probe(
+        &self,
+        key_hash: &[u8; 32],
+    ) -> Result<(Vec<KVMapProbe<DEPTH>>, usize), MerkleError> {
+        let start = Self::key_to_offset(key_hash);
+        let mut probes = vec![];
+        let mut found = None;
+        for i in 0..MAX_PROBE {
+            let index = Self::offset_to_index(start + i as u64);
+            let (leaf, proof) = self.merkle.get_leaf_with_proof(index)?;
+            let entry = if leaf.data == [0; 32] {
+                None
+            } else {
+                let record = self
+                    .datahash
+                    .get_record(&leaf.data)
+                    .expect("Unexpected DB Error");
+                let entry = record.and_then(|x| decode_entry(&x.data));
+                if found.is_none() && entry.is_none() {
+                    return Err(MerkleError::new(
+                        leaf.data,
+                        index,
+                        MerkleErrorCode::MissingData,
+                    ));
+                }
+                entry
+            };
+            if found.is_none() && entry.map_or(true, |(k, _)| k == *key_hash) {
+                found = Some(i);
+            }
+            probes.push(KVMapProbe {
+                proof,
+                data: leaf.data,
+                entry,
+            });
+        }
+        let found = found.ok_or(MerkleError::new(
+            [0; 32],
+            Self::offset_to_index(start),
+            MerkleErrorCode::KeyProbeExhausted,
+        ))?;
+        Ok((probes, found))
+    }
+
+    /// Get the value of key_hash together with the proof of the leaf that holds it.
+    /// If it is absent the proof is for the empty leaf that terminates its probe sequence.
+    pub fn get_by_hash(
+        &self,
+        key_hash: &[u8; 32],
+    ) -> Result<(Option<[u8; 32]>, MerkleProof<[u8; 32], DEPTH>), MerkleError> {
+        let (mut probes, found) = self.probe(key_hash)?;
+        let probe = probes.swap_remove(found);
+        Ok((probe.entry.map(|(_, v)| v), probe.proof))
+    }
+
+    /// Set the value of key_hash and return the proof of the updated leaf against the new root
+    pub fn set_by_hash(
+        &mut self,
+        key_hash: &[u8; 32],
+        value: &[u8; 32],
+    ) -> Result<MerkleProof<[u8; 32], DEPTH>, MerkleError> {
+        let (_, proof) = self.get_by_hash(key_hash)?;
+        let record = DataHashRecord::from_data(&encode_entry(key_hash, value));
+        let hash = record.hash;
+        self.datahash
+            .update_record(record)
+            .expect("Unexpected DB Error");
+        let (mut leaf, _) = self.merkle.get_leaf_with_proof(proof.index)?;
+        leaf.set(&hash.to_vec());
+        self.merkle.set_leaf_with_proof(&leaf)
+    }
+
+    pub fn get(
+        &self,
+        key: &[u8],
+    ) -> Result<(Option<[u8; 32]>, MerkleProof<[u8; 32], DEPTH>), MerkleError> {
+        self.get_by_hash(&key_hash(key))
+    }
+
+    pub fn set(
+        &mut self,
+        key: &[u8],
+        value: &[u8; 32],
+    ) -> Result<MerkleProof<[u8; 32], DEPTH>, MerkleError> {
+        self.set_by_hash(&key_hash(key), value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_entry, encode_entry, key_hash, MongoKVMap, MAX_PROBE};
+    use crate::host::merkle::MerkleTree;
+    use crate::host::mongomerkle::{MongoMerkle, DEFAULT_HASH_VEC};
+
+    #[test]
+    fn test_kvmap_entry_encoding() {
+        let data = encode_entry(&[1u8; 32], &[2u8; 32]);
+        let (k, v) = decode_entry(&data).unwrap();
+        assert_eq!(k, [1u8; 32]);
+        assert_eq!(v, [2u8; 32]);
+        assert!(decode_entry(&data[0..4]).is_none());
+    }
+
+    #[test]
+    /* Set two keys, read them back after reloading from the new root
+     * and check that an absent key returns None with a valid proof.
+     */
+    fn test_kvmap_get_set() {
+        const DEPTH: usize = 32;
+        const TEST_ADDR: [u8; 32] = [7; 32];
+        let mut map = MongoKVMap::<DEPTH>::construct(TEST_ADDR, DEFAULT_HASH_VEC[DEPTH], None);
+        let proof = map.set(b"alice", &[1u8; 32]).unwrap();
+        let mt = MongoMerkle::<DEPTH>::construct(TEST_ADDR, map.get_root_hash(), None);
+        assert!(mt.verify_proof(&proof).unwrap());
+        map.set(b"bob", &[2u8; 32]).unwrap();
+        map.set(b"alice", &[3u8; 32]).unwrap();
+
+        let map = MongoKVMap::<DEPTH>::construct(TEST_ADDR, map.get_root_hash(), None);
+        let (v, proof) = map.get(b"alice").unwrap();
+        assert_eq!(v, Some([3u8; 32]));
+        assert!(mt.verify_proof(&proof).unwrap());
+        let (v, _) = map.get(b"bob").unwrap();
+        assert_eq!(v, Some([2u8; 32]));
+        let (v, proof) = map.get(b"carol").unwrap();
+        assert_eq!(v, None);
+        assert!(mt.verify_proof(&proof).unwrap());
+    }
+
+    #[test]
+    /* Two key hashes with the same first slot are probed one after the other */
+    fn test_kvmap_probe_collision() {
+        const DEPTH: usize = 8;
+        const TEST_ADDR: [u8; 32] = [8; 32];
+        let mut map = MongoKVMap::<DEPTH>::construct(TEST_ADDR, DEFAULT_HASH_VEC[DEPTH], None);
+        let a = [1u8; 32];
+        let mut b = a;
+        b[31] = 2;
+        map.set_by_hash(&a, &[3u8; 32]).unwrap();
+        map.set_by_hash(&b, &[4u8; 32]).unwrap();
+        let (probes, found) = map.probe(&b).unwrap();
+        assert_eq!(probes.len(), MAX_PROBE);
+        assert_eq!(found, 1);
+        assert_eq!(probes[0].entry, Some((a, [3u8; 32])));
+        assert_eq!(probes[1].proof.index, probes[0].proof.index + 1);
+        assert_eq!(map.get_by_hash(&b).unwrap().0, Some([4u8; 32]));
+        assert_eq!(map.get(b"alice").unwrap().0, None);
+        assert_eq!(map.get_by_hash(&key_hash(b"alice")).unwrap().0, None);
+    }
+}
@@ -1,13 +1,21 @@
+pub mod blake2;
 pub mod bls;
 pub mod bn256;
 pub mod cache;
 pub mod db;
+pub mod groth16;
 pub mod jubjub;
+pub mod keccak256;
 pub mod mongomerkle;
+pub mod pedersen;
 pub mod merkle;
 pub mod poseidon;
+pub mod poseidon2;
 pub mod rmd160;
+pub mod secp256k1;
+pub mod secp256r1;
 pub mod datahash;
+pub mod kvmap;
 
 use halo2_proofs::arithmetic::FieldExt;
 use serde::{Deserialize, Serialize};
@@ -55,6 +63,101 @@ pub enum ForeignInst {
     JubjubSumNew,
     JubjubSumPush,
     JubjubSumResult,
+    Secp256k1VerifyMsg,
+    Secp256k1VerifySig,
+    Secp256k1VerifyPk,
+    Secp256k1VerifyResult,
+    Secp256k1RecoverMsg,
+    Secp256k1RecoverSig,
+    Secp256k1RecoverPk,
+    Secp256r1VerifyMsg,
+    Secp256r1VerifySig,
+    Secp256r1VerifyPk,
+    Secp256r1VerifyResult,
+    JubjubEddsaVerifyMsg,
+    JubjubEddsaVerifySig,
+    JubjubEddsaVerifyPk,
+    JubjubEddsaVerifyResult,
+    Keccak256New,
+    Keccak256Push,
+    Keccak256Finalize,
+    Blake2sNew,
+    Blake2sPush,
+    Blake2sFinalize,
+    Blake2bNew,
+    Blake2bPush,
+    Blake2bFinalize,
+    PoseidonCircomNew,
+    PoseidonCircomPush,
+    PoseidonCircomFinalize,
+    JubjubPedersenCommitScalar,
+    JubjubPedersenCommitResult,
+    JubjubPedersenHashMsg,
+    JubjubPedersenHashResult,
+    Bn254PairCheckG1,
+    Bn254PairCheckG2,
+    Bn254PairCheckResult,
+    BlsPairCheckG1,
+    BlsPairCheckG2,
+    BlsPairCheckResult,
+    Groth16VerifyVkHash,
+    Groth16VerifyVk,
+    Groth16VerifyInput,
+    Groth16VerifyProof,
+    Groth16VerifyResult,
+    BlsKzgPointEvalZ,
+    BlsKzgPointEvalY,
+    BlsKzgPointEvalCommitment,
+    BlsKzgPointEvalProof,
+    BlsKzgPointEvalResult,
+    BlsVerifyPubkey,
+    BlsVerifyUniformBytes,
+    BlsVerifySignature,
+    BlsVerifyResult,
+    BlsSumG2New,
+    BlsSumG2Scalar,
+    BlsSumG2,
+    BlsSumG2Result,
+    Bn254SumG2New,
+    Bn254SumG2Scalar,
+    Bn254SumG2,
+    Bn254SumG2Result,
+    Bn254PairCompressedG1,
+    Bn254PairCompressedG2,
+    Bn254PairCompressedGt,
+    BlsPairCompressedG1,
+    BlsPairCompressedG2,
+    BlsPairCompressedGt,
+    Bn254MsmNew,
+    Bn254MsmScalar,
+    Bn254MsmG1,
+    Bn254MsmResult,
+    BlsMsmNew,
+    BlsMsmScalar,
+    BlsMsmG1,
+    BlsMsmResult,
+    JubjubMsmNew,
+    JubjubMsmPush,
+    JubjubMsmResult,
+    Bn254GtOp,
+    Bn254GtA,
+    Bn254GtB,
+    Bn254GtResult,
+    BlsGtOp,
+    BlsGtA,
+    BlsGtB,
+    BlsGtResult,
+    BlsKzgPointEvalCompressed,
+    PoseidonSpongeNew,
+    PoseidonSpongePush,
+    PoseidonSpongeFinalize,
+    KVMapSetRoot,
+    KVMapGetRoot,
+    KVMapKey,
+    KVMapSet,
+    KVMapGet,
+    BlsKzgPointEvalVersionedHash,
+    Secp256k1RecoverResult,
 }
 
 pub enum ReduceRule<F: FieldExt> {
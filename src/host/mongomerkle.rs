@@ -2,7 +2,10 @@ use crate::host::cache::MERKLE_CACHE;
 use crate::host::db;
 use crate::host::db::{MongoDB, TreeDB};
 use crate::host::merkle::{MerkleError, MerkleErrorCode, MerkleNode, MerkleProof, MerkleTree};
+#[cfg(not(feature = "merkle-poseidon2"))]
 use crate::host::poseidon::MERKLE_HASHER;
+#[cfg(feature = "merkle-poseidon2")]
+use crate::host::poseidon2::{poseidon2_compress, POSEIDON2_SPEC};
 use crate::host::poseidon::POSEIDON_HASHER;
 use ff::PrimeField;
 use halo2_proofs::pairing::bn256::Fr;
@@ -15,6 +18,7 @@ use serde::{
     Deserialize, Deserializer, Serialize, Serializer,
 };
 use std::cell::RefCell;
+use std::ops::Range;
 use std::rc::Rc;
 
 fn deserialize_u64_as_binary<'de, D>(deserializer: D) -> Result<u64, D::Error>
@@ -314,6 +318,275 @@ impl<const DEPTH: usize> MongoMerkle<DEPTH> {
     }
 }
 
+/// Iterator over the populated leaves of a MongoMerkle below a given root.
+/// Subtrees whose hash equals the default hash of their depth are skipped
+/// without touching the DB, and leaves are yielded in ascending index order.
+pub struct MerkleLeafIter<'a, const DEPTH: usize> {
+    merkle: &'a MongoMerkle<DEPTH>,
+    stack: Vec<(u64, [u8; 32])>,
+    range: Range<u64>,
+}
+
+impl<'a, const DEPTH: usize> MerkleLeafIter<'a, DEPTH> {
+    /// The range of leaf indices covered by the subtree rooted at index
+    fn leaf_span(index: u64) -> Range<u64> {
+        let height = (index + 1).ilog2() as usize;
+        let shift = DEPTH - height;
+        let first = ((index - ((1u64 << height) - 1)) << shift) + (1u64 << DEPTH) - 1;
+        first..first + (1u64 << shift)
+    }
+}
+
+impl<'a, const DEPTH: usize> Iterator for MerkleLeafIter<'a, DEPTH> {
+    type Item = Result<(u64, [u8; 32]), MerkleError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((index, hash)) = self.stack.pop() {
+            let height = (index + 1).ilog2() as usize;
+            let span = Self::leaf_span(index);
+            if span.end <= self.range.start || span.start >= self.range.end {
+                continue;
+            }
+            match self.merkle.get_default_hash(height) {
+                Ok(default) if default == hash => continue,
+                Ok(_) => (),
+                Err(e) => return Some(Err(e)),
+            }
+            let node = match self.merkle.get_or_generate_node(index, &hash) {
+                Ok((node, _)) => node,
+                Err(e) => return Some(Err(e)),
+            };
+            if height == DEPTH {
+                return Some(Ok((index, node.data)));
+            }
+            // push right first so that the left subtree is visited first
+            self.stack.push(((index + 1) * 2, node.right));
+            self.stack.push(((index + 1) * 2 - 1, node.left));
+        }
+        None
+    }
+}
+
+impl<const DEPTH: usize> MongoMerkle<DEPTH> {
+    /// Iterate over all non-empty leaves with index in range under root.
+    /// Leaf indices are the node indices used by get_leaf_with_proof.
+    pub fn leaves_in_range(&self, root: &[u8; 32], range: Range<u64>) -> MerkleLeafIter<'_, DEPTH> {
+        MerkleLeafIter {
+            merkle: self,
+            stack: vec![(0, *root)],
+            range,
+        }
+    }
+}
+
+/// A leaf whose content differs between two roots of the same tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeafDiff {
+    pub index: u64,
+    pub before: [u8; 32],
+    pub after: [u8; 32],
+}
+
+impl<const DEPTH: usize> MongoMerkle<DEPTH> {
+    /// Walk the trees under root_a and root_b in parallel and collect the leaves that differ.
+    /// Subtrees with identical hashes are pruned so the cost is proportional to the number
+    /// of differing leaves times DEPTH. Results are in ascending leaf index order.
+    pub fn diff_roots(
+        &self,
+        root_a: &[u8; 32],
+        root_b: &[u8; 32],
+    ) -> Result<Vec<LeafDiff>, MerkleError> {
+        let mut diffs = vec![];
+        let mut stack = vec![(0u64, *root_a, *root_b)];
+        while let Some((index, hash_a, hash_b)) = stack.pop() {
+            if hash_a == hash_b {
+                continue;
+            }
+            let (node_a, _) = self.get_or_generate_node(index, &hash_a)?;
+            let (node_b, _) = self.get_or_generate_node(index, &hash_b)?;
+            if (index + 1).ilog2() as usize == DEPTH {
+                diffs.push(LeafDiff {
+                    index,
+                    before: node_a.data,
+                    after: node_b.data,
+                });
+            } else {
+                stack.push(((index + 1) * 2, node_a.right, node_b.right));
+                stack.push(((index + 1) * 2 - 1, node_a.left, node_b.left));
+            }
+        }
+        Ok(diffs)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FsckIssueKind {
+    /// No record with the expected (index, hash) exists in the DB
+    MissingRecord,
+    /// The record exists but its hash does not match its children or data
+    InconsistentHash,
+}
+
+#[derive(Debug, Clone)]
+pub struct FsckIssue {
+    pub index: u64,
+    pub hash: [u8; 32],
+    pub kind: FsckIssueKind,
+    pub repaired: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct FsckReport {
+    pub visited: u64,
+    pub issues: Vec<FsckIssue>,
+}
+
+impl FsckReport {
+    pub fn is_clean(&self) -> bool {
+        self.issues.iter().all(|x| x.repaired)
+    }
+}
+
+impl<const DEPTH: usize> MongoMerkle<DEPTH> {
+    /// Check that a stored record is consistent with its children (or its data for leaves)
+    fn record_is_consistent(record: &MerkleRecord) -> bool {
+        if (record.index + 1).ilog2() as usize == DEPTH {
+            let mut leaf = record.clone();
+            leaf.set(&record.data.to_vec());
+            leaf.hash == record.hash
+        } else {
+            Self::hash(&record.left, &record.right) == record.hash
+        }
+    }
+
+    /// Candidates for the node at index are all its stored records plus the default node
+    fn node_candidates(&self, index: u64) -> Result<Vec<MerkleRecord>, MerkleError> {
+        let mut candidates = self
+            .db
+            .borrow()
+            .get_merkle_records_by_index(index)
+            .expect("Unexpected DB Error");
+        candidates.push(self.generate_default_node(index)?);
+        Ok(candidates)
+    }
+
+    /// Check that the node (index, hash) is the default node or has a stored record
+    fn node_is_known(&self, index: u64, hash: &[u8; 32]) -> Result<bool, MerkleError> {
+        if self.get_default_hash((index + 1).ilog2() as usize)? == *hash {
+            return Ok(true);
+        }
+        let record = self
+            .db
+            .borrow()
+            .get_merkle_record(index, hash)
+            .expect("Unexpected DB Error");
+        Ok(record.is_some())
+    }
+
+    /// Rebuild the inconsistent record (index, hash) from the child hashes it stores.
+    /// The child whose stored hash is still known is kept and the candidates of the other
+    /// child are scanned for the one that hashes to the record with it.
+    /// Leaves can not be rebuilt since their data is not derivable from other records.
+    fn repair_from_children(
+        &mut self,
+        index: u64,
+        hash: &[u8; 32],
+        stored: &MerkleRecord,
+    ) -> Result<Option<MerkleRecord>, MerkleError> {
+        if (index + 1).ilog2() as usize == DEPTH {
+            return Ok(None);
+        }
+        let left_index = (index + 1) * 2 - 1;
+        let right_index = (index + 1) * 2;
+        let mut children = None;
+        if self.node_is_known(left_index, &stored.left)? {
+            children = self
+                .node_candidates(right_index)?
+                .into_iter()
+                .find(|right| Self::hash(&stored.left, &right.hash) == *hash)
+                .map(|right| (stored.left, right.hash));
+        }
+        if children.is_none() && self.node_is_known(right_index, &stored.right)? {
+            children = self
+                .node_candidates(left_index)?
+                .into_iter()
+                .find(|left| Self::hash(&left.hash, &stored.right) == *hash)
+                .map(|left| (left.hash, stored.right));
+        }
+        Ok(children.map(|(left, right)| {
+            let record = MerkleRecord {
+                index,
+                hash: *hash,
+                data: [0; 32],
+                left,
+                right,
+            };
+            let mut db = self.db.borrow_mut();
+            db.delete_merkle_record(index, hash)
+                .expect("Unexpected DB Error");
+            db.set_merkle_record(record.clone())
+                .expect("Unexpected DB Error");
+            MERKLE_CACHE
+                .lock()
+                .unwrap()
+                .push((index, *hash), Some(record.clone()));
+            record
+        }))
+    }
+
+    /// Verify every non-default node reachable from root against the DB (bypassing the cache).
+    /// Each record is recomputed with MongoMerkle::hash (or the data hasher for leaves).
+    /// If repair is set, inconsistent internal nodes are rebuilt from their children.
+    /// Missing records are only reported since there are no stored child hashes to start from.
+    /// Subtrees below a node that is broken and can not be repaired are not visited.
+    pub fn fsck(&mut self, root: &[u8; 32], repair: bool) -> Result<FsckReport, MerkleError> {
+        let mut report = FsckReport::default();
+        let mut stack = vec![(0u64, *root)];
+        while let Some((index, hash)) = stack.pop() {
+            let height = (index + 1).ilog2() as usize;
+            if self.get_default_hash(height)? == hash {
+                continue;
+            }
+            report.visited += 1;
+            let stored = self
+                .db
+                .borrow()
+                .get_merkle_record(index, &hash)
+                .expect("Unexpected DB Error");
+            let record = match stored {
+                Some(record) if Self::record_is_consistent(&record) => Some(record),
+                stored => {
+                    let kind = if stored.is_none() {
+                        FsckIssueKind::MissingRecord
+                    } else {
+                        FsckIssueKind::InconsistentHash
+                    };
+                    let repaired = match stored {
+                        Some(stored) if repair => {
+                            self.repair_from_children(index, &hash, &stored)?
+                        }
+                        _ => None,
+                    };
+                    report.issues.push(FsckIssue {
+                        index,
+                        hash,
+                        kind,
+                        repaired: repaired.is_some(),
+                    });
+                    repaired
+                }
+            };
+            if let Some(record) = record {
+                if height < DEPTH {
+                    stack.push(((index + 1) * 2, record.right));
+                    stack.push(((index + 1) * 2 - 1, record.left));
+                }
+            }
+        }
+        Ok(report)
+    }
+}
+
 // In default_hash vec, it is from leaf to root.
 // For example, suppose that the height of merkle tree is 20.
 // DEFAULT_HASH_VEC[0] represents the default leaf hash.
@@ -352,6 +625,7 @@ impl<const DEPTH: usize> MerkleTree<[u8; 32], DEPTH> for MongoMerkle<DEPTH> {
         self.root_hash = hash.clone();
     }
 
+    #[cfg(not(feature = "merkle-poseidon2"))]
     fn hash(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
         let mut hasher = MERKLE_HASHER.clone();
         let a = Fr::from_repr(*a).unwrap();
@@ -359,6 +633,13 @@ impl<const DEPTH: usize> MerkleTree<[u8; 32], DEPTH> for MongoMerkle<DEPTH> {
         hasher.update_exact(&[a, b]).to_repr()
     }
 
+    #[cfg(feature = "merkle-poseidon2")]
+    fn hash(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+        let a = Fr::from_repr(*a).unwrap();
+        let b = Fr::from_repr(*b).unwrap();
+        poseidon2_compress(&POSEIDON2_SPEC, a, b).to_repr()
+    }
+
     fn set_parent(
         &mut self,
         index: u64,
@@ -459,7 +740,7 @@ impl<const DEPTH: usize> MerkleTree<[u8; 32], DEPTH> for MongoMerkle<DEPTH> {
 #[cfg(test)]
 mod tests {
     use super::db::get_collection;
-    use super::{MerkleRecord, MongoMerkle, DEFAULT_HASH_VEC};
+    use super::{FsckIssueKind, LeafDiff, MerkleLeafIter, MerkleRecord, MongoMerkle, DEFAULT_HASH_VEC};
     use crate::host::db::{get_collection_name, MONGODB_DATABASE, MONGODB_DATA_NAME_PREFIX};
     use crate::host::merkle::{MerkleNode, MerkleTree};
     use crate::utils::{bytes_to_u64, field_to_bytes};
@@ -663,6 +944,139 @@ mod tests {
         assert!(mt.verify_proof(&proof).unwrap());
     }
 
+    #[test]
+    /* Update three leaves and check that the leaf iterator yields exactly
+     * the populated ones, in order, and respects the requested range.
+     */
+    fn test_mongo_merkle_leaves_in_range() {
+        const DEPTH: usize = 32;
+        const TEST_ADDR: [u8; 32] = [6; 32];
+        const FIRST: u64 = 2_u64.pow(DEPTH as u32) - 1;
+        let indexes = [FIRST + 3, FIRST + 1024, 2_u64.pow((DEPTH + 1) as u32) - 2];
+        let mut mt = MongoMerkle::<DEPTH>::construct(TEST_ADDR, DEFAULT_HASH_VEC[DEPTH], None);
+        for (i, index) in indexes.iter().enumerate() {
+            let mut data = [0u8; 32];
+            data[0] = (i + 1) as u8;
+            let (mut leaf, _) = mt.get_leaf_with_proof(*index).unwrap();
+            leaf.set(&data.to_vec());
+            mt.set_leaf_with_proof(&leaf).unwrap();
+        }
+        let root = mt.get_root_hash();
+        let all = mt
+            .leaves_in_range(&root, FIRST..2_u64.pow((DEPTH + 1) as u32) - 1)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(all.iter().map(|(i, _)| *i).collect::<Vec<_>>(), indexes.to_vec());
+        assert_eq!(all[1].1[0], 2);
+
+        let part = mt
+            .leaves_in_range(&root, FIRST + 4..FIRST + 1025)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(part.len(), 1);
+        assert_eq!(part[0].0, FIRST + 1024);
+
+        let empty = mt.leaves_in_range(&DEFAULT_HASH_VEC[DEPTH], FIRST..FIRST + 1025);
+        assert_eq!(empty.count(), 0);
+    }
+
+    #[test]
+    /* Update two leaves from a snapshot root and check that diff_roots reports
+     * exactly those two leaves with their before and after data.
+     */
+    fn test_mongo_merkle_diff_roots() {
+        const DEPTH: usize = 32;
+        const TEST_ADDR: [u8; 32] = [8; 32];
+        const FIRST: u64 = 2_u64.pow(DEPTH as u32) - 1;
+        let mut mt = MongoMerkle::<DEPTH>::construct(TEST_ADDR, DEFAULT_HASH_VEC[DEPTH], None);
+        let (mut leaf, _) = mt.get_leaf_with_proof(FIRST + 5).unwrap();
+        leaf.set(&[1u8; 32].to_vec());
+        mt.set_leaf_with_proof(&leaf).unwrap();
+        let before = mt.get_root_hash();
+
+        let (mut leaf, _) = mt.get_leaf_with_proof(FIRST + 5).unwrap();
+        leaf.set(&[2u8; 32].to_vec());
+        mt.set_leaf_with_proof(&leaf).unwrap();
+        let (mut leaf, _) = mt.get_leaf_with_proof(FIRST + 77).unwrap();
+        leaf.set(&[3u8; 32].to_vec());
+        mt.set_leaf_with_proof(&leaf).unwrap();
+        let after = mt.get_root_hash();
+
+        let diffs = mt.diff_roots(&before, &after).unwrap();
+        assert_eq!(
+            diffs,
+            vec![
+                LeafDiff {
+                    index: FIRST + 5,
+                    before: [1u8; 32],
+                    after: [2u8; 32],
+                },
+                LeafDiff {
+                    index: FIRST + 77,
+                    before: [0u8; 32],
+                    after: [3u8; 32],
+                },
+            ]
+        );
+        assert!(mt.diff_roots(&after, &after).unwrap().is_empty());
+    }
+
+    #[test]
+    /* Remove an internal node of a populated tree from the DB and check that
+     * fsck reports it, then store it with a corrupted child hash and check that
+     * fsck rebuilds it from its children when asked to.
+     */
+    fn test_mongo_merkle_fsck() {
+        const DEPTH: usize = 32;
+        const TEST_ADDR: [u8; 32] = [9; 32];
+        const FIRST: u64 = 2_u64.pow(DEPTH as u32) - 1;
+        let mut mt = MongoMerkle::<DEPTH>::construct(TEST_ADDR, DEFAULT_HASH_VEC[DEPTH], None);
+        let (mut leaf, _) = mt.get_leaf_with_proof(FIRST + 3).unwrap();
+        leaf.set(&[1u8; 32].to_vec());
+        mt.set_leaf_with_proof(&leaf).unwrap();
+        let root = mt.get_root_hash();
+
+        let report = mt.fsck(&root, false).unwrap();
+        assert_eq!(report.visited, DEPTH as u64 + 1);
+        assert!(report.issues.is_empty());
+
+        let parent = (FIRST + 3 - 1) / 2;
+        let mut hash = root;
+        let mut index = 0;
+        while index != parent {
+            let (node, _) = mt.get_or_generate_node(index, &hash).unwrap();
+            let left = (index + 1) * 2 - 1;
+            if MerkleLeafIter::<DEPTH>::leaf_span(left).contains(&(FIRST + 3)) {
+                index = left;
+                hash = node.left;
+            } else {
+                index = left + 1;
+                hash = node.right;
+            }
+        }
+        let record = mt.db.borrow().get_merkle_record(parent, &hash).unwrap().unwrap();
+        mt.db.borrow_mut().delete_merkle_record(parent, &hash).unwrap();
+
+        let report = mt.fsck(&root, false).unwrap();
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].index, parent);
+        assert_eq!(report.issues[0].kind, FsckIssueKind::MissingRecord);
+        let report = mt.fsck(&root, true).unwrap();
+        assert!(!report.is_clean());
+
+        let mut corrupted = record.clone();
+        corrupted.left = [0; 32];
+        mt.db.borrow_mut().set_merkle_record(corrupted).unwrap();
+        let report = mt.fsck(&root, false).unwrap();
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].kind, FsckIssueKind::InconsistentHash);
+
+        let report = mt.fsck(&root, true).unwrap();
+        assert!(report.is_clean());
+        let report = mt.fsck(&root, false).unwrap();
+        assert!(report.issues.is_empty());
+    }
+
     #[test]
     fn test_mongo_merkle_multi_leaves_update() {
         _test_mongo_merkle_multi_leaves_update(3);
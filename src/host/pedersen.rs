@@ -0,0 +1,149 @@
+/* Pedersen commitments and the windowed Pedersen hash over the alt BabyJubjub curve.
+ *
+ * All generators are nothing-up-my-sleeve points: the x coordinate is the poseidon hash
+ * of (domain, index, counter) for the first counter that lands on the curve, the smaller
+ * square root is taken as y and the point is multiplied by the cofactor 8 so that it
+ * lies in the prime order subgroup.
+ *
+ * The commitment of a value v with the blinding r is v * G + r * H.
+ *
+ * The hash splits a bit string of at most HASH_MAX_BITS bits into windows of 4 bits
+ * (little endian, the last window is padded with zeros) and returns the sum of m_j * P_j
+ * where m_j is the value of the j-th window and P_j is an independent generator. Same
+ * as other Pedersen hashes it is only collision resistant for inputs of a fixed length.
+ */
+use crate::host::jubjub::{fixed_base_tables, Point, D};
+use crate::host::poseidon::POSEIDON_HASHER;
+use crate::utils::field_to_bn;
+use ff::Field;
+use halo2_proofs::pairing::bn256::Fr;
+use lazy_static::lazy_static;
+use num_bigint::BigUint;
+
+pub const HASH_WINDOW_BITS: usize = 4;
+pub const HASH_MAX_BITS: usize = 256;
+pub const HASH_WINDOWS: usize = HASH_MAX_BITS / HASH_WINDOW_BITS;
+
+const COMMIT_DOMAIN: u64 = 0;
+const HASH_DOMAIN: u64 = 1;
+
+/// The nothing-up-my-sleeve generator of the domain at index
+pub fn generator(domain: u64, index: u64) -> Point {
+    let mut counter = 0u64;
+    loop {
+        let mut hasher = POSEIDON_HASHER.clone();
+        hasher.update(&[Fr::from(domain), Fr::from(index), Fr::from(counter)]);
+        let x = hasher.squeeze();
+        // -x^2 + y^2 = 1 + d * x^2 * y^2
+        let x2 = x.square();
+        let y2 = (Fr::one() + x2) * (Fr::one() - *D * x2).invert().unwrap();
+        if let Some(y) = Option::<Fr>::from(y2.sqrt()) {
+            let y = if field_to_bn(&y) < field_to_bn(&-y) { y } else { -y };
+            let p = Point { x, y }.mul_scalar(&BigUint::from(8u64));
+            if p != Point::identity() {
+                return p;
+            }
+        }
+        counter += 1;
+    }
+}
+
+lazy_static! {
+    pub static ref PEDERSEN_G: Point = generator(COMMIT_DOMAIN, 0);
+    pub static ref PEDERSEN_H: Point = generator(COMMIT_DOMAIN, 1);
+    pub static ref PEDERSEN_G_TABLES: Vec<Vec<Point>> = fixed_base_tables(&PEDERSEN_G);
+    pub static ref PEDERSEN_H_TABLES: Vec<Vec<Point>> = fixed_base_tables(&PEDERSEN_H);
+    pub static ref PEDERSEN_HASH_GENERATORS: Vec<Point> = (0..HASH_WINDOWS)
+        .map(|i| generator(HASH_DOMAIN, i as u64))
+        .collect();
+    pub static ref PEDERSEN_HASH_TABLES: Vec<Vec<Point>> =
+        (0..HASH_WINDOWS).map(hash_window_table).collect();
+}
+
+pub fn pedersen_commit(v: &Fr, r: &Fr) -> Point {
+    PEDERSEN_G
+        .mul_scalar(&field_to_bn(v))
+        .add(&PEDERSEN_H.mul_scalar(&field_to_bn(r)))
+}
+
+/// The multiples m * P_j of the j-th window generator for every window value m
+pub fn hash_window_table(j: usize) -> Vec<Point> {
+    let mut table = vec![Point::identity()];
+    for m in 1..(1 << HASH_WINDOW_BITS) {
+        table.push(table[m - 1].add(&PEDERSEN_HASH_GENERATORS[j]));
+    }
+    table
+}
+
+pub fn pedersen_hash(bits: &[bool]) -> Point {
+    assert!(bits.len() <= HASH_MAX_BITS);
+    bits.chunks(HASH_WINDOW_BITS)
+        .enumerate()
+        .fold(Point::identity(), |acc, (j, window)| {
+            let m = window
+                .iter()
+                .rev()
+                .fold(0u64, |m, b| (m << 1) + *b as u64);
+            acc.add(&PEDERSEN_HASH_GENERATORS[j].mul_scalar(&BigUint::from(m)))
+        })
+}
+
+/// The little endian bits of the u64 words
+pub fn words_to_bits(words: &[u64]) -> Vec<bool> {
+    words
+        .iter()
+        .flat_map(|w| (0..64).map(move |i| (w >> i) & 1 == 1))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        hash_window_table, pedersen_commit, pedersen_hash, words_to_bits, PEDERSEN_G,
+        PEDERSEN_H, PEDERSEN_HASH_GENERATORS,
+    };
+    use crate::host::jubjub::{Point, SUBGROUP_ORDER};
+    use crate::utils::field_to_bn;
+    use halo2_proofs::pairing::bn256::Fr;
+    use num_bigint::BigUint;
+
+    #[test]
+    fn test_pedersen_generators() {
+        for p in [&*PEDERSEN_G, &*PEDERSEN_H, &PEDERSEN_HASH_GENERATORS[63]] {
+            assert_ne!(*p, Point::identity());
+            assert_eq!(p.mul_scalar(&SUBGROUP_ORDER), Point::identity());
+        }
+        assert_ne!(*PEDERSEN_G, *PEDERSEN_H);
+    }
+
+    #[test]
+    fn test_pedersen_commit() {
+        let (v1, r1) = (Fr::from(100u64), Fr::from(7u64));
+        let (v2, r2) = (Fr::from(23u64), Fr::from(5u64));
+        // commitments are additively homomorphic
+        assert_eq!(
+            pedersen_commit(&v1, &r1).add(&pedersen_commit(&v2, &r2)),
+            pedersen_commit(&(v1 + v2), &(r1 + r2))
+        );
+        assert_eq!(
+            pedersen_commit(&v1, &Fr::zero()),
+            PEDERSEN_G.mul_scalar(&field_to_bn(&v1))
+        );
+    }
+
+    #[test]
+    fn test_pedersen_hash() {
+        let words = [0x0123456789abcdefu64, 0, u64::MAX, 42];
+        let bits = words_to_bits(&words);
+        let expected = (0..64).fold(Point::identity(), |acc, j| {
+            let m = (words[j / 16] >> (4 * (j % 16))) & 0xf;
+            acc.add(&hash_window_table(j)[m as usize])
+        });
+        assert_eq!(pedersen_hash(&bits), expected);
+        // a partial window is padded with zeros
+        assert_eq!(
+            pedersen_hash(&[true, false, true]),
+            PEDERSEN_HASH_GENERATORS[0].mul_scalar(&BigUint::from(5u64))
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use halo2_proofs::arithmetic::FieldExt;
 use halo2_proofs::pairing::bn256::Fr;
 use poseidon::Poseidon;
 use poseidon::Spec;
@@ -6,18 +7,182 @@ pub const PREFIX_CHALLENGE: u64 = 0u64;
 pub const PREFIX_POINT: u64 = 1u64;
 pub const PREFIX_SCALAR: u64 = 2u64;
 
+/// The way a family of poseidon host calls uses the permutation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PoseidonFamily {
+    /// The sponge of the poseidon crate: state[0] starts as 2^64, the inputs are
+    /// absorbed in state[1..] and the result is state[1].
+    Sponge,
+    /// circomlib: the state starts as zero, exactly T - 1 inputs are absorbed in
+    /// state[1..] and the result is state[0].
+    Circom,
+}
+
+impl PoseidonFamily {
+    /// the initial value of state[0]
+    pub fn capacity(&self) -> Fr {
+        match self {
+            PoseidonFamily::Sponge => Fr::from_u128(1u128 << 64),
+            PoseidonFamily::Circom => Fr::zero(),
+        }
+    }
+
+    /// the index of the result in the permuted state
+    pub fn result_index(&self) -> usize {
+        match self {
+            PoseidonFamily::Sponge => 1,
+            PoseidonFamily::Circom => 0,
+        }
+    }
+}
+
+/// (width, full rounds, partial rounds) of the sponge hashers
+pub const SPONGE_ROUNDS: [(usize, usize, usize); 2] = [(3, 8, 57), (9, 8, 63)];
+
+/// (width, full rounds, partial rounds) of the circomlib parameters over BN254. Only
+/// the widths with PoseidonCircom* opcodes and a HostOpSelector are registered, a
+/// wider width (circomlib goes up to 17 with 8 full rounds) has to come with its own.
+pub const CIRCOM_ROUNDS: [(usize, usize, usize); 1] = [(3, 8, 57)];
+
+/// the (full rounds, partial rounds) registered for a family and a width, the round
+/// constants and the mds matrix are then generated from them by Spec::new
+pub fn spec_rounds(family: PoseidonFamily, width: usize) -> (usize, usize) {
+    let registry: &[(usize, usize, usize)] = match family {
+        PoseidonFamily::Sponge => &SPONGE_ROUNDS,
+        PoseidonFamily::Circom => &CIRCOM_ROUNDS,
+    };
+    registry
+        .iter()
+        .find(|(t, _, _)| *t == width)
+        .map(|(_, r_f, r_p)| (*r_f, *r_p))
+        .unwrap_or_else(|| panic!("no poseidon spec of width {} for {:?}", width, family))
+}
+
+pub fn poseidon_spec<const T: usize, const RATE: usize>(
+    family: PoseidonFamily,
+) -> Spec<Fr, T, RATE> {
+    let (r_f, r_p) = spec_rounds(family, T);
+    Spec::new(r_f, r_p)
+}
+
+pub fn poseidon_hasher<const T: usize, const RATE: usize>() -> Poseidon<Fr, T, RATE> {
+    let (r_f, r_p) = spec_rounds(PoseidonFamily::Sponge, T);
+    Poseidon::new(r_f, r_p)
+}
+
 // We have two hasher here
 // 1. MERKLE_HASHER that is used for non sponge hash for hash two merkle siblings
 // 2. POSEIDON_HASHER thas is use for poseidon hash of data
 lazy_static::lazy_static! {
-    pub static ref POSEIDON_HASHER: poseidon::Poseidon<Fr, 9, 8> = Poseidon::<Fr, 9, 8>::new(8, 63);
-    pub static ref MERKLE_HASHER: poseidon::Poseidon<Fr, 3, 2> = Poseidon::<Fr, 3, 2>::new(8, 57);
-    pub static ref POSEIDON_HASHER_SPEC: poseidon::Spec<Fr, 9, 8> = Spec::new(8, 63);
-    pub static ref MERKLE_HASHER_SPEC: poseidon::Spec<Fr, 3, 2> = Spec::new(8, 57);
+    pub static ref POSEIDON_HASHER: poseidon::Poseidon<Fr, 9, 8> = poseidon_hasher::<9, 8>();
+    pub static ref MERKLE_HASHER: poseidon::Poseidon<Fr, 3, 2> = poseidon_hasher::<3, 2>();
+    pub static ref POSEIDON_HASHER_SPEC: poseidon::Spec<Fr, 9, 8> = poseidon_spec(PoseidonFamily::Sponge);
+    pub static ref MERKLE_HASHER_SPEC: poseidon::Spec<Fr, 3, 2> = poseidon_spec(PoseidonFamily::Sponge);
+    pub static ref CIRCOM_HASHER_SPEC: poseidon::Spec<Fr, 3, 2> = poseidon_spec(PoseidonFamily::Circom);
+}
+
+fn sbox_with_constant(x: Fr, constant: Fr) -> Fr {
+    x * x * x * x * x + constant
+}
+
+fn apply_mds<const T: usize>(state: &mut [Fr; T], mds: &[[Fr; T]; T]) {
+    let r = mds
+        .iter()
+        .map(|row| {
+            row.iter()
+                .zip(state.iter())
+                .fold(Fr::zero(), |acc, (m, x)| acc + *m * *x)
+        })
+        .collect::<Vec<_>>();
+    *state = r.try_into().unwrap();
+}
+
+fn full_round<const T: usize>(state: &mut [Fr; T], constants: &[Fr; T], mds: &[[Fr; T]; T]) {
+    for (x, c) in state.iter_mut().zip(constants.iter()) {
+        *x = sbox_with_constant(*x, *c);
+    }
+    apply_mds(state, mds);
+}
+
+/// Native mirror of PoseidonState::permute in the circuit: add the inputs into
+/// state[1..] and apply the optimized permutation of the spec
+pub fn poseidon_permute<const T: usize, const RATE: usize>(
+    spec: &Spec<Fr, T, RATE>,
+    state: &mut [Fr; T],
+    inputs: &[Fr; RATE],
+) {
+    let r_f = spec.r_f() / 2;
+    let mds = &spec.mds_matrices().mds().rows();
+
+    let constants = &spec.constants().start();
+    state[0] = state[0] + constants[0][0];
+    for i in 1..T {
+        state[i] = state[i] + inputs[i - 1] + constants[0][i];
+    }
+
+    for constants in constants.iter().skip(1).take(r_f - 1) {
+        full_round(state, constants, mds);
+    }
+    let pre_sparse_mds = &spec.mds_matrices().pre_sparse_mds().rows();
+    full_round(state, constants.last().unwrap(), pre_sparse_mds);
+
+    let sparse_matrices = &spec.mds_matrices().sparse_matrices();
+    for (constant, sparse_mds) in spec
+        .constants()
+        .partial()
+        .iter()
+        .zip(sparse_matrices.iter())
+    {
+        let x0 = sbox_with_constant(state[0], *constant);
+        state[0] = x0;
+        let s0 = sparse_mds
+            .row()
+            .iter()
+            .zip(state.iter())
+            .fold(Fr::zero(), |acc, (m, x)| acc + *m * *x);
+        for (x, e) in state.iter_mut().skip(1).zip(sparse_mds.col_hat().iter()) {
+            *x = *x + *e * x0;
+        }
+        state[0] = s0;
+    }
+
+    for constants in spec.constants().end().iter() {
+        full_round(state, constants, mds);
+    }
+    full_round(state, &[Fr::zero(); T], mds);
+}
+
+/// circomlib poseidon of exactly T - 1 inputs
+pub fn circom_poseidon<const T: usize, const RATE: usize>(
+    spec: &Spec<Fr, T, RATE>,
+    inputs: &[Fr; RATE],
+) -> Fr {
+    let family = PoseidonFamily::Circom;
+    let mut state = [Fr::zero(); T];
+    state[0] = family.capacity();
+    poseidon_permute(spec, &mut state, inputs);
+    state[family.result_index()]
+}
+
+/// Split the inputs into the absorb rounds of the sponge. The last round is padded
+/// with one followed by zeros, so permuting the rounds with update_exact ends with
+/// the same result as update(inputs) followed by squeeze().
+pub fn sponge_rounds<const RATE: usize>(inputs: &[Fr]) -> Vec<[Fr; RATE]> {
+    let mut padded = inputs.to_vec();
+    padded.push(Fr::one());
+    padded.resize((padded.len() + RATE - 1) / RATE * RATE, Fr::zero());
+    padded
+        .chunks_exact(RATE)
+        .map(|x| x.to_vec().try_into().unwrap())
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
+    use super::{
+        circom_poseidon, poseidon_permute, sponge_rounds, PoseidonFamily, CIRCOM_HASHER_SPEC,
+        POSEIDON_HASHER_SPEC,
+    };
     use halo2_proofs::pairing::bn256::Fr;
     #[test]
     fn test_poseidon() {
@@ -29,4 +194,42 @@ mod tests {
         println!("hash result is {:?}", result);
         assert_eq!(result.to_string(), ZERO_HASHER_SQUEEZE);
     }
+
+    #[test]
+    fn test_poseidon_sponge_rounds() {
+        for len in [0, 1, 7, 8, 9, 20] {
+            let inputs = (0..len).map(|x| Fr::from(x as u64 + 1)).collect::<Vec<_>>();
+            let mut hasher = super::POSEIDON_HASHER.clone();
+            hasher.update(&inputs);
+            let expected = hasher.squeeze();
+
+            let rounds = sponge_rounds::<8>(&inputs);
+            assert_eq!(rounds.len(), len / 8 + 1);
+            let mut hasher = super::POSEIDON_HASHER.clone();
+            let mut result = Fr::zero();
+            for round in rounds.iter() {
+                result = hasher.update_exact(round);
+            }
+            assert_eq!(result, expected);
+        }
+    }
+
+    #[test]
+    fn test_poseidon_permute_mirror() {
+        let inputs = [0u64; 8].map(|x| Fr::from(x + 3));
+        let mut state = [Fr::zero(); 9];
+        state[0] = PoseidonFamily::Sponge.capacity();
+        poseidon_permute(&POSEIDON_HASHER_SPEC, &mut state, &inputs);
+        let mut hasher = super::POSEIDON_HASHER.clone();
+        assert_eq!(state[1], hasher.update_exact(&inputs));
+    }
+
+    #[test]
+    fn test_circom_poseidon() {
+        let result = circom_poseidon(&CIRCOM_HASHER_SPEC, &[Fr::from(1u64), Fr::from(2u64)]);
+        assert_eq!(
+            result.to_string(),
+            "0x115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a"
+        );
+    }
 }
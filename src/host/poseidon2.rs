@@ -0,0 +1,184 @@
+/* Native reference of the Poseidon2 permutation over the bn256 scalar field.
+ *
+ * Only the width 3 instance (R_F = 8, R_P = 56, x^5) is supported, which is the one
+ * used for two to one compression in merkle trees. The round constants are sampled
+ * from the grain LFSR of the reference implementation: 3 constants for each of the
+ * first 4 external rounds, one constant for each internal round and 3 constants for
+ * each of the last 4 external rounds.
+ *
+ * The external matrix is circ(2, 1, 1) and the internal matrix is 1 + diag(1, 1, 2),
+ * so both of them are computed from the sum of the state.
+ */
+use ff::PrimeField;
+use halo2_proofs::pairing::bn256::Fr;
+
+pub const T: usize = 3;
+pub const R_F: usize = 8;
+pub const R_P: usize = 56;
+
+/// the diagonal of the internal matrix minus the identity
+pub const INTERNAL_DIAG: [u64; T] = [1, 1, 2];
+
+#[derive(Clone, Debug)]
+pub struct Poseidon2Spec {
+    /// constants of the external rounds, the first R_F / 2 are used before the
+    /// internal rounds and the rest after them
+    pub external: Vec<[Fr; T]>,
+    pub internal: Vec<Fr>,
+}
+
+struct Grain {
+    bits: Vec<bool>,
+}
+
+impl Grain {
+    fn new(width: usize, r_f: usize, r_p: usize) -> Self {
+        let mut bits = vec![];
+        // prime field, x^alpha sbox, field size in bits, width and rounds
+        for (v, w) in [(1, 2), (0, 4), (254, 12), (width, 12), (r_f, 10), (r_p, 10)] {
+            bits.extend((0..w).rev().map(|i| (v >> i) & 1 == 1));
+        }
+        bits.extend([true; 30]);
+        let mut grain = Grain { bits };
+        for _ in 0..160 {
+            grain.step();
+        }
+        grain
+    }
+
+    fn step(&mut self) -> bool {
+        let b = &self.bits;
+        let bit = b[62] ^ b[51] ^ b[38] ^ b[23] ^ b[13] ^ b[0];
+        self.bits.remove(0);
+        self.bits.push(bit);
+        bit
+    }
+
+    /// the self-shrinking output: a pair (1, x) outputs x and a pair (0, x) is dropped
+    fn next_bit(&mut self) -> bool {
+        loop {
+            let select = self.step();
+            let bit = self.step();
+            if select {
+                return bit;
+            }
+        }
+    }
+
+    /// sample 254 bits (big endian) and retry until the value is less than the modulus
+    fn next_field(&mut self) -> Fr {
+        loop {
+            let mut repr = [0u8; 32];
+            for i in (0..254).rev() {
+                if self.next_bit() {
+                    repr[i / 8] |= 1 << (i % 8);
+                }
+            }
+            if let Some(f) = Option::<Fr>::from(Fr::from_repr(repr)) {
+                return f;
+            }
+        }
+    }
+}
+
+impl Poseidon2Spec {
+    pub fn new() -> Self {
+        let mut grain = Grain::new(T, R_F, R_P);
+        let sample_round = |grain: &mut Grain| [(); T].map(|_| grain.next_field());
+        let mut external = (0..R_F / 2)
+            .map(|_| sample_round(&mut grain))
+            .collect::<Vec<_>>();
+        let internal = (0..R_P).map(|_| grain.next_field()).collect();
+        external.extend((0..R_F / 2).map(|_| sample_round(&mut grain)));
+        Poseidon2Spec { external, internal }
+    }
+}
+
+lazy_static::lazy_static! {
+    pub static ref POSEIDON2_SPEC: Poseidon2Spec = Poseidon2Spec::new();
+}
+
+fn sbox(x: Fr) -> Fr {
+    x * x * x * x * x
+}
+
+pub fn apply_external(state: &mut [Fr; T]) {
+    let sum = state.iter().fold(Fr::zero(), |acc, x| acc + x);
+    for x in state.iter_mut() {
+        *x = *x + sum;
+    }
+}
+
+pub fn apply_internal(state: &mut [Fr; T]) {
+    let sum = state.iter().fold(Fr::zero(), |acc, x| acc + x);
+    for (x, d) in state.iter_mut().zip(INTERNAL_DIAG.iter()) {
+        *x = sum + *x * Fr::from(*d);
+    }
+}
+
+fn external_round(state: &mut [Fr; T], constants: &[Fr; T]) {
+    for (x, c) in state.iter_mut().zip(constants.iter()) {
+        *x = sbox(*x + c);
+    }
+    apply_external(state);
+}
+
+pub fn poseidon2_permute(spec: &Poseidon2Spec, state: &mut [Fr; T]) {
+    apply_external(state);
+    for constants in spec.external.iter().take(R_F / 2) {
+        external_round(state, constants);
+    }
+    for constant in spec.internal.iter() {
+        state[0] = sbox(state[0] + constant);
+        apply_internal(state);
+    }
+    for constants in spec.external.iter().skip(R_F / 2) {
+        external_round(state, constants);
+    }
+}
+
+/// Two to one compression used by the merkle trees: the first element of the
+/// permutation of [a, b, 0]
+pub fn poseidon2_compress(spec: &Poseidon2Spec, a: Fr, b: Fr) -> Fr {
+    let mut state = [a, b, Fr::zero()];
+    poseidon2_permute(spec, &mut state);
+    state[0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{poseidon2_compress, poseidon2_permute, POSEIDON2_SPEC, R_F, R_P};
+    use halo2_proofs::pairing::bn256::Fr;
+
+    #[test]
+    fn test_poseidon2_constants() {
+        assert_eq!(POSEIDON2_SPEC.external.len(), R_F);
+        assert_eq!(POSEIDON2_SPEC.internal.len(), R_P);
+        assert_eq!(
+            POSEIDON2_SPEC.external[0][0].to_string(),
+            "0x1d066a255517b7fd8bddd3a93f7804ef7f8fcde48bb4c37a59a09a1a97052816"
+        );
+        assert_eq!(
+            POSEIDON2_SPEC.internal[0].to_string(),
+            "0x1a1d063e54b1e764b63e1855bff015b8cedd192f47308731499573f23597d4b5"
+        );
+    }
+
+    #[test]
+    fn test_poseidon2_permute() {
+        let mut state = [0u64, 1, 2].map(Fr::from);
+        poseidon2_permute(&POSEIDON2_SPEC, &mut state);
+        assert_eq!(
+            state.map(|x| x.to_string()),
+            [
+                "0x0bb61d24daca55eebcb1929a82650f328134334da98ea4f847f760054f4a3033",
+                "0x303b6f7c86d043bfcbcc80214f26a30277a15d3f74ca654992defe7ff8d03570",
+                "0x1ed25194542b12eef8617361c3ba7c52e660b145994427cc86296242cf766ec8",
+            ]
+        );
+        assert_eq!(
+            poseidon2_compress(&POSEIDON2_SPEC, Fr::from(1u64), Fr::from(2u64)).to_string(),
+            "0x2afac3bdc3663b71eefeecdf21b147d0ba7dd7a169a7757c05ed6bfb065bffd2"
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use crate::utils::{bn_to_field, field_to_bn};
+use halo2_proofs::arithmetic::{CurveAffine, Field};
+use halo2_proofs::pairing::group::{Curve, Group};
+use halo2_proofs::pairing::secp256k1::{Fp, Fq, Secp256k1, Secp256k1Affine};
+
+/* Native reference of the secp256k1 ECDSA host calls.
+ *
+ * The x coordinate of the nonce point R is required to be equal to r as an integer,
+ * which means that signatures whose R.x is not smaller than the group order are
+ * rejected. This matches the behaviour of the circuit and of Ethereum's ecrecover
+ * which only accepts the recovery ids 0 and 1.
+ *
+ * A failed recovery returns the result 0 with the identity as the key, which is
+ * pushed with zero coordinates.
+ */
+
+fn fp_to_fq(x: &Fp) -> Fq {
+    bn_to_field(&field_to_bn(x))
+}
+
+fn is_odd(y: &Fp) -> bool {
+    field_to_bn(y).bit(0)
+}
+
+/// Sign msg with secret key sk and nonce k. Returns (r, s, v) where v is the recovery id.
+pub fn ecdsa_sign(sk: &Fq, msg: &Fq, k: &Fq) -> Option<(Fq, Fq, u64)> {
+    let rp = (Secp256k1::generator() * *k).to_affine();
+    let coordinates = Option::from(rp.coordinates())?;
+    let (x, y): (&Fp, &Fp) = (coordinates.x(), coordinates.y());
+    let r = fp_to_fq(x);
+    if field_to_bn(&r) != field_to_bn(x) || r == Fq::zero() {
+        return None;
+    }
+    let s = Option::<Fq>::from(k.invert())? * (*msg + r * sk);
+    if s == Fq::zero() {
+        return None;
+    }
+    Some((r, s, is_odd(y) as u64))
+}
+
+/// Returns the point R = (msg * G + r * pk) / s, or None if it is not well defined.
+fn ecdsa_nonce_point(msg: &Fq, r: &Fq, s: &Fq, pk: &Secp256k1Affine) -> Option<Secp256k1Affine> {
+    if bool::from(pk.is_identity()) || *r == Fq::zero() {
+        return None;
+    }
+    let s_inv: Fq = Option::from(s.invert())?;
+    let rp = (Secp256k1::generator() * (*msg * s_inv) + *pk * (*r * s_inv)).to_affine();
+    if bool::from(rp.is_identity()) {
+        None
+    } else {
+        Some(rp)
+    }
+}
+
+pub fn ecdsa_verify(msg: &Fq, r: &Fq, s: &Fq, pk: &Secp256k1Affine) -> bool {
+    ecdsa_nonce_point(msg, r, s, pk).map_or(false, |rp| {
+        let coordinates = rp.coordinates().unwrap();
+        field_to_bn(coordinates.x()) == field_to_bn(r)
+    })
+}
+
+/// Recover the public key that signed msg, None if (r, s, v) is not a valid signature.
+pub fn ecdsa_recover(msg: &Fq, r: &Fq, s: &Fq, v: u64) -> Option<Secp256k1Affine> {
+    if v > 1 || *r == Fq::zero() || *s == Fq::zero() {
+        return None;
+    }
+    let x: Fp = bn_to_field(&field_to_bn(r));
+    let y: Fp = Option::from((x.square() * x + Secp256k1Affine::b()).sqrt())?;
+    let y = if is_odd(&y) == (v == 1) { y } else { -y };
+    let rp: Secp256k1Affine = Option::from(Secp256k1Affine::from_xy(x, y))?;
+    let r_inv: Fq = r.invert().unwrap();
+    let pk = (rp * (*s * r_inv) - Secp256k1::generator() * (*msg * r_inv)).to_affine();
+    if bool::from(pk.is_identity()) {
+        None
+    } else {
+        Some(pk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ecdsa_recover, ecdsa_sign, ecdsa_verify};
+    use crate::host::{ExternalHostCallEntry, ExternalHostCallEntryTable, ForeignInst};
+    use halo2_proofs::arithmetic::{CurveAffine, Field};
+    use halo2_proofs::pairing::group::{Curve, Group};
+    use halo2_proofs::pairing::secp256k1::{Fp, Fq, Secp256k1, Secp256k1Affine};
+    use rand::rngs::OsRng;
+    use std::fs::File;
+
+    fn random_signature() -> (Fq, Fq, Fq, u64, Secp256k1Affine) {
+        loop {
+            let sk = Fq::random(&mut OsRng);
+            let msg = Fq::random(&mut OsRng);
+            let k = Fq::random(&mut OsRng);
+            if let Some((r, s, v)) = ecdsa_sign(&sk, &msg, &k) {
+                let pk = (Secp256k1::generator() * sk).to_affine();
+                return (msg, r, s, v, pk);
+            }
+        }
+    }
+
+    fn secp256k1_pk_to_args(pk: Secp256k1Affine, op: ForeignInst) -> Vec<ExternalHostCallEntry> {
+        let (x, y) = if bool::from(pk.is_identity()) {
+            (Fp::zero(), Fp::zero())
+        } else {
+            let coordinates = pk.coordinates().unwrap();
+            (*coordinates.x(), *coordinates.y())
+        };
+        let mut r = vec![];
+        r.append(&mut crate::adaptor::fr_to_args(x, 5, 54, op));
+        r.append(&mut crate::adaptor::fr_to_args(y, 5, 54, op));
+        r.push(ExternalHostCallEntry {
+            op: op as usize,
+            value: pk.is_identity().unwrap_u8() as u64,
+            is_ret: false,
+        });
+        r
+    }
+
+    #[test]
+    fn test_secp256k1_ecdsa_verify_recover() {
+        for _ in 0..8 {
+            let (msg, r, s, v, pk) = random_signature();
+            assert!(ecdsa_verify(&msg, &r, &s, &pk));
+            assert_eq!(ecdsa_recover(&msg, &r, &s, v), Some(pk));
+            assert!(!ecdsa_verify(&(msg + Fq::one()), &r, &s, &pk));
+            assert_ne!(ecdsa_recover(&msg, &r, &s, 1 - v), Some(pk));
+            assert_eq!(ecdsa_recover(&msg, &r, &Fq::zero(), v), None);
+        }
+    }
+
+    #[test]
+    fn generate_secp256k1_verify_input() {
+        let mut inputs = vec![];
+        for i in 0..4 {
+            let (msg, r, s, _, pk) = random_signature();
+            // corrupt every other message so that both results are covered
+            let msg = if i % 2 == 0 { msg } else { msg + Fq::one() };
+            inputs.append(&mut crate::adaptor::fr_to_args(msg, 5, 54, ForeignInst::Secp256k1VerifyMsg));
+            inputs.append(&mut crate::adaptor::fr_to_args(r, 5, 54, ForeignInst::Secp256k1VerifySig));
+            inputs.append(&mut crate::adaptor::fr_to_args(s, 5, 54, ForeignInst::Secp256k1VerifySig));
+            inputs.append(&mut secp256k1_pk_to_args(pk, ForeignInst::Secp256k1VerifyPk));
+            inputs.push(ExternalHostCallEntry {
+                op: ForeignInst::Secp256k1VerifyResult as usize,
+                value: ecdsa_verify(&msg, &r, &s, &pk) as u64,
+                is_ret: true,
+            });
+        }
+        let table = ExternalHostCallEntryTable(inputs);
+        let file = File::create("secp256k1verifytest.json").expect("can not create file");
+        serde_json::to_writer_pretty(file, &table).expect("can not write to file");
+    }
+
+    #[test]
+    fn generate_secp256k1_recover_input() {
+        let mut inputs = vec![];
+        for i in 0..4 {
+            let (msg, r, s, v, _) = random_signature();
+            // fail the second call with a zero s and the last with an r that is not the
+            // x coordinate of a point
+            let s = if i == 1 { Fq::zero() } else { s };
+            let mut r = r;
+            while i == 3 && ecdsa_recover(&msg, &r, &s, v).is_some() {
+                r += Fq::one();
+            }
+            let pk = ecdsa_recover(&msg, &r, &s, v);
+            inputs.append(&mut crate::adaptor::fr_to_args(msg, 5, 54, ForeignInst::Secp256k1RecoverMsg));
+            inputs.append(&mut crate::adaptor::fr_to_args(r, 5, 54, ForeignInst::Secp256k1RecoverSig));
+            inputs.append(&mut crate::adaptor::fr_to_args(s, 5, 54, ForeignInst::Secp256k1RecoverSig));
+            inputs.push(ExternalHostCallEntry {
+                op: ForeignInst::Secp256k1RecoverSig as usize,
+                value: v,
+                is_ret: false,
+            });
+            let mut pk_args = secp256k1_pk_to_args(
+                pk.unwrap_or(Secp256k1Affine::identity()),
+                ForeignInst::Secp256k1RecoverPk,
+            );
+            pk_args.iter_mut().for_each(|x| x.is_ret = true);
+            inputs.append(&mut pk_args);
+            inputs.push(ExternalHostCallEntry {
+                op: ForeignInst::Secp256k1RecoverResult as usize,
+                value: pk.is_some() as u64,
+                is_ret: true,
+            });
+        }
+        let table = ExternalHostCallEntryTable(inputs);
+        let file = File::create("secp256k1recovertest.json").expect("can not create file");
+        serde_json::to_writer_pretty(file, &table).expect("can not write to file");
+    }
+}
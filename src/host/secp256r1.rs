@@ -0,0 +1,222 @@
+use crate::utils::{bn_to_field, field_to_bn};
+use halo2_proofs::arithmetic::Field;
+use halo2_proofs::pairing::secp256r1::{Fp, Fq};
+use num_bigint::BigUint;
+
+/* Native reference of the secp256r1 (P-256) ECDSA host calls.
+ *
+ * P-256 has a = -3 so the short Weierstrass formulas of halo2ecc-s (which assume
+ * a = 0) do not apply. Points are kept in projective coordinates and added with the
+ * complete formulas of Renes, Costello and Batina (2015, algorithm 4), which have
+ * no exceptional cases. The circuit uses exactly the same sequence of operations.
+ *
+ * As for secp256k1, R.x has to be equal to r as an integer.
+ */
+
+pub const P256_B: &str = "5ac635d8aa3a93e7b3ebbd55769886bc651d06b0cc53b0f63bce3c3e27d2604b";
+pub const P256_GX: &str = "6b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296";
+pub const P256_GY: &str = "4fe342e2fe1a7f9b8ee7eb4a7c0f9e162bce33576b315ececbb6406837bf51f5";
+
+pub fn hex_to_field<F: halo2_proofs::arithmetic::BaseExt>(hex: &str) -> F {
+    bn_to_field(&BigUint::parse_bytes(hex.as_bytes(), 16).unwrap())
+}
+
+pub fn p256_b() -> Fp {
+    hex_to_field(P256_B)
+}
+
+/// A point in projective coordinates, the identity is (0 : 1 : 0)
+#[derive(Clone, Copy, Debug)]
+pub struct P256Point {
+    pub x: Fp,
+    pub y: Fp,
+    pub z: Fp,
+}
+
+impl P256Point {
+    pub fn identity() -> Self {
+        P256Point {
+            x: Fp::zero(),
+            y: Fp::one(),
+            z: Fp::zero(),
+        }
+    }
+
+    pub fn generator() -> Self {
+        Self::from_affine(hex_to_field(P256_GX), hex_to_field(P256_GY))
+    }
+
+    pub fn from_affine(x: Fp, y: Fp) -> Self {
+        P256Point { x, y, z: Fp::one() }
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.z == Fp::zero()
+    }
+
+    pub fn to_affine(&self) -> Option<(Fp, Fp)> {
+        let z_inv: Option<Fp> = self.z.invert().into();
+        z_inv.map(|z_inv| (self.x * z_inv, self.y * z_inv))
+    }
+
+    pub fn add(&self, other: &Self) -> Self {
+        let b = p256_b();
+        let (x1, y1, z1) = (self.x, self.y, self.z);
+        let (x2, y2, z2) = (other.x, other.y, other.z);
+        let mut t0 = x1 * x2;
+        let mut t1 = y1 * y2;
+        let mut t2 = z1 * z2;
+        let mut t3 = (x1 + y1) * (x2 + y2);
+        let mut t4 = t0 + t1;
+        t3 = t3 - t4;
+        t4 = (y1 + z1) * (y2 + z2);
+        let mut x3 = t1 + t2;
+        t4 = t4 - x3;
+        x3 = (x1 + z1) * (x2 + z2);
+        let mut y3 = t0 + t2;
+        y3 = x3 - y3;
+        let mut z3 = b * t2;
+        x3 = y3 - z3;
+        z3 = x3 + x3;
+        x3 = x3 + z3;
+        z3 = t1 - x3;
+        x3 = t1 + x3;
+        y3 = b * y3;
+        t1 = t2 + t2;
+        t2 = t1 + t2;
+        y3 = y3 - t2;
+        y3 = y3 - t0;
+        t1 = y3 + y3;
+        y3 = t1 + y3;
+        t1 = t0 + t0;
+        t0 = t1 + t0;
+        t0 = t0 - t2;
+        t1 = t4 * y3;
+        t2 = t0 * y3;
+        y3 = x3 * z3;
+        y3 = y3 + t2;
+        x3 = t3 * x3;
+        x3 = x3 - t1;
+        z3 = t4 * z3;
+        t1 = t3 * t0;
+        z3 = z3 + t1;
+        P256Point {
+            x: x3,
+            y: y3,
+            z: z3,
+        }
+    }
+
+    pub fn mul(&self, k: &Fq) -> Self {
+        let k = field_to_bn(k);
+        let mut acc = Self::identity();
+        for i in (0..256).rev() {
+            acc = acc.add(&acc);
+            if k.bit(i) {
+                acc = acc.add(self);
+            }
+        }
+        acc
+    }
+}
+
+fn fq_to_fp(x: &Fq) -> Fp {
+    bn_to_field(&field_to_bn(x))
+}
+
+/// Sign msg with secret key sk and nonce k. Returns (r, s).
+pub fn ecdsa_sign(sk: &Fq, msg: &Fq, k: &Fq) -> Option<(Fq, Fq)> {
+    let (x, _) = P256Point::generator().mul(k).to_affine()?;
+    let r: Fq = bn_to_field(&field_to_bn(&x));
+    if field_to_bn(&r) != field_to_bn(&x) || r == Fq::zero() {
+        return None;
+    }
+    let s = Option::<Fq>::from(k.invert())? * (*msg + r * sk);
+    if s == Fq::zero() {
+        return None;
+    }
+    Some((r, s))
+}
+
+/// Checks R = msg/s * G + r/s * pk is not the identity and R.x == r, i.e. X == r * Z
+pub fn ecdsa_verify(msg: &Fq, r: &Fq, s: &Fq, pk: &P256Point) -> bool {
+    if pk.is_identity() || *r == Fq::zero() {
+        return false;
+    }
+    let s_inv: Option<Fq> = s.invert().into();
+    s_inv.map_or(false, |s_inv| {
+        let nonce = P256Point::generator()
+            .mul(&(*msg * s_inv))
+            .add(&pk.mul(&(*r * s_inv)));
+        !nonce.is_identity() && nonce.x == fq_to_fp(r) * nonce.z
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ecdsa_sign, ecdsa_verify, hex_to_field, P256Point};
+    use crate::host::{ExternalHostCallEntry, ExternalHostCallEntryTable, ForeignInst};
+    use halo2_proofs::arithmetic::Field;
+    use halo2_proofs::pairing::secp256r1::{Fp, Fq};
+    use rand::rngs::OsRng;
+    use std::fs::File;
+
+    fn secp256r1_pk_to_args(pk: &P256Point, op: ForeignInst) -> Vec<ExternalHostCallEntry> {
+        let (x, y) = pk.to_affine().unwrap_or((Fp::zero(), Fp::zero()));
+        let mut r = vec![];
+        r.append(&mut crate::adaptor::fr_to_args(x, 5, 54, op));
+        r.append(&mut crate::adaptor::fr_to_args(y, 5, 54, op));
+        r.push(ExternalHostCallEntry {
+            op: op as usize,
+            value: pk.is_identity() as u64,
+            is_ret: false,
+        });
+        r
+    }
+
+    #[test]
+    fn test_secp256r1_ecdsa_vector() {
+        let sk: Fq = hex_to_field("c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721");
+        let k: Fq = hex_to_field("7a1a7e52797fc8caaa435d2a4dace39158504bf204fbe19f14dbb427faee4ae7");
+        let msg: Fq = hex_to_field("af2bdbe1aa9b6ec1e2ade1d694f41fc71a831d0268e9891562113d8a62add1bf");
+        let pk = P256Point::generator().mul(&sk);
+        let (x, y) = pk.to_affine().unwrap();
+        assert_eq!(x, hex_to_field("60fed4ba255a9d31c961eb74c6356d68c049b8923b61fa6ce669622e60f29fb6"));
+        assert_eq!(y, hex_to_field("7903fe1008b8bc99a41ae9e95628bc64f2f1b20c2d7e9f5177a3c294d4462299"));
+        let (r, s) = ecdsa_sign(&sk, &msg, &k).unwrap();
+        assert_eq!(r, hex_to_field("8c5a2a6d0e64e82d254614a9f75a8972c2448ecf920d52dff0175b931c98259e"));
+        assert_eq!(s, hex_to_field("ccfd4d6ad33457330557f9aa52f195020ca1ff94590aeb42e3e83ac8c56e984a"));
+        assert!(ecdsa_verify(&msg, &r, &s, &pk));
+        assert!(!ecdsa_verify(&(msg + Fq::one()), &r, &s, &pk));
+        assert!(!ecdsa_verify(&msg, &r, &s, &P256Point::identity()));
+    }
+
+    #[test]
+    fn generate_secp256r1_verify_input() {
+        let mut inputs = vec![];
+        let mut i = 0;
+        while i < 4 {
+            let sk = Fq::random(&mut OsRng);
+            let msg = Fq::random(&mut OsRng);
+            let k = Fq::random(&mut OsRng);
+            if let Some((r, s)) = ecdsa_sign(&sk, &msg, &k) {
+                // corrupt every other message so that both results are covered
+                let msg = if i % 2 == 0 { msg } else { msg + Fq::one() };
+                let pk = P256Point::generator().mul(&sk);
+                inputs.append(&mut crate::adaptor::fr_to_args(msg, 5, 54, ForeignInst::Secp256r1VerifyMsg));
+                inputs.append(&mut crate::adaptor::fr_to_args(r, 5, 54, ForeignInst::Secp256r1VerifySig));
+                inputs.append(&mut crate::adaptor::fr_to_args(s, 5, 54, ForeignInst::Secp256r1VerifySig));
+                inputs.append(&mut secp256r1_pk_to_args(&pk, ForeignInst::Secp256r1VerifyPk));
+                inputs.push(ExternalHostCallEntry {
+                    op: ForeignInst::Secp256r1VerifyResult as usize,
+                    value: ecdsa_verify(&msg, &r, &s, &pk) as u64,
+                    is_ret: true,
+                });
+                i += 1;
+            }
+        }
+        let table = ExternalHostCallEntryTable(inputs);
+        let file = File::create("secp256r1verifytest.json").expect("can not create file");
+        serde_json::to_writer_pretty(file, &table).expect("can not write to file");
+    }
+}
@@ -9,7 +9,9 @@ pub mod proof;
 
 use clap::{arg, value_parser, App, Arg, ArgMatches};
 use std::path::PathBuf;
-use crate::proof::{exec_create_host_proof, OpType, read_host_call_table};
+use crate::host::merkle::MerkleTree;
+use crate::host::mongomerkle::MongoMerkle;
+use crate::proof::{exec_create_host_proof, OpType, read_host_call_table, MERKLE_DEPTH};
 
 const DEFAULT_CIRCUITS_K: u32 = 22;
 
@@ -85,21 +87,90 @@ fn parse_opname(matches: &ArgMatches) -> OpType {
         .clone()
 }
 
-#[allow(clippy::many_single_char_names)]
-fn main() {
-    let clap_app = App::new("hostcircuit")
+fn parse_hash(h: &String) -> [u8; 32] {
+    hex::decode(h.trim_start_matches("0x"))
+        .expect("hash should be in hex")
+        .try_into()
+        .expect("hash should be 32 bytes")
+}
+
+fn diff_subcommand<'a>() -> App<'a> {
+    App::new("diff")
+        .about("Print the merkle leaves that differ between two roots")
+        .arg(arg!(--address <ADDRESS> "Hex id of the merkle collection").required(false))
+        .arg(arg!(--from <FROM_ROOT> "Hex of the first merkle root"))
+        .arg(arg!(--to <TO_ROOT> "Hex of the second merkle root"))
+}
+
+fn exec_diff(matches: &ArgMatches) {
+    let addr = matches
+        .get_one::<String>("address")
+        .map_or([0u8; 32], |x| parse_hash(x));
+    let from = parse_hash(matches.get_one::<String>("from").expect("from root is required"));
+    let to = parse_hash(matches.get_one::<String>("to").expect("to root is required"));
+    let mt = MongoMerkle::<MERKLE_DEPTH>::construct(addr, from, None);
+    let diffs = mt.diff_roots(&from, &to).expect("diff merkle roots error");
+    for diff in diffs.iter() {
+        println!(
+            "leaf {}: {} -> {}",
+            diff.index,
+            hex::encode(diff.before),
+            hex::encode(diff.after)
+        );
+    }
+    println!("{} leaves differ", diffs.len());
+}
+
+// the proof arguments are required unless a subcommand is given
+fn host_circuit_app<'a>() -> App<'a> {
+    App::new("hostcircuit")
         .arg(input_file())
         .arg(output_folder())
         .arg(param_folder())
         .arg(opname())
-        .arg(circuits_k());
+        .arg(circuits_k())
+        .subcommand(diff_subcommand())
+        .subcommand_negates_reqs(true)
+}
 
-    let matches = clap_app.get_matches();
+#[allow(clippy::many_single_char_names)]
+fn main() {
+    let matches = host_circuit_app().get_matches();
+    if let Some(sub_matches) = matches.subcommand_matches("diff") {
+        exec_diff(sub_matches);
+        return;
+    }
     let input_file = parse_input_file(&matches);
     let cache_folder = parse_output_folder(&matches);
     let param_folder = parse_param_folder(&matches);
     let opname = parse_opname(&matches);
     let k = parse_circuits_k(&matches);
 
-    exec_create_host_proof("host", k as usize, &read_host_call_table(input_file), opname, &cache_folder, &param_folder);
+    if let Err(e) = exec_create_host_proof("host", k as usize, &read_host_call_table(input_file), opname, &cache_folder, &param_folder) {
+        eprintln!("invalid host call: {}", e);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::host_circuit_app;
+
+    #[test]
+    fn test_parse_diff_subcommand() {
+        let root = format!("0x{}", "00".repeat(32));
+        let matches = host_circuit_app()
+            .try_get_matches_from(vec!["hostcircuit", "diff", "--from", &root, "--to", &root])
+            .unwrap();
+        let sub_matches = matches.subcommand_matches("diff").unwrap();
+        assert_eq!(sub_matches.get_one::<String>("from"), Some(&root));
+
+        // the proof arguments are still required without a subcommand
+        assert!(host_circuit_app().try_get_matches_from(vec!["hostcircuit"]).is_err());
+        assert!(host_circuit_app()
+            .try_get_matches_from(vec![
+                "hostcircuit", "-i", "in.json", "-o", "output/", "-p", "params/", "-n", "bn256sum",
+            ])
+            .is_ok());
+    }
 }
@@ -5,21 +5,43 @@ use halo2_proofs::{
     plonk::{Circuit, ConstraintSystem, Error},
     pairing::bn256::{Bn256, Fr},
 };
-use crate::circuits::babyjub::AltJubChip;
+use crate::circuits::babyjub::{AltJubBatchMsmChip, AltJubMsmChip};
 use crate::circuits::{
+    blake2::{Blake2bChip, Blake2sChip},
     bls::Bls381PairChip,
+    bls::Bls381PairCheckChip,
+    bls::Bls381PairCompressedChip,
+    bls::Bls381GtChip,
+    bls::Bls381KzgChip,
+    bls::Bls381VerifyChip,
     bls::Bls381SumChip,
+    bls::Bls381MsmChip,
+    bls::Bls381SumG2Chip,
     bn256::Bn256PairChip,
+    bn256::Bn256PairCheckChip,
+    bn256::Bn256PairCompressedChip,
+    bn256::Bn256GtChip,
     bn256::Bn256SumChip,
+    bn256::Bn256MsmChip,
+    bn256::Bn256SumG2Chip,
+    eddsa::AltJubEddsaChip,
+    groth16::Groth16VerifyChip,
+    pedersen::{AltJubPedersenCommitChip, AltJubPedersenHashChip},
     host::{HostOpChip, HostOpConfig, HostOpSelector},
+    keccak256::KeccakChip,
+    kvmap::KVMapChip,
     merkle::MerkleChip,
-    poseidon::PoseidonChip,
+    poseidon::{PoseidonChip, PoseidonSpongeChip},
+    secp256k1::Secp256k1RecoverChip,
+    secp256k1::Secp256k1VerifyChip,
+    secp256r1::Secp256r1VerifyChip,
 };
 
 use circuits_batcher::proof::CircuitInfo;
 use circuits_batcher::proof::Prover;
 use circuits_batcher::args::HashType::Poseidon;
 
+use crate::adaptor::validate::HostCallError;
 use crate::host::ExternalHostCallEntryTable;
 
 pub const MERKLE_DEPTH: usize = 32;
@@ -38,11 +60,37 @@ struct ArgOpName {
 pub enum OpType {
     BLS381PAIR,
     BLS381SUM,
+    BLS381MSM,
+    BLS381SUMG2,
+    BLS381PAIRCHECK,
+    BLS381PAIRCOMPRESSED,
+    BLS381GT,
+    BLS381KZG,
+    BLS381VERIFY,
     BN256PAIR,
     BN256SUM,
+    BN256MSM,
+    BN256SUMG2,
+    BN256PAIRCHECK,
+    BN256PAIRCOMPRESSED,
+    BN256GT,
+    GROTH16VERIFY,
     POSEIDONHASH,
+    POSEIDONSPONGEHASH,
+    POSEIDONCIRCOMHASH,
+    KECCAKHASH,
+    BLAKE2SHASH,
+    BLAKE2BHASH,
     MERKLE,
+    KVMAP,
     JUBJUBSUM,
+    JUBJUBMSM,
+    JUBJUBEDDSAVERIFY,
+    JUBJUBPEDERSENCOMMIT,
+    JUBJUBPEDERSENHASH,
+    SECP256K1VERIFY,
+    SECP256K1RECOVER,
+    SECP256R1VERIFY,
 }
 
 #[derive(Clone)]
@@ -127,18 +175,20 @@ pub fn read_host_call_table(input_file: PathBuf) -> ExternalHostCallEntryTable {
     v
 }
 
+/// The circuit of the host call table, once the calls of the op pass its validation
 pub fn build_host_circuit<S: HostOpSelector>(
     v: &ExternalHostCallEntryTable,
-) -> HostOpCircuit<Fr, S> {
+) -> Result<HostOpCircuit<Fr, S>, HostCallError> {
     // Prepare the private and public inputs to the circuit!
     let shared_operands = v.0.iter().map(|x| Fr::from(x.value as u64)).collect();
     let shared_opcodes = v.0.iter().map(|x| Fr::from(x.op as u64)).collect();
+    S::validate(&shared_operands, &shared_opcodes)?;
 
-    HostOpCircuit::<Fr, S> {
+    Ok(HostOpCircuit::<Fr, S> {
         shared_operands,
         shared_opcodes,
         _marker: PhantomData,
-    }
+    })
 }
 
 pub fn exec_create_host_proof(
@@ -148,14 +198,14 @@ pub fn exec_create_host_proof(
     opname: OpType,
     cache_folder: &PathBuf,
     param_folder: &PathBuf,
-) {
+) -> Result<(), HostCallError> {
     // Instantiate the circuit with the private inputs.
     // Given the correct public input, our circuit will verify.
     use circuits_batcher::proof::K_PARAMS_CACHE;
     use circuits_batcher::proof::PKEY_CACHE;
     match opname {
         OpType::BLS381PAIR => {
-            let bls381pair_circuit = build_host_circuit::<Bls381PairChip<Fr>>(&v);
+            let bls381pair_circuit = build_host_circuit::<Bls381PairChip<Fr>>(&v)?;
             let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, Bls381PairChip<Fr>>> =
                 CircuitInfo::new(bls381pair_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
             //prover.mock_proof(k as u32);
@@ -163,15 +213,71 @@ pub fn exec_create_host_proof(
             prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
         }
         OpType::BLS381SUM => {
-            let bls381sum_circuit = build_host_circuit::<Bls381SumChip<Fr>>(&v);
+            let bls381sum_circuit = build_host_circuit::<Bls381SumChip<Fr>>(&v)?;
             let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, Bls381SumChip<Fr>>> =
                 CircuitInfo::new(bls381sum_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
             //prover.mock_proof(k as u32);
             prover.proofloadinfo.save(&cache_folder.as_path());
             prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
         }
+        OpType::BLS381MSM => {
+            let bls381msm_circuit = build_host_circuit::<Bls381MsmChip<Fr>>(&v)?;
+            let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, Bls381MsmChip<Fr>>> =
+                CircuitInfo::new(bls381msm_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
+            //prover.mock_proof(k as u32);
+            prover.proofloadinfo.save(&cache_folder.as_path());
+            prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
+        }
+        OpType::BLS381SUMG2 => {
+            let bls381sumg2_circuit = build_host_circuit::<Bls381SumG2Chip<Fr>>(&v)?;
+            let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, Bls381SumG2Chip<Fr>>> =
+                CircuitInfo::new(bls381sumg2_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
+            //prover.mock_proof(k as u32);
+            prover.proofloadinfo.save(&cache_folder.as_path());
+            prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
+        }
+        OpType::BLS381PAIRCHECK => {
+            let bls381paircheck_circuit = build_host_circuit::<Bls381PairCheckChip<Fr>>(&v)?;
+            let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, Bls381PairCheckChip<Fr>>> =
+                CircuitInfo::new(bls381paircheck_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
+            //prover.mock_proof(k as u32);
+            prover.proofloadinfo.save(&cache_folder.as_path());
+            prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
+        }
+        OpType::BLS381PAIRCOMPRESSED => {
+            let bls381paircompressed_circuit = build_host_circuit::<Bls381PairCompressedChip<Fr>>(&v)?;
+            let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, Bls381PairCompressedChip<Fr>>> =
+                CircuitInfo::new(bls381paircompressed_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
+            //prover.mock_proof(k as u32);
+            prover.proofloadinfo.save(&cache_folder.as_path());
+            prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
+        }
+        OpType::BLS381GT => {
+            let bls381gt_circuit = build_host_circuit::<Bls381GtChip<Fr>>(&v)?;
+            let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, Bls381GtChip<Fr>>> =
+                CircuitInfo::new(bls381gt_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
+            //prover.mock_proof(k as u32);
+            prover.proofloadinfo.save(&cache_folder.as_path());
+            prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
+        }
+        OpType::BLS381KZG => {
+            let bls381kzg_circuit = build_host_circuit::<Bls381KzgChip<Fr>>(&v)?;
+            let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, Bls381KzgChip<Fr>>> =
+                CircuitInfo::new(bls381kzg_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
+            //prover.mock_proof(k as u32);
+            prover.proofloadinfo.save(&cache_folder.as_path());
+            prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
+        }
+        OpType::BLS381VERIFY => {
+            let bls381verify_circuit = build_host_circuit::<Bls381VerifyChip<Fr>>(&v)?;
+            let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, Bls381VerifyChip<Fr>>> =
+                CircuitInfo::new(bls381verify_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
+            //prover.mock_proof(k as u32);
+            prover.proofloadinfo.save(&cache_folder.as_path());
+            prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
+        }
         OpType::BN256PAIR => {
-            let bn256pair_circuit = build_host_circuit::<Bn256PairChip<Fr>>(&v);
+            let bn256pair_circuit = build_host_circuit::<Bn256PairChip<Fr>>(&v)?;
             let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, Bn256PairChip<Fr>>> =
                 CircuitInfo::new(bn256pair_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
             //prover.mock_proof(k as u32);
@@ -179,15 +285,63 @@ pub fn exec_create_host_proof(
             prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
         }
         OpType::BN256SUM => {
-            let bn256sum_circuit = build_host_circuit::<Bn256SumChip<Fr>>(&v);
+            let bn256sum_circuit = build_host_circuit::<Bn256SumChip<Fr>>(&v)?;
             let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, Bn256SumChip<Fr>>> =
                 CircuitInfo::new(bn256sum_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
             //prover.mock_proof(k as u32);
             prover.proofloadinfo.save(&cache_folder.as_path());
             prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
         }
+        OpType::BN256MSM => {
+            let bn256msm_circuit = build_host_circuit::<Bn256MsmChip<Fr>>(&v)?;
+            let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, Bn256MsmChip<Fr>>> =
+                CircuitInfo::new(bn256msm_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
+            //prover.mock_proof(k as u32);
+            prover.proofloadinfo.save(&cache_folder.as_path());
+            prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
+        }
+        OpType::BN256SUMG2 => {
+            let bn256sumg2_circuit = build_host_circuit::<Bn256SumG2Chip<Fr>>(&v)?;
+            let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, Bn256SumG2Chip<Fr>>> =
+                CircuitInfo::new(bn256sumg2_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
+            //prover.mock_proof(k as u32);
+            prover.proofloadinfo.save(&cache_folder.as_path());
+            prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
+        }
+        OpType::BN256PAIRCHECK => {
+            let bn256paircheck_circuit = build_host_circuit::<Bn256PairCheckChip<Fr>>(&v)?;
+            let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, Bn256PairCheckChip<Fr>>> =
+                CircuitInfo::new(bn256paircheck_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
+            //prover.mock_proof(k as u32);
+            prover.proofloadinfo.save(&cache_folder.as_path());
+            prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
+        }
+        OpType::BN256PAIRCOMPRESSED => {
+            let bn256paircompressed_circuit = build_host_circuit::<Bn256PairCompressedChip<Fr>>(&v)?;
+            let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, Bn256PairCompressedChip<Fr>>> =
+                CircuitInfo::new(bn256paircompressed_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
+            //prover.mock_proof(k as u32);
+            prover.proofloadinfo.save(&cache_folder.as_path());
+            prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
+        }
+        OpType::BN256GT => {
+            let bn256gt_circuit = build_host_circuit::<Bn256GtChip<Fr>>(&v)?;
+            let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, Bn256GtChip<Fr>>> =
+                CircuitInfo::new(bn256gt_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
+            //prover.mock_proof(k as u32);
+            prover.proofloadinfo.save(&cache_folder.as_path());
+            prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
+        }
+        OpType::GROTH16VERIFY => {
+            let groth16_circuit = build_host_circuit::<Groth16VerifyChip<Fr>>(&v)?;
+            let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, Groth16VerifyChip<Fr>>> =
+                CircuitInfo::new(groth16_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
+            //prover.mock_proof(k as u32);
+            prover.proofloadinfo.save(&cache_folder.as_path());
+            prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
+        }
         OpType::POSEIDONHASH => {
-            let poseidon_circuit = build_host_circuit::<PoseidonChip<Fr, 9, 8>>(&v);
+            let poseidon_circuit = build_host_circuit::<PoseidonChip<Fr, 9, 8>>(&v)?;
             let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, PoseidonChip<Fr, 9, 8>>> =
                 CircuitInfo::new(poseidon_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
             prover.mock_proof(k as u32);
@@ -195,8 +349,53 @@ pub fn exec_create_host_proof(
             prover.proofloadinfo.save(&cache_folder.as_path());
             prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
         }
+        OpType::POSEIDONSPONGEHASH => {
+            let poseidon_circuit = build_host_circuit::<PoseidonSpongeChip<Fr, 9, 8>>(&v)?;
+            let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, PoseidonSpongeChip<Fr, 9, 8>>> =
+                CircuitInfo::new(poseidon_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
+            prover.mock_proof(k as u32);
+            println!("mock proof for poseidon sponge hash success");
+            prover.proofloadinfo.save(&cache_folder.as_path());
+            prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
+        }
+        OpType::POSEIDONCIRCOMHASH => {
+            let poseidon_circuit = build_host_circuit::<PoseidonChip<Fr, 3, 2>>(&v)?;
+            let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, PoseidonChip<Fr, 3, 2>>> =
+                CircuitInfo::new(poseidon_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
+            prover.mock_proof(k as u32);
+            println!("mock proof for circom poseidon hash success");
+            prover.proofloadinfo.save(&cache_folder.as_path());
+            prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
+        }
+        OpType::KECCAKHASH => {
+            let keccak_circuit = build_host_circuit::<KeccakChip<Fr>>(&v)?;
+            let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, KeccakChip<Fr>>> =
+                CircuitInfo::new(keccak_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
+            prover.mock_proof(k as u32);
+            println!("mock proof for keccak hash success");
+            prover.proofloadinfo.save(&cache_folder.as_path());
+            prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
+        }
+        OpType::BLAKE2SHASH => {
+            let blake2s_circuit = build_host_circuit::<Blake2sChip<Fr>>(&v)?;
+            let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, Blake2sChip<Fr>>> =
+                CircuitInfo::new(blake2s_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
+            prover.mock_proof(k as u32);
+            println!("mock proof for blake2s hash success");
+            prover.proofloadinfo.save(&cache_folder.as_path());
+            prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
+        }
+        OpType::BLAKE2BHASH => {
+            let blake2b_circuit = build_host_circuit::<Blake2bChip<Fr>>(&v)?;
+            let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, Blake2bChip<Fr>>> =
+                CircuitInfo::new(blake2b_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
+            prover.mock_proof(k as u32);
+            println!("mock proof for blake2b hash success");
+            prover.proofloadinfo.save(&cache_folder.as_path());
+            prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
+        }
         OpType::MERKLE => {
-            let merkle_circuit = build_host_circuit::<MerkleChip<Fr, MERKLE_DEPTH>>(&v);
+            let merkle_circuit = build_host_circuit::<MerkleChip<Fr, MERKLE_DEPTH>>(&v)?;
             let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, MerkleChip<Fr, MERKLE_DEPTH>>> =
                 CircuitInfo::new(merkle_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
             prover.mock_proof(k as u32);
@@ -204,16 +403,86 @@ pub fn exec_create_host_proof(
             prover.proofloadinfo.save(&cache_folder.as_path());
             prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
         }
+        OpType::KVMAP => {
+            let kvmap_circuit = build_host_circuit::<KVMapChip<Fr, MERKLE_DEPTH>>(&v)?;
+            let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, KVMapChip<Fr, MERKLE_DEPTH>>> =
+                CircuitInfo::new(kvmap_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
+            prover.mock_proof(k as u32);
+            println!("mock proof for kvmap success");
+            prover.proofloadinfo.save(&cache_folder.as_path());
+            prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
+        }
         OpType::JUBJUBSUM => {
-            let jubjub_circuit = build_host_circuit::<AltJubChip<Fr>>(&v);
-            let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, AltJubChip<Fr>>> =
+            let jubjub_circuit = build_host_circuit::<AltJubMsmChip<Fr>>(&v)?;
+            let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, AltJubMsmChip<Fr>>> =
                 CircuitInfo::new(jubjub_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
             prover.mock_proof(k as u32);
             println!("mock proof for jubjub success");
             prover.proofloadinfo.save(&cache_folder.as_path());
             prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
         }
+        OpType::JUBJUBMSM => {
+            let jubjub_circuit = build_host_circuit::<AltJubBatchMsmChip<Fr>>(&v)?;
+            let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, AltJubBatchMsmChip<Fr>>> =
+                CircuitInfo::new(jubjub_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
+            prover.mock_proof(k as u32);
+            println!("mock proof for jubjub batch msm success");
+            prover.proofloadinfo.save(&cache_folder.as_path());
+            prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
+        }
+        OpType::JUBJUBEDDSAVERIFY => {
+            let eddsa_circuit = build_host_circuit::<AltJubEddsaChip<Fr>>(&v)?;
+            let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, AltJubEddsaChip<Fr>>> =
+                CircuitInfo::new(eddsa_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
+            prover.mock_proof(k as u32);
+            println!("mock proof for jubjub eddsa verify success");
+            prover.proofloadinfo.save(&cache_folder.as_path());
+            prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
+        }
+        OpType::JUBJUBPEDERSENCOMMIT => {
+            let pedersen_circuit = build_host_circuit::<AltJubPedersenCommitChip<Fr>>(&v)?;
+            let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, AltJubPedersenCommitChip<Fr>>> =
+                CircuitInfo::new(pedersen_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
+            prover.mock_proof(k as u32);
+            println!("mock proof for jubjub pedersen commit success");
+            prover.proofloadinfo.save(&cache_folder.as_path());
+            prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
+        }
+        OpType::JUBJUBPEDERSENHASH => {
+            let pedersen_circuit = build_host_circuit::<AltJubPedersenHashChip<Fr>>(&v)?;
+            let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, AltJubPedersenHashChip<Fr>>> =
+                CircuitInfo::new(pedersen_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
+            prover.mock_proof(k as u32);
+            println!("mock proof for jubjub pedersen hash success");
+            prover.proofloadinfo.save(&cache_folder.as_path());
+            prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
+        }
+        OpType::SECP256K1VERIFY => {
+            let secp256k1verify_circuit = build_host_circuit::<Secp256k1VerifyChip<Fr>>(&v)?;
+            let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, Secp256k1VerifyChip<Fr>>> =
+                CircuitInfo::new(secp256k1verify_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
+            //prover.mock_proof(k as u32);
+            prover.proofloadinfo.save(&cache_folder.as_path());
+            prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
+        }
+        OpType::SECP256K1RECOVER => {
+            let secp256k1recover_circuit = build_host_circuit::<Secp256k1RecoverChip<Fr>>(&v)?;
+            let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, Secp256k1RecoverChip<Fr>>> =
+                CircuitInfo::new(secp256k1recover_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
+            //prover.mock_proof(k as u32);
+            prover.proofloadinfo.save(&cache_folder.as_path());
+            prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
+        }
+        OpType::SECP256R1VERIFY => {
+            let secp256r1verify_circuit = build_host_circuit::<Secp256r1VerifyChip<Fr>>(&v)?;
+            let prover: CircuitInfo<Bn256, HostOpCircuit<Fr, Secp256r1VerifyChip<Fr>>> =
+                CircuitInfo::new(secp256r1verify_circuit, format!("{}.{:?}", name, opname), vec![], k, Poseidon);
+            //prover.mock_proof(k as u32);
+            prover.proofloadinfo.save(&cache_folder.as_path());
+            prover.exec_create_proof(cache_folder.as_path(), param_folder.as_path(), PKEY_CACHE.lock().as_mut().unwrap(), 0, K_PARAMS_CACHE.lock().as_mut().unwrap());
+        }
 
     };
     println!("Proof generated.");
+    Ok(())
 }
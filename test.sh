@@ -1,2 +1,4 @@
 cargo test generate_poseidon_input_multi
 cargo run --release --features cuda -- --input blssumtest.json --opname bls381sum --output output --param params
+cargo test generate_bls_sum_identity_input
+cargo run --release --features cuda -- --input blssumidentity.json --opname bls381sum --output output --param params
@@ -0,0 +1,3 @@
+cargo test generate_blake2
+cargo run --release -- --input blake2stest.json --opname blake2shash --output output/ --param params
+cargo run --release -- --input blake2btest.json --opname blake2bhash --output output/ --param params
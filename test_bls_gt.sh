@@ -0,0 +1,2 @@
+cargo test generate_bls_gt_input
+cargo run --release --features cuda -- --input blsgt.json --opname bls381gt --output output/ --param params/
@@ -0,0 +1,2 @@
+cargo test generate_bls_kzg_input
+cargo run --release --features cuda -- --input blskzg.json --opname bls381kzg --output output/ --param params/
@@ -0,0 +1,2 @@
+cargo test generate_bls_msm_input
+cargo run --release --features cuda -- --input blsmsm.json --opname bls381msm --output output/ --param params/
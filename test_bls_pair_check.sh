@@ -0,0 +1,2 @@
+cargo test generate_bls_pair_check_input
+cargo run --release --features cuda -- --input blspaircheck.json --opname bls381paircheck --output output/ --param params/
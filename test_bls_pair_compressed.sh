@@ -0,0 +1,2 @@
+cargo test generate_bls_pair_compressed_input
+cargo run --release --features cuda -- --input blspaircompressed.json --opname bls381paircompressed --output output/ --param params/
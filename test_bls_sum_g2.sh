@@ -0,0 +1,2 @@
+cargo test generate_bls_sum_g2_input
+cargo run --release --features cuda -- --input blssumg2test.json --opname bls381sumg2 --output output/ --param params/
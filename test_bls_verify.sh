@@ -0,0 +1,2 @@
+cargo test generate_bls_verify_input
+cargo run --release --features cuda -- --input blsverify.json --opname bls381verify --output output/ --param params/
@@ -1,2 +1,4 @@
 cargo test generate_bn256_sum_input
 cargo run --release --features cuda -- --input bn256sumtest.json --opname bn256sum --output output/ --param params/
+cargo test generate_bn256_sum_identity_input
+cargo run --release --features cuda -- --input bn256sumidentity.json --opname bn256sum --output output/ --param params/
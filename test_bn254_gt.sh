@@ -0,0 +1,2 @@
+cargo test generate_bn256_gt_input
+cargo run --release --features cuda -- --input bn256gt.json --opname bn256gt --output output/ --param params/
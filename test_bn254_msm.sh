@@ -0,0 +1,2 @@
+cargo test generate_bn256_msm_input
+cargo run --release --features cuda -- --input bn256msm.json --opname bn256msm --output output/ --param params/
@@ -0,0 +1,2 @@
+cargo test generate_bn256_pair_check_input
+cargo run --release --features cuda -- --input bn256paircheck.json --opname bn256paircheck --output output/ --param params/
@@ -0,0 +1,2 @@
+cargo test generate_bn256_pair_compressed_input
+cargo run --release --features cuda -- --input bn256paircompressed.json --opname bn256paircompressed --output output/ --param params/
@@ -0,0 +1,2 @@
+cargo test generate_bn256_sum_g2_input
+cargo run --release --features cuda -- --input bn256sumg2test.json --opname bn256sumg2 --output output/ --param params/
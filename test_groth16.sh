@@ -0,0 +1,2 @@
+cargo test generate_groth16_verify_input
+cargo run --release --features cuda -- --input groth16verify.json --opname groth16verify --output output/ --param params/
@@ -4,3 +4,8 @@ cargo run --release -- --input jubjub_multi.json --opname jubjubsum --output out
 
 
 
+cargo test generate_jubjub_msm_input_identity
+cargo run --release -- --input jubjub_identity.json --opname jubjubsum --output output/ --param params
+
+cargo test generate_jubjub_batch_msm_input
+cargo run --release -- --input jubjub_batch.json --opname jubjubmsm --output output/ --param params
@@ -0,0 +1,2 @@
+cargo test generate_jubjub_eddsa_input
+cargo run --release -- --input jubjub_eddsa.json --opname jubjubeddsaverify --output output/ --param params
@@ -0,0 +1,3 @@
+cargo test generate_jubjub_pedersen_input
+cargo run --release -- --input jubjub_pedersen_commit.json --opname jubjubpedersencommit --output output/ --param params
+cargo run --release -- --input jubjub_pedersen_hash.json --opname jubjubpedersenhash --output output/ --param params
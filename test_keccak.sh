@@ -0,0 +1,3 @@
+cargo test generate_keccak_input
+cargo run --release -- --input keccaktest.json --opname keccakhash --output output/ --param params
+cargo run --release -- --input keccaktest_multi.json --opname keccakhash --output output/ --param params
@@ -0,0 +1,4 @@
+cargo test generate_secp256k1_verify_input
+cargo test generate_secp256k1_recover_input
+cargo run --release -- --input secp256k1verifytest.json --opname secp256k1verify --output output/ --param params/
+cargo run --release -- --input secp256k1recovertest.json --opname secp256k1recover --output output/ --param params/
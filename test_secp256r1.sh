@@ -0,0 +1,2 @@
+cargo test generate_secp256r1_verify_input
+cargo run --release -- --input secp256r1verifytest.json --opname secp256r1verify --output output/ --param params/